@@ -51,6 +51,8 @@ impl<T> CollectibleDataset for Vec<([T; 3], Option<T>)>
 where
     T: TTerm + CopyTerm + 'static,
 {
+    type CollectError = Infallible;
+
     fn from_quad_source<QS: QuadSource>(quads: QS) -> StreamResult<Self, QS::Error, Infallible> {
         quads
             .map_quads(|q| {
@@ -134,6 +136,8 @@ where
     T: TTerm + CopyTerm + Eq + Hash + 'static,
     S: BuildHasher + Default,
 {
+    type CollectError = Infallible;
+
     fn from_quad_source<QS: QuadSource>(quads: QS) -> StreamResult<Self, QS::Error, Infallible> {
         quads
             .map_quads(|q| {
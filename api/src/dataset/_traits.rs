@@ -531,7 +531,13 @@ pub trait Dataset {
 
 /// A dataset that can be constructed from a [`QuadSource`]
 pub trait CollectibleDataset: Dataset + Sized {
-    fn from_quad_source<QS: QuadSource>(quad: QS) -> StreamResult<Self, QS::Error, Self::Error>;
+    /// The error type that this dataset may raise while being constructed
+    /// (e.g. when it can not store more quads).
+    type CollectError: 'static + Error;
+
+    fn from_quad_source<QS: QuadSource>(
+        quad: QS,
+    ) -> StreamResult<Self, QS::Error, Self::CollectError>;
 }
 
 /// Type alias for results produced by a mutable dataset.
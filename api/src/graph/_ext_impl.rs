@@ -51,6 +51,8 @@ impl<T> CollectibleGraph for Vec<[T; 3]>
 where
     T: TTerm + CopyTerm + 'static,
 {
+    type CollectError = Infallible;
+
     fn from_triple_source<TS: TripleSource>(
         triples: TS,
     ) -> StreamResult<Self, TS::Error, Infallible> {
@@ -118,6 +120,8 @@ where
     T: TTerm + CopyTerm + Eq + Hash + 'static,
     BH: BuildHasher + Default,
 {
+    type CollectError = Infallible;
+
     fn from_triple_source<TS: TripleSource>(
         triples: TS,
     ) -> StreamResult<Self, TS::Error, Infallible> {
@@ -399,9 +399,13 @@ pub trait Graph {
 
 /// A graph that can be constructed from a [`TripleSource`]
 pub trait CollectibleGraph: Graph + Sized {
+    /// The error type that this graph may raise while being constructed
+    /// (e.g. when it can not store more triples).
+    type CollectError: 'static + Error;
+
     fn from_triple_source<TS: TripleSource>(
         triples: TS,
-    ) -> StreamResult<Self, TS::Error, Self::Error>;
+    ) -> StreamResult<Self, TS::Error, Self::CollectError>;
 }
 
 /// Type alias for results produced by a mutable graph.
@@ -3,6 +3,8 @@
 use crate::quad::stream::QuadSource;
 use crate::triple::stream::TripleSource;
 
+mod _limits;
+pub use _limits::*;
 mod _location;
 pub use _location::*;
 
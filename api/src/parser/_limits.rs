@@ -0,0 +1,124 @@
+// this module is transparently re-exported by its parent `parser`
+use crate::term::{TTerm, TermKind};
+use std::fmt;
+
+/// Limits on the resources consumed by a parser,
+/// to protect against malicious or accidentally huge input.
+///
+/// Every limit is optional; the default value imposes no limit.
+/// Parsers supporting limits fail with a dedicated error
+/// (wrapping [`LimitExceeded`]) as soon as one of them is exceeded.
+/// Such errors are never skipped, even by lenient parsers.
+#[derive(Clone, Copy, Debug, Default, Eq, Hash, PartialEq)]
+pub struct ParserLimits {
+    /// The maximum size of the input, in bytes.
+    pub max_input_bytes: Option<usize>,
+    /// The maximum number of triples (or quads) produced.
+    pub max_triples: Option<usize>,
+    /// The maximum length of the lexical form of a literal, in bytes.
+    pub max_literal_len: Option<usize>,
+    /// The maximum length of an IRI, in bytes.
+    pub max_iri_len: Option<usize>,
+    /// The maximum nesting depth of the syntactic structures of the parsed format
+    /// (e.g. collections and blank node property lists, XML elements, JSON arrays and objects).
+    pub max_nesting_depth: Option<usize>,
+}
+
+impl ParserLimits {
+    /// Check that `value` does not exceed the given `limit`.
+    pub fn check(&self, limit: Limit, value: usize) -> Result<(), LimitExceeded> {
+        let max = match limit {
+            Limit::InputBytes => self.max_input_bytes,
+            Limit::Triples => self.max_triples,
+            Limit::LiteralLength => self.max_literal_len,
+            Limit::IriLength => self.max_iri_len,
+            Limit::NestingDepth => self.max_nesting_depth,
+        };
+        match max {
+            Some(max) if value > max => Err(LimitExceeded { limit, max }),
+            _ => Ok(()),
+        }
+    }
+
+    /// Check that `term` does not exceed the limits on the length of IRIs and literals.
+    ///
+    /// This is convenient for parsers which can not check those limits
+    /// while reading the input.
+    pub fn check_term<T: TTerm + ?Sized>(&self, term: &T) -> Result<(), LimitExceeded> {
+        match term.kind() {
+            TermKind::Iri => self.check(Limit::IriLength, term.value_raw().len()),
+            TermKind::Literal => {
+                self.check(Limit::LiteralLength, term.value_raw().len())?;
+                match term.datatype() {
+                    Some(dt) => self.check(Limit::IriLength, dt.value_raw().len()),
+                    None => Ok(()),
+                }
+            }
+            _ => Ok(()),
+        }
+    }
+}
+
+/// One of the limits of [`ParserLimits`].
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum Limit {
+    /// See [`ParserLimits::max_input_bytes`].
+    InputBytes,
+    /// See [`ParserLimits::max_triples`].
+    Triples,
+    /// See [`ParserLimits::max_literal_len`].
+    LiteralLength,
+    /// See [`ParserLimits::max_iri_len`].
+    IriLength,
+    /// See [`ParserLimits::max_nesting_depth`].
+    NestingDepth,
+}
+
+impl fmt::Display for Limit {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Limit::InputBytes => "input size",
+            Limit::Triples => "number of triples",
+            Limit::LiteralLength => "literal length",
+            Limit::IriLength => "IRI length",
+            Limit::NestingDepth => "nesting depth",
+        }
+        .fmt(f)
+    }
+}
+
+/// The error raised when one of the [`ParserLimits`] is exceeded.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq, thiserror::Error)]
+#[error("{limit} exceeds the limit of {max}")]
+pub struct LimitExceeded {
+    /// The exceeded limit.
+    pub limit: Limit,
+    /// The value of that limit.
+    pub max: usize,
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn check() {
+        let limits = ParserLimits {
+            max_triples: Some(10),
+            ..ParserLimits::default()
+        };
+        assert!(limits.check(Limit::Triples, 10).is_ok());
+        assert_eq!(
+            limits.check(Limit::Triples, 11),
+            Err(LimitExceeded {
+                limit: Limit::Triples,
+                max: 10
+            })
+        );
+        assert!(limits.check(Limit::IriLength, usize::MAX).is_ok());
+        assert_eq!(
+            limits.check(Limit::Triples, 11).unwrap_err().to_string(),
+            "number of triples exceeds the limit of 10"
+        );
+    }
+}
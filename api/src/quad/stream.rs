@@ -137,7 +137,7 @@ pub trait QuadSource {
         (0, None)
     }
     /// Collect these quads into a new dataset.
-    fn collect_quads<D>(self) -> StreamResult<D, Self::Error, <D as Dataset>::Error>
+    fn collect_quads<D>(self) -> StreamResult<D, Self::Error, D::CollectError>
     where
         Self: Sized,
         D: CollectibleDataset,
@@ -164,7 +164,7 @@ pub trait TripleSource {
         (0, None)
     }
     /// Collect these triples into a new graph.
-    fn collect_triples<G>(self) -> StreamResult<G, Self::Error, <G as Graph>::Error>
+    fn collect_triples<G>(self) -> StreamResult<G, Self::Error, G::CollectError>
     where
         Self: Sized,
         G: CollectibleGraph,
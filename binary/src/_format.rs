@@ -0,0 +1,54 @@
+//! Constants and low-level encoding shared by the parser and the serializer.
+//!
+//! See the [crate documentation](crate) for a description of the format.
+
+use std::io;
+
+pub const MAGIC: &[u8; 4] = b"SRDF";
+pub const VERSION: u8 = 1;
+
+pub const KIND_TRIPLES: u8 = 0;
+pub const KIND_QUADS: u8 = 1;
+
+// record tags
+pub const END: u8 = 0;
+pub const PREFIX: u8 = 1;
+pub const RESET: u8 = 2;
+pub const STATEMENT: u8 = 3;
+
+// term tags
+pub const REPEAT: u8 = 0;
+pub const IRI_REF: u8 = 1;
+pub const IRI_NEW: u8 = 2;
+pub const BNODE_REF: u8 = 3;
+pub const BNODE_NEW: u8 = 4;
+pub const STRING: u8 = 5;
+pub const LANG: u8 = 6;
+pub const TYPED: u8 = 7;
+pub const VARIABLE: u8 = 8;
+pub const DEFAULT_GRAPH: u8 = 9;
+
+/// The maximum number of bytes of a varint encoding a `u64`.
+pub const MAX_VARINT_LEN: usize = 10;
+
+pub fn write_varint<W: io::Write>(mut write: W, mut value: u64) -> io::Result<()> {
+    let mut buf = [0; MAX_VARINT_LEN];
+    let mut len = 0;
+    loop {
+        let byte = (value & 0x7f) as u8;
+        value >>= 7;
+        if value == 0 {
+            buf[len] = byte;
+            len += 1;
+            break;
+        }
+        buf[len] = byte | 0x80;
+        len += 1;
+    }
+    write.write_all(&buf[..len])
+}
+
+pub fn write_string<W: io::Write>(mut write: W, value: &str) -> io::Result<()> {
+    write_varint(&mut write, value.len() as u64)?;
+    write.write_all(value.as_bytes())
+}
@@ -0,0 +1,58 @@
+//! This crate is part of [Sophia],
+//! an [RDF] and [Linked Data] toolkit in Rust.
+//!
+//! Parser and serializer for a compact binary serialization of RDF,
+//! designed for fast interchange between applications using Sophia.
+//!
+//! Both the parser and the serializer are streaming:
+//! triples and quads are written as they come,
+//! and produced as soon as they are read.
+//! IRIs and blank nodes are written only once,
+//! and subsequently referred to by their index in a table;
+//! IRIs are further compressed by sharing their namespace through a prefix table.
+//! A term identical to the one in the same position of the previous statement
+//! is encoded as a single byte.
+//!
+//! # Format
+//!
+//! A document is made of a header followed by a sequence of records.
+//! Integers are encoded as unsigned [LEB128] varints,
+//! and strings as their length in bytes (a varint) followed by their UTF-8 encoding.
+//!
+//! ```text
+//! document  := "SRDF" version:u8 kind:u8 record* END
+//! kind      := 0 (triples) | 1 (quads)
+//! record    := PREFIX ns:string      -- appends ns to the prefix table
+//!            | RESET                 -- empties all tables
+//!            | STATEMENT term{3}     -- in triple documents
+//!            | STATEMENT term{3} graph -- in quad documents
+//! term      := REPEAT                -- same term as in the previous statement
+//!            | IRI_REF id:varint     -- the id-th IRI of the IRI table
+//!            | IRI_NEW prefix:varint suffix:string
+//!                                    -- appended to the IRI table
+//!            | BNODE_REF id:varint   -- the id-th blank node of the blank node table
+//!            | BNODE_NEW id:string   -- appended to the blank node table
+//!            | STRING value:string   -- a literal of type xsd:string
+//!            | LANG value:string tag:string
+//!            | TYPED value:string (IRI_REF|IRI_NEW)
+//!            | VARIABLE name:string
+//! graph     := DEFAULT_GRAPH | term
+//! ```
+//!
+//! Prefix number 0 is the empty string,
+//! other prefixes are numbered from 1 in their order of appearance.
+//! IRIs and blank nodes are numbered from 0 in their order of appearance.
+//! The END record makes truncated documents detectable;
+//! the parser never reads beyond it,
+//! so that several documents can be sent in a row through the same connection.
+//!
+//! [Sophia]: https://docs.rs/sophia/latest/sophia/
+//! [RDF]: https://www.w3.org/TR/rdf-primer/
+//! [Linked Data]: http://linkeddata.org/
+//! [LEB128]: https://en.wikipedia.org/wiki/LEB128
+
+mod _format;
+
+pub mod parser;
+
+pub mod serializer;
@@ -0,0 +1,454 @@
+//! Parser for the binary format described in the [crate documentation](crate).
+//!
+//! [`BinaryParser`] is both a [`TripleParser`] and a [`QuadParser`].
+//! As a quad parser, it accepts triple documents (all triples are in the default graph);
+//! as a triple parser, it accepts quad documents,
+//! but fails on the first quad in a named graph.
+
+use crate::_format::*;
+use sophia_api::ns::xsd;
+use sophia_api::parser::{QuadParser, TripleParser};
+use sophia_term::iri::Iri;
+use sophia_term::literal::Literal;
+use sophia_term::{ArcTerm, TermError};
+use std::io::{self, BufRead, Read};
+use std::sync::Arc;
+
+/// Binary RDF parser.
+#[derive(Clone, Debug, Default)]
+pub struct BinaryParser {}
+
+impl<B: BufRead> TripleParser<B> for BinaryParser {
+    type Source = BinaryTripleSource<B>;
+    fn parse(&self, data: B) -> Self::Source {
+        BinaryTripleSource(BinaryQuadSource::new(data))
+    }
+}
+
+impl<B: BufRead> QuadParser<B> for BinaryParser {
+    type Source = BinaryQuadSource<B>;
+    fn parse(&self, data: B) -> Self::Source {
+        BinaryQuadSource::new(data)
+    }
+}
+
+/// The type of triples produced by [`BinaryParser`].
+pub type BinaryTriple = [ArcTerm; 3];
+
+/// The type of quads produced by [`BinaryParser`].
+pub type BinaryQuad = ([ArcTerm; 3], Option<ArcTerm>);
+
+/// The error raised when parsing a binary RDF document.
+#[derive(Debug, thiserror::Error)]
+pub enum BinaryError {
+    /// The underlying reader failed.
+    #[error("I/O error: {0}")]
+    Io(#[from] io::Error),
+    /// The data does not comply with the binary format.
+    #[error("Invalid binary RDF: {message} at byte {position}")]
+    Format {
+        /// A description of the error.
+        message: String,
+        /// The position of the error in the document.
+        position: usize,
+    },
+    /// The document contains an invalid term.
+    #[error("Invalid term: {source} at byte {position}")]
+    Term {
+        /// The error raised when building the term.
+        source: TermError,
+        /// The position of the error in the document.
+        position: usize,
+    },
+}
+
+/// The quad source returned by [`BinaryParser`].
+///
+/// Quads are produced as they are read,
+/// and reading stops at the end of the document,
+/// even if the underlying reader contains more data.
+pub struct BinaryQuadSource<B> {
+    read: B,
+    /// the number of bytes read so far
+    position: usize,
+    state: State,
+    prefixes: Vec<Arc<str>>,
+    iris: Vec<Iri<Arc<str>>>,
+    bnodes: Vec<ArcTerm>,
+    /// the terms of the previous statement (None for the default graph)
+    previous: [Option<ArcTerm>; 4],
+    xsd_string: Iri<Arc<str>>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum State {
+    /// Before the header
+    Start,
+    /// After the header; the flag indicates whether statements have a graph name
+    Body(bool),
+    /// After the end of the document, or after an error
+    Done,
+}
+
+impl<B: BufRead> Iterator for BinaryQuadSource<B> {
+    type Item = Result<BinaryQuad, BinaryError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.state == State::Done {
+            return None;
+        }
+        match self.read_quad() {
+            Ok(Some(quad)) => Some(Ok(quad)),
+            Ok(None) => {
+                self.state = State::Done;
+                None
+            }
+            Err(err) => {
+                self.state = State::Done;
+                Some(Err(err))
+            }
+        }
+    }
+}
+
+impl<B: BufRead> BinaryQuadSource<B> {
+    fn new(read: B) -> Self {
+        BinaryQuadSource {
+            read,
+            position: 0,
+            state: State::Start,
+            prefixes: vec!["".into()],
+            iris: Vec::new(),
+            bnodes: Vec::new(),
+            previous: [None, None, None, None],
+            xsd_string: xsd::string.into(),
+        }
+    }
+
+    fn read_quad(&mut self) -> Result<Option<BinaryQuad>, BinaryError> {
+        let quads = match self.state {
+            State::Start => self.read_header()?,
+            State::Body(quads) => quads,
+            State::Done => return Ok(None),
+        };
+        loop {
+            match self.read_u8()? {
+                END => return Ok(None),
+                PREFIX => {
+                    let ns = self.read_string()?;
+                    self.prefixes.push(ns.into());
+                }
+                RESET => {
+                    self.prefixes.truncate(1);
+                    self.iris.clear();
+                    self.bnodes.clear();
+                }
+                STATEMENT => {
+                    let s = self.read_term(0)?;
+                    let p = self.read_term(1)?;
+                    let o = self.read_term(2)?;
+                    let g = if quads { self.read_graph_name()? } else { None };
+                    return Ok(Some(([s, p, o], g)));
+                }
+                tag => return Err(self.error(format!("unknown record {}", tag))),
+            }
+        }
+    }
+
+    /// Read the header, and return whether statements have a graph name.
+    fn read_header(&mut self) -> Result<bool, BinaryError> {
+        let mut magic = [0; 4];
+        self.read_exact(&mut magic)?;
+        if &magic != MAGIC {
+            return Err(self.error("not a binary RDF document"));
+        }
+        let version = self.read_u8()?;
+        if version != VERSION {
+            return Err(self.error(format!("unsupported version {}", version)));
+        }
+        let quads = match self.read_u8()? {
+            KIND_TRIPLES => false,
+            KIND_QUADS => true,
+            kind => return Err(self.error(format!("unknown document kind {}", kind))),
+        };
+        self.state = State::Body(quads);
+        Ok(quads)
+    }
+
+    /// Read the term at position `pos` (0 to 3) of the current statement.
+    fn read_term(&mut self, pos: usize) -> Result<ArcTerm, BinaryError> {
+        let tag = self.read_u8()?;
+        self.read_term_with_tag(pos, tag)
+    }
+
+    fn read_graph_name(&mut self) -> Result<Option<ArcTerm>, BinaryError> {
+        match self.read_u8()? {
+            DEFAULT_GRAPH => {
+                self.previous[3] = None;
+                Ok(None)
+            }
+            tag => self.read_term_with_tag(3, tag).map(Some),
+        }
+    }
+
+    fn read_term_with_tag(&mut self, pos: usize, tag: u8) -> Result<ArcTerm, BinaryError> {
+        let term = match tag {
+            REPEAT => {
+                return self.previous[pos]
+                    .clone()
+                    .ok_or_else(|| self.error("no term to repeat"));
+            }
+            IRI_REF | IRI_NEW => self.read_iri(tag)?.into(),
+            BNODE_REF => {
+                let id = self.read_index()?;
+                match self.bnodes.get(id) {
+                    Some(bnode) => bnode.clone(),
+                    None => return Err(self.error(format!("unknown blank node {}", id))),
+                }
+            }
+            BNODE_NEW => {
+                let id = self.read_string()?;
+                let bnode = ArcTerm::new_bnode(id).map_err(|e| self.term_error(e))?;
+                self.bnodes.push(bnode.clone());
+                bnode
+            }
+            STRING => {
+                let value = self.read_string()?;
+                Literal::new_dt(value, self.xsd_string.clone()).into()
+            }
+            LANG => {
+                let value = self.read_string()?;
+                let tag = self.read_string()?;
+                ArcTerm::new_literal_lang(value, tag).map_err(|e| self.term_error(e))?
+            }
+            TYPED => {
+                let value = self.read_string()?;
+                let datatype = match self.read_u8()? {
+                    tag @ (IRI_REF | IRI_NEW) => self.read_iri(tag)?,
+                    _ => return Err(self.error("datatype must be an IRI")),
+                };
+                Literal::new_dt(value, datatype).into()
+            }
+            VARIABLE => {
+                let name = self.read_string()?;
+                ArcTerm::new_variable(name).map_err(|e| self.term_error(e))?
+            }
+            _ => return Err(self.error(format!("unexpected term {}", tag))),
+        };
+        self.previous[pos] = Some(term.clone());
+        Ok(term)
+    }
+
+    fn read_iri(&mut self, tag: u8) -> Result<Iri<Arc<str>>, BinaryError> {
+        if tag == IRI_REF {
+            let id = self.read_index()?;
+            return match self.iris.get(id) {
+                Some(iri) => Ok(iri.clone()),
+                None => Err(self.error(format!("unknown IRI {}", id))),
+            };
+        }
+        let prefix = self.read_index()?;
+        let suffix = self.read_string()?;
+        let iri = match self.prefixes.get(prefix) {
+            Some(_) if prefix == 0 => Iri::new(suffix),
+            Some(ns) => Iri::new_suffixed(ns.clone(), suffix),
+            None => return Err(self.error(format!("unknown prefix {}", prefix))),
+        }
+        .map_err(|e| self.term_error(e))?;
+        self.iris.push(iri.clone());
+        Ok(iri)
+    }
+
+    fn read_u8(&mut self) -> Result<u8, BinaryError> {
+        let mut byte = [0];
+        self.read_exact(&mut byte)?;
+        Ok(byte[0])
+    }
+
+    fn read_exact(&mut self, buf: &mut [u8]) -> Result<(), BinaryError> {
+        match self.read.read_exact(buf) {
+            Ok(()) => {
+                self.position += buf.len();
+                Ok(())
+            }
+            Err(err) if err.kind() == io::ErrorKind::UnexpectedEof => {
+                Err(self.error("unexpected end of data"))
+            }
+            Err(err) => Err(err.into()),
+        }
+    }
+
+    fn read_varint(&mut self) -> Result<u64, BinaryError> {
+        let mut value = 0;
+        for i in 0..MAX_VARINT_LEN {
+            let byte = self.read_u8()?;
+            value |= u64::from(byte & 0x7f) << (7 * i);
+            if byte & 0x80 == 0 {
+                return Ok(value);
+            }
+        }
+        Err(self.error("varint too long"))
+    }
+
+    /// Read a varint used as an index in one of the tables.
+    fn read_index(&mut self) -> Result<usize, BinaryError> {
+        let value = self.read_varint()?;
+        usize::try_from(value).map_err(|_| self.error(format!("index {} too big", value)))
+    }
+
+    fn read_string(&mut self) -> Result<String, BinaryError> {
+        let len = self.read_varint()?;
+        // the length is not trusted to pre-allocate the whole buffer
+        let mut buf = Vec::with_capacity(len.min(4096) as usize);
+        let read = (&mut self.read).take(len).read_to_end(&mut buf)?;
+        self.position += read;
+        if (read as u64) < len {
+            return Err(self.error("unexpected end of data"));
+        }
+        String::from_utf8(buf).map_err(|_| self.error("invalid UTF-8 string"))
+    }
+
+    fn error<T: ToString>(&self, message: T) -> BinaryError {
+        BinaryError::Format {
+            message: message.to_string(),
+            position: self.position,
+        }
+    }
+
+    fn term_error(&self, source: TermError) -> BinaryError {
+        BinaryError::Term {
+            source,
+            position: self.position,
+        }
+    }
+}
+
+/// The triple source returned by [`BinaryParser`].
+pub struct BinaryTripleSource<B>(BinaryQuadSource<B>);
+
+impl<B: BufRead> Iterator for BinaryTripleSource<B> {
+    type Item = Result<BinaryTriple, BinaryError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        match self.0.next()? {
+            Ok((spo, None)) => Some(Ok(spo)),
+            Ok((_, Some(_))) => {
+                self.0.state = State::Done;
+                Some(Err(self.0.error("named graph in a triple source")))
+            }
+            Err(err) => Some(Err(err)),
+        }
+    }
+}
+
+// ---------------------------------------------------------------------------------
+//                                      tests
+// ---------------------------------------------------------------------------------
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::serializer::{BinaryConfig, BinarySerializer};
+    use sophia_api::dataset::{isomorphic_datasets, Dataset};
+    use sophia_api::graph::{isomorphic_graphs, Graph};
+    use sophia_api::quad::stream::QuadSource;
+    use sophia_api::quad::Quad;
+    use sophia_api::serializer::{QuadSerializer, TripleSerializer};
+    use sophia_api::term::TTerm;
+    use sophia_api::triple::stream::TripleSource;
+    use sophia_inmem::dataset::FastDataset;
+    use sophia_inmem::graph::FastGraph;
+
+    const TRIG: &str = r#"
+        PREFIX : <http://example.org/ns/>
+        PREFIX xsd: <http://www.w3.org/2001/XMLSchema#>
+        :alice a :Person; :name "Alice", "Alicia"@es; :age 42; :note "line 1\nline 2".
+        :bob a :Person; :knows :alice, _:x; :height "1.80"^^xsd:decimal.
+        GRAPH :g1 {
+            :bob :knows _:x.
+            _:x :name "X"^^:custom.
+            <tag:no-slash> <http://example.org/ns#p> <http://example.org/>.
+        }
+        GRAPH _:g2 {
+            _:x :p :alice.
+        }
+    "#;
+
+    #[test]
+    fn roundtrip_quads() -> Result<(), Box<dyn std::error::Error>> {
+        let d1: FastDataset = sophia_turtle::parser::trig::parse_str(TRIG).collect_quads()?;
+        for max_table_size in [4, 5, 1 << 16] {
+            let mut data = Vec::new();
+            let config = BinaryConfig::new().with_max_table_size(max_table_size);
+            BinarySerializer::new_with_config(&mut data, config).serialize_dataset(&d1)?;
+            let d2: FastDataset = QuadParser::parse(&BinaryParser {}, &data[..]).collect_quads()?;
+            assert!(isomorphic_datasets(&d1, &d2)?);
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn roundtrip_triples() -> Result<(), Box<dyn std::error::Error>> {
+        let d: FastDataset = sophia_turtle::parser::trig::parse_str(TRIG).collect_quads()?;
+        let g1: FastGraph = d.graph(None::<&ArcTerm>).triples().collect_triples()?;
+        let mut data = Vec::new();
+        BinarySerializer::new(&mut data).serialize_graph(&g1)?;
+        let g2: FastGraph = TripleParser::parse(&BinaryParser {}, &data[..]).collect_triples()?;
+        assert!(isomorphic_graphs(&g1, &g2)?);
+
+        // a triple document can be parsed as quads
+        let d2: FastDataset = QuadParser::parse(&BinaryParser {}, &data[..]).collect_quads()?;
+        assert_eq!(d2.quads().count(), g1.triples().count());
+        assert!(d2
+            .quads()
+            .all(|q| q.map(|q| q.g().is_none()).unwrap_or(false)));
+        Ok(())
+    }
+
+    #[test]
+    fn consecutive_documents() -> Result<(), Box<dyn std::error::Error>> {
+        let mut data = Vec::new();
+        let nq1 = "<tag:s> <tag:p> <tag:o1> <tag:g>.\n";
+        let nq2 = "<tag:s> <tag:p> <tag:o2>.\n";
+        BinarySerializer::new(&mut data)
+            .serialize_quads(sophia_turtle::parser::nq::parse_str(nq1))?
+            .serialize_quads(sophia_turtle::parser::nq::parse_str(nq2))?;
+        let mut read = &data[..];
+        let q1: Vec<BinaryQuad> =
+            QuadParser::parse(&BinaryParser {}, &mut read).collect::<Result<_, _>>()?;
+        let q2: Vec<BinaryQuad> =
+            QuadParser::parse(&BinaryParser {}, &mut read).collect::<Result<_, _>>()?;
+        assert!(read.is_empty());
+        assert_eq!(q1.len(), 1);
+        assert_eq!(q1[0].0[2].value(), "tag:o1");
+        assert!(q1[0].1.is_some());
+        assert_eq!(q2.len(), 1);
+        assert_eq!(q2[0].0[2].value(), "tag:o2");
+        assert!(q2[0].1.is_none());
+        Ok(())
+    }
+
+    #[test]
+    fn errors() -> Result<(), Box<dyn std::error::Error>> {
+        let mut data = Vec::new();
+        let nq = "<tag:s> <tag:p> <tag:o> <tag:g>.\n";
+        BinarySerializer::new(&mut data)
+            .serialize_quads(sophia_turtle::parser::nq::parse_str(nq))?;
+        let parse = |data: &[u8]| {
+            QuadParser::parse(&BinaryParser {}, data).collect::<Result<Vec<BinaryQuad>, _>>()
+        };
+        let truncated = parse(&data[..data.len() - 1]);
+        assert!(matches!(truncated, Err(BinaryError::Format { .. })));
+        assert!(parse(b"<tag:s> <tag:p> <tag:o>.").is_err());
+        assert!(parse(b"SRDF\x02\x01\x00").is_err());
+        // a reference to an unknown IRI
+        assert!(parse(b"SRDF\x01\x00\x03\x01\x00\x00\x00\x00").is_err());
+        // an invalid IRI
+        assert!(parse(b"SRDF\x01\x00\x03\x02\x00\x03a b\x00").is_err());
+        // a named graph in a triple source
+        let triples: Result<Vec<BinaryTriple>, _> =
+            TripleParser::parse(&BinaryParser {}, &data[..]).collect();
+        assert!(triples.is_err());
+        Ok(())
+    }
+}
@@ -0,0 +1,334 @@
+//! Serializer for the binary format described in the [crate documentation](crate).
+//!
+//! Each call to [`serialize_triples`](TripleSerializer::serialize_triples)
+//! or [`serialize_quads`](QuadSerializer::serialize_quads)
+//! writes a complete document.
+//!
+//! **Important**:
+//! the methods in this module accepting a [`Write`]
+//! make no effort to minimize the number of write operations.
+//! Hence, in most cased, they should be passed a [`BufWriter`].
+//!
+//! [`Write`]: https://doc.rust-lang.org/std/io/trait.Write.html
+//! [`BufWriter`]: https://doc.rust-lang.org/std/io/struct.BufWriter.html
+
+use crate::_format::*;
+use sophia_api::ns::xsd;
+use sophia_api::quad::stream::{QuadSource, SinkError, StreamResult};
+use sophia_api::quad::Quad;
+use sophia_api::serializer::*;
+use sophia_api::term::{term_eq, CopyTerm, TTerm, TermKind};
+use sophia_api::triple::stream::TripleSource;
+use sophia_api::triple::Triple;
+use sophia_term::BoxTerm;
+use std::collections::HashMap;
+use std::io;
+
+/// Binary serializer configuration.
+#[derive(Clone, Debug)]
+pub struct BinaryConfig {
+    max_table_size: usize,
+}
+
+impl BinaryConfig {
+    /// Maximum number of entries in each table (defaults to 65536).
+    ///
+    /// When one of the tables is full, all tables are emptied,
+    /// so that the memory used by the serializer and the parser stays bounded.
+    pub fn max_table_size(&self) -> usize {
+        self.max_table_size
+    }
+
+    /// Build a new default [`BinaryConfig`].
+    pub fn new() -> Self {
+        BinaryConfig {
+            max_table_size: 1 << 16,
+        }
+    }
+
+    /// Transform a [`BinaryConfig`] by setting the [`max_table_size`][`BinaryConfig::max_table_size`] flag.
+    ///
+    /// # Precondition
+    /// `max_table_size` must be at least 4
+    /// (the number of entries that a single quad can add to a table),
+    /// otherwise this method will panic.
+    pub fn with_max_table_size(mut self, max_table_size: usize) -> Self {
+        assert!(max_table_size >= 4);
+        self.max_table_size = max_table_size;
+        self
+    }
+}
+
+impl Default for BinaryConfig {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Binary serializer.
+pub struct BinarySerializer<W> {
+    config: BinaryConfig,
+    write: W,
+}
+
+impl<W> BinarySerializer<W>
+where
+    W: io::Write,
+{
+    /// Build a new binary serializer writing to `write`, with the default config.
+    #[inline]
+    pub fn new(write: W) -> BinarySerializer<W> {
+        Self::new_with_config(write, BinaryConfig::default())
+    }
+
+    /// Build a new binary serializer writing to `write`, with the given config.
+    pub fn new_with_config(write: W, config: BinaryConfig) -> BinarySerializer<W> {
+        BinarySerializer { config, write }
+    }
+
+    /// Borrow this serializer's configuration.
+    pub fn config(&self) -> &BinaryConfig {
+        &self.config
+    }
+}
+
+impl<W> TripleSerializer for BinarySerializer<W>
+where
+    W: io::Write,
+{
+    type Error = io::Error;
+
+    fn serialize_triples<TS>(
+        &mut self,
+        mut source: TS,
+    ) -> StreamResult<&mut Self, TS::Error, Self::Error>
+    where
+        TS: TripleSource,
+    {
+        let write = &mut self.write;
+        let mut encoder = Encoder::new(&self.config);
+        encoder
+            .write_header(&mut *write, KIND_TRIPLES)
+            .map_err(SinkError)?;
+        source.try_for_each_triple(|t| {
+            encoder.start_statement(&mut *write)?;
+            encoder.write_term(&mut *write, 0, t.s())?;
+            encoder.write_term(&mut *write, 1, t.p())?;
+            encoder.write_term(&mut *write, 2, t.o())?;
+            encoder.end_statement(&mut *write)
+        })?;
+        encoder.write_end(write).map_err(SinkError)?;
+        Ok(self)
+    }
+}
+
+impl<W> QuadSerializer for BinarySerializer<W>
+where
+    W: io::Write,
+{
+    type Error = io::Error;
+
+    fn serialize_quads<QS>(
+        &mut self,
+        mut source: QS,
+    ) -> StreamResult<&mut Self, QS::Error, Self::Error>
+    where
+        QS: QuadSource,
+    {
+        let write = &mut self.write;
+        let mut encoder = Encoder::new(&self.config);
+        encoder
+            .write_header(&mut *write, KIND_QUADS)
+            .map_err(SinkError)?;
+        source.try_for_each_quad(|q| {
+            encoder.start_statement(&mut *write)?;
+            encoder.write_term(&mut *write, 0, q.s())?;
+            encoder.write_term(&mut *write, 1, q.p())?;
+            encoder.write_term(&mut *write, 2, q.o())?;
+            encoder.write_graph_name(&mut *write, q.g())?;
+            encoder.end_statement(&mut *write)
+        })?;
+        encoder.write_end(write).map_err(SinkError)?;
+        Ok(self)
+    }
+}
+
+/// The state of the serialization of one document.
+///
+/// Statements are encoded in an internal buffer,
+/// while the prefixes they require are written directly,
+/// so that they always precede the statement.
+struct Encoder {
+    max_table_size: usize,
+    prefixes: HashMap<Box<str>, u64>,
+    iris: HashMap<Box<str>, u64>,
+    bnodes: HashMap<Box<str>, u64>,
+    /// the terms of the previous statement (None for the default graph)
+    previous: [Option<BoxTerm>; 4],
+    buffer: Vec<u8>,
+}
+
+impl Encoder {
+    fn new(config: &BinaryConfig) -> Self {
+        Encoder {
+            max_table_size: config.max_table_size,
+            prefixes: HashMap::new(),
+            iris: HashMap::new(),
+            bnodes: HashMap::new(),
+            previous: [None, None, None, None],
+            buffer: Vec::new(),
+        }
+    }
+
+    fn write_header<W: io::Write>(&mut self, mut write: W, kind: u8) -> io::Result<()> {
+        write.write_all(MAGIC)?;
+        write.write_all(&[VERSION, kind])
+    }
+
+    fn write_end<W: io::Write>(&mut self, mut write: W) -> io::Result<()> {
+        write.write_all(&[END])?;
+        write.flush()
+    }
+
+    fn start_statement<W: io::Write>(&mut self, mut write: W) -> io::Result<()> {
+        let max = self.max_table_size - 4;
+        if self.prefixes.len() > max || self.iris.len() > max || self.bnodes.len() > max {
+            write.write_all(&[RESET])?;
+            self.prefixes.clear();
+            self.iris.clear();
+            self.bnodes.clear();
+        }
+        self.buffer.clear();
+        self.buffer.push(STATEMENT);
+        Ok(())
+    }
+
+    fn end_statement<W: io::Write>(&mut self, mut write: W) -> io::Result<()> {
+        write.write_all(&self.buffer)
+    }
+
+    /// Encode `term`, at position `pos` (0 to 3) of the current statement.
+    fn write_term<W, T>(&mut self, write: W, pos: usize, term: &T) -> io::Result<()>
+    where
+        W: io::Write,
+        T: TTerm + ?Sized,
+    {
+        if let Some(previous) = &self.previous[pos] {
+            if term_eq(previous, term) {
+                self.buffer.push(REPEAT);
+                return Ok(());
+            }
+        }
+        let value = term.value();
+        match term.kind() {
+            TermKind::Iri => self.write_iri(write, &value)?,
+            TermKind::BlankNode => match self.bnodes.get(&*value) {
+                Some(id) => {
+                    self.buffer.push(BNODE_REF);
+                    write_varint(&mut self.buffer, *id)?;
+                }
+                None => {
+                    self.bnodes
+                        .insert(value.as_ref().into(), self.bnodes.len() as u64);
+                    self.buffer.push(BNODE_NEW);
+                    write_string(&mut self.buffer, &value)?;
+                }
+            },
+            TermKind::Literal => match term.language() {
+                Some(tag) => {
+                    self.buffer.push(LANG);
+                    write_string(&mut self.buffer, &value)?;
+                    write_string(&mut self.buffer, tag)?;
+                }
+                None => {
+                    let datatype = term.datatype().unwrap();
+                    if datatype == xsd::string {
+                        self.buffer.push(STRING);
+                        write_string(&mut self.buffer, &value)?;
+                    } else {
+                        self.buffer.push(TYPED);
+                        write_string(&mut self.buffer, &value)?;
+                        self.write_iri(write, &datatype.value())?;
+                    }
+                }
+            },
+            TermKind::Variable => {
+                self.buffer.push(VARIABLE);
+                write_string(&mut self.buffer, &value)?;
+            }
+        }
+        self.previous[pos] = Some(BoxTerm::copy(term));
+        Ok(())
+    }
+
+    fn write_graph_name<W, T>(&mut self, write: W, graph_name: Option<&T>) -> io::Result<()>
+    where
+        W: io::Write,
+        T: TTerm + ?Sized,
+    {
+        match graph_name {
+            Some(g) => self.write_term(write, 3, g),
+            None => {
+                self.buffer.push(DEFAULT_GRAPH);
+                self.previous[3] = None;
+                Ok(())
+            }
+        }
+    }
+
+    fn write_iri<W: io::Write>(&mut self, mut write: W, iri: &str) -> io::Result<()> {
+        if let Some(id) = self.iris.get(iri) {
+            self.buffer.push(IRI_REF);
+            return write_varint(&mut self.buffer, *id);
+        }
+        let split = iri.rfind(['#', '/']).map_or(0, |i| i + 1);
+        let (ns, suffix) = iri.split_at(split);
+        let prefix = if ns.is_empty() {
+            0
+        } else {
+            match self.prefixes.get(ns) {
+                Some(id) => *id,
+                None => {
+                    let id = self.prefixes.len() as u64 + 1;
+                    self.prefixes.insert(ns.into(), id);
+                    write.write_all(&[PREFIX])?;
+                    write_string(&mut write, ns)?;
+                    id
+                }
+            }
+        };
+        self.iris.insert(iri.into(), self.iris.len() as u64);
+        self.buffer.push(IRI_NEW);
+        write_varint(&mut self.buffer, prefix)?;
+        write_string(&mut self.buffer, suffix)
+    }
+}
+
+// ---------------------------------------------------------------------------------
+//                                      tests
+// ---------------------------------------------------------------------------------
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn encoding() -> Result<(), Box<dyn std::error::Error>> {
+        let nq = r#"
+            <tag:s> <http://ex.org/p> "a"@en.
+            <tag:s> <http://ex.org/p> "b" <http://ex.org/g>.
+            _:b <http://ex.org/q> "1"^^<http://ex.org/dt> <http://ex.org/g>.
+        "#;
+        let mut output = Vec::new();
+        BinarySerializer::new(&mut output)
+            .serialize_quads(sophia_turtle::parser::nq::parse_str(nq))?;
+        let mut expected = b"SRDF\x01\x01".to_vec();
+        expected.extend(b"\x01\x0Ehttp://ex.org/");
+        expected.extend(b"\x03\x02\x00\x05tag:s\x02\x01\x01p\x06\x01a\x02en\x09");
+        expected.extend(b"\x03\x00\x00\x05\x01b\x02\x01\x01g");
+        expected.extend(b"\x03\x04\x01b\x02\x01\x01q\x07\x011\x02\x01\x02dt\x00");
+        expected.push(END);
+        assert_eq!(output, expected);
+        Ok(())
+    }
+}
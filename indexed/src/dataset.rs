@@ -1,15 +1,22 @@
 //! A utility trait for building datasets using indexed terms.
 
+use std::collections::HashSet;
 use std::hash::Hash;
 
+use sophia_api::quad::stream::{QuadSource, SinkError, StreamResult};
+use sophia_api::quad::Quad;
 use sophia_api::term::TTerm;
+use sophia_term::index_map::TermIndexFullError;
 use sophia_term::*;
 
+use crate::graph::BULK_BATCH_SIZE;
+
 /// Symbols from other crates, re-exported for the sake of macros
 pub mod reexport {
     pub use sophia_api::dataset::MdResult;
     pub use sophia_api::quad::stream::{QuadSource, StreamResult};
-    pub use sophia_api::quad::Quad;
+    pub use sophia_api::quad::stream::{SinkError, SourceError};
+    pub use sophia_term::index_map::TermIndexFullError;
 }
 
 /// A utility trait for implementing [`Dataset`](sophia_api::dataset::Dataset)
@@ -56,21 +63,66 @@ pub trait IndexedDataset {
     #[allow(clippy::option_option)]
     fn get_graph_name(&self, i: Self::Index) -> Option<Option<&Term<Self::TermData>>>;
 
+    /// Return the index for the given term, creating it if required,
+    /// and increasing its ref count.
+    ///
+    /// Every call to this method must eventually be balanced
+    /// by a call to [`dec_ref`](IndexedDataset::dec_ref).
+    fn make_index<T>(&mut self, t: &T) -> Result<Self::Index, TermIndexFullError>
+    where
+        T: TTerm + ?Sized;
+
+    /// Decrease the ref count of the given index,
+    /// previously obtained with [`make_index`](IndexedDataset::make_index).
+    fn dec_ref(&mut self, i: Self::Index);
+
+    /// Iterate over all the quads in this Dataset, as tuples of indices.
+    fn indexed_quads(&self) -> Box<dyn Iterator<Item = [Self::Index; 4]> + '_>;
+
     /// Insert a triple in this Dataset,
-    /// and return the corresponding tuple of indices.
+    /// and return the corresponding tuple of indices
+    /// (or `None` if the quad was already present).
+    ///
+    /// Fails if the dataset has no index left for a new term.
     fn insert_indexed<TS, TP, TO, TG>(
         &mut self,
         s: &TS,
         p: &TP,
         o: &TO,
         g: Option<&TG>,
-    ) -> Option<[Self::Index; 4]>
+    ) -> Result<Option<[Self::Index; 4]>, TermIndexFullError>
     where
         TS: TTerm + ?Sized,
         TP: TTerm + ?Sized,
         TO: TTerm + ?Sized,
         TG: TTerm + ?Sized;
 
+    /// Insert a quad in this Dataset, given as a tuple of indices
+    /// (obtained with [`make_index`](IndexedDataset::make_index),
+    /// or [`get_index_for_graph_name`](IndexedDataset::get_index_for_graph_name) for the default graph),
+    /// and return it (or `None` if the quad was already present).
+    ///
+    /// Fails if the ref count of one of the indices can not be increased.
+    fn insert_indices(
+        &mut self,
+        q: [Self::Index; 4],
+    ) -> Result<Option<[Self::Index; 4]>, TermIndexFullError>;
+
+    /// Insert a batch of quads in this Dataset, given as tuples of indices
+    /// (see [`insert_indices`](IndexedDataset::insert_indices)),
+    /// and return those that were not already present.
+    ///
+    /// Implementations are expected to process the whole batch at once,
+    /// updating each of their internal indexes in a single pass.
+    /// See also [`bulk_insert`].
+    ///
+    /// Fails if the ref count of one of the indices can not be increased,
+    /// in which case the dataset is left unchanged.
+    fn insert_all_indices(
+        &mut self,
+        quads: Vec<[Self::Index; 4]>,
+    ) -> Result<Vec<[Self::Index; 4]>, TermIndexFullError>;
+
     /// Remove a triple from this Dataset,
     /// and return the corresponding tuple of indices.
     fn remove_indexed<TS, TP, TO, TG>(
@@ -89,6 +141,9 @@ pub trait IndexedDataset {
 
 /// Defines the implementation of [`CollectibleDataset`](sophia_api::dataset::CollectibleDataset)
 /// for [`IndexedDataset`].
+///
+/// [`TermIndexFullError`] is raised (as a sink error)
+/// if the dataset has no index left for a new term.
 #[macro_export]
 macro_rules! impl_collectible_dataset_for_indexed_dataset {
     ($indexed_mutable_dataset: ty) => {
@@ -97,19 +152,15 @@ macro_rules! impl_collectible_dataset_for_indexed_dataset {
         }
     };
     () => {
+        type CollectError = $crate::dataset::reexport::TermIndexFullError;
+
         fn from_quad_source<QS: $crate::dataset::reexport::QuadSource>(
-            mut quads: QS,
-        ) -> $crate::dataset::reexport::StreamResult<Self, QS::Error, Self::Error> {
-            use $crate::dataset::reexport::Quad;
+            quads: QS,
+        ) -> $crate::dataset::reexport::StreamResult<Self, QS::Error, Self::CollectError> {
             let (tmin, tmax) = quads.size_hint_quads();
             let cap = tmax.unwrap_or(tmin);
-            let mut g = Self::with_capacity(cap);
-            quads
-                .try_for_each_quad(|q| -> Result<(), Self::Error> {
-                    g.insert_indexed(q.s(), q.p(), q.o(), q.g());
-                    Ok(())
-                })
-                .map(|_| g)
+            let mut d = Self::with_capacity(cap);
+            $crate::dataset::bulk_insert(&mut d, quads).map(|_| d)
         }
     };
 }
@@ -124,7 +175,7 @@ macro_rules! impl_mutable_dataset_for_indexed_dataset {
         }
     };
     () => {
-        type MutationError = std::convert::Infallible;
+        type MutationError = $crate::dataset::reexport::TermIndexFullError;
 
         fn insert<TS_, TP_, TO_, TG_>(
             &mut self,
@@ -139,7 +190,16 @@ macro_rules! impl_mutable_dataset_for_indexed_dataset {
             TO_: sophia_api::term::TTerm + ?Sized,
             TG_: sophia_api::term::TTerm + ?Sized,
         {
-            Ok(self.insert_indexed(s, p, o, g).is_some())
+            Ok(self.insert_indexed(s, p, o, g)?.is_some())
+        }
+        fn insert_all<QS_>(
+            &mut self,
+            src: QS_,
+        ) -> $crate::dataset::reexport::StreamResult<usize, QS_::Error, Self::MutationError>
+        where
+            QS_: $crate::dataset::reexport::QuadSource,
+        {
+            $crate::dataset::bulk_insert(self, src)
         }
         fn remove<TS_, TP_, TO_, TG_>(
             &mut self,
@@ -159,6 +219,91 @@ macro_rules! impl_mutable_dataset_for_indexed_dataset {
     };
 }
 
+/// Insert all the quads of `quads` into `dataset`,
+/// and return the number of quads that were not already present.
+///
+/// Instead of inserting quads one by one,
+/// quads are converted to tuples of indices and collected in batches
+/// (of at most [`BULK_BATCH_SIZE`] quads),
+/// which are then inserted with [`IndexedDataset::insert_all_indices`].
+///
+/// In case of failure, the batches processed before the failure
+/// remain in the dataset.
+pub fn bulk_insert<D, QS>(
+    dataset: &mut D,
+    mut quads: QS,
+) -> StreamResult<usize, QS::Error, TermIndexFullError>
+where
+    D: IndexedDataset + ?Sized,
+    QS: QuadSource,
+{
+    let (qmin, qmax) = quads.size_hint_quads();
+    let mut batch = Vec::with_capacity(qmax.unwrap_or(qmin).min(BULK_BATCH_SIZE));
+    let default_graph = dataset
+        .get_index_for_graph_name(None as Option<&RefTerm>)
+        .expect("default graph should always have an index");
+    // the indices acquired for this batch, released once it is inserted
+    let mut acquired = HashSet::new();
+    let mut count = 0;
+    let res = quads.try_for_each_quad(|q| -> Result<(), TermIndexFullError> {
+        let s = index_for(dataset, &mut acquired, q.s())?;
+        let p = index_for(dataset, &mut acquired, q.p())?;
+        let o = index_for(dataset, &mut acquired, q.o())?;
+        let g = match q.g() {
+            None => default_graph,
+            Some(g) => index_for(dataset, &mut acquired, g)?,
+        };
+        batch.push([s, p, o, g]);
+        if batch.len() == BULK_BATCH_SIZE {
+            count += flush_batch(dataset, &mut batch, &mut acquired)?;
+        }
+        Ok(())
+    });
+    let res = res.and_then(|_| flush_batch(dataset, &mut batch, &mut acquired).map_err(SinkError));
+    for i in acquired {
+        dataset.dec_ref(i);
+    }
+    res.map(|n| count + n)
+}
+
+/// Return the index of `t` in `dataset`, creating it if required.
+///
+/// The first time a term is met in a batch, the ref acquired by `make_index`
+/// is recorded in `acquired`, to be released once the batch is inserted;
+/// subsequent refs are released immediately.
+fn index_for<D, T>(
+    dataset: &mut D,
+    acquired: &mut HashSet<D::Index>,
+    t: &T,
+) -> Result<D::Index, TermIndexFullError>
+where
+    D: IndexedDataset + ?Sized,
+    T: TTerm + ?Sized,
+{
+    let i = dataset.make_index(t)?;
+    if !acquired.insert(i) {
+        dataset.dec_ref(i);
+    }
+    Ok(i)
+}
+
+/// Insert `batch` into `dataset`, then release the `acquired` indices,
+/// which are now used by the inserted quads.
+fn flush_batch<D>(
+    dataset: &mut D,
+    batch: &mut Vec<[D::Index; 4]>,
+    acquired: &mut HashSet<D::Index>,
+) -> Result<usize, TermIndexFullError>
+where
+    D: IndexedDataset + ?Sized,
+{
+    let inserted = dataset.insert_all_indices(std::mem::take(batch))?;
+    for i in acquired.drain() {
+        dataset.dec_ref(i);
+    }
+    Ok(inserted.len())
+}
+
 #[cfg(test)]
 mod test {
     // Nothing really worth testing here
@@ -1,17 +1,22 @@
 //! A utility trait for building graphs using indexed terms.
 
 use std::collections::hash_map::Entry;
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::hash::Hash;
 
 use sophia_api::term::TTerm;
+use sophia_api::triple::stream::{SinkError, StreamResult, TripleSource};
+use sophia_api::triple::Triple;
+use sophia_term::index_map::TermIndexFullError;
 use sophia_term::*;
 
 /// Symbols from other crates, re-exported for the sake of macros
 pub mod reexport {
     pub use sophia_api::graph::MgResult;
+    pub use sophia_api::triple::stream::{SinkError, SourceError};
     pub use sophia_api::triple::stream::{StreamResult, TripleSource};
     pub use sophia_api::triple::Triple;
+    pub use sophia_term::index_map::TermIndexFullError;
 }
 
 /// A utility trait for implementing [`Graph`](sophia_api::graph::Graph)
@@ -43,13 +48,62 @@ pub trait IndexedGraph {
     /// Return the term for the given index, if it exists.
     fn get_term(&self, i: Self::Index) -> Option<&Term<Self::TermData>>;
 
+    /// Return the index for the given term, creating it if required,
+    /// and increasing its ref count.
+    ///
+    /// Every call to this method must eventually be balanced
+    /// by a call to [`dec_ref`](IndexedGraph::dec_ref).
+    fn make_index<T>(&mut self, t: &T) -> Result<Self::Index, TermIndexFullError>
+    where
+        T: TTerm + ?Sized;
+
+    /// Decrease the ref count of the given index,
+    /// previously obtained with [`make_index`](IndexedGraph::make_index).
+    fn dec_ref(&mut self, i: Self::Index);
+
+    /// Iterate over all the triples in this Graph, as tuples of indices.
+    fn indexed_triples(&self) -> Box<dyn Iterator<Item = [Self::Index; 3]> + '_>;
+
     /// Insert a triple in this Graph,
-    /// and return the corresponding tuple of indices.
-    fn insert_indexed<TS, TP, TO>(&mut self, s: &TS, p: &TP, o: &TO) -> Option<[Self::Index; 3]>
+    /// and return the corresponding tuple of indices
+    /// (or `None` if the triple was already present).
+    ///
+    /// Fails if the graph has no index left for a new term.
+    fn insert_indexed<TS, TP, TO>(
+        &mut self,
+        s: &TS,
+        p: &TP,
+        o: &TO,
+    ) -> Result<Option<[Self::Index; 3]>, TermIndexFullError>
     where
         TS: TTerm + ?Sized,
         TP: TTerm + ?Sized,
         TO: TTerm + ?Sized;
+    /// Insert a triple in this Graph, given as a tuple of indices
+    /// (obtained with [`make_index`](IndexedGraph::make_index)),
+    /// and return it (or `None` if the triple was already present).
+    ///
+    /// Fails if the ref count of one of the indices can not be increased.
+    fn insert_indices(
+        &mut self,
+        t: [Self::Index; 3],
+    ) -> Result<Option<[Self::Index; 3]>, TermIndexFullError>;
+
+    /// Insert a batch of triples in this Graph, given as tuples of indices
+    /// (obtained with [`make_index`](IndexedGraph::make_index)),
+    /// and return those that were not already present.
+    ///
+    /// Implementations are expected to process the whole batch at once,
+    /// updating each of their internal indexes in a single pass.
+    /// See also [`bulk_insert`].
+    ///
+    /// Fails if the ref count of one of the indices can not be increased,
+    /// in which case the graph is left unchanged.
+    fn insert_all_indices(
+        &mut self,
+        triples: Vec<[Self::Index; 3]>,
+    ) -> Result<Vec<[Self::Index; 3]>, TermIndexFullError>;
+
     /// Remove a triple from this Graph,
     /// and return the corresponding tuple of indices.
     fn remove_indexed<TS, TP, TO>(&mut self, s: &TS, p: &TP, o: &TO) -> Option<[Self::Index; 3]>
@@ -61,6 +115,9 @@ pub trait IndexedGraph {
 
 /// Defines the implementation of [`CollectibleGraph`](sophia_api::graph::CollectibleGraph)
 /// for [`IndexedGraph`].
+///
+/// [`TermIndexFullError`] is raised (as a sink error)
+/// if the graph has no index left for a new term.
 #[macro_export]
 macro_rules! impl_collectible_graph_for_indexed_graph {
     ($indexed_mutable_graph: ty) => {
@@ -69,19 +126,15 @@ macro_rules! impl_collectible_graph_for_indexed_graph {
         }
     };
     () => {
+        type CollectError = $crate::graph::reexport::TermIndexFullError;
+
         fn from_triple_source<TS: $crate::graph::reexport::TripleSource>(
-            mut triples: TS,
-        ) -> $crate::graph::reexport::StreamResult<Self, TS::Error, Self::Error> {
-            use $crate::graph::reexport::Triple;
+            triples: TS,
+        ) -> $crate::graph::reexport::StreamResult<Self, TS::Error, Self::CollectError> {
             let (tmin, tmax) = triples.size_hint_triples();
             let cap = tmax.unwrap_or(tmin);
             let mut g = Self::with_capacity(cap);
-            triples
-                .try_for_each_triple(|t| -> Result<(), Self::Error> {
-                    g.insert_indexed(t.s(), t.p(), t.o());
-                    Ok(())
-                })
-                .map(|_| g)
+            $crate::graph::bulk_insert(&mut g, triples).map(|_| g)
         }
     };
 }
@@ -96,7 +149,7 @@ macro_rules! impl_mutable_graph_for_indexed_graph {
         }
     };
     () => {
-        type MutationError = std::convert::Infallible;
+        type MutationError = $crate::graph::reexport::TermIndexFullError;
 
         fn insert<TS_, TP_, TO_>(
             &mut self,
@@ -109,7 +162,16 @@ macro_rules! impl_mutable_graph_for_indexed_graph {
             TP_: sophia_api::term::TTerm + ?Sized,
             TO_: sophia_api::term::TTerm + ?Sized,
         {
-            Ok(self.insert_indexed(s, p, o).is_some())
+            Ok(self.insert_indexed(s, p, o)?.is_some())
+        }
+        fn insert_all<TS_>(
+            &mut self,
+            src: TS_,
+        ) -> $crate::graph::reexport::StreamResult<usize, TS_::Error, Self::MutationError>
+        where
+            TS_: $crate::graph::reexport::TripleSource,
+        {
+            $crate::graph::bulk_insert(self, src)
         }
         fn remove<TS_, TP_, TO_>(
             &mut self,
@@ -127,6 +189,87 @@ macro_rules! impl_mutable_graph_for_indexed_graph {
     };
 }
 
+/// The number of triples inserted at once by [`bulk_insert`].
+pub const BULK_BATCH_SIZE: usize = 1 << 20;
+
+/// Insert all the triples of `triples` into `graph`,
+/// and return the number of triples that were not already present.
+///
+/// Instead of inserting triples one by one,
+/// triples are converted to tuples of indices and collected in batches
+/// (of at most [`BULK_BATCH_SIZE`] triples),
+/// which are then inserted with [`IndexedGraph::insert_all_indices`].
+///
+/// In case of failure, the batches processed before the failure
+/// remain in the graph.
+pub fn bulk_insert<G, TS>(
+    graph: &mut G,
+    mut triples: TS,
+) -> StreamResult<usize, TS::Error, TermIndexFullError>
+where
+    G: IndexedGraph + ?Sized,
+    TS: TripleSource,
+{
+    let (tmin, tmax) = triples.size_hint_triples();
+    let mut batch = Vec::with_capacity(tmax.unwrap_or(tmin).min(BULK_BATCH_SIZE));
+    // the indices acquired for this batch, released once it is inserted
+    let mut acquired = HashSet::new();
+    let mut count = 0;
+    let res = triples.try_for_each_triple(|t| -> Result<(), TermIndexFullError> {
+        let s = index_for(graph, &mut acquired, t.s())?;
+        let p = index_for(graph, &mut acquired, t.p())?;
+        let o = index_for(graph, &mut acquired, t.o())?;
+        batch.push([s, p, o]);
+        if batch.len() == BULK_BATCH_SIZE {
+            count += flush_batch(graph, &mut batch, &mut acquired)?;
+        }
+        Ok(())
+    });
+    let res = res.and_then(|_| flush_batch(graph, &mut batch, &mut acquired).map_err(SinkError));
+    for i in acquired {
+        graph.dec_ref(i);
+    }
+    res.map(|n| count + n)
+}
+
+/// Return the index of `t` in `graph`, creating it if required.
+///
+/// The first time a term is met in a batch, the ref acquired by `make_index`
+/// is recorded in `acquired`, to be released once the batch is inserted;
+/// subsequent refs are released immediately.
+fn index_for<G, T>(
+    graph: &mut G,
+    acquired: &mut HashSet<G::Index>,
+    t: &T,
+) -> Result<G::Index, TermIndexFullError>
+where
+    G: IndexedGraph + ?Sized,
+    T: TTerm + ?Sized,
+{
+    let i = graph.make_index(t)?;
+    if !acquired.insert(i) {
+        graph.dec_ref(i);
+    }
+    Ok(i)
+}
+
+/// Insert `batch` into `graph`, then release the `acquired` indices,
+/// which are now used by the inserted triples.
+fn flush_batch<G>(
+    graph: &mut G,
+    batch: &mut Vec<[G::Index; 3]>,
+    acquired: &mut HashSet<G::Index>,
+) -> Result<usize, TermIndexFullError>
+where
+    G: IndexedGraph + ?Sized,
+{
+    let inserted = graph.insert_all_indices(std::mem::take(batch))?;
+    for i in acquired.drain() {
+        graph.dec_ref(i);
+    }
+    Ok(inserted.len())
+}
+
 /// Insert an absent value in the Vec value of a HashMap,
 /// creating the Vec if it does not exist.
 ///
@@ -150,6 +293,41 @@ where
     ret
 }
 
+/// Insert a batch of absent values in the Vec values of a HashMap,
+/// creating the Vecs that do not exist.
+///
+/// The `(key, value)` pairs must be pairwise distinct.
+/// They are first grouped by key,
+/// so that each Vec of `hm` is looked up and extended only once.
+///
+/// # Returns
+///
+/// The keys whose Vec was created,
+/// meaning that "parent" indexes need to be updated.
+///
+pub fn insert_all_in_index<K, W, It>(hm: &mut HashMap<K, Vec<W>>, entries: It) -> Vec<K>
+where
+    K: Copy + Eq + Hash,
+    W: Copy + Eq,
+    It: IntoIterator<Item = (K, W)>,
+{
+    let mut groups: HashMap<K, Vec<W>> = HashMap::new();
+    for (k, w) in entries {
+        groups.entry(k).or_default().push(w);
+    }
+    let mut created = Vec::new();
+    for (k, ws) in groups {
+        match hm.entry(k) {
+            Entry::Occupied(mut e) => e.get_mut().extend(ws),
+            Entry::Vacant(e) => {
+                created.push(k);
+                e.insert(ws);
+            }
+        }
+    }
+    created
+}
+
 /// Remove an existing value in the Vec value of a HashMap,
 /// removing the entry completely if the Vec ends up empty.
 ///
@@ -7,7 +7,7 @@
 //! It also provides two pre-defined trade-offs:
 //! [`FastDataset`] and [`LightDataset`],
 //! provided in different flavours
-//! ([default](#types), [`small`], [`sync`]).
+//! ([default](#types), [`small`], [`large`], [`sync`]).
 //!
 //! This module is the twin of the [`graph`](super::graph) module.
 //! See its documentation for more details.
@@ -35,7 +35,7 @@ pub use self::_ogps_wrapper::*;
 ///
 /// `I` must be a type for which [`TermIndexMapU`]
 /// implements [`TermIndexMap`](sophia_term::index_map::TermIndexMap),
-/// typically `u16` or `u32`.
+/// typically `u16`, `u32` or `u64`.
 ///
 /// `F` must implement [`TermFactory`].
 ///
@@ -77,6 +77,27 @@ pub mod small {
     sophia_api::test_dataset_impl!(test_lightd, LightDataset);
 }
 
+/// Flavours of Dataset implementations with a larger capacity.
+///
+/// The trade-off is that these implementations have a higher memory-footprint,
+/// but can contain a very large number (2^64) of terms.
+///
+pub mod large {
+    use super::*;
+
+    /// A heavily indexed dataset.
+    /// Fast to query but slow to load, with a relatively high memory footprint.
+    pub type FastDataset = FastWrapper<GenericDataset<u64, RcTermFactory>>;
+    /// A dataset with no triple index.
+    /// Fast to load but slow to query, with a relatively low memory footprint.
+    pub type LightDataset = GenericDataset<u64, RcTermFactory>;
+
+    #[cfg(all(test, feature = "all_tests"))]
+    sophia_api::test_dataset_impl!(test_fastd, FastDataset);
+    #[cfg(all(test, feature = "all_tests"))]
+    sophia_api::test_dataset_impl!(test_lightd, LightDataset);
+}
+
 /// Flavours of Dataset implementations which are safe to share across threads.
 pub mod sync {
     use super::*;
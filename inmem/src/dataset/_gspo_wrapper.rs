@@ -174,6 +174,20 @@ where
         }
     }
 
+    fn idw_hook_insert_all_indexed(&mut self, inserted: &[[T::Index; 4]]) {
+        let new_gsp = insert_all_in_index(
+            &mut self.gsp2o,
+            inserted
+                .iter()
+                .map(|[si, pi, oi, gi]| ([*gi, *si, *pi], *oi)),
+        );
+        let new_gs = insert_all_in_index(
+            &mut self.gs2p,
+            new_gsp.into_iter().map(|[gi, si, pi]| ([gi, si], pi)),
+        );
+        insert_all_in_index(&mut self.g2s, new_gs.into_iter().map(|[gi, si]| (gi, si)));
+    }
+
     #[allow(clippy::collapsible_if)] // it is more regular that way
     #[inline]
     fn idw_hook_remove_indexed(&mut self, modified: &Option<[T::Index; 4]>) {
@@ -4,13 +4,14 @@ use std::convert::Infallible;
 use std::hash::Hash;
 
 use crate::dataset::*;
+use crate::graph::{SharedTermIndexMap, Unsigned};
 use sophia_api::quad::stream::QuadSource;
 use sophia_api::quad::streaming_mode::{ByTermRefs, StreamedQuad};
 use sophia_api::term::TTerm;
 use sophia_api::triple::stream::StreamResult;
 use sophia_indexed::dataset::IndexedDataset;
 use sophia_term::factory::TermFactory;
-use sophia_term::index_map::TermIndexMap;
+use sophia_term::index_map::{TermIndexFullError, TermIndexMap};
 
 /// A generic implementation of [`Dataset`] and [`MutableDataset`],
 /// storing its terms in a [`TermIndexMap`],
@@ -50,6 +51,116 @@ where
     pub fn is_empty(&self) -> bool {
         self.quads.is_empty()
     }
+
+    /// Increase the ref count of all the terms of `q`,
+    /// leaving them unchanged in case of failure.
+    fn inc_refs(&mut self, q: &[I::Index; 4]) -> Result<(), TermIndexFullError> {
+        for (n, i) in q.iter().enumerate() {
+            if let Err(err) = self.terms.inc_ref(*i) {
+                q[..n].iter().for_each(|i| self.terms.dec_ref(*i));
+                return Err(err);
+            }
+        }
+        Ok(())
+    }
+}
+
+impl<U, F> HashDataset<SharedTermIndexMap<U, F>>
+where
+    U: Unsigned,
+    F: TermFactory + Default,
+    F::TermData: 'static,
+{
+    /// Build a new dataset, storing its terms in the given [`SharedTermIndexMap`] and its triples in a Hashset.
+    pub fn with_shared_terms(terms: SharedTermIndexMap<U, F>) -> Self {
+        HashDataset {
+            terms,
+            quads: HashSet::new(),
+        }
+    }
+
+    /// Whether this dataset and `other` store their terms in the same dictionary.
+    pub fn shares_terms_with(&self, other: &Self) -> bool {
+        self.terms.shares_dictionary_with(&other.terms)
+    }
+
+    /// Insert all the quads of `other` in this dataset,
+    /// and return the number of quads that were actually inserted.
+    ///
+    /// If both datasets [share their terms](Self::shares_terms_with),
+    /// this is done without hashing any term.
+    pub fn insert_all_from(&mut self, other: &Self) -> Result<usize, TermIndexFullError> {
+        let mut count = 0;
+        if self.shares_terms_with(other) {
+            for q in other.quads.iter() {
+                if self.insert_indices(*q)?.is_some() {
+                    count += 1;
+                }
+            }
+        } else {
+            for [si, pi, oi, gi] in other.quads.iter() {
+                let s = other.terms.get_term(*si).unwrap();
+                let p = other.terms.get_term(*pi).unwrap();
+                let o = other.terms.get_term(*oi).unwrap();
+                let g = other.terms.get_graph_name(*gi).unwrap();
+                if self.insert_indexed(s, p, o, g)?.is_some() {
+                    count += 1;
+                }
+            }
+        }
+        Ok(count)
+    }
+
+    /// Build a new dataset containing the quads of this dataset that are also in `other`.
+    ///
+    /// The returned dataset shares its terms with this dataset.
+    pub fn intersection(&self, other: &Self) -> Result<Self, TermIndexFullError> {
+        self.filtered(|q| other.contains_from(self, q))
+    }
+
+    /// Build a new dataset containing the quads of this dataset that are not in `other`.
+    ///
+    /// The returned dataset shares its terms with this dataset.
+    pub fn difference(&self, other: &Self) -> Result<Self, TermIndexFullError> {
+        self.filtered(|q| !other.contains_from(self, q))
+    }
+
+    /// Whether this dataset and `other` contain exactly the same quads.
+    ///
+    /// NB: blank nodes are compared by their identifier;
+    /// see [`isomorphic_datasets`](sophia_api::dataset::isomorphic_datasets) for a comparison modulo blank node renaming.
+    pub fn same_quads_as(&self, other: &Self) -> bool {
+        self.len() == other.len() && self.quads.iter().all(|q| other.contains_from(self, q))
+    }
+
+    /// Whether this dataset contains the quad `q`, indexed by dataset `from`.
+    fn contains_from(&self, from: &Self, q: &[U; 4]) -> bool {
+        if self.shares_terms_with(from) {
+            return self.quads.contains(q);
+        }
+        let mut qi = [U::ZERO; 4];
+        for (j, i) in qi.iter_mut().zip(q.iter()) {
+            let term = from.terms.get_graph_name(*i).unwrap();
+            match self.get_index_for_graph_name(term) {
+                Some(k) => *j = k,
+                None => return false,
+            }
+        }
+        self.quads.contains(&qi)
+    }
+
+    /// Build a new dataset, sharing its terms with this one,
+    /// containing the quads of this dataset satisfying `predicate`.
+    fn filtered<P>(&self, mut predicate: P) -> Result<Self, TermIndexFullError>
+    where
+        P: FnMut(&[U; 4]) -> bool,
+    {
+        let mut ret = Self::with_shared_terms(self.terms.clone());
+        for q in self.quads.iter().filter(|q| predicate(q)) {
+            ret.insert_indices(*q)?;
+        }
+        Ok(ret)
+    }
 }
 
 impl<I> IndexedDataset for HashDataset<I>
@@ -102,34 +213,95 @@ where
         self.terms.get_graph_name(i)
     }
 
+    #[inline]
+    fn make_index<T>(&mut self, t: &T) -> Result<Self::Index, TermIndexFullError>
+    where
+        T: TTerm + ?Sized,
+    {
+        self.terms.make_index(&t.into())
+    }
+
+    #[inline]
+    fn dec_ref(&mut self, i: Self::Index) {
+        self.terms.dec_ref(i)
+    }
+
+    fn indexed_quads(&self) -> Box<dyn Iterator<Item = [Self::Index; 4]> + '_> {
+        Box::new(self.quads.iter().copied())
+    }
+
+    fn insert_indices(
+        &mut self,
+        q: [Self::Index; 4],
+    ) -> Result<Option<[Self::Index; 4]>, TermIndexFullError> {
+        if self.quads.contains(&q) {
+            return Ok(None);
+        }
+        self.inc_refs(&q)?;
+        self.quads.insert(q);
+        Ok(Some(q))
+    }
+
+    fn insert_all_indices(
+        &mut self,
+        mut quads: Vec<[Self::Index; 4]>,
+    ) -> Result<Vec<[Self::Index; 4]>, TermIndexFullError> {
+        // keep only new quads, without duplicates
+        let mut batch = HashSet::with_capacity(quads.len());
+        quads.retain(|q| !self.quads.contains(q) && batch.insert(*q));
+        for (n, q) in quads.iter().enumerate() {
+            if let Err(err) = self.inc_refs(q) {
+                // roll back, so that the dataset is left unchanged
+                for q in &quads[..n] {
+                    q.iter().for_each(|i| self.terms.dec_ref(*i));
+                }
+                return Err(err);
+            }
+        }
+        if self.quads.is_empty() && self.quads.capacity() <= batch.capacity() {
+            // no need to hash the batch again
+            self.quads = batch;
+        } else {
+            self.quads.extend(batch);
+        }
+        Ok(quads)
+    }
+
     fn insert_indexed<TS, TP, TO, TG>(
         &mut self,
         s: &TS,
         p: &TP,
         o: &TO,
         g: Option<&TG>,
-    ) -> Option<[Self::Index; 4]>
+    ) -> Result<Option<[Self::Index; 4]>, TermIndexFullError>
     where
         TS: TTerm + ?Sized,
         TP: TTerm + ?Sized,
         TO: TTerm + ?Sized,
         TG: TTerm + ?Sized,
     {
-        let si = self.terms.make_index(&s.into());
-        let pi = self.terms.make_index(&p.into());
-        let oi = self.terms.make_index(&o.into());
-        let gi = self
-            .terms
-            .make_index_for_graph_name(g.map(|g| g.into()).as_ref());
-        let modified = self.quads.insert([si, pi, oi, gi]);
+        let terms = [
+            Some(s.into()),
+            Some(p.into()),
+            Some(o.into()),
+            g.map(|g| g.into()),
+        ];
+        let mut q = [I::NULL_INDEX; 4];
+        for (n, t) in terms.iter().enumerate() {
+            match self.terms.make_index_for_graph_name(t.as_ref()) {
+                Ok(i) => q[n] = i,
+                Err(err) => {
+                    q[..n].iter().for_each(|i| self.terms.dec_ref(*i));
+                    return Err(err);
+                }
+            }
+        }
+        let modified = self.quads.insert(q);
         if modified {
-            Some([si, pi, oi, gi])
+            Ok(Some(q))
         } else {
-            self.terms.dec_ref(si);
-            self.terms.dec_ref(pi);
-            self.terms.dec_ref(oi);
-            self.terms.dec_ref(gi);
-            None
+            q.iter().for_each(|i| self.terms.dec_ref(*i));
+            Ok(None)
         }
     }
 
@@ -192,7 +364,11 @@ where
     I::Index: Hash,
     <I::Factory as TermFactory>::TermData: 'static,
 {
-    fn from_quad_source<QS: QuadSource>(quads: QS) -> StreamResult<Self, QS::Error, Infallible> {
+    type CollectError = TermIndexFullError;
+
+    fn from_quad_source<QS: QuadSource>(
+        quads: QS,
+    ) -> StreamResult<Self, QS::Error, TermIndexFullError> {
         let (tmin, tmax) = quads.size_hint_quads();
         let cap = tmax.unwrap_or(tmin);
         let mut hash_dataset = HashDataset {
@@ -225,6 +401,145 @@ where
 
 #[cfg(test)]
 mod test {
-    // The code from this module is tested through its use in other modules
+    // The code from this module is mostly tested through its use in other modules
     // (especially in ./inmem.rs).
+
+    use super::*;
+    use sophia_api::ns::{rdf, rdfs};
+    use sophia_term::factory::RcTermFactory;
+
+    type SharedDataset = HashDataset<SharedTermIndexMap<u32, RcTermFactory>>;
+
+    fn make_datasets(terms: [SharedTermIndexMap<u32, RcTermFactory>; 2]) -> [SharedDataset; 2] {
+        let [t1, t2] = terms;
+        let mut d1 = SharedDataset::with_shared_terms(t1);
+        d1.insert(
+            &rdf::type_,
+            &rdf::type_,
+            &rdf::Property,
+            None as Option<&Term<&str>>,
+        )
+        .unwrap();
+        d1.insert(
+            &rdf::value,
+            &rdf::type_,
+            &rdf::Property,
+            Some(&rdfs::Resource),
+        )
+        .unwrap();
+        let mut d2 = SharedDataset::with_shared_terms(t2);
+        d2.insert(
+            &rdf::value,
+            &rdf::type_,
+            &rdf::Property,
+            Some(&rdfs::Resource),
+        )
+        .unwrap();
+        d2.insert(
+            &rdfs::label,
+            &rdf::type_,
+            &rdf::Property,
+            None as Option<&Term<&str>>,
+        )
+        .unwrap();
+        [d1, d2]
+    }
+
+    fn check_set_operations(d1: SharedDataset, d2: SharedDataset) {
+        let inter = d1.intersection(&d2).unwrap();
+        assert_eq!(inter.len(), 1);
+        assert!(inter
+            .contains(
+                &rdf::value,
+                &rdf::type_,
+                &rdf::Property,
+                Some(&rdfs::Resource)
+            )
+            .unwrap());
+        assert!(inter.shares_terms_with(&d1));
+
+        let diff = d1.difference(&d2).unwrap();
+        assert_eq!(diff.len(), 1);
+        assert!(diff
+            .contains(
+                &rdf::type_,
+                &rdf::type_,
+                &rdf::Property,
+                None as Option<&Term<&str>>
+            )
+            .unwrap());
+
+        let mut union = SharedDataset::with_shared_terms(d1.terms.clone());
+        assert_eq!(union.insert_all_from(&d1), Ok(2));
+        assert_eq!(union.insert_all_from(&d2), Ok(1));
+        assert_eq!(union.len(), 3);
+        assert!(!union.same_quads_as(&d1));
+        assert!(d1.same_quads_as(&d1));
+        assert!(inter.same_quads_as(&d2.intersection(&d1).unwrap()));
+
+        drop(union);
+        drop(inter);
+        drop(d1);
+        assert!(diff
+            .contains(
+                &rdf::type_,
+                &rdf::type_,
+                &rdf::Property,
+                None as Option<&Term<&str>>
+            )
+            .unwrap());
+    }
+
+    #[test]
+    fn set_operations_shared_terms() {
+        let terms = SharedTermIndexMap::new();
+        let [d1, d2] = make_datasets([terms.clone(), terms]);
+        assert!(d1.shares_terms_with(&d2));
+        check_set_operations(d1, d2);
+    }
+
+    #[test]
+    fn set_operations_distinct_terms() {
+        let [d1, d2] = make_datasets([SharedTermIndexMap::new(), SharedTermIndexMap::new()]);
+        assert!(!d1.shares_terms_with(&d2));
+        check_set_operations(d1, d2);
+    }
+
+    #[test]
+    fn bulk_insert() -> Result<(), Box<dyn std::error::Error>> {
+        use sophia_api::dataset::{Dataset, MutableDataset};
+        use sophia_api::quad::stream::IntoQuadSource;
+        use sophia_api::term::matcher::ANY;
+        use sophia_indexed::dataset::IndexedDataset;
+
+        let mut d = crate::dataset::FastDataset::new();
+        d.insert(&rdf::subject, &rdf::type_, &rdf::Property, Some(&rdf::nil))?;
+        let quads = vec![
+            ([rdf::subject, rdf::type_, rdf::Property], Some(rdf::nil)),
+            ([rdf::object, rdf::type_, rdf::Property], Some(rdf::nil)),
+            ([rdf::object, rdf::type_, rdf::Property], Some(rdf::nil)),
+            ([rdf::object, rdf::type_, rdf::Property], None),
+            ([rdf::Property, rdf::type_, rdfs::Class], Some(rdfs::Class)),
+        ];
+        assert_eq!(d.insert_all(quads.into_iter().into_quad_source())?, 3);
+        assert_eq!(d.quads().count(), 4);
+        // indexes of the wrappers are up to date
+        assert_eq!(d.quads_with_g(Some(&rdf::nil)).count(), 2);
+        assert_eq!(d.quads_with_sg(&rdf::object, Some(&rdf::nil)).count(), 1);
+        assert_eq!(d.quads_with_o(&rdf::Property).count(), 3);
+        assert_eq!(d.quads_with_og(&rdfs::Class, Some(&rdfs::Class)).count(), 1);
+        assert_eq!(d.graph_names()?.len(), 2);
+        // ref counts are balanced
+        d.remove(
+            &rdf::Property,
+            &rdf::type_,
+            &rdfs::Class,
+            Some(&rdfs::Class),
+        )?;
+        assert!(d.get_index(&rdfs::Class).is_none());
+        d.remove_matching(&ANY, &rdf::type_, &rdf::Property, &ANY)?;
+        assert!(d.get_index(&rdf::type_).is_none());
+        assert!(d.get_index(&rdf::nil).is_none());
+        Ok(())
+    }
 }
@@ -172,6 +172,20 @@ where
         }
     }
 
+    fn idw_hook_insert_all_indexed(&mut self, inserted: &[[T::Index; 4]]) {
+        let new_ogp = insert_all_in_index(
+            &mut self.ogp2s,
+            inserted
+                .iter()
+                .map(|[si, pi, oi, gi]| ([*oi, *gi, *pi], *si)),
+        );
+        let new_og = insert_all_in_index(
+            &mut self.og2p,
+            new_ogp.into_iter().map(|[oi, gi, pi]| ([oi, gi], pi)),
+        );
+        insert_all_in_index(&mut self.o2g, new_og.into_iter().map(|[oi, gi]| (oi, gi)));
+    }
+
     #[allow(clippy::collapsible_if)] // it is more regular that way
     #[inline]
     fn idw_hook_remove_indexed(&mut self, modified: &Option<[T::Index; 4]>) {
@@ -578,6 +578,18 @@ where
     /// [`IndexedDataset::insert_indexed`].
     fn idw_hook_insert_indexed(&mut self, modified: &Option<[T::Index; 4]>);
 
+    /// Hook to be executed at the end of
+    /// [`IndexedDataset::insert_all_indices`].
+    ///
+    /// The default implementation calls
+    /// [`idw_hook_insert_indexed`](IndexedDatasetWrapper::idw_hook_insert_indexed)
+    /// for each inserted tuple.
+    fn idw_hook_insert_all_indexed(&mut self, inserted: &[[T::Index; 4]]) {
+        for t in inserted {
+            self.idw_hook_insert_indexed(&Some(*t));
+        }
+    }
+
     /// Hook to be executed at the end of
     /// [`IndexedDataset::remove_indexed`].
     fn idw_hook_remove_indexed(&mut self, modified: &Option<[T::Index; 4]>);
@@ -631,22 +643,61 @@ macro_rules! impl_indexed_dataset_for_wrapper {
             self.get_wrapped().get_graph_name(i)
         }
 
+        #[inline]
+        fn make_index<U>(
+            &mut self,
+            t: &U,
+        ) -> Result<Self::Index, sophia_term::index_map::TermIndexFullError>
+        where
+            U: sophia_api::term::TTerm + ?Sized,
+        {
+            self.get_wrapped_mut().make_index(t)
+        }
+
+        #[inline]
+        fn dec_ref(&mut self, i: Self::Index) {
+            self.get_wrapped_mut().dec_ref(i)
+        }
+
+        #[inline]
+        fn indexed_quads(&self) -> Box<dyn Iterator<Item = [Self::Index; 4]> + '_> {
+            self.get_wrapped().indexed_quads()
+        }
+
         fn insert_indexed<TS, TP, TO, TG>(
             &mut self,
             s: &TS,
             p: &TP,
             o: &TO,
             g: Option<&TG>,
-        ) -> Option<[Self::Index; 4]>
+        ) -> Result<Option<[Self::Index; 4]>, sophia_term::index_map::TermIndexFullError>
         where
             TS: sophia_api::term::TTerm + ?Sized,
             TP: sophia_api::term::TTerm + ?Sized,
             TO: sophia_api::term::TTerm + ?Sized,
             TG: sophia_api::term::TTerm + ?Sized,
         {
-            let modified = self.get_wrapped_mut().insert_indexed(s, p, o, g);
+            let modified = self.get_wrapped_mut().insert_indexed(s, p, o, g)?;
             self.idw_hook_insert_indexed(&modified);
-            modified
+            Ok(modified)
+        }
+
+        fn insert_indices(
+            &mut self,
+            q: [Self::Index; 4],
+        ) -> Result<Option<[Self::Index; 4]>, sophia_term::index_map::TermIndexFullError> {
+            let modified = self.get_wrapped_mut().insert_indices(q)?;
+            self.idw_hook_insert_indexed(&modified);
+            Ok(modified)
+        }
+
+        fn insert_all_indices(
+            &mut self,
+            quads: Vec<[Self::Index; 4]>,
+        ) -> Result<Vec<[Self::Index; 4]>, sophia_term::index_map::TermIndexFullError> {
+            let inserted = self.get_wrapped_mut().insert_all_indices(quads)?;
+            self.idw_hook_insert_all_indexed(&inserted);
+            Ok(inserted)
         }
 
         fn remove_indexed<TS, TP, TO, TG>(
@@ -7,7 +7,18 @@
 //! It also provides two pre-defined trade-offs:
 //! [`FastGraph`] and [`LightGraph`],
 //! provided in different flavors
-//! ([default](#types), [`small`], [`sync`].
+//! ([default](#types), [`small`], [`large`], [`sync`]).
+//!
+//! # Capacity
+//!
+//! Each flavor can only contain a limited number of distinct terms
+//! (2^16 for [`small`], 2^32 for the default flavor, 2^64 for [`large`]),
+//! and each term can only be used in a limited number of triples
+//! (by the same bound).
+//! When this capacity is exceeded, [`MutableGraph::insert`] fails with a
+//! [`TermIndexFullError`](sophia_term::index_map::TermIndexFullError),
+//! and so does [`CollectibleGraph::from_triple_source`](sophia_api::graph::CollectibleGraph::from_triple_source)
+//! (as a [`SinkError`](sophia_api::triple::stream::SinkError)).
 //!
 //! # Customized trade-off
 //!
@@ -46,15 +57,23 @@ mod _ops_wrapper;
 pub use self::_ops_wrapper::*;
 mod _term_index_map_u;
 pub use self::_term_index_map_u::*;
+mod _compact_term_index_map;
+pub use self::_compact_term_index_map::*;
+mod _shared_term_index_map;
+pub use self::_shared_term_index_map::*;
 
 /// A generic in-memory graph.
 ///
 /// `I` must be a type for which [`TermIndexMapU`]
 /// implements [`TermIndexMap`](sophia_term::index_map::TermIndexMap),
-/// typically `u16` or `u32`.
+/// typically `u16`, `u32` or `u64`.
 ///
 /// `F` must implement [`TermFactory`].
 ///
+/// See also [`CompactTermIndexMap`] for an alternative `TermIndexMap`
+/// with a lower memory footprint,
+/// to be used with [`HashGraph`] directly.
+///
 pub type GenericGraph<I, F> = HashGraph<TermIndexMapU<I, F>>;
 
 type FastWrapper<T> = OpsWrapper<SpoWrapper<T>>;
@@ -92,6 +111,27 @@ pub mod small {
     sophia_api::test_graph_impl!(test_lightg, LightGraph);
 }
 
+/// Flavors of Graph implementations with a larger capacity.
+///
+/// The trade-off is that these implementations have a higher memory-footprint,
+/// but can contain a very large number (2^64) of terms.
+///
+pub mod large {
+    use super::*;
+
+    /// A heavily indexed graph.
+    /// Fast to query but slow to load, with a relatively high memory footprint.
+    pub type FastGraph = FastWrapper<GenericGraph<u64, RcTermFactory>>;
+    /// A graph with no triple index.
+    /// Fast to load but slow to query, with a relatively low memory footprint.
+    pub type LightGraph = GenericGraph<u64, RcTermFactory>;
+
+    #[cfg(all(test, feature = "all_tests"))]
+    sophia_api::test_graph_impl!(test_fastg, FastGraph);
+    #[cfg(all(test, feature = "all_tests"))]
+    sophia_api::test_graph_impl!(test_lightg, LightGraph);
+}
+
 /// Flavors of Graph implementations which are safe to share across threads.
 pub mod sync {
     use super::*;
@@ -0,0 +1,142 @@
+// this module is transparently re-exported by its parent `graph::inmem`
+use sophia_term::factory::{FTerm, TermFactory};
+use sophia_term::index_map::{TermIndexFullError, TermIndexMap};
+use sophia_term::iri::Normalization;
+use sophia_term::*;
+
+use super::{TermIndexMapU, Unsigned};
+
+/// An implementation of [`TermIndexMap`]
+/// trading a little insertion time for a lower memory footprint.
+///
+/// It behaves exactly like [`TermIndexMapU`],
+/// except that every IRI (including literal datatypes)
+/// is split at its last [gen-delim](sophia_term::iri::GEN_DELIMS) before being stored.
+/// As the namespace and the local name are interned separately by the factory,
+/// IRIs sharing a long namespace store that namespace only once.
+///
+/// It can be used as a drop-in replacement for [`TermIndexMapU`]:
+///
+/// ```
+/// use sophia_term::factory::RcTermFactory;
+/// use sophia_inmem::graph::*;
+///
+/// type MyGraph = OpsWrapper<SpoWrapper<HashGraph<CompactTermIndexMap<u32, RcTermFactory>>>>;
+/// let g = MyGraph::new();
+/// ```
+pub struct CompactTermIndexMap<I, F>
+where
+    F: TermFactory,
+{
+    inner: TermIndexMapU<I, F>,
+}
+
+impl<I, F> CompactTermIndexMap<I, F>
+where
+    I: Unsigned,
+    F: TermFactory + Default,
+{
+    /// Build a `CompactTermIndexMap`.
+    pub fn new() -> CompactTermIndexMap<I, F> {
+        Self::default()
+    }
+}
+
+impl<I, F> Default for CompactTermIndexMap<I, F>
+where
+    I: Unsigned,
+    F: TermFactory + Default,
+{
+    fn default() -> CompactTermIndexMap<I, F> {
+        CompactTermIndexMap {
+            inner: TermIndexMapU::default(),
+        }
+    }
+}
+
+impl<T, F> TermIndexMap for CompactTermIndexMap<T, F>
+where
+    T: Unsigned,
+    F: TermFactory + Default,
+{
+    type Index = T;
+    type Factory = F;
+
+    const NULL_INDEX: Self::Index = T::ZERO;
+
+    #[inline]
+    fn get_index(&self, t: &RefTerm) -> Option<T> {
+        // hashing and comparison of terms do not depend on how IRIs are split,
+        // so there is no need to normalize t here
+        self.inner.get_index(t)
+    }
+
+    fn make_index(&mut self, t: &RefTerm) -> Result<T, TermIndexFullError> {
+        let t = t.normalized(Normalization::LastGenDelim);
+        self.inner.make_index(&t.as_ref_str())
+    }
+
+    #[inline]
+    fn get_term(&self, i: T) -> Option<&FTerm<F>> {
+        self.inner.get_term(i)
+    }
+
+    #[inline]
+    fn inc_ref(&mut self, i: T) -> Result<(), TermIndexFullError> {
+        self.inner.inc_ref(i)
+    }
+
+    #[inline]
+    fn dec_ref(&mut self, i: T) {
+        self.inner.dec_ref(i)
+    }
+
+    fn shrink_to_fit(&mut self) {
+        self.inner.shrink_to_fit()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use sophia_term::factory::RcTermFactory;
+    use sophia_term::index_map::assert_term_index_map_works;
+    use std::rc::Rc;
+
+    #[test]
+    fn test_term_index() {
+        let mut ti = CompactTermIndexMap::<u16, RcTermFactory>::default();
+        assert_term_index_map_works(&mut ti);
+    }
+
+    #[test]
+    fn test_namespaces_are_shared() {
+        let mut ti = CompactTermIndexMap::<u16, RcTermFactory>::default();
+        let t1 = RefTerm::new_iri("http://example.org/ns#foo").unwrap();
+        let t2 = RefTerm::new_iri("http://example.org/ns#bar").unwrap();
+        let i1 = ti.make_index(&t1).unwrap();
+        let i2 = ti.make_index(&t2).unwrap();
+        assert_ne!(i1, i2);
+        assert_eq!(ti.get_index(&t1), Some(i1));
+        assert_eq!(ti.get_index(&t2), Some(i2));
+
+        let (ns1, ns2) = match (ti.get_term(i1).unwrap(), ti.get_term(i2).unwrap()) {
+            (Term::Iri(iri1), Term::Iri(iri2)) => (iri1.ns(), iri2.ns()),
+            _ => panic!("expected IRIs"),
+        };
+        assert_eq!(&ns1[..], "http://example.org/ns#");
+        assert!(Rc::ptr_eq(ns1, ns2));
+        assert_eq!(ti.get_term(i1).unwrap(), &t1);
+        assert_eq!(ti.get_term(i2).unwrap(), &t2);
+    }
+
+    #[test]
+    fn test_suffixed_lookup() {
+        let mut ti = CompactTermIndexMap::<u16, RcTermFactory>::default();
+        let t1 = RefTerm::new_iri("http://example.org/ns#foo").unwrap();
+        let i1 = ti.make_index(&t1).unwrap();
+        let t2 = RefTerm::new_iri_suffixed("http://example.org/", "ns#foo").unwrap();
+        assert_eq!(ti.get_index(&t2), Some(i1));
+        assert_eq!(ti.make_index(&t2), Ok(i1));
+    }
+}
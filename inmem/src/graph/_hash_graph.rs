@@ -10,7 +10,7 @@ use sophia_api::triple::stream::{StreamResult, TripleSource};
 use sophia_api::triple::streaming_mode::{ByTermRefs, StreamedTriple};
 use sophia_indexed::graph::IndexedGraph;
 use sophia_term::factory::TermFactory;
-use sophia_term::index_map::TermIndexMap;
+use sophia_term::index_map::{TermIndexFullError, TermIndexMap};
 use sophia_term::{RefTerm, Term};
 
 /// A generic implementation of [`Graph`] and [`MutableGraph`],
@@ -50,6 +50,116 @@ where
     pub fn is_empty(&self) -> bool {
         self.triples.is_empty()
     }
+
+    /// Increase the ref count of all the terms of `t`,
+    /// leaving them unchanged in case of failure.
+    fn inc_refs(&mut self, t: &[I::Index; 3]) -> Result<(), TermIndexFullError> {
+        for (n, i) in t.iter().enumerate() {
+            if let Err(err) = self.terms.inc_ref(*i) {
+                t[..n].iter().for_each(|i| self.terms.dec_ref(*i));
+                return Err(err);
+            }
+        }
+        Ok(())
+    }
+}
+
+impl<U, F> HashGraph<SharedTermIndexMap<U, F>>
+where
+    U: Unsigned,
+    F: TermFactory + Default,
+    F::TermData: 'static,
+{
+    /// Build a new graph which stores its terms in the given [`SharedTermIndexMap`],
+    /// and its triples in a `HashSet`.
+    pub fn with_shared_terms(terms: SharedTermIndexMap<U, F>) -> Self {
+        HashGraph {
+            terms,
+            triples: HashSet::new(),
+        }
+    }
+
+    /// Whether this graph and `other` store their terms in the same dictionary.
+    pub fn shares_terms_with(&self, other: &Self) -> bool {
+        self.terms.shares_dictionary_with(&other.terms)
+    }
+
+    /// Insert all the triples of `other` in this graph,
+    /// and return the number of triples that were actually inserted.
+    ///
+    /// If both graphs [share their terms](Self::shares_terms_with),
+    /// this is done without hashing any term.
+    pub fn insert_all_from(&mut self, other: &Self) -> Result<usize, TermIndexFullError> {
+        let mut count = 0;
+        if self.shares_terms_with(other) {
+            for t in other.triples.iter() {
+                if self.insert_indices(*t)?.is_some() {
+                    count += 1;
+                }
+            }
+        } else {
+            for [si, pi, oi] in other.triples.iter() {
+                let s = other.terms.get_term(*si).unwrap();
+                let p = other.terms.get_term(*pi).unwrap();
+                let o = other.terms.get_term(*oi).unwrap();
+                if self.insert_indexed(s, p, o)?.is_some() {
+                    count += 1;
+                }
+            }
+        }
+        Ok(count)
+    }
+
+    /// Build a new graph containing the triples of this graph that are also in `other`.
+    ///
+    /// The returned graph shares its terms with this graph.
+    pub fn intersection(&self, other: &Self) -> Result<Self, TermIndexFullError> {
+        self.filtered(|t| other.contains_from(self, t))
+    }
+
+    /// Build a new graph containing the triples of this graph that are not in `other`.
+    ///
+    /// The returned graph shares its terms with this graph.
+    pub fn difference(&self, other: &Self) -> Result<Self, TermIndexFullError> {
+        self.filtered(|t| !other.contains_from(self, t))
+    }
+
+    /// Whether this graph and `other` contain exactly the same triples.
+    ///
+    /// NB: blank nodes are compared by their identifier;
+    /// see [`isomorphic_graphs`](sophia_api::graph::isomorphic_graphs) for a comparison modulo blank node renaming.
+    pub fn same_triples_as(&self, other: &Self) -> bool {
+        self.len() == other.len() && self.triples.iter().all(|t| other.contains_from(self, t))
+    }
+
+    /// Whether this graph contains the triple `t`, indexed by graph `from`.
+    fn contains_from(&self, from: &Self, t: &[U; 3]) -> bool {
+        if self.shares_terms_with(from) {
+            return self.triples.contains(t);
+        }
+        let mut ti = [U::ZERO; 3];
+        for (j, i) in ti.iter_mut().zip(t.iter()) {
+            let term = from.terms.get_term(*i).unwrap();
+            match self.terms.get_index(&RefTerm::from(term)) {
+                Some(k) => *j = k,
+                None => return false,
+            }
+        }
+        self.triples.contains(&ti)
+    }
+
+    /// Build a new graph, sharing its terms with this one,
+    /// containing the triples of this graph satisfying `predicate`.
+    fn filtered<P>(&self, mut predicate: P) -> Result<Self, TermIndexFullError>
+    where
+        P: FnMut(&[U; 3]) -> bool,
+    {
+        let mut ret = Self::with_shared_terms(self.terms.clone());
+        for t in self.triples.iter().filter(|t| predicate(t)) {
+            ret.insert_indices(*t)?;
+        }
+        Ok(ret)
+    }
 }
 
 impl<I> IndexedGraph for HashGraph<I>
@@ -88,23 +198,95 @@ where
         self.terms.get_term(i)
     }
 
-    fn insert_indexed<TS, TP, TO>(&mut self, s: &TS, p: &TP, o: &TO) -> Option<[Self::Index; 3]>
+    #[inline]
+    fn make_index<T>(&mut self, t: &T) -> Result<Self::Index, TermIndexFullError>
+    where
+        T: TTerm + ?Sized,
+    {
+        self.terms.make_index(&RefTerm::from(t))
+    }
+
+    #[inline]
+    fn dec_ref(&mut self, i: Self::Index) {
+        self.terms.dec_ref(i)
+    }
+
+    fn indexed_triples(&self) -> Box<dyn Iterator<Item = [Self::Index; 3]> + '_> {
+        Box::new(self.triples.iter().copied())
+    }
+
+    fn insert_indices(
+        &mut self,
+        t: [Self::Index; 3],
+    ) -> Result<Option<[Self::Index; 3]>, TermIndexFullError> {
+        if self.triples.contains(&t) {
+            return Ok(None);
+        }
+        self.inc_refs(&t)?;
+        self.triples.insert(t);
+        Ok(Some(t))
+    }
+
+    fn insert_all_indices(
+        &mut self,
+        mut triples: Vec<[Self::Index; 3]>,
+    ) -> Result<Vec<[Self::Index; 3]>, TermIndexFullError> {
+        // keep only new triples, without duplicates
+        let mut batch = HashSet::with_capacity(triples.len());
+        triples.retain(|t| !self.triples.contains(t) && batch.insert(*t));
+        for (n, t) in triples.iter().enumerate() {
+            if let Err(err) = self.inc_refs(t) {
+                // roll back, so that the graph is left unchanged
+                for t in &triples[..n] {
+                    t.iter().for_each(|i| self.terms.dec_ref(*i));
+                }
+                return Err(err);
+            }
+        }
+        if self.triples.is_empty() && self.triples.capacity() <= batch.capacity() {
+            // no need to hash the batch again
+            self.triples = batch;
+        } else {
+            self.triples.extend(batch);
+        }
+        Ok(triples)
+    }
+
+    fn insert_indexed<TS, TP, TO>(
+        &mut self,
+        s: &TS,
+        p: &TP,
+        o: &TO,
+    ) -> Result<Option<[Self::Index; 3]>, TermIndexFullError>
     where
         TS: TTerm + ?Sized,
         TP: TTerm + ?Sized,
         TO: TTerm + ?Sized,
     {
-        let si = self.terms.make_index(&RefTerm::from(s));
-        let pi = self.terms.make_index(&RefTerm::from(p));
-        let oi = self.terms.make_index(&RefTerm::from(o));
+        let si = self.terms.make_index(&RefTerm::from(s))?;
+        let pi = match self.terms.make_index(&RefTerm::from(p)) {
+            Ok(pi) => pi,
+            Err(err) => {
+                self.terms.dec_ref(si);
+                return Err(err);
+            }
+        };
+        let oi = match self.terms.make_index(&RefTerm::from(o)) {
+            Ok(oi) => oi,
+            Err(err) => {
+                self.terms.dec_ref(si);
+                self.terms.dec_ref(pi);
+                return Err(err);
+            }
+        };
         let modified = self.triples.insert([si, pi, oi]);
         if modified {
-            Some([si, pi, oi])
+            Ok(Some([si, pi, oi]))
         } else {
             self.terms.dec_ref(si);
             self.terms.dec_ref(pi);
             self.terms.dec_ref(oi);
-            None
+            Ok(None)
         }
     }
 
@@ -156,9 +338,11 @@ where
     I::Index: Hash,
     <I::Factory as TermFactory>::TermData: 'static,
 {
+    type CollectError = TermIndexFullError;
+
     fn from_triple_source<TS: TripleSource>(
         triples: TS,
-    ) -> StreamResult<Self, TS::Error, Infallible> {
+    ) -> StreamResult<Self, TS::Error, TermIndexFullError> {
         let (tmin, tmax) = triples.size_hint_triples();
         let cap = tmax.unwrap_or(tmin);
         let mut hash_graph = HashGraph {
@@ -191,6 +375,71 @@ where
 
 #[cfg(test)]
 mod test {
-    // The code from this module is tested through its use in other modules
+    // The code from this module is mostly tested through its use in other modules
     // (especially in ./inmem.rs).
+
+    use super::*;
+    use sophia_api::graph::Graph;
+    use sophia_api::ns::rdf;
+    use sophia_api::term::CopyTerm;
+    use sophia_api::triple::stream::{IntoTripleSource, SinkError};
+    use sophia_term::BoxTerm;
+
+    #[test]
+    fn insert_fails_when_full() {
+        let mut g = crate::graph::small::LightGraph::new();
+        let p = rdf::value;
+        let lit = |i: u16| BoxTerm::new_literal_dt(format!("{}", i), rdf::PlainLiteral).unwrap();
+        for i in 0..(u16::MAX - 1) {
+            assert_eq!(g.insert(&lit(i), &p, &lit(i)), Ok(true));
+        }
+        // all indexes (except the null index) are used
+        let x = BoxTerm::new_literal_dt("x", rdf::PlainLiteral).unwrap();
+        assert_eq!(g.insert(&x, &p, &x), Err(TermIndexFullError));
+        assert_eq!(
+            g.insert(&lit(0), &rdf::type_, &lit(0)),
+            Err(TermIndexFullError)
+        );
+        assert_eq!(g.len(), (u16::MAX - 1) as usize);
+        // existing terms can still be used
+        assert_eq!(g.insert(&lit(0), &p, &lit(0)), Ok(false));
+        assert_eq!(g.insert(&lit(0), &p, &lit(1)), Ok(true));
+        // removing a triple frees some room
+        assert_eq!(g.remove(&lit(2), &p, &lit(2)), Ok(true));
+        assert_eq!(g.insert(&x, &p, &x), Ok(true));
+    }
+
+    #[test]
+    fn collect_fails_when_full() {
+        let p = BoxTerm::copy(&rdf::value);
+        let lit = |i: u16| BoxTerm::new_literal_dt(format!("{}", i), rdf::PlainLiteral).unwrap();
+        // one term too many for the u16 indexes of LightGraph
+        let triples = (0..u16::MAX).map(move |i| [lit(i), p.clone(), lit(i)]);
+        let res: Result<crate::graph::small::LightGraph, _> =
+            triples.into_triple_source().collect_triples();
+        assert!(matches!(res, Err(SinkError(TermIndexFullError))));
+    }
+
+    #[test]
+    fn bulk_insert() -> Result<(), Box<dyn std::error::Error>> {
+        let mut g = crate::graph::FastGraph::new();
+        g.insert(&rdf::subject, &rdf::type_, &rdf::Property)?;
+        let triples = vec![
+            [rdf::subject, rdf::type_, rdf::Property],
+            [rdf::object, rdf::type_, rdf::Property],
+            [rdf::object, rdf::type_, rdf::Property],
+            [rdf::Property, rdf::type_, rdf::List],
+        ];
+        assert_eq!(g.insert_all(triples.into_iter().into_triple_source())?, 2);
+        // indexes of the wrappers are up to date
+        assert_eq!(g.triples_with_s(&rdf::object).count(), 1);
+        assert_eq!(g.triples_with_o(&rdf::Property).count(), 2);
+        // ref counts are balanced
+        g.remove(&rdf::Property, &rdf::type_, &rdf::List)?;
+        assert!(g.get_index(&rdf::List).is_none());
+        g.remove_matching(&sophia_api::term::matcher::ANY, &rdf::type_, &rdf::Property)?;
+        assert!(g.get_index(&rdf::type_).is_none());
+        assert!(g.get_index(&rdf::Property).is_none());
+        Ok(())
+    }
 }
@@ -129,6 +129,14 @@ where
         }
     }
 
+    fn igw_hook_insert_all_indexed(&mut self, inserted: &[[T::Index; 3]]) {
+        let new_po = insert_all_in_index(
+            &mut self.po2s,
+            inserted.iter().map(|[si, pi, oi]| ([*pi, *oi], *si)),
+        );
+        insert_all_in_index(&mut self.o2p, new_po.into_iter().map(|[pi, oi]| (oi, pi)));
+    }
+
     #[inline]
     fn igw_hook_remove_indexed(&mut self, modified: &Option<[T::Index; 3]>) {
         if let Some([si, pi, oi]) = *modified {
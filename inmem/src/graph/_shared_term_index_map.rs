@@ -0,0 +1,326 @@
+// this module is transparently re-exported by its parent `graph::inmem`
+use std::cell::{Cell, RefCell};
+use std::collections::HashMap;
+use std::rc::{Rc, Weak};
+
+use sophia_term::factory::{FTerm, TermFactory};
+use sophia_term::index_map::{TermIndexFullError, TermIndexMap};
+use sophia_term::*;
+
+use super::Unsigned;
+
+/// An implementation of [`TermIndexMap`]
+/// which can be shared by several graphs and datasets.
+///
+/// Cloning a `SharedTermIndexMap` does not copy the underlying dictionary,
+/// but returns a new handle to it.
+/// All graphs and datasets built with handles to the same dictionary
+/// (see [`HashGraph::with_shared_terms`](super::HashGraph::with_shared_terms)
+/// and [`HashDataset::with_shared_terms`](crate::dataset::HashDataset::with_shared_terms))
+/// use the same index for any given term,
+/// so that copying triples between them, or comparing them,
+/// can be done without hashing any term.
+///
+/// ```
+/// use sophia_api::graph::MutableGraph;
+/// use sophia_api::ns::rdf;
+/// use sophia_term::factory::RcTermFactory;
+/// use sophia_inmem::graph::*;
+///
+/// let terms = SharedTermIndexMap::<u32, RcTermFactory>::new();
+/// let mut g1 = HashGraph::with_shared_terms(terms.clone());
+/// let mut g2 = HashGraph::with_shared_terms(terms.clone());
+/// g1.insert(&rdf::type_, &rdf::type_, &rdf::Property)?;
+/// g2.insert_all_from(&g1)?;
+/// assert!(g2.same_triples_as(&g1));
+/// # Ok::<(), Box<dyn std::error::Error>>(())
+/// ```
+///
+/// # Memory
+///
+/// A term may still be borrowed (through [`TermIndexMap::get_term`])
+/// from one handle while another handle releases it.
+/// In that case, the term is kept alive by the borrowing handle
+/// until that handle is next mutated (which ends all its borrows) or dropped.
+/// Terms released while no other handle has lent any term are freed immediately,
+/// and indexes are always recycled immediately.
+pub struct SharedTermIndexMap<I, F>
+where
+    F: TermFactory,
+{
+    dict: Rc<RefCell<SharedDict<I, F>>>,
+    handle: Rc<Handle<F>>,
+}
+
+struct SharedDict<I, F>
+where
+    F: TermFactory,
+{
+    factory: F,
+    i2t: Vec<Option<Rc<FTerm<F>>>>,
+    i2c: Vec<I>,
+    t2i: HashMap<StaticTerm, I>,
+    handles: Vec<Weak<Handle<F>>>,
+    // factory, i2t, i2c and t2i are used exactly as in TermIndexMapU
+    // (including the use of i2c[0] to store the next free index),
+    // except that terms in i2t are reference-counted, so that they never move in memory,
+    // and can outlive their removal from i2t;
+    // handles contains the state of every handle to this dictionary
+}
+
+/// The state of one handle to a [`SharedDict`].
+struct Handle<F>
+where
+    F: TermFactory,
+{
+    /// whether terms may have been borrowed through this handle since it was last mutated
+    lent: Cell<bool>,
+    /// released terms that may still be borrowed through this handle
+    pinned: RefCell<Vec<Rc<FTerm<F>>>>,
+}
+
+// Implementation note:
+//
+// As in TermIndexMapU, keys in t2i are not really 'static,
+// they borrow data from the terms in i2t.
+//
+// Furthermore, get_term returns references into the reference-counted terms,
+// that outlive the borrow of the RefCell.
+// Such a reference is only valid as long as the handle it was obtained from is borrowed,
+// and that handle can not be mutated (nor dropped) until then.
+// So when a term is released, it is pinned in every *other* handle that has lent terms,
+// and a handle unpins all its terms whenever it is mutably borrowed.
+
+impl<I, F> SharedTermIndexMap<I, F>
+where
+    I: Unsigned,
+    F: TermFactory + Default,
+{
+    /// Build a `SharedTermIndexMap` with a new empty dictionary.
+    pub fn new() -> SharedTermIndexMap<I, F> {
+        Self::default()
+    }
+}
+
+impl<I, F> SharedTermIndexMap<I, F>
+where
+    F: TermFactory,
+{
+    /// Whether `self` and `other` are handles to the same dictionary.
+    pub fn shares_dictionary_with(&self, other: &Self) -> bool {
+        Rc::ptr_eq(&self.dict, &other.dict)
+    }
+
+    /// Build a new handle to `dict`.
+    fn new_handle(dict: Rc<RefCell<SharedDict<I, F>>>) -> Self {
+        let handle = Rc::new(Handle {
+            lent: Cell::new(false),
+            pinned: RefCell::new(Vec::new()),
+        });
+        {
+            let mut d = dict.borrow_mut();
+            d.handles.retain(|h| h.strong_count() > 0);
+            d.handles.push(Rc::downgrade(&handle));
+        }
+        SharedTermIndexMap { dict, handle }
+    }
+
+    /// Called by every method borrowing `self` mutably:
+    /// no term borrowed through this handle can be in use anymore.
+    fn unpin(&mut self) {
+        self.handle.lent.set(false);
+        self.handle.pinned.borrow_mut().clear();
+    }
+}
+
+impl<I, F> Clone for SharedTermIndexMap<I, F>
+where
+    F: TermFactory,
+{
+    fn clone(&self) -> Self {
+        Self::new_handle(Rc::clone(&self.dict))
+    }
+}
+
+impl<I, F> Default for SharedTermIndexMap<I, F>
+where
+    I: Unsigned,
+    F: TermFactory + Default,
+{
+    fn default() -> SharedTermIndexMap<I, F> {
+        Self::new_handle(Rc::new(RefCell::new(SharedDict {
+            factory: F::default(),
+            i2c: vec![I::ONE],
+            i2t: vec![None],
+            t2i: HashMap::default(),
+            handles: Vec::new(),
+        })))
+    }
+}
+
+impl<T, F> TermIndexMap for SharedTermIndexMap<T, F>
+where
+    T: Unsigned,
+    F: TermFactory + Default,
+{
+    type Index = T;
+    type Factory = F;
+
+    const NULL_INDEX: Self::Index = T::ZERO;
+
+    #[inline]
+    fn get_index(&self, t: &RefTerm) -> Option<T> {
+        self.dict.borrow().t2i.get(t).cloned()
+    }
+
+    fn make_index(&mut self, t: &RefTerm) -> Result<T, TermIndexFullError> {
+        self.unpin();
+        if let Some(i) = self.get_index(t) {
+            self.inc_ref(i)?;
+            return Ok(i);
+        }
+        let mut dict = self.dict.borrow_mut();
+        let i = dict.i2c[0];
+        if i == T::ZERO {
+            return Err(TermIndexFullError);
+        }
+        let t = Rc::new(dict.factory.clone_term(t));
+        let rt = unsafe { fake_static(&t) };
+        dict.t2i.insert(rt, i);
+        let i = i.as_usize();
+        if i == dict.i2t.len() {
+            dict.i2c[0] = dict.i2c[0].checked_inc().unwrap_or(T::ZERO);
+            dict.i2t.push(Some(t));
+            dict.i2c.push(T::ONE);
+        } else {
+            dict.i2c[0] = dict.i2c[i];
+            dict.i2t[i] = Some(t);
+            dict.i2c[i] = T::ONE;
+        }
+        Ok(T::from_usize(i))
+    }
+
+    fn get_term(&self, i: T) -> Option<&FTerm<F>> {
+        let dict = self.dict.borrow();
+        let t: &FTerm<F> = dict.i2t.get(i.as_usize())?.as_deref()?;
+        self.handle.lent.set(true);
+        // the term will not be dropped while self is borrowed (see implementation note above)
+        Some(unsafe { &*(t as *const FTerm<F>) })
+    }
+
+    fn inc_ref(&mut self, i: T) -> Result<(), TermIndexFullError> {
+        self.unpin();
+        if i == Self::NULL_INDEX {
+            return Ok(());
+        }
+        let mut dict = self.dict.borrow_mut();
+        let c = &mut dict.i2c[i.as_usize()];
+        *c = c.checked_inc().ok_or(TermIndexFullError)?;
+        Ok(())
+    }
+
+    fn dec_ref(&mut self, i: T) {
+        self.unpin();
+        if i == Self::NULL_INDEX {
+            return;
+        }
+        let mut dict = self.dict.borrow_mut();
+        let i = i.as_usize();
+        dict.i2c[i].dec();
+        if dict.i2c[i] == T::ZERO {
+            let t = dict.i2t[i].take().unwrap();
+            dict.t2i.remove(unsafe { &fake_static(&t) });
+            dict.i2c[i] = dict.i2c[0];
+            dict.i2c[0] = T::from_usize(i);
+            for handle in dict.handles.iter().filter_map(Weak::upgrade) {
+                if handle.lent.get() {
+                    handle.pinned.borrow_mut().push(Rc::clone(&t));
+                }
+            }
+            // t is freed here, unless pinned by another handle
+        }
+    }
+
+    fn shrink_to_fit(&mut self) {
+        self.unpin();
+        self.handle.pinned.borrow_mut().shrink_to_fit();
+        let mut dict = self.dict.borrow_mut();
+        dict.handles.retain(|h| h.strong_count() > 0);
+        dict.handles.shrink_to_fit();
+        dict.factory.shrink_to_fit();
+        dict.i2c.shrink_to_fit();
+        dict.i2t.shrink_to_fit();
+        dict.t2i.shrink_to_fit();
+        debug_assert_eq!(dict.i2c.len(), dict.i2t.len());
+    }
+}
+
+/// Unsafely converts a boxed term into a StaticTerm.
+/// See `fake_static` in `_term_index_map_u.rs`.
+#[inline]
+unsafe fn fake_static<S>(t: &Term<S>) -> StaticTerm
+where
+    S: TermData,
+{
+    t.clone_map(|txt| &*(txt as *const str))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use sophia_api::ns::rdf;
+    use sophia_term::factory::RcTermFactory;
+    use sophia_term::index_map::assert_term_index_map_works;
+
+    #[test]
+    fn test_term_index() {
+        let mut ti = SharedTermIndexMap::<u16, RcTermFactory>::default();
+        assert_term_index_map_works(&mut ti);
+    }
+
+    #[test]
+    fn test_shared_term_index() {
+        let mut ti = SharedTermIndexMap::<u16, RcTermFactory>::default();
+        let mut ti2 = ti.clone();
+        assert!(ti.shares_dictionary_with(&ti2));
+        assert!(!ti.shares_dictionary_with(&SharedTermIndexMap::default()));
+
+        let i1 = ti.make_index(&rdf::subject.into()).unwrap();
+        assert_eq!(ti2.get_index(&rdf::subject.into()), Some(i1));
+        assert_eq!(ti2.make_index(&rdf::subject.into()), Ok(i1));
+        let t = ti.get_term(i1).unwrap();
+        ti2.dec_ref(i1);
+        ti2.dec_ref(i1);
+        // released term is still available through t
+        assert_eq!(t, &rdf::subject);
+        assert_eq!(ti.get_term(i1), None);
+        assert_eq!(ti.get_index(&rdf::subject.into()), None);
+
+        // the released term is pinned by ti only, as ti2 has lent no term
+        assert_eq!(ti.handle.pinned.borrow().len(), 1);
+        assert_eq!(ti2.handle.pinned.borrow().len(), 0);
+        // mutating ti ends all its borrows, hence unpins the term
+        ti.shrink_to_fit();
+        assert_eq!(ti.handle.pinned.borrow().len(), 0);
+    }
+
+    #[test]
+    fn test_released_terms_are_freed() {
+        let mut ti = SharedTermIndexMap::<u16, RcTermFactory>::default();
+        let ti2 = ti.clone();
+        let _ti3 = ti2.clone();
+        assert_eq!(ti2.get_term(1), None);
+        for _ in 0..100 {
+            let i = ti.make_index(&rdf::subject.into()).unwrap();
+            let t = Rc::downgrade(ti.dict.borrow().i2t[i.as_usize()].as_ref().unwrap());
+            ti.dec_ref(i);
+            // no handle had lent this term, so it is freed immediately
+            assert_eq!(t.strong_count(), 0);
+        }
+        assert_eq!(ti2.handle.pinned.borrow().len(), 0);
+        drop(ti2);
+        assert_eq!(ti.dict.borrow().handles.len(), 3);
+        ti.shrink_to_fit();
+        assert_eq!(ti.dict.borrow().handles.len(), 2);
+    }
+}
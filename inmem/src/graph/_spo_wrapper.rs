@@ -129,6 +129,14 @@ where
         }
     }
 
+    fn igw_hook_insert_all_indexed(&mut self, inserted: &[[T::Index; 3]]) {
+        let new_sp = insert_all_in_index(
+            &mut self.sp2o,
+            inserted.iter().map(|[si, pi, oi]| ([*si, *pi], *oi)),
+        );
+        insert_all_in_index(&mut self.s2p, new_sp.into_iter().map(|[si, pi]| (si, pi)));
+    }
+
     #[inline]
     fn igw_hook_remove_indexed(&mut self, modified: &Option<[T::Index; 3]>) {
         if let Some([si, pi, oi]) = *modified {
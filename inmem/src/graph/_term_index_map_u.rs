@@ -3,7 +3,7 @@ use std::borrow::Borrow;
 use std::collections::HashMap;
 
 use sophia_term::factory::{FTerm, TermFactory};
-use sophia_term::index_map::TermIndexMap;
+use sophia_term::index_map::{TermIndexFullError, TermIndexMap};
 use sophia_term::*;
 
 /// An in-memory implementation of [`TermIndexMap`]
@@ -27,7 +27,8 @@ where
     //
     // 0 is the null_index (index not mapped to any Term), so
     // - i2t[0] is initialized to None but never used,
-    // - i2c[0] is used to store the first free index
+    // - i2c[0] is used to store the first free index,
+    //   or 0 if all the values of I are already used
 }
 
 // Implementation note:
@@ -79,7 +80,8 @@ where
     }
 
     fn inc_next_free(&mut self) {
-        self.i2c[0].inc()
+        let next = self.i2c[0].checked_inc().unwrap_or(I::ZERO);
+        self.set_next_free(next)
     }
 }
 
@@ -102,14 +104,17 @@ where
         self.t2i.get(t).cloned()
     }
 
-    fn make_index(&mut self, t: &RefTerm) -> T {
-        let t = self.factory.clone_term(t);
-        let rt = unsafe { fake_static(&t) };
-        if let Some(i) = self.get_index(&rt) {
-            self.i2c[i.as_usize()].inc();
-            return i;
+    fn make_index(&mut self, t: &RefTerm) -> Result<T, TermIndexFullError> {
+        if let Some(i) = self.get_index(t) {
+            self.inc_ref(i)?;
+            return Ok(i);
         }
         let i = self.next_free();
+        if i == T::ZERO {
+            return Err(TermIndexFullError);
+        }
+        let t = self.factory.clone_term(t);
+        let rt = unsafe { fake_static(&t) };
         self.t2i.insert(rt, i);
         let i = i.as_usize();
         if i == self.i2t.len() {
@@ -121,7 +126,7 @@ where
             self.i2t[i] = Some(t);
             self.i2c[i] = T::ONE;
         }
-        T::from_usize(i)
+        Ok(T::from_usize(i))
     }
 
     fn get_term(&self, i: T) -> Option<&FTerm<F>> {
@@ -133,12 +138,13 @@ where
         }
     }
 
-    fn inc_ref(&mut self, i: T) {
+    fn inc_ref(&mut self, i: T) -> Result<(), TermIndexFullError> {
         if i == Self::NULL_INDEX {
-            return;
+            return Ok(());
         }
-        let i = i.as_usize();
-        self.i2c[i].inc();
+        let c = &mut self.i2c[i.as_usize()];
+        *c = c.checked_inc().ok_or(TermIndexFullError)?;
+        Ok(())
     }
 
     fn dec_ref(&mut self, i: T) {
@@ -185,6 +191,9 @@ pub trait Unsigned: Copy + Eq + std::hash::Hash {
 
     /// Decrement the value.
     fn dec(&mut self);
+
+    /// Return the incremented value, or `None` if the value is already the maximum.
+    fn checked_inc(&self) -> Option<Self>;
 }
 
 macro_rules! impl_unsigned_for {
@@ -209,6 +218,10 @@ macro_rules! impl_unsigned_for {
             fn dec(&mut self) {
                 *self -= 1
             }
+            #[inline]
+            fn checked_inc(&self) -> Option<Self> {
+                self.checked_add(1)
+            }
         }
     };
 }
@@ -251,30 +264,30 @@ mod test {
         use sophia_api::ns::rdf;
 
         assert_eq!(ti.get_index(&rdf::subject.into()), None);
-        assert_eq!(ti.make_index(&rdf::subject.into()), 1);
+        assert_eq!(ti.make_index(&rdf::subject.into()).unwrap(), 1);
         assert_eq!(ti.get_index(&rdf::subject.into()), Some(1));
         assert_eq!(ti.i2c[1], 1);
         assert_eq!(ti.next_free(), 2);
         assert_eq!(ti.i2t.len(), 2);
 
         assert_eq!(ti.get_index(&rdf::predicate.into()), None);
-        assert_eq!(ti.make_index(&rdf::predicate.into()), 2);
+        assert_eq!(ti.make_index(&rdf::predicate.into()).unwrap(), 2);
         assert_eq!(ti.get_index(&rdf::predicate.into()), Some(2));
         assert_eq!(ti.i2c[2], 1);
         assert_eq!(ti.next_free(), 3);
         assert_eq!(ti.i2t.len(), 3);
 
         assert_eq!(ti.get_index(&rdf::object.into()), None);
-        assert_eq!(ti.make_index(&rdf::object.into()), 3);
+        assert_eq!(ti.make_index(&rdf::object.into()).unwrap(), 3);
         assert_eq!(ti.get_index(&rdf::object.into()), Some(3));
         assert_eq!(ti.i2c[3], 1);
         assert_eq!(ti.next_free(), 4);
         assert_eq!(ti.i2t.len(), 4);
 
-        assert_eq!(ti.make_index(&rdf::predicate.into()), 2);
+        assert_eq!(ti.make_index(&rdf::predicate.into()).unwrap(), 2);
         assert_eq!(ti.i2c[2], 2);
 
-        ti.inc_ref(2);
+        ti.inc_ref(2).unwrap();
         assert_eq!(ti.i2c[2], 3);
 
         ti.dec_ref(2);
@@ -295,20 +308,57 @@ mod test {
         assert_eq!(ti.next_free(), 1);
         assert_eq!(ti.i2c[1], 2); // now the previous version of next_free()
 
-        assert_eq!(ti.make_index(&rdf::type_.into()), 1);
+        assert_eq!(ti.make_index(&rdf::type_.into()).unwrap(), 1);
         assert_eq!(ti.i2c[1], 1);
         assert_eq!(ti.i2t.len(), 4);
         assert_eq!(ti.next_free(), 2);
 
         // re-inserting rdf::subject, now ends up in a different place
-        assert_eq!(ti.make_index(&rdf::subject.into()), 2);
+        assert_eq!(ti.make_index(&rdf::subject.into()).unwrap(), 2);
         assert_eq!(ti.i2c[2], 1);
         assert_eq!(ti.i2t.len(), 4);
         assert_eq!(ti.next_free(), 4);
 
-        assert_eq!(ti.make_index(&rdf::Property.into()), 4);
+        assert_eq!(ti.make_index(&rdf::Property.into()).unwrap(), 4);
         assert_eq!(ti.i2c[4], 1);
         assert_eq!(ti.i2t.len(), 5);
         assert_eq!(ti.next_free(), 5);
     }
+
+    #[test]
+    fn test_term_index_full() {
+        let mut ti = TermIndexMapU::<u16, RcTermFactory>::default();
+        let ns = "http://example.org/";
+        for i in 1..=u16::MAX {
+            let suffix = format!("{}", i);
+            let t = RefTerm::new_iri_suffixed(ns, &suffix[..]).unwrap();
+            assert_eq!(ti.make_index(&t), Ok(i));
+        }
+        assert_eq!(ti.next_free(), 0);
+        let t = RefTerm::new_iri_suffixed(ns, "0").unwrap();
+        assert_eq!(ti.make_index(&t), Err(TermIndexFullError));
+
+        // freeing an index makes room for exactly one new term
+        ti.dec_ref(42);
+        assert_eq!(ti.make_index(&t), Ok(42));
+        assert_eq!(ti.next_free(), 0);
+        let t = RefTerm::new_iri_suffixed(ns, "42").unwrap();
+        assert_eq!(ti.make_index(&t), Err(TermIndexFullError));
+    }
+
+    #[test]
+    fn test_term_index_ref_count_full() {
+        use sophia_api::ns::rdf;
+
+        let mut ti = TermIndexMapU::<u16, RcTermFactory>::default();
+        let t = RefTerm::from(&rdf::type_);
+        for _ in 0..u16::MAX {
+            assert_eq!(ti.make_index(&t), Ok(1));
+        }
+        assert_eq!(ti.make_index(&t), Err(TermIndexFullError));
+        assert_eq!(ti.inc_ref(1), Err(TermIndexFullError));
+        ti.dec_ref(1);
+        assert_eq!(ti.inc_ref(1), Ok(()));
+        assert_eq!(ti.i2c[1], u16::MAX);
+    }
 }
@@ -390,6 +390,18 @@ where
     /// [`IndexedGraph::insert_indexed`].
     fn igw_hook_insert_indexed(&mut self, modified: &Option<[T::Index; 3]>);
 
+    /// Hook to be executed at the end of
+    /// [`IndexedGraph::insert_all_indices`].
+    ///
+    /// The default implementation calls
+    /// [`igw_hook_insert_indexed`](IndexedGraphWrapper::igw_hook_insert_indexed)
+    /// for each inserted tuple.
+    fn igw_hook_insert_all_indexed(&mut self, inserted: &[[T::Index; 3]]) {
+        for t in inserted {
+            self.igw_hook_insert_indexed(&Some(*t));
+        }
+    }
+
     /// Hook to be executed at the end of
     /// [`IndexedGraph::remove_indexed`].
     fn igw_hook_remove_indexed(&mut self, modified: &Option<[T::Index; 3]>);
@@ -430,20 +442,59 @@ macro_rules! impl_indexed_graph_for_wrapper {
             self.get_wrapped().get_term(i)
         }
 
+        #[inline]
+        fn make_index<U_>(
+            &mut self,
+            t: &U_,
+        ) -> Result<Self::Index, sophia_term::index_map::TermIndexFullError>
+        where
+            U_: sophia_api::term::TTerm + ?Sized,
+        {
+            self.get_wrapped_mut().make_index(t)
+        }
+
+        #[inline]
+        fn dec_ref(&mut self, i: Self::Index) {
+            self.get_wrapped_mut().dec_ref(i)
+        }
+
+        #[inline]
+        fn indexed_triples(&self) -> Box<dyn Iterator<Item = [Self::Index; 3]> + '_> {
+            self.get_wrapped().indexed_triples()
+        }
+
         fn insert_indexed<TS_, TP_, TO_>(
             &mut self,
             s: &TS_,
             p: &TP_,
             o: &TO_,
-        ) -> Option<[Self::Index; 3]>
+        ) -> Result<Option<[Self::Index; 3]>, sophia_term::index_map::TermIndexFullError>
         where
             TS_: sophia_api::term::TTerm + ?Sized,
             TP_: sophia_api::term::TTerm + ?Sized,
             TO_: sophia_api::term::TTerm + ?Sized,
         {
-            let modified = self.get_wrapped_mut().insert_indexed(s, p, o);
+            let modified = self.get_wrapped_mut().insert_indexed(s, p, o)?;
             self.igw_hook_insert_indexed(&modified);
-            modified
+            Ok(modified)
+        }
+
+        fn insert_indices(
+            &mut self,
+            t: [Self::Index; 3],
+        ) -> Result<Option<[Self::Index; 3]>, sophia_term::index_map::TermIndexFullError> {
+            let modified = self.get_wrapped_mut().insert_indices(t)?;
+            self.igw_hook_insert_indexed(&modified);
+            Ok(modified)
+        }
+
+        fn insert_all_indices(
+            &mut self,
+            triples: Vec<[Self::Index; 3]>,
+        ) -> Result<Vec<[Self::Index; 3]>, sophia_term::index_map::TermIndexFullError> {
+            let inserted = self.get_wrapped_mut().insert_all_indices(triples)?;
+            self.igw_hook_insert_all_indexed(&inserted);
+            Ok(inserted)
         }
 
         fn remove_indexed<TS_, TP_, TO_>(
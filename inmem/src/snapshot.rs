@@ -0,0 +1,644 @@
+//! Binary snapshots of in-memory graphs and datasets.
+//!
+//! A snapshot contains the terms of a graph (or dataset),
+//! each of them stored only once,
+//! and its triples (or quads) as tuples of integer identifiers.
+//! Loading a snapshot is therefore much faster than parsing a textual RDF format:
+//! no syntax needs to be parsed, no IRI needs to be checked,
+//! and each term is hashed only once, regardless of the number of triples using it.
+//!
+//! Note however that the term dictionary and the indexes of the graph are *not* stored as such,
+//! as the hash tables they are made of can not be persisted:
+//! they are rebuilt when loading the snapshot,
+//! the triples being inserted in a single batch with [`IndexedGraph::insert_all_indices`]
+//! (resp. [`IndexedDataset::insert_all_indices`]).
+//!
+//! ```
+//! use sophia_api::graph::{Graph, MutableGraph};
+//! use sophia_api::ns::rdf;
+//! use sophia_inmem::graph::FastGraph;
+//! use sophia_inmem::snapshot::{load_graph, save_graph};
+//!
+//! let mut g = FastGraph::new();
+//! g.insert(&rdf::type_, &rdf::type_, &rdf::Property)?;
+//!
+//! let mut buffer = vec![];
+//! save_graph(&g, &mut buffer)?;
+//! let g2: FastGraph = load_graph(&buffer[..])?;
+//! assert_eq!(g2.triples().count(), 1);
+//! # Ok::<(), Box<dyn std::error::Error>>(())
+//! ```
+//!
+//! # Format
+//!
+//! All integers are encoded as unsigned [LEB128](https://en.wikipedia.org/wiki/LEB128),
+//! and all strings as their length followed by their UTF-8 bytes.
+//! A snapshot is made of
+//! * the magic number `SOPHSNAP`,
+//! * the [version](VERSION) of the format (one byte),
+//! * the arity (one byte: 3 for graphs, 4 for datasets),
+//! * the number of terms,
+//! * the number of triples (resp. quads),
+//! * an 8-bytes little-endian checksum of all the preceding bytes,
+//!   so that the counts above can be checked before they are used,
+//! * the terms, each of them introduced by a one-byte tag,
+//! * the triples (resp. quads), as tuples of term identifiers
+//!   (starting at 1, 0 being used for the default graph),
+//! * an 8-bytes little-endian [FNV-1a](https://en.wikipedia.org/wiki/Fowler%E2%80%93Noll%E2%80%93Vo_hash_function)
+//!   checksum of all the preceding bytes.
+//!
+//! # Trust
+//!
+//! The validity of terms is *not* checked when loading a snapshot,
+//! so snapshots should only be loaded from trusted sources.
+//! The checksums only protect against accidental corruption;
+//! still, the counts are never trusted to pre-allocate more than
+//! a bounded amount of memory.
+
+use std::collections::HashMap;
+use std::hash::Hash;
+use std::io::{self, BufRead, BufWriter, Read, Write};
+
+use sophia_api::term::{TTerm, TermKind};
+use sophia_indexed::dataset::IndexedDataset;
+use sophia_indexed::graph::IndexedGraph;
+use sophia_term::index_map::TermIndexFullError;
+use sophia_term::{RefTerm, Term, TermData};
+use thiserror::Error;
+
+/// The magic number starting every snapshot.
+pub const MAGIC: &[u8; 8] = b"SOPHSNAP";
+
+/// The version of the snapshot format produced by this module.
+pub const VERSION: u8 = 1;
+
+const TAG_IRI: u8 = 1;
+const TAG_BNODE: u8 = 2;
+const TAG_LITERAL_LANG: u8 = 3;
+const TAG_LITERAL_DT: u8 = 4;
+const TAG_VARIABLE: u8 = 5;
+
+/// The maximum number of items (terms or tuples) pre-allocated from the counts of a snapshot.
+const MAX_PREALLOC: usize = 1 << 20;
+
+/// An error raised while loading a snapshot.
+#[derive(Debug, Error)]
+pub enum SnapshotError {
+    /// The underlying reader failed.
+    #[error("I/O error: {0}")]
+    Io(#[from] io::Error),
+    /// The data does not start with the [magic number](MAGIC).
+    #[error("Not a sophia snapshot")]
+    BadMagic,
+    /// The snapshot was produced with an unsupported version of the format.
+    #[error("Unsupported snapshot version {0}")]
+    UnsupportedVersion(u8),
+    /// The snapshot contains triples while quads are expected, or vice-versa.
+    #[error("Expected a snapshot of arity {expected}, found {found}")]
+    WrongArity {
+        /// The expected arity (3 for graphs, 4 for datasets).
+        expected: u8,
+        /// The arity of the snapshot.
+        found: u8,
+    },
+    /// The snapshot is malformed.
+    #[error("Malformed snapshot: {0}")]
+    Malformed(String),
+    /// The checksum of the snapshot does not match its content.
+    #[error("Snapshot checksum mismatch")]
+    ChecksumMismatch,
+    /// The target graph or dataset can not contain all the terms of the snapshot.
+    #[error("{0}")]
+    Full(#[from] TermIndexFullError),
+}
+
+/// Write a snapshot of `graph` into `write`.
+pub fn save_graph<G, W>(graph: &G, write: W) -> io::Result<()>
+where
+    G: IndexedGraph,
+    W: Write,
+{
+    save(
+        3,
+        || graph.indexed_triples(),
+        |i| Some(graph.get_term(i).expect("index should be valid")),
+        write,
+    )
+}
+
+/// Load a graph from a snapshot produced by [`save_graph`].
+///
+/// Nothing is read from `read` past the end of the snapshot,
+/// so that any data following it remains available to the caller.
+pub fn load_graph<G, R>(read: R) -> Result<G, SnapshotError>
+where
+    G: IndexedGraph,
+    R: BufRead,
+{
+    let mut r = SnapshotReader::new(read, 3)?;
+    let (term_count, triple_count) = r.read_counts()?;
+    let mut g = G::with_capacity(triple_count.min(MAX_PREALLOC));
+    let mut indices = Vec::with_capacity(term_count.min(MAX_PREALLOC));
+    for _ in 0..term_count {
+        let i = r.read_term(|t| g.make_index(t))??;
+        indices.push(i);
+    }
+    let mut triples = Vec::with_capacity(triple_count.min(MAX_PREALLOC));
+    for _ in 0..triple_count {
+        let s = r.read_id(&indices, None)?;
+        let p = r.read_id(&indices, None)?;
+        let o = r.read_id(&indices, None)?;
+        triples.push([s, p, o]);
+    }
+    r.check_sum()?;
+    g.insert_all_indices(triples)?;
+    // release the refs acquired by make_index
+    for i in indices {
+        g.dec_ref(i);
+    }
+    g.shrink_to_fit();
+    Ok(g)
+}
+
+/// Write a snapshot of `dataset` into `write`.
+pub fn save_dataset<D, W>(dataset: &D, write: W) -> io::Result<()>
+where
+    D: IndexedDataset,
+    W: Write,
+{
+    save(
+        4,
+        || dataset.indexed_quads(),
+        |i| dataset.get_graph_name(i).expect("index should be valid"),
+        write,
+    )
+}
+
+/// Load a dataset from a snapshot produced by [`save_dataset`].
+///
+/// Nothing is read from `read` past the end of the snapshot,
+/// so that any data following it remains available to the caller.
+pub fn load_dataset<D, R>(read: R) -> Result<D, SnapshotError>
+where
+    D: IndexedDataset,
+    R: BufRead,
+{
+    let mut r = SnapshotReader::new(read, 4)?;
+    let (term_count, quad_count) = r.read_counts()?;
+    let mut d = D::with_capacity(quad_count.min(MAX_PREALLOC));
+    let default_graph = d
+        .get_index_for_graph_name(None as Option<&RefTerm>)
+        .expect("default graph should always have an index");
+    let mut indices = Vec::with_capacity(term_count.min(MAX_PREALLOC));
+    for _ in 0..term_count {
+        let i = r.read_term(|t| d.make_index(t))??;
+        indices.push(i);
+    }
+    let mut quads = Vec::with_capacity(quad_count.min(MAX_PREALLOC));
+    for _ in 0..quad_count {
+        let s = r.read_id(&indices, None)?;
+        let p = r.read_id(&indices, None)?;
+        let o = r.read_id(&indices, None)?;
+        let g = r.read_id(&indices, Some(default_graph))?;
+        quads.push([s, p, o, g]);
+    }
+    r.check_sum()?;
+    d.insert_all_indices(quads)?;
+    // release the refs acquired by make_index
+    for i in indices {
+        d.dec_ref(i);
+    }
+    d.shrink_to_fit();
+    Ok(d)
+}
+
+/// Write a snapshot of the given tuples into `write`.
+///
+/// `get_term` returns `None` for the default graph.
+fn save<'a, I, TD, It, F, W, const N: usize>(
+    arity: u8,
+    tuples: impl Fn() -> It,
+    get_term: F,
+    write: W,
+) -> io::Result<()>
+where
+    I: Copy + Eq + Hash,
+    TD: TermData + 'a,
+    It: Iterator<Item = [I; N]>,
+    F: Fn(I) -> Option<&'a Term<TD>>,
+    W: Write,
+{
+    // first pass: assign an identifier to each term
+    let mut ids: HashMap<I, u64> = HashMap::new();
+    let mut terms = vec![];
+    let mut tuple_count = 0;
+    for tuple in tuples() {
+        tuple_count += 1;
+        for i in tuple {
+            if let Some(t) = get_term(i) {
+                ids.entry(i).or_insert_with(|| {
+                    terms.push(t);
+                    terms.len() as u64
+                });
+            } else {
+                ids.insert(i, 0);
+            }
+        }
+    }
+
+    // second pass: write everything
+    let mut w = SnapshotWriter::new(write);
+    w.write_all(MAGIC)?;
+    w.write_all(&[VERSION, arity])?;
+    w.write_u64(terms.len() as u64)?;
+    w.write_u64(tuple_count)?;
+    w.write_sum()?;
+    for t in terms {
+        w.write_term(t)?;
+    }
+    for tuple in tuples() {
+        for i in tuple {
+            w.write_u64(ids[&i])?;
+        }
+    }
+    w.finish()
+}
+
+/// Computes the 64-bits FNV-1a hash of a byte stream.
+struct Fnv64(u64);
+
+impl Fnv64 {
+    fn new() -> Self {
+        Fnv64(0xcbf2_9ce4_8422_2325)
+    }
+
+    fn update(&mut self, bytes: &[u8]) {
+        for b in bytes {
+            self.0 ^= u64::from(*b);
+            self.0 = self.0.wrapping_mul(0x0100_0000_01b3);
+        }
+    }
+}
+
+struct SnapshotWriter<W: Write> {
+    write: BufWriter<W>,
+    hash: Fnv64,
+}
+
+impl<W: Write> SnapshotWriter<W> {
+    fn new(write: W) -> Self {
+        SnapshotWriter {
+            write: BufWriter::new(write),
+            hash: Fnv64::new(),
+        }
+    }
+
+    fn write_all(&mut self, bytes: &[u8]) -> io::Result<()> {
+        self.hash.update(bytes);
+        self.write.write_all(bytes)
+    }
+
+    fn write_u64(&mut self, mut n: u64) -> io::Result<()> {
+        let mut buf = [0; 10];
+        let mut len = 0;
+        loop {
+            let b = (n & 0x7f) as u8;
+            n >>= 7;
+            if n == 0 {
+                buf[len] = b;
+                len += 1;
+                break;
+            }
+            buf[len] = b | 0x80;
+            len += 1;
+        }
+        self.write_all(&buf[..len])
+    }
+
+    fn write_str(&mut self, txt: &str) -> io::Result<()> {
+        self.write_u64(txt.len() as u64)?;
+        self.write_all(txt.as_bytes())
+    }
+
+    fn write_term<TD: TermData>(&mut self, t: &Term<TD>) -> io::Result<()> {
+        match t.kind() {
+            TermKind::Iri => {
+                self.write_all(&[TAG_IRI])?;
+                self.write_str(&t.value())
+            }
+            TermKind::BlankNode => {
+                self.write_all(&[TAG_BNODE])?;
+                self.write_str(&t.value())
+            }
+            TermKind::Literal => match t.language() {
+                Some(tag) => {
+                    self.write_all(&[TAG_LITERAL_LANG])?;
+                    self.write_str(&t.value())?;
+                    self.write_str(tag)
+                }
+                None => {
+                    self.write_all(&[TAG_LITERAL_DT])?;
+                    self.write_str(&t.value())?;
+                    self.write_str(&t.datatype().unwrap().value())
+                }
+            },
+            TermKind::Variable => {
+                self.write_all(&[TAG_VARIABLE])?;
+                self.write_str(&t.value())
+            }
+        }
+    }
+
+    /// Write the checksum of the data written so far.
+    fn write_sum(&mut self) -> io::Result<()> {
+        let checksum = self.hash.0.to_le_bytes();
+        self.write_all(&checksum)
+    }
+
+    fn finish(mut self) -> io::Result<()> {
+        let checksum = self.hash.0.to_le_bytes();
+        self.write.write_all(&checksum)?;
+        self.write.flush()
+    }
+}
+
+struct SnapshotReader<R: BufRead> {
+    read: R,
+    hash: Fnv64,
+}
+
+impl<R: BufRead> SnapshotReader<R> {
+    /// Build a reader, after checking the header of the snapshot.
+    fn new(read: R, arity: u8) -> Result<Self, SnapshotError> {
+        let mut r = SnapshotReader {
+            read,
+            hash: Fnv64::new(),
+        };
+        let mut header = [0; 10];
+        r.read_exact(&mut header)?;
+        if &header[..8] != MAGIC {
+            return Err(SnapshotError::BadMagic);
+        }
+        if header[8] != VERSION {
+            return Err(SnapshotError::UnsupportedVersion(header[8]));
+        }
+        if header[9] != arity {
+            return Err(SnapshotError::WrongArity {
+                expected: arity,
+                found: header[9],
+            });
+        }
+        Ok(r)
+    }
+
+    fn read_exact(&mut self, bytes: &mut [u8]) -> Result<(), SnapshotError> {
+        self.read.read_exact(bytes)?;
+        self.hash.update(bytes);
+        Ok(())
+    }
+
+    fn read_u8(&mut self) -> Result<u8, SnapshotError> {
+        let mut b = [0];
+        self.read_exact(&mut b)?;
+        Ok(b[0])
+    }
+
+    fn read_u64(&mut self) -> Result<u64, SnapshotError> {
+        let mut n = 0;
+        for shift in (0..64).step_by(7) {
+            let b = self.read_u8()?;
+            n |= u64::from(b & 0x7f) << shift;
+            if b & 0x80 == 0 {
+                return Ok(n);
+            }
+        }
+        Err(SnapshotError::Malformed("integer overflow".into()))
+    }
+
+    fn read_usize(&mut self) -> Result<usize, SnapshotError> {
+        usize::try_from(self.read_u64()?)
+            .map_err(|_| SnapshotError::Malformed("integer overflow".into()))
+    }
+
+    /// Read the number of terms and the number of tuples,
+    /// and check them against the header checksum.
+    fn read_counts(&mut self) -> Result<(usize, usize), SnapshotError> {
+        let counts = (self.read_usize()?, self.read_usize()?);
+        let expected = self.hash.0;
+        let mut checksum = [0; 8];
+        self.read_exact(&mut checksum)?;
+        if u64::from_le_bytes(checksum) != expected {
+            return Err(SnapshotError::ChecksumMismatch);
+        }
+        Ok(counts)
+    }
+
+    fn read_str(&mut self) -> Result<String, SnapshotError> {
+        let len = self.read_u64()?;
+        // the length is not trusted to pre-allocate the whole buffer
+        let mut bytes = Vec::with_capacity(len.min(4096) as usize);
+        let read = (&mut self.read).take(len).read_to_end(&mut bytes)?;
+        if (read as u64) < len {
+            return Err(io::Error::from(io::ErrorKind::UnexpectedEof).into());
+        }
+        self.hash.update(&bytes);
+        String::from_utf8(bytes).map_err(|_| SnapshotError::Malformed("invalid UTF-8".into()))
+    }
+
+    /// Read a term, and pass it to `f`.
+    fn read_term<T, F>(&mut self, f: F) -> Result<T, SnapshotError>
+    where
+        F: FnOnce(&RefTerm) -> T,
+    {
+        let tag = self.read_u8()?;
+        let txt = self.read_str()?;
+        let aux = if tag == TAG_LITERAL_LANG || tag == TAG_LITERAL_DT {
+            self.read_str()?
+        } else {
+            String::new()
+        };
+        let (txt, aux) = (txt.as_str(), aux.as_str());
+        let t: RefTerm = match tag {
+            TAG_IRI => RefTerm::new_iri_unchecked(txt),
+            TAG_BNODE => RefTerm::new_bnode_unchecked(txt),
+            TAG_LITERAL_LANG => RefTerm::new_literal_lang_unchecked(txt, aux),
+            TAG_LITERAL_DT => {
+                RefTerm::new_literal_dt_unchecked(txt, RefTerm::new_iri_unchecked(aux))
+            }
+            TAG_VARIABLE => RefTerm::new_variable_unchecked(txt),
+            _ => {
+                return Err(SnapshotError::Malformed(format!(
+                    "unknown term tag {}",
+                    tag
+                )))
+            }
+        };
+        Ok(f(&t))
+    }
+
+    /// Read a term identifier, and return the corresponding index.
+    ///
+    /// `default` is the index to return for identifier 0, if allowed.
+    fn read_id<I: Copy>(&mut self, indices: &[I], default: Option<I>) -> Result<I, SnapshotError> {
+        let id = self.read_usize()?;
+        match id {
+            0 => default,
+            _ => indices.get(id - 1).copied(),
+        }
+        .ok_or_else(|| SnapshotError::Malformed(format!("invalid term identifier {}", id)))
+    }
+
+    /// Check that the checksum matches the data read so far.
+    fn check_sum(&mut self) -> Result<(), SnapshotError> {
+        let mut checksum = [0; 8];
+        self.read.read_exact(&mut checksum)?;
+        if u64::from_le_bytes(checksum) != self.hash.0 {
+            return Err(SnapshotError::ChecksumMismatch);
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::dataset::FastDataset;
+    use crate::graph::{FastGraph, LightGraph};
+    use sophia_api::dataset::{isomorphic_datasets, MutableDataset};
+    use sophia_api::graph::{isomorphic_graphs, Graph, MutableGraph};
+    use sophia_api::ns::{rdf, xsd};
+    use sophia_term::BoxTerm;
+
+    fn some_terms() -> Vec<BoxTerm> {
+        vec![
+            BoxTerm::new_iri("http://example.org/é").unwrap(),
+            BoxTerm::new_bnode("b1").unwrap(),
+            BoxTerm::new_literal_lang("chat", "fr").unwrap(),
+            BoxTerm::new_literal_dt("42", xsd::integer).unwrap(),
+            BoxTerm::new_literal_dt("", xsd::string).unwrap(),
+        ]
+    }
+
+    #[test]
+    fn graph_round_trip() -> Result<(), Box<dyn std::error::Error>> {
+        let mut g = FastGraph::new();
+        for t in some_terms() {
+            g.insert(&rdf::subject, &rdf::value, &t)?;
+            g.insert(&t, &rdf::type_, &rdf::Property)?;
+        }
+        let mut buf = vec![];
+        save_graph(&g, &mut buf)?;
+        let g2: FastGraph = load_graph(&buf[..])?;
+        assert!(isomorphic_graphs(&g, &g2)?);
+        // snapshots are independent of the implementation
+        let g3: LightGraph = load_graph(&buf[..])?;
+        assert!(isomorphic_graphs(&g, &g3)?);
+        Ok(())
+    }
+
+    #[test]
+    fn dataset_round_trip() -> Result<(), Box<dyn std::error::Error>> {
+        let mut d = FastDataset::new();
+        for t in some_terms() {
+            d.insert(&rdf::subject, &rdf::value, &t, None as Option<&BoxTerm>)?;
+            d.insert(&rdf::subject, &rdf::value, &t, Some(&rdf::nil))?;
+        }
+        let mut buf = vec![];
+        save_dataset(&d, &mut buf)?;
+        let d2: FastDataset = load_dataset(&buf[..])?;
+        assert!(isomorphic_datasets(&d, &d2)?);
+        Ok(())
+    }
+
+    #[test]
+    fn no_read_past_end() -> Result<(), Box<dyn std::error::Error>> {
+        let mut g = FastGraph::new();
+        g.insert(&rdf::subject, &rdf::value, &rdf::nil)?;
+        let mut buf = vec![];
+        save_graph(&g, &mut buf)?;
+        save_graph(&FastGraph::new(), &mut buf)?;
+        buf.extend_from_slice(b"trailing");
+        let mut read = io::BufReader::new(&buf[..]);
+        let g1: FastGraph = load_graph(&mut read)?;
+        assert!(isomorphic_graphs(&g, &g1)?);
+        let g2: FastGraph = load_graph(&mut read)?;
+        assert_eq!(g2.triples().count(), 0);
+        let mut rest = vec![];
+        read.read_to_end(&mut rest)?;
+        assert_eq!(rest, b"trailing");
+        Ok(())
+    }
+
+    #[test]
+    fn empty_round_trip() -> Result<(), Box<dyn std::error::Error>> {
+        let mut buf = vec![];
+        save_graph(&FastGraph::new(), &mut buf)?;
+        let g: FastGraph = load_graph(&buf[..])?;
+        assert_eq!(g.triples().count(), 0);
+        Ok(())
+    }
+
+    #[test]
+    fn corrupted() -> Result<(), Box<dyn std::error::Error>> {
+        let mut g = FastGraph::new();
+        g.insert(&rdf::subject, &rdf::value, &rdf::nil)?;
+        let mut buf = vec![];
+        save_graph(&g, &mut buf)?;
+
+        let mut bad = buf.clone();
+        bad[0] = b'X';
+        assert!(matches!(
+            load_graph::<FastGraph, _>(&bad[..]),
+            Err(SnapshotError::BadMagic)
+        ));
+
+        assert!(matches!(
+            load_dataset::<FastDataset, _>(&buf[..]),
+            Err(SnapshotError::WrongArity {
+                expected: 4,
+                found: 3
+            })
+        ));
+
+        let mut bad = buf.clone();
+        let last = bad.len() - 9;
+        bad[last] ^= 1;
+        assert!(matches!(
+            load_graph::<FastGraph, _>(&bad[..]),
+            Err(SnapshotError::ChecksumMismatch | SnapshotError::Malformed(_))
+        ));
+
+        // a huge count must not be trusted before the header checksum is checked
+        let mut bad = buf[..10].to_vec();
+        bad.extend([0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0x7f]);
+        bad.extend(&buf[11..]);
+        assert!(matches!(
+            load_graph::<FastGraph, _>(&bad[..]),
+            Err(SnapshotError::ChecksumMismatch)
+        ));
+
+        // nor when the header checksum is correct
+        let mut bad = buf[..10].to_vec();
+        let mut w = SnapshotWriter::new(&mut bad);
+        w.hash.update(MAGIC);
+        w.hash.update(&[VERSION, 3]);
+        w.write_u64(u64::MAX >> 1)?;
+        w.write_u64(u64::MAX >> 1)?;
+        w.write_sum()?;
+        w.write_all(&[TAG_IRI])?;
+        w.write_u64(u64::MAX >> 1)?;
+        w.write_all(b"http://example.org/")?;
+        drop(w);
+        assert!(matches!(
+            load_graph::<FastGraph, _>(&bad[..]),
+            Err(SnapshotError::Io(_))
+        ));
+
+        let truncated = &buf[..buf.len() - 1];
+        assert!(matches!(
+            load_graph::<FastGraph, _>(truncated),
+            Err(SnapshotError::Io(_))
+        ));
+        Ok(())
+    }
+}
@@ -1,5 +1,6 @@
 //! Implementation of IRI resolution as per
-//! [\[RFC 3987\]](https://tools.ietf.org/html/rfc3987).
+//! [\[RFC 3987\]](https://tools.ietf.org/html/rfc3987),
+//! and of its inverse operation, IRI relativization.
 
 use super::{error::*, *};
 use mownstr::MownStr;
@@ -12,6 +13,16 @@ pub trait Resolve<S, T> {
     fn resolve(&self, other: S) -> T;
 }
 
+/// Make some kind of IRI relative to `self` as the base.
+///
+/// This is the inverse of [`Resolve`]:
+/// resolving the result with `self` as the base yields the original IRI.
+pub trait Relativize<S, T> {
+    /// Make the IRI(s) somewhat contained in `other` relative to `self` as
+    /// the base IRI, if possible.
+    fn relativize(&self, other: S) -> T;
+}
+
 /// Keeps track of the different components of an IRI reference.
 ///
 /// NB: this type does not store the actual text of the IRI reference,
@@ -113,6 +124,60 @@ impl<'a> IriParsed<'a> {
         }
     }
 
+    /// Compute a relative IRI reference that, resolved with this IRI as the base,
+    /// yields `other`.
+    ///
+    /// Return `None` if `other` has a different scheme or authority than `self`,
+    /// or if no relative IRI reference can yield it (e.g. if it contains dot-segments).
+    pub fn make_relative(&self, other: &IriParsed<'a>) -> Option<IriParsed<'a>> {
+        if !self.is_absolute() || self.scheme != other.scheme {
+            return None;
+        }
+        let mut rel = IriParsed {
+            scheme: None,
+            authority: None,
+            path: vec![],
+            query: other.query,
+            fragment: other.fragment,
+        };
+        if self.authority != other.authority {
+            return None;
+        } else if self.path == other.path && (self.query == other.query || other.query.is_some()) {
+            // same-document or query-only reference
+            if self.query == other.query {
+                rel.query = None;
+            }
+        } else if other.path.is_empty() {
+            return None;
+        } else if self.path.is_empty() {
+            // absolute-path reference
+            rel.path = other.path.clone();
+        } else {
+            let base_dir = &self.path[..self.path.len() - 1];
+            let other_dir = &other.path[..other.path.len() - 1];
+            let common = base_dir
+                .iter()
+                .zip(other_dir)
+                .take_while(|(s1, s2)| s1 == s2)
+                .count();
+            if common == 0 && other.path[0].is_empty() {
+                // absolute-path reference
+                rel.path = other.path.clone();
+            } else {
+                // relative-path reference
+                rel.path = std::iter::repeat_n("..", base_dir.len() - common)
+                    .chain(other.path[common..].iter().cloned())
+                    .collect();
+                if rel.path[0].is_empty() || rel.path[0].contains(':') {
+                    // would be mistaken for an absolute path or a scheme
+                    rel.path.insert(0, ".");
+                }
+            }
+        }
+        // check the result, to rule out corner cases (e.g. dot-segments in `other`)
+        (self.join(&rel).to_string() == other.to_string()).then_some(rel)
+    }
+
     /// Appends the given path to `self`'s own path.
     fn merged_path(&self, path: &[&'a str]) -> Vec<&'a str> {
         if self.authority.is_some() && self.path.is_empty() {
@@ -174,6 +239,24 @@ impl<'a, 'b> Resolve<&'a str, Result<MownStr<'a>>> for IriParsed<'b> {
     }
 }
 
+impl<'a> Relativize<&IriParsed<'a>, Option<IriParsed<'a>>> for IriParsed<'a> {
+    /// Just a call to `IriParsed::make_relative()`
+    fn relativize(&self, other: &IriParsed<'a>) -> Option<IriParsed<'a>> {
+        self.make_relative(other)
+    }
+}
+
+impl<'a, 'b> Relativize<&'a str, Option<String>> for IriParsed<'b> {
+    /// Relativize an IRI given as `str`.
+    ///
+    /// Returns `None` if `other` is not a valid IRI,
+    /// or can not be made relative to `self`.
+    fn relativize(&self, other: &'a str) -> Option<String> {
+        let other_parsed = IriParsed::new(other).ok()?;
+        self.make_relative(&other_parsed).map(|rel| rel.to_string())
+    }
+}
+
 fn remove_dot_segments(path: &mut Vec<&str>) {
     if path.is_empty() {
         return;
@@ -263,4 +346,45 @@ mod test {
             assert!(base.resolve(*txt).is_err());
         }
     }
+
+    #[test]
+    fn relativize_iri_parsed() {
+        let base = IriParsed::new("http://a/b/c/d;p?q").unwrap();
+        for (_, abs) in RELATIVE_IRIS {
+            let abs = IriParsed::new(abs).unwrap();
+            if abs.scheme != base.scheme || abs.authority != base.authority {
+                assert!(base.relativize(&abs).is_none());
+                continue;
+            }
+            let rel = base.relativize(&abs);
+            assert!(rel.is_some(), "<{}> → {:?}", abs, rel);
+            assert_eq!(base.join(&rel.unwrap()).to_string(), abs.to_string());
+        }
+    }
+
+    #[test]
+    fn relativize_str() {
+        let base = IriParsed::new("http://a/b/c/d;p?q").unwrap();
+        for (abs, rel) in [
+            ("g:h", None),
+            ("http://a/b/c/g", Some("g")),
+            ("http://a/b/c/g/", Some("g/")),
+            ("http://a/b/c/", Some("./")),
+            ("http://a/b/g", Some("../g")),
+            ("http://a/g", Some("../../g")),
+            ("http://a/", Some("../../")),
+            ("http://g", None),
+            ("http://a/b/c/d;p?y", Some("?y")),
+            ("http://a/b/c/d;p?q#s", Some("#s")),
+            ("http://a/b/c/d;p?q", Some("")),
+            ("http://a/b/c/d;p", Some("d;p")),
+            ("http://a/b/c/x:y", Some("./x:y")),
+            ("http://a/b/c/./g", None),
+            ("not an IRI", None),
+        ] {
+            assert_eq!(base.relativize(abs).as_deref(), rel, "<{}>", abs);
+        }
+        let base = IriParsed::new("urn:a:b").unwrap();
+        assert_eq!(base.relativize("urn:a:c").as_deref(), Some("./a:c"));
+    }
 }
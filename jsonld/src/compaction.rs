@@ -0,0 +1,967 @@
+//! The [compaction algorithms] of JSON-LD.
+//!
+//! [compaction algorithms]: https://www.w3.org/TR/json-ld11-api/#compaction-algorithms
+use crate::context::*;
+use crate::error::{JsonLdError, JsonLdErrorCode::*};
+use crate::expansion::{
+    add_value, is_graph_object, is_list_object, is_scalar, is_value_object, sorted_keys,
+};
+use crate::loader::DocumentLoader;
+use json::object::Object;
+use json::JsonValue;
+use sophia_iri::resolve::{IriParsed, Relativize};
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::rc::Rc;
+
+/// An [inverse context], mapping IRIs to containers to type/language maps.
+///
+/// [inverse context]: https://www.w3.org/TR/json-ld11-api/#dfn-inverse-context
+type InverseContext = HashMap<String, HashMap<String, TypeLanguageMaps>>;
+
+/// The `@language`, `@type` and `@any` maps of an inverse context entry.
+#[derive(Default)]
+struct TypeLanguageMaps {
+    language: HashMap<String, String>,
+    typ: HashMap<String, String>,
+    any: HashMap<String, String>,
+}
+
+impl TypeLanguageMaps {
+    fn get(&self, type_language: &str) -> &HashMap<String, String> {
+        match type_language {
+            "@language" => &self.language,
+            "@type" => &self.typ,
+            _ => &self.any,
+        }
+    }
+}
+
+/// Implements the compaction algorithms.
+pub struct Compactor<'p, 'a, L: ?Sized> {
+    processor: &'p ContextProcessor<'a, L>,
+    inverse_cache: RefCell<Vec<(Rc<Context>, Rc<InverseContext>)>>,
+}
+
+impl<'p, 'a, L: DocumentLoader + ?Sized> Compactor<'p, 'a, L> {
+    /// Build a new compactor, using the given context processor.
+    pub fn new(processor: &'p ContextProcessor<'a, L>) -> Self {
+        Compactor {
+            processor,
+            inverse_cache: RefCell::new(vec![]),
+        }
+    }
+
+    /// Compact a whole expanded document, as per the [`compact()`] method.
+    ///
+    /// `context` is the (unprocessed) context from which `active` was built,
+    /// and is added to the result if not empty.
+    ///
+    /// [`compact()`]: https://www.w3.org/TR/json-ld11-api/#dom-jsonldprocessor-compact
+    pub fn compact_document(
+        &self,
+        active: &Rc<Context>,
+        context: &JsonValue,
+        expanded: &JsonValue,
+    ) -> Result<JsonValue, JsonLdError> {
+        let compacted = match self.compact(active, None, expanded)? {
+            JsonValue::Array(items) if items.is_empty() => Object::new(),
+            JsonValue::Array(items) => {
+                let mut obj = Object::new();
+                obj.insert(
+                    &self.compact_iri(active, "@graph", None, true, false)?,
+                    JsonValue::Array(items),
+                );
+                obj
+            }
+            JsonValue::Object(obj) => obj,
+            other => {
+                let mut obj = Object::new();
+                obj.insert(
+                    &self.compact_iri(active, "@graph", None, true, false)?,
+                    JsonValue::Array(vec![other]),
+                );
+                obj
+            }
+        };
+        Ok(with_context(context, compacted))
+    }
+
+    /// The [compaction algorithm].
+    ///
+    /// [compaction algorithm]: https://www.w3.org/TR/json-ld11-api/#compaction-algorithm
+    pub fn compact(
+        &self,
+        active: &Rc<Context>,
+        active_property: Option<&str>,
+        element: &JsonValue,
+    ) -> Result<JsonValue, JsonLdError> {
+        let type_scoped = active.clone();
+        let element = match element {
+            JsonValue::Array(items) => {
+                let mut result = vec![];
+                for item in items {
+                    let compacted = self.compact(active, active_property, item)?;
+                    if !compacted.is_null() {
+                        result.push(compacted);
+                    }
+                }
+                let keep_array = result.len() != 1
+                    || matches!(active_property, Some("@graph" | "@set"))
+                    || active_property
+                        .map(|p| {
+                            active.has_container(p, "@list") || active.has_container(p, "@set")
+                        })
+                        .unwrap_or(false);
+                return Ok(if keep_array {
+                    JsonValue::Array(result)
+                } else {
+                    result.pop().unwrap()
+                });
+            }
+            JsonValue::Object(obj) => obj,
+            scalar => return Ok(scalar.clone()),
+        };
+
+        let mut active = active.clone();
+        if let Some(previous) = &active.previous {
+            let only_id = element.len() == 1 && element.get("@id").is_some();
+            if element.get("@value").is_none() && !only_id {
+                active = previous.clone();
+            }
+        }
+        let property_def = active_property.and_then(|p| type_scoped.get(p));
+        if let Some(def) = property_def {
+            if let Some(ctx) = &def.context {
+                active = Rc::new(self.processor.process(
+                    &active,
+                    ctx,
+                    def.base_url.as_deref(),
+                    &[],
+                    true,
+                    true,
+                    true,
+                )?);
+            }
+        }
+
+        if element.get("@value").is_some() || element.get("@id").is_some() {
+            let compacted = self.compact_value(&active, active_property, element)?;
+            let is_json = property_def.and_then(|d| d.type_mapping.as_deref()) == Some("@json");
+            if is_scalar(&compacted) || compacted.is_null() || is_json {
+                return Ok(compacted);
+            }
+        }
+
+        if let Some(items) = element.get("@list") {
+            if active_property
+                .map(|p| active.has_container(p, "@list"))
+                .unwrap_or(false)
+            {
+                return self.compact(&active, active_property, items);
+            }
+        }
+
+        let inside_reverse = active_property == Some("@reverse");
+        let mut result = Object::new();
+
+        if let Some(types) = element.get("@type") {
+            let mut compacted_types = vec![];
+            for t in as_slice(types).iter().filter_map(JsonValue::as_str) {
+                compacted_types.push(self.compact_iri(&active, t, None, true, false)?);
+            }
+            compacted_types.sort_unstable();
+            for term in compacted_types {
+                if let Some(def) = type_scoped.get(&term) {
+                    if let Some(ctx) = &def.context {
+                        active = Rc::new(self.processor.process(
+                            &active,
+                            ctx,
+                            def.base_url.as_deref(),
+                            &[],
+                            false,
+                            false,
+                            true,
+                        )?);
+                    }
+                }
+            }
+        }
+
+        for expanded_property in sorted_keys(element) {
+            let expanded_value = &element[expanded_property];
+            match expanded_property {
+                "@id" => {
+                    let id = expanded_value.as_str().unwrap_or_default();
+                    let compacted = self.compact_iri(&active, id, None, false, false)?;
+                    let alias = self.compact_iri(&active, "@id", None, true, false)?;
+                    result.insert(&alias, compacted.into());
+                    continue;
+                }
+                "@type" => {
+                    let mut compacted = vec![];
+                    for t in as_slice(expanded_value) {
+                        compacted.push(match t.as_str() {
+                            Some(t) => self.compact_iri(&type_scoped, t, None, true, false)?.into(),
+                            None => t.clone(),
+                        });
+                    }
+                    let alias = self.compact_iri(&active, "@type", None, true, false)?;
+                    let as_array = !self.processor.is_10() && active.has_container(&alias, "@set");
+                    let compacted = if compacted.len() == 1 && !as_array {
+                        compacted.pop().unwrap()
+                    } else {
+                        JsonValue::Array(compacted)
+                    };
+                    add_value(&mut result, &alias, compacted, as_array);
+                    continue;
+                }
+                "@reverse" => {
+                    let compacted = self.compact(&active, Some("@reverse"), expanded_value)?;
+                    let mut remaining = Object::new();
+                    if let JsonValue::Object(compacted) = compacted {
+                        for (property, value) in compacted.iter() {
+                            match active.get(property) {
+                                Some(def) if def.reverse => {
+                                    let as_array = def.has_container("@set");
+                                    add_value(&mut result, property, value.clone(), as_array);
+                                }
+                                _ => remaining.insert(property, value.clone()),
+                            }
+                        }
+                    }
+                    if !remaining.is_empty() {
+                        let alias = self.compact_iri(&active, "@reverse", None, true, false)?;
+                        result.insert(&alias, JsonValue::Object(remaining));
+                    }
+                    continue;
+                }
+                "@preserve" => {
+                    let compacted = self.compact(&active, active_property, expanded_value)?;
+                    if !matches!(&compacted, JsonValue::Array(v) if v.is_empty()) {
+                        result.insert("@preserve", compacted);
+                    }
+                    continue;
+                }
+                "@index"
+                    if active_property
+                        .map(|p| active.has_container(p, "@index"))
+                        .unwrap_or(false) =>
+                {
+                    continue;
+                }
+                "@direction" | "@index" | "@language" | "@value" => {
+                    let alias = self.compact_iri(&active, expanded_property, None, true, false)?;
+                    result.insert(&alias, expanded_value.clone());
+                    continue;
+                }
+                _ => {}
+            }
+
+            if as_slice(expanded_value).is_empty() {
+                let item_property = self.compact_iri(
+                    &active,
+                    expanded_property,
+                    Some(expanded_value),
+                    true,
+                    inside_reverse,
+                )?;
+                let nest_result = self.nest_result(&active, &mut result, &item_property)?;
+                add_value(nest_result, &item_property, JsonValue::Array(vec![]), true);
+            }
+
+            for expanded_item in as_slice(expanded_value) {
+                let item_property = self.compact_iri(
+                    &active,
+                    expanded_property,
+                    Some(expanded_item),
+                    true,
+                    inside_reverse,
+                )?;
+                let container = active
+                    .get(&item_property)
+                    .map(|def| def.container.clone())
+                    .unwrap_or_default();
+                let has_container = |c: &str| container.iter().any(|x| x == c);
+                let as_array =
+                    has_container("@set") || matches!(item_property.as_str(), "@graph" | "@list");
+                let is_list = is_list_object(expanded_item);
+                let is_graph = is_graph_object(expanded_item);
+                let to_compact = if is_list {
+                    &expanded_item["@list"]
+                } else if is_graph {
+                    &expanded_item["@graph"]
+                } else {
+                    expanded_item
+                };
+                let mut compacted = self.compact(&active, Some(&item_property), to_compact)?;
+                let nest_result = self.nest_result(&active, &mut result, &item_property)?;
+
+                if is_list {
+                    let compacted = JsonValue::Array(crate::context::as_array(compacted));
+                    if has_container("@list") {
+                        nest_result.insert(&item_property, compacted);
+                    } else {
+                        let mut wrapper = Object::new();
+                        wrapper.insert(
+                            &self.compact_iri(&active, "@list", None, true, false)?,
+                            compacted,
+                        );
+                        if let Some(index) = expanded_item.as_object_ref().get("@index") {
+                            wrapper.insert(
+                                &self.compact_iri(&active, "@index", None, true, false)?,
+                                index.clone(),
+                            );
+                        }
+                        add_value(
+                            nest_result,
+                            &item_property,
+                            JsonValue::Object(wrapper),
+                            as_array,
+                        );
+                    }
+                } else if is_graph {
+                    let id = expanded_item["@id"].as_str();
+                    let index = expanded_item.as_object_ref().get("@index");
+                    if has_container("@graph") && has_container("@id") {
+                        let map_key = match id {
+                            Some(id) => self.compact_iri(&active, id, None, false, false)?,
+                            None => self.compact_iri(&active, "@none", None, true, false)?,
+                        };
+                        let map = map_object(nest_result, &item_property);
+                        add_value(map, &map_key, compacted, as_array);
+                    } else if has_container("@graph") && has_container("@index") && id.is_none() {
+                        let map_key = match index.and_then(JsonValue::as_str) {
+                            Some(index) => index.to_string(),
+                            None => self.compact_iri(&active, "@none", None, true, false)?,
+                        };
+                        let map = map_object(nest_result, &item_property);
+                        add_value(map, &map_key, compacted, as_array);
+                    } else if has_container("@graph") && id.is_none() {
+                        if matches!(&compacted, JsonValue::Array(v) if v.len() > 1) {
+                            let mut wrapper = Object::new();
+                            wrapper.insert(
+                                &self.compact_iri(&active, "@included", None, true, false)?,
+                                compacted,
+                            );
+                            compacted = JsonValue::Object(wrapper);
+                        }
+                        add_value(nest_result, &item_property, compacted, as_array);
+                    } else {
+                        let mut wrapper = Object::new();
+                        wrapper.insert(
+                            &self.compact_iri(&active, "@graph", None, true, false)?,
+                            compacted,
+                        );
+                        if let Some(id) = id {
+                            wrapper.insert(
+                                &self.compact_iri(&active, "@id", None, true, false)?,
+                                self.compact_iri(&active, id, None, false, false)?.into(),
+                            );
+                        }
+                        if let Some(index) = index {
+                            wrapper.insert(
+                                &self.compact_iri(&active, "@index", None, true, false)?,
+                                index.clone(),
+                            );
+                        }
+                        add_value(
+                            nest_result,
+                            &item_property,
+                            JsonValue::Object(wrapper),
+                            as_array,
+                        );
+                    }
+                } else if (has_container("@language")
+                    || has_container("@index")
+                    || has_container("@id")
+                    || has_container("@type"))
+                    && !has_container("@graph")
+                {
+                    let map_key = self.map_key(
+                        &active,
+                        &item_property,
+                        &container,
+                        expanded_item,
+                        &mut compacted,
+                    )?;
+                    let map_key = match map_key {
+                        Some(key) => key,
+                        None => self.compact_iri(&active, "@none", None, true, false)?,
+                    };
+                    let map = map_object(nest_result, &item_property);
+                    add_value(map, &map_key, compacted, as_array);
+                } else {
+                    add_value(nest_result, &item_property, compacted, as_array);
+                }
+            }
+        }
+        Ok(JsonValue::Object(result))
+    }
+
+    /// The object where values of `item_property` must be added:
+    /// either `result` itself, or the nested object designated by its `@nest` mapping.
+    fn nest_result<'r>(
+        &self,
+        active: &Context,
+        result: &'r mut Object,
+        item_property: &str,
+    ) -> Result<&'r mut Object, JsonLdError> {
+        let nest_term = match active.get(item_property).and_then(|def| def.nest.as_ref()) {
+            None => return Ok(result),
+            Some(nest_term) => nest_term,
+        };
+        if active.expand_iri(nest_term, false, true).as_deref() != Some("@nest") {
+            return Err(JsonLdError::processing(InvalidNestValue, nest_term));
+        }
+        Ok(map_object(result, nest_term))
+    }
+
+    /// Compute the key under which `compacted` must be stored in a map container,
+    /// possibly modifying `compacted` (steps 12.8.9.2 to 12.8.9.9 of the compaction algorithm).
+    fn map_key(
+        &self,
+        active: &Rc<Context>,
+        item_property: &str,
+        container: &[String],
+        expanded_item: &JsonValue,
+        compacted: &mut JsonValue,
+    ) -> Result<Option<String>, JsonLdError> {
+        let has_container = |c: &str| container.iter().any(|x| x == c);
+        let index_key = active
+            .get(item_property)
+            .and_then(|def| def.index.clone())
+            .unwrap_or_else(|| "@index".to_string());
+        if has_container("@language") {
+            if is_value_object(expanded_item) {
+                if let JsonValue::Object(obj) = compacted {
+                    if let Some(value) = obj.get("@value") {
+                        *compacted = value.clone();
+                    }
+                }
+            }
+            return Ok(expanded_item["@language"].as_str().map(str::to_string));
+        }
+        if has_container("@index") && index_key == "@index" {
+            return Ok(expanded_item["@index"].as_str().map(str::to_string));
+        }
+        if has_container("@index") {
+            let expanded_key = active
+                .expand_iri(&index_key, false, true)
+                .unwrap_or_default();
+            let container_key = self.compact_iri(active, &expanded_key, None, true, false)?;
+            return Ok(take_first_string(compacted, &container_key));
+        }
+        if has_container("@id") {
+            let container_key = self.compact_iri(active, "@id", None, true, false)?;
+            let key = match compacted {
+                JsonValue::Object(obj) => obj.remove(&container_key),
+                _ => None,
+            };
+            return Ok(key.and_then(|k| k.as_str().map(str::to_string)));
+        }
+        // @type container
+        let container_key = self.compact_iri(active, "@type", None, true, false)?;
+        let key = take_first_string(compacted, &container_key);
+        let only_id = match compacted {
+            JsonValue::Object(obj) => {
+                obj.len() == 1
+                    && obj
+                        .iter()
+                        .all(|(k, _)| active.expand_iri(k, false, true).as_deref() == Some("@id"))
+            }
+            _ => false,
+        };
+        if only_id {
+            let reference = json::object! {"@id": expanded_item["@id"].clone()};
+            *compacted = self.compact(active, Some(item_property), &reference)?;
+        }
+        Ok(key)
+    }
+
+    /// The [value compaction] algorithm.
+    ///
+    /// [value compaction]: https://www.w3.org/TR/json-ld11-api/#value-compaction
+    fn compact_value(
+        &self,
+        active: &Rc<Context>,
+        active_property: Option<&str>,
+        value: &Object,
+    ) -> Result<JsonValue, JsonLdError> {
+        let def = active_property.and_then(|p| active.get(p));
+        let type_mapping = def.and_then(|d| d.type_mapping.as_deref());
+        let has_container = |c: &str| def.map(|d| d.has_container(c)).unwrap_or(false);
+        let language = match def.and_then(|d| d.language.as_ref()) {
+            Some(lang) => lang.as_deref(),
+            None => active.language.as_deref(),
+        };
+        let direction = match def.and_then(|d| d.direction.as_ref()) {
+            Some(dir) => dir.as_deref(),
+            None => active.direction.as_deref(),
+        };
+        let index_ok = value.get("@index").is_none() || has_container("@index");
+
+        if let Some(id) = value.get("@id").and_then(JsonValue::as_str) {
+            let only_id = value.len() == 1 || (value.len() == 2 && value.get("@index").is_some());
+            if only_id {
+                match type_mapping {
+                    Some("@id") => {
+                        return Ok(self.compact_iri(active, id, None, false, false)?.into())
+                    }
+                    Some("@vocab") => {
+                        return Ok(self.compact_iri(active, id, None, true, false)?.into())
+                    }
+                    _ => {}
+                }
+            }
+            return Ok(JsonValue::Object(value.clone()));
+        }
+        let typ = value.get("@type").and_then(JsonValue::as_str);
+        if typ.is_some() && typ == type_mapping {
+            if index_ok {
+                return Ok(value["@value"].clone());
+            }
+        } else if type_mapping == Some("@none") || typ.is_some() {
+            // value compaction is disabled
+        } else if !value["@value"].is_string() {
+            if index_ok {
+                return Ok(value["@value"].clone());
+            }
+        } else {
+            let value_language = value.get("@language").and_then(JsonValue::as_str);
+            let value_direction = value.get("@direction").and_then(JsonValue::as_str);
+            let same_language = match (value_language, language) {
+                (Some(l1), Some(l2)) => l1.eq_ignore_ascii_case(l2),
+                (None, None) => true,
+                _ => false,
+            };
+            if same_language && value_direction == direction && index_ok {
+                return Ok(value["@value"].clone());
+            }
+        }
+        Ok(JsonValue::Object(value.clone()))
+    }
+
+    /// The [IRI compaction] algorithm.
+    ///
+    /// [IRI compaction]: https://www.w3.org/TR/json-ld11-api/#iri-compaction
+    pub fn compact_iri(
+        &self,
+        active: &Rc<Context>,
+        var: &str,
+        value: Option<&JsonValue>,
+        vocab: bool,
+        reverse: bool,
+    ) -> Result<String, JsonLdError> {
+        let inverse = self.inverse(active);
+        if vocab && inverse.contains_key(var) {
+            if let Some(term) = self.select_term_for(active, &inverse, var, value, reverse) {
+                return Ok(term);
+            }
+        }
+        if vocab {
+            if let Some(suffix) = active.vocab.as_deref().and_then(|v| var.strip_prefix(v)) {
+                if !suffix.is_empty() && active.get(suffix).is_none() {
+                    return Ok(suffix.to_string());
+                }
+            }
+        }
+        let mut compact_iri: Option<String> = None;
+        for (term, def) in &active.terms {
+            let iri = match &def.iri {
+                Some(iri) if def.prefix && iri != var && var.starts_with(iri.as_str()) => iri,
+                _ => continue,
+            };
+            let candidate = format!("{}:{}", term, &var[iri.len()..]);
+            let better = match &compact_iri {
+                None => true,
+                Some(c) => {
+                    candidate.len() < c.len() || (candidate.len() == c.len() && candidate < *c)
+                }
+            };
+            let usable = match active.get(&candidate) {
+                None => true,
+                Some(def) => def.iri.as_deref() == Some(var) && value.is_none(),
+            };
+            if better && usable {
+                compact_iri = Some(candidate);
+            }
+        }
+        if let Some(compact_iri) = compact_iri {
+            return Ok(compact_iri);
+        }
+        if let Some(pos) = var.find(':') {
+            let (scheme, rest) = (&var[..pos], &var[pos + 1..]);
+            if let Some(def) = active.get(scheme) {
+                if def.prefix && !rest.starts_with("//") {
+                    return Err(JsonLdError::processing(IriConfusedWithPrefix, var));
+                }
+            }
+        }
+        if !vocab {
+            if let Some(base) = &active.base {
+                let relative = IriParsed::new(base).ok().and_then(|b| b.relativize(var));
+                if let Some(relative) = relative {
+                    return Ok(relative);
+                }
+            }
+        }
+        Ok(var.to_string())
+    }
+
+    /// Step 4 of the IRI compaction algorithm.
+    fn select_term_for(
+        &self,
+        active: &Context,
+        inverse: &InverseContext,
+        var: &str,
+        value: Option<&JsonValue>,
+        reverse: bool,
+    ) -> Option<String> {
+        let default_language = default_language(active);
+        let mut value = value;
+        if let Some(preserved) = value.and_then(|v| v.as_object_ref().get("@preserve")) {
+            value = as_slice(preserved).first();
+        }
+        let is_10 = self.processor.is_10();
+        let mut containers: Vec<&str> = vec![];
+        let mut type_language = "@language";
+        let mut type_language_value: Option<String> = None;
+        let value_obj = value.map(JsonValue::as_object_ref);
+        let has = |key: &str| value_obj.map(|obj| obj.get(key).is_some()).unwrap_or(false);
+        let is_graph = value.map(is_graph_object).unwrap_or(false);
+
+        if has("@index") && !is_graph {
+            containers.extend(["@index", "@index@set"]);
+        }
+        if reverse {
+            type_language = "@type";
+            type_language_value = Some("@reverse".into());
+            containers.push("@set");
+        } else if value.map(is_list_object).unwrap_or(false) {
+            let value = value.unwrap();
+            if !has("@index") {
+                containers.push("@list");
+            }
+            let list = as_slice(&value["@list"]);
+            let mut common_type: Option<String> = None;
+            let mut common_language: Option<String> = None;
+            if list.is_empty() {
+                common_language = Some(default_language.clone());
+            }
+            for item in list {
+                let mut item_language = "@none".to_string();
+                let mut item_type = "@none".to_string();
+                if is_value_object(item) {
+                    if let Some(dir) = item["@direction"].as_str() {
+                        let lang = item["@language"].as_str().unwrap_or("");
+                        item_language = format!("{}_{}", lang, dir).to_ascii_lowercase();
+                    } else if let Some(lang) = item["@language"].as_str() {
+                        item_language = lang.to_ascii_lowercase();
+                    } else if let Some(typ) = item["@type"].as_str() {
+                        item_type = typ.to_string();
+                    } else {
+                        item_language = "@null".into();
+                    }
+                } else {
+                    item_type = "@id".into();
+                }
+                match &common_language {
+                    None => common_language = Some(item_language),
+                    Some(l) if *l != item_language && is_value_object(item) => {
+                        common_language = Some("@none".into())
+                    }
+                    _ => {}
+                }
+                match &common_type {
+                    None => common_type = Some(item_type),
+                    Some(t) if *t != item_type => common_type = Some("@none".into()),
+                    _ => {}
+                }
+                if common_language.as_deref() == Some("@none")
+                    && common_type.as_deref() == Some("@none")
+                {
+                    break;
+                }
+            }
+            let common_language = common_language.unwrap_or_else(|| "@none".into());
+            let common_type = common_type.unwrap_or_else(|| "@none".into());
+            if common_type != "@none" {
+                type_language = "@type";
+                type_language_value = Some(common_type);
+            } else {
+                type_language_value = Some(common_language);
+            }
+        } else if is_graph {
+            if has("@index") {
+                containers.extend(["@graph@index", "@graph@index@set"]);
+            }
+            if has("@id") {
+                containers.extend(["@graph@id", "@graph@id@set"]);
+            }
+            containers.extend(["@graph", "@graph@set", "@set"]);
+            if !has("@index") {
+                containers.extend(["@graph@index", "@graph@index@set"]);
+            }
+            if !has("@id") {
+                containers.extend(["@graph@id", "@graph@id@set"]);
+            }
+            containers.extend(["@index", "@index@set"]);
+            type_language = "@type";
+            type_language_value = Some("@id".into());
+        } else {
+            match value {
+                Some(v) if is_value_object(v) => {
+                    if let (Some(dir), false) = (v["@direction"].as_str(), has("@index")) {
+                        let lang = v["@language"].as_str().unwrap_or("");
+                        type_language_value =
+                            Some(format!("{}_{}", lang, dir).to_ascii_lowercase());
+                        containers.extend(["@language", "@language@set"]);
+                    } else if let (Some(lang), false) = (v["@language"].as_str(), has("@index")) {
+                        type_language_value = Some(lang.to_ascii_lowercase());
+                        containers.extend(["@language", "@language@set"]);
+                    } else if let Some(typ) = v["@type"].as_str() {
+                        type_language_value = Some(typ.to_string());
+                        type_language = "@type";
+                    }
+                }
+                _ => {
+                    type_language = "@type";
+                    type_language_value = Some("@id".into());
+                    containers.extend(["@id", "@id@set", "@type", "@set@type"]);
+                }
+            }
+            containers.push("@set");
+        }
+        containers.push("@none");
+        if !is_10 && !has("@index") {
+            containers.extend(["@index", "@index@set"]);
+        }
+        if !is_10 {
+            if let Some(obj) = value_obj {
+                if obj.len() == 1 && obj.get("@value").is_some() {
+                    containers.extend(["@language", "@language@set"]);
+                }
+            }
+        }
+        let type_language_value = type_language_value.unwrap_or_else(|| "@null".into());
+        let mut preferred: Vec<String> = vec![];
+        if type_language_value == "@reverse" {
+            preferred.push("@reverse".into());
+        }
+        let id = value.and_then(|v| v["@id"].as_str());
+        if let (Some(id), true) = (
+            id,
+            matches!(type_language_value.as_str(), "@id" | "@reverse"),
+        ) {
+            let compacted = self
+                .compact_iri_vocab_only(active, inverse, id)
+                .and_then(|term| active.get(&term))
+                .and_then(|def| def.iri.as_deref())
+                == Some(id);
+            if compacted {
+                preferred.extend(["@vocab".into(), "@id".into(), "@none".into()]);
+            } else {
+                preferred.extend(["@id".into(), "@vocab".into(), "@none".into()]);
+            }
+        } else {
+            preferred.push(type_language_value);
+            preferred.push("@none".into());
+            let empty_list = value
+                .map(|v| is_list_object(v) && as_slice(&v["@list"]).is_empty())
+                .unwrap_or(false);
+            if empty_list {
+                type_language = "@any";
+            }
+        }
+        preferred.push("@any".into());
+        let underscored: Vec<String> = preferred
+            .iter()
+            .filter_map(|p| p.find('_').map(|pos| p[pos..].to_string()))
+            .collect();
+        preferred.extend(underscored);
+
+        let container_map = inverse.get(var)?;
+        for container in containers {
+            if let Some(maps) = container_map.get(container) {
+                let map = maps.get(type_language);
+                for pv in &preferred {
+                    if let Some(term) = map.get(pv) {
+                        return Some(term.clone());
+                    }
+                }
+            }
+        }
+        None
+    }
+
+    /// IRI compaction of `var` with `vocab` set, limited to term selection and `@vocab`;
+    /// used to check whether an `@id` can be compacted as a term (step 4.19.1).
+    fn compact_iri_vocab_only(
+        &self,
+        active: &Context,
+        inverse: &InverseContext,
+        var: &str,
+    ) -> Option<String> {
+        if inverse.contains_key(var) {
+            if let Some(term) = self.select_term_for(active, inverse, var, None, false) {
+                return Some(term);
+            }
+        }
+        let suffix = active.vocab.as_deref().and_then(|v| var.strip_prefix(v))?;
+        (!suffix.is_empty() && active.get(suffix).is_none()).then(|| suffix.to_string())
+    }
+
+    /// The inverse context of `active`, created on demand.
+    fn inverse(&self, active: &Rc<Context>) -> Rc<InverseContext> {
+        let mut cache = self.inverse_cache.borrow_mut();
+        if let Some((_, inverse)) = cache.iter().find(|(ctx, _)| Rc::ptr_eq(ctx, active)) {
+            return inverse.clone();
+        }
+        let inverse = Rc::new(create_inverse_context(active));
+        cache.push((active.clone(), inverse.clone()));
+        inverse
+    }
+}
+
+/// The default language of `active`, combined with its default base direction, if any.
+fn default_language(active: &Context) -> String {
+    match (&active.language, &active.direction) {
+        (lang, Some(dir)) => {
+            format!("{}_{}", lang.as_deref().unwrap_or(""), dir).to_ascii_lowercase()
+        }
+        (Some(lang), None) => lang.to_ascii_lowercase(),
+        (None, None) => "@none".into(),
+    }
+}
+
+/// The [inverse context creation] algorithm.
+///
+/// [inverse context creation]: https://www.w3.org/TR/json-ld11-api/#inverse-context-creation
+fn create_inverse_context(active: &Context) -> InverseContext {
+    let default_language = default_language(active);
+    let mut result = InverseContext::new();
+    let mut terms: Vec<&String> = active.terms.keys().collect();
+    terms.sort_unstable_by(|t1, t2| t1.len().cmp(&t2.len()).then(t1.cmp(t2)));
+    for term in terms {
+        let def = &active.terms[term];
+        let iri = match &def.iri {
+            Some(iri) => iri,
+            None => continue,
+        };
+        let mut container = def.container.clone();
+        container.sort_unstable();
+        let container = if container.is_empty() {
+            "@none".to_string()
+        } else {
+            container.concat()
+        };
+        let maps = result
+            .entry(iri.clone())
+            .or_default()
+            .entry(container)
+            .or_insert_with(|| {
+                let mut maps = TypeLanguageMaps::default();
+                maps.any.insert("@none".into(), term.clone());
+                maps
+            });
+        let set_default = |map: &mut HashMap<String, String>, key: String| {
+            map.entry(key).or_insert_with(|| term.clone());
+        };
+        if def.reverse {
+            set_default(&mut maps.typ, "@reverse".into());
+        } else if def.type_mapping.as_deref() == Some("@none") {
+            set_default(&mut maps.language, "@any".into());
+            set_default(&mut maps.typ, "@any".into());
+        } else if let Some(typ) = &def.type_mapping {
+            set_default(&mut maps.typ, typ.clone());
+        } else if let (Some(lang), Some(dir)) = (&def.language, &def.direction) {
+            let key = match (lang, dir) {
+                (Some(lang), Some(dir)) => format!("{}_{}", lang, dir),
+                (Some(lang), None) => lang.clone(),
+                (None, Some(dir)) => format!("_{}", dir),
+                (None, None) => "@null".into(),
+            };
+            set_default(&mut maps.language, key.to_ascii_lowercase());
+        } else if let Some(lang) = &def.language {
+            let key = lang.as_deref().unwrap_or("@null").to_ascii_lowercase();
+            set_default(&mut maps.language, key);
+        } else if let Some(dir) = &def.direction {
+            let key = match dir {
+                Some(dir) => format!("_{}", dir),
+                None => "@none".into(),
+            };
+            set_default(&mut maps.language, key);
+        } else {
+            set_default(&mut maps.language, default_language.clone());
+            set_default(&mut maps.language, "@none".into());
+            set_default(&mut maps.typ, "@none".into());
+        }
+    }
+    result
+}
+
+/// Add `context` as the first entry of `compacted`, unless it is empty.
+pub fn with_context(context: &JsonValue, compacted: Object) -> JsonValue {
+    let empty_context = match context {
+        JsonValue::Null => true,
+        JsonValue::Object(obj) => obj.is_empty(),
+        JsonValue::Array(items) => items.is_empty(),
+        _ => false,
+    };
+    if empty_context {
+        return JsonValue::Object(compacted);
+    }
+    let mut result = Object::new();
+    result.insert("@context", context.clone());
+    for (key, value) in compacted.iter() {
+        result.insert(key, value.clone());
+    }
+    JsonValue::Object(result)
+}
+
+/// The map stored in `obj` under `key`, created if necessary.
+fn map_object<'o>(obj: &'o mut Object, key: &str) -> &'o mut Object {
+    if !matches!(obj.get(key), Some(JsonValue::Object(_))) {
+        obj.insert(key, JsonValue::Object(Object::new()));
+    }
+    match obj.get_mut(key) {
+        Some(JsonValue::Object(map)) => map,
+        _ => unreachable!(),
+    }
+}
+
+/// Remove the first string value of `key` in `compacted` (if it is a map), and return it.
+fn take_first_string(compacted: &mut JsonValue, key: &str) -> Option<String> {
+    let obj = match compacted {
+        JsonValue::Object(obj) => obj,
+        _ => return None,
+    };
+    let mut values = as_array(obj.remove(key)?);
+    let first = if values.first().map(JsonValue::is_string).unwrap_or(false) {
+        Some(values.remove(0).as_str().unwrap().to_string())
+    } else {
+        None
+    };
+    match values.len() {
+        0 => {}
+        1 => obj.insert(key, values.pop().unwrap()),
+        _ => obj.insert(key, JsonValue::Array(values)),
+    }
+    first
+}
+
+/// Utility trait to access the map of a [`JsonValue`], if any.
+trait AsObjectRef {
+    fn as_object_ref(&self) -> &Object;
+}
+
+impl AsObjectRef for JsonValue {
+    fn as_object_ref(&self) -> &Object {
+        static EMPTY: std::sync::OnceLock<Object> = std::sync::OnceLock::new();
+        match self {
+            JsonValue::Object(obj) => obj,
+            _ => EMPTY.get_or_init(Object::new),
+        }
+    }
+}
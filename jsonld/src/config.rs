@@ -1,8 +1,54 @@
-//! JSON-LD serializer configuration.
+//! JSON-LD parser and serializer configuration.
 
-/// JSON-LD serializer configuration.
+use json::JsonValue;
+use sophia_api::parser::ParserLimits;
+
+/// JSON-LD parser and serializer configuration.
 #[derive(Clone, Debug, Default)]
 pub struct JsonLdConfig {
+    /// The base IRI used by the parser to resolve relative IRIs,
+    /// unless it is overridden by the document itself.
+    ///
+    /// When compacting, the serializer makes IRIs relative to this base,
+    /// and declares it with `@base` in the context of its output.
+    pub base: Option<String>,
+
+    /// A context used by the serializer to [compact] its output.
+    ///
+    /// If `None` (and [`frame`](Self::frame) is also `None`),
+    /// the output is in [expanded document form].
+    ///
+    /// [compact]: https://www.w3.org/TR/json-ld11-api/#compaction-algorithm
+    /// [expanded document form]: https://www.w3.org/TR/json-ld11/#expanded-document-form
+    pub compact_context: Option<JsonValue>,
+
+    /// A context used by the parser to initialize the active context,
+    /// before processing the document (see [`expandContext`]).
+    ///
+    /// [`expandContext`]: https://www.w3.org/TR/json-ld11-api/#dom-jsonldoptions-expandcontext
+    pub expand_context: Option<JsonValue>,
+
+    /// A [frame] used by the serializer to shape its output.
+    ///
+    /// The output is compacted with the context of the frame,
+    /// or with [`compact_context`](Self::compact_context) if the frame has none.
+    ///
+    /// [frame]: https://www.w3.org/TR/json-ld11-framing/#dfn-frame
+    pub frame: Option<JsonValue>,
+
+    /// Limits on the resources consumed by the parser (none by default).
+    ///
+    /// The nesting depth is that of JSON arrays and objects.
+    /// The other limits are checked on the whole document
+    /// and on the quads it produces.
+    pub limits: ParserLimits,
+
+    /// The [`produceGeneralizedRdf`] flag, which allows the parser
+    /// to produce triples whose predicate is a blank node.
+    ///
+    /// [`produceGeneralizedRdf`]: https://www.w3.org/TR/json-ld11-api/#dom-jsonldoptions-producegeneralizedrdf
+    pub produce_generalized_rdf: bool,
+
     /// A JSON-LD option determining how value objects containing
     /// a base direction are transformed to and from RDF.
     ///
@@ -18,6 +64,19 @@ pub struct JsonLdConfig {
     /// [`JSON-LD 1.1`]: https://www.w3.org/TR/json-ld11/
     pub spec_version: JsonLdSpecVersion,
 
+    /// Whether the serializer writes node objects as soon as possible,
+    /// rather than building the whole output in memory first.
+    ///
+    /// This assumes that quads sharing the same subject are consecutive
+    /// (as in sorted N-Quads), but produces a valid output in any case.
+    /// Nodes that may be part of a list or a compound literal are still buffered,
+    /// and nodes referring to a blank node are held until that blank node is described.
+    ///
+    /// This only applies to serializers writing to a [`std::io::Write`],
+    /// when neither [`compact_context`](Self::compact_context)
+    /// nor [`frame`](Self::frame) is set.
+    pub streaming: bool,
+
     /// The [`useNativeTypes`] flag, which causes the `Serialize RDF as JSON-LD Algorithm`
     /// to use native JSON values in value objects avoiding the need for an explicit `@type`.
     ///
@@ -33,11 +92,59 @@ pub struct JsonLdConfig {
 }
 
 impl JsonLdConfig {
-    /// Build a new JSON-LD serializer configuration.
+    /// Build a new JSON-LD configuration.
     pub fn new() -> Self {
         Self::default()
     }
 
+    /// Set the base IRI used by the parser, and by the serializer when compacting.
+    pub fn base<T: Into<String>>(mut self, base: T) -> Self {
+        self.base = Some(base.into());
+        self
+    }
+
+    /// Set the context used by the serializer to compact its output.
+    ///
+    /// `context` can be either a context, or a document with a `@context` entry.
+    pub fn compact_context(mut self, context: JsonValue) -> Self {
+        self.compact_context = Some(context);
+        self
+    }
+
+    /// Set the context used by the parser to initialize the active context.
+    pub fn expand_context(mut self, context: JsonValue) -> Self {
+        self.expand_context = Some(context);
+        self
+    }
+
+    /// Set the frame used by the serializer to shape its output.
+    pub fn frame(mut self, frame: JsonValue) -> Self {
+        self.frame = Some(frame);
+        self
+    }
+
+    /// Set the limits on the resources consumed by the parser.
+    pub fn limits(mut self, limits: ParserLimits) -> Self {
+        self.limits = limits;
+        self
+    }
+
+    /// Set the [`produceGeneralizedRdf`] flag.
+    ///
+    /// [`produceGeneralizedRdf`]: https://www.w3.org/TR/json-ld11-api/#dom-jsonldoptions-producegeneralizedrdf
+    pub fn produce_generalized_rdf(mut self, flag: bool) -> Self {
+        self.produce_generalized_rdf = flag;
+        self
+    }
+
+    /// Set the [`rdfDirection`] option.
+    ///
+    /// [`rdfDirection`]: https://www.w3.org/TR/json-ld11-api/#dom-jsonldoptions-rdfdirection
+    pub fn rdf_direction(mut self, mode: Option<RdfDirectionMode>) -> Self {
+        self.rdf_direction = mode;
+        self
+    }
+
     /// Set the number of spaces to indent new blocks with.
     pub fn spaces(mut self, spaces: u16) -> Self {
         self.spaces = spaces;
@@ -50,6 +157,12 @@ impl JsonLdConfig {
         self
     }
 
+    /// Set the [`streaming`](Self::streaming) flag.
+    pub fn streaming(mut self, flag: bool) -> Self {
+        self.streaming = flag;
+        self
+    }
+
     /// Set the [`useNativeTypes`] flag.
     ///
     /// [`useNativeTypes`]: https://www.w3.org/TR/json-ld11-api/#dom-jsonldoptions-usenativetypes
@@ -0,0 +1,914 @@
+//! Active contexts, and the algorithms to build them from JSON-LD contexts,
+//! as defined in the [JSON-LD 1.1 Processing Algorithms and API].
+//!
+//! [JSON-LD 1.1 Processing Algorithms and API]: https://www.w3.org/TR/json-ld11-api/#context-processing-algorithms
+use crate::config::JsonLdSpecVersion;
+use crate::error::{JsonLdError, JsonLdErrorCode::*};
+use crate::loader::{DocumentLoader, RemoteDocument};
+use json::object::Object;
+use json::JsonValue;
+use sophia_iri::resolve::{IriParsed, Resolve};
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::rc::Rc;
+
+/// The maximum number of nested remote contexts.
+const MAX_REMOTE_CONTEXTS: usize = 32;
+
+const KEYWORDS: &[&str] = &[
+    "@base",
+    "@container",
+    "@context",
+    "@default",
+    "@direction",
+    "@embed",
+    "@explicit",
+    "@graph",
+    "@id",
+    "@import",
+    "@included",
+    "@index",
+    "@json",
+    "@language",
+    "@list",
+    "@nest",
+    "@none",
+    "@omitDefault",
+    "@prefix",
+    "@preserve",
+    "@propagate",
+    "@protected",
+    "@requireAll",
+    "@reverse",
+    "@set",
+    "@type",
+    "@value",
+    "@version",
+    "@vocab",
+];
+
+/// Whether `txt` is a JSON-LD keyword.
+pub fn is_keyword(txt: &str) -> bool {
+    KEYWORDS.contains(&txt)
+}
+
+/// Whether `txt` has the form of a keyword (`@` followed by letters only).
+pub fn has_keyword_form(txt: &str) -> bool {
+    txt.len() > 1 && txt.starts_with('@') && txt[1..].bytes().all(|b| b.is_ascii_alphabetic())
+}
+
+/// Whether `txt` is an absolute IRI.
+pub fn is_iri(txt: &str) -> bool {
+    sophia_iri::is_absolute_iri_ref(txt)
+}
+
+/// Whether `txt` is a blank node identifier.
+pub fn is_bnode_id(txt: &str) -> bool {
+    txt.starts_with("_:")
+}
+
+/// Resolve `iri` against `base` (if any).
+pub fn resolve_iri(base: Option<&str>, iri: &str) -> Option<String> {
+    match base {
+        None => is_iri(iri).then(|| iri.to_string()),
+        Some(base) => {
+            let base = IriParsed::new(base).ok()?;
+            base.resolve(iri).ok().map(|iri| iri.to_string())
+        }
+    }
+}
+
+/// Convert `value` to an array (if it is not already one).
+pub fn as_array(value: JsonValue) -> Vec<JsonValue> {
+    match value {
+        JsonValue::Array(v) => v,
+        value => vec![value],
+    }
+}
+
+/// Convert `value` to a slice of values.
+pub fn as_slice(value: &JsonValue) -> &[JsonValue] {
+    match value {
+        JsonValue::Array(v) => &v[..],
+        value => std::slice::from_ref(value),
+    }
+}
+
+/// A term definition in an active context.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct TermDefinition {
+    /// The IRI mapping (`None` if the term is explicitly mapped to null).
+    pub iri: Option<String>,
+    pub prefix: bool,
+    pub protected: bool,
+    pub reverse: bool,
+    pub base_url: Option<String>,
+    /// The (scoped) local context.
+    pub context: Option<JsonValue>,
+    pub container: Vec<String>,
+    /// The direction mapping (`Some(None)` means explicitly null).
+    pub direction: Option<Option<String>>,
+    pub index: Option<String>,
+    /// The language mapping (`Some(None)` means explicitly null).
+    pub language: Option<Option<String>>,
+    pub nest: Option<String>,
+    pub type_mapping: Option<String>,
+}
+
+impl TermDefinition {
+    /// Whether the container mapping of this definition includes `container`.
+    pub fn has_container(&self, container: &str) -> bool {
+        self.container.iter().any(|c| c == container)
+    }
+
+    /// Whether `self` and `other` are identical, except for their `protected` flag.
+    fn same_as(&self, other: &TermDefinition) -> bool {
+        TermDefinition {
+            protected: other.protected,
+            ..self.clone()
+        } == *other
+    }
+}
+
+/// An [active context](https://www.w3.org/TR/json-ld11-api/#dfn-active-context).
+#[derive(Clone, Debug, Default)]
+pub struct Context {
+    pub terms: HashMap<String, TermDefinition>,
+    pub base: Option<String>,
+    pub original_base: Option<String>,
+    pub vocab: Option<String>,
+    pub language: Option<String>,
+    pub direction: Option<String>,
+    pub previous: Option<Rc<Context>>,
+}
+
+impl Context {
+    /// A new active context with the given base IRI.
+    pub fn new(base: Option<String>) -> Self {
+        Context {
+            original_base: base.clone(),
+            base,
+            ..Context::default()
+        }
+    }
+
+    /// The term definition of `term`, if any.
+    pub fn get(&self, term: &str) -> Option<&TermDefinition> {
+        self.terms.get(term)
+    }
+
+    /// The container mapping of `term` includes `container`.
+    pub fn has_container(&self, term: &str, container: &str) -> bool {
+        self.get(term)
+            .map(|def| def.has_container(container))
+            .unwrap_or(false)
+    }
+
+    /// The [IRI expansion] algorithm, once the local context (if any) has been processed.
+    ///
+    /// [IRI expansion]: https://www.w3.org/TR/json-ld11-api/#iri-expansion
+    pub fn expand_iri(&self, value: &str, document_relative: bool, vocab: bool) -> Option<String> {
+        if is_keyword(value) {
+            return Some(value.to_string());
+        }
+        if has_keyword_form(value) {
+            return None;
+        }
+        if let Some(def) = self.get(value) {
+            if let Some(iri) = &def.iri {
+                if is_keyword(iri) {
+                    return Some(iri.clone());
+                }
+            }
+            if vocab {
+                return def.iri.clone();
+            }
+        }
+        if let Some(pos) = value.get(1..).and_then(|v| v.find(':')).map(|p| p + 1) {
+            let (prefix, suffix) = (&value[..pos], &value[pos + 1..]);
+            if prefix == "_" || suffix.starts_with("//") {
+                return Some(value.to_string());
+            }
+            if let Some(def) = self.get(prefix) {
+                if let (Some(iri), true) = (&def.iri, def.prefix) {
+                    return Some(format!("{}{}", iri, suffix));
+                }
+            }
+            if is_iri(value) {
+                return Some(value.to_string());
+            }
+        }
+        if vocab {
+            if let Some(v) = &self.vocab {
+                return Some(format!("{}{}", v, value));
+            }
+        }
+        if document_relative {
+            if let Some(base) = &self.base {
+                if let Some(iri) = resolve_iri(Some(base), value) {
+                    return Some(iri);
+                }
+            }
+        }
+        Some(value.to_string())
+    }
+
+    /// Whether any term definition of this context is protected.
+    fn has_protected_terms(&self) -> bool {
+        self.terms.values().any(|def| def.protected)
+    }
+}
+
+/// Implements the context processing algorithms.
+pub struct ContextProcessor<'a, L: ?Sized> {
+    loader: &'a L,
+    pub mode: JsonLdSpecVersion,
+    cache: RefCell<HashMap<String, Rc<RemoteDocument>>>,
+}
+
+/// The parameters of the [create term definition] algorithm
+/// that are constant while processing a given local context.
+///
+/// [create term definition]: https://www.w3.org/TR/json-ld11-api/#create-term-definition
+struct TermScope<'b> {
+    local: &'b Object,
+    base_url: Option<&'b str>,
+    protected: bool,
+    override_protected: bool,
+    remote_contexts: &'b [String],
+}
+
+impl<'a, L: DocumentLoader + ?Sized> ContextProcessor<'a, L> {
+    /// Build a new context processor.
+    pub fn new(loader: &'a L, mode: JsonLdSpecVersion) -> Self {
+        ContextProcessor {
+            loader,
+            mode,
+            cache: RefCell::new(HashMap::new()),
+        }
+    }
+
+    /// Whether the processing mode is JSON-LD 1.0.
+    pub fn is_10(&self) -> bool {
+        self.mode == JsonLdSpecVersion::JsonLd10
+    }
+
+    /// Load a remote document, using the cache if possible.
+    pub fn load(&self, url: &str) -> Result<Rc<RemoteDocument>, JsonLdError> {
+        if let Some(doc) = self.cache.borrow().get(url) {
+            return Ok(doc.clone());
+        }
+        let doc = Rc::new(self.loader.load_document(url)?);
+        self.cache.borrow_mut().insert(url.to_string(), doc.clone());
+        Ok(doc)
+    }
+
+    /// The [context processing] algorithm.
+    ///
+    /// [context processing]: https://www.w3.org/TR/json-ld11-api/#context-processing-algorithm
+    #[allow(clippy::too_many_arguments)]
+    pub fn process(
+        &self,
+        active: &Context,
+        local: &JsonValue,
+        base_url: Option<&str>,
+        remote_contexts: &[String],
+        override_protected: bool,
+        mut propagate: bool,
+        validate_scoped: bool,
+    ) -> Result<Context, JsonLdError> {
+        let mut result = active.clone();
+        if let JsonValue::Object(obj) = local {
+            if let Some(p) = obj.get("@propagate") {
+                propagate = p
+                    .as_bool()
+                    .ok_or_else(|| JsonLdError::processing(InvalidPropagateValue, p.dump()))?;
+            }
+        }
+        if !propagate && result.previous.is_none() {
+            result.previous = Some(Rc::new(active.clone()));
+        }
+        let mut remote_contexts = remote_contexts.to_vec();
+        for context in as_slice(local) {
+            match context {
+                JsonValue::Null => {
+                    if !override_protected && result.has_protected_terms() {
+                        return Err(JsonLdError::processing(
+                            InvalidContextNullification,
+                            "protected terms can not be nullified",
+                        ));
+                    }
+                    let previous = result;
+                    result = Context::new(active.original_base.clone());
+                    if !propagate {
+                        result.previous = Some(Rc::new(previous));
+                    }
+                }
+                JsonValue::String(_) | JsonValue::Short(_) => {
+                    let context = context.as_str().unwrap();
+                    let url = resolve_iri(base_url, context).ok_or_else(|| {
+                        JsonLdError::processing(LoadingRemoteContextFailed, context)
+                    })?;
+                    if !validate_scoped && remote_contexts.contains(&url) {
+                        continue;
+                    }
+                    if remote_contexts.len() >= MAX_REMOTE_CONTEXTS {
+                        return Err(JsonLdError::processing(ContextOverflow, url));
+                    }
+                    remote_contexts.push(url.clone());
+                    let doc = self
+                        .load(&url)
+                        .map_err(|e| JsonLdError::processing(LoadingRemoteContextFailed, e))?;
+                    let loaded = match &doc.document {
+                        JsonValue::Object(obj) => obj.get("@context"),
+                        _ => None,
+                    }
+                    .ok_or_else(|| JsonLdError::processing(InvalidRemoteContext, &url))?;
+                    result = self.process(
+                        &result,
+                        loaded,
+                        Some(&doc.document_url),
+                        &remote_contexts,
+                        false,
+                        true,
+                        validate_scoped,
+                    )?;
+                }
+                JsonValue::Object(obj) => {
+                    self.process_object(
+                        &mut result,
+                        obj,
+                        base_url,
+                        &remote_contexts,
+                        override_protected,
+                    )?;
+                }
+                _ => return Err(JsonLdError::processing(InvalidLocalContext, context.dump())),
+            }
+        }
+        Ok(result)
+    }
+
+    /// Steps 5.5 to 5.13 of the context processing algorithm.
+    fn process_object(
+        &self,
+        result: &mut Context,
+        context: &Object,
+        base_url: Option<&str>,
+        remote_contexts: &[String],
+        override_protected: bool,
+    ) -> Result<(), JsonLdError> {
+        if let Some(version) = context.get("@version") {
+            if version.as_f64() != Some(1.1) {
+                return Err(JsonLdError::processing(InvalidVersionValue, version.dump()));
+            }
+            if self.is_10() {
+                return Err(JsonLdError::processing(
+                    ProcessingModeConflict,
+                    "@version 1.1 in JSON-LD 1.0 mode",
+                ));
+            }
+        }
+        let imported;
+        let context = match context.get("@import") {
+            None => context,
+            Some(import) => {
+                if self.is_10() {
+                    return Err(JsonLdError::processing(
+                        InvalidContextEntry,
+                        "@import in JSON-LD 1.0 mode",
+                    ));
+                }
+                let import = import
+                    .as_str()
+                    .ok_or_else(|| JsonLdError::processing(InvalidImportValue, import.dump()))?;
+                let url = resolve_iri(base_url, import)
+                    .ok_or_else(|| JsonLdError::processing(InvalidImportValue, import))?;
+                let doc = self
+                    .load(&url)
+                    .map_err(|e| JsonLdError::processing(LoadingRemoteContextFailed, e))?;
+                let mut import_context = match &doc.document {
+                    JsonValue::Object(obj) => match obj.get("@context") {
+                        Some(JsonValue::Object(ctx)) => ctx.clone(),
+                        _ => return Err(JsonLdError::processing(InvalidRemoteContext, url)),
+                    },
+                    _ => return Err(JsonLdError::processing(InvalidRemoteContext, url)),
+                };
+                if import_context.get("@import").is_some() {
+                    return Err(JsonLdError::processing(
+                        InvalidContextEntry,
+                        "imported context contains @import",
+                    ));
+                }
+                for (key, value) in context.iter() {
+                    import_context.insert(key, value.clone());
+                }
+                imported = import_context;
+                &imported
+            }
+        };
+        if let (Some(value), true) = (context.get("@base"), remote_contexts.is_empty()) {
+            if value.is_null() {
+                result.base = None;
+            } else {
+                let iri = value
+                    .as_str()
+                    .ok_or_else(|| JsonLdError::processing(InvalidBaseIri, value.dump()))?;
+                if is_iri(iri) {
+                    result.base = Some(iri.to_string());
+                } else if let Some(base) = &result.base {
+                    result.base = Some(
+                        resolve_iri(Some(base), iri)
+                            .ok_or_else(|| JsonLdError::processing(InvalidBaseIri, iri))?,
+                    );
+                } else {
+                    return Err(JsonLdError::processing(InvalidBaseIri, iri));
+                }
+            }
+        }
+        if let Some(value) = context.get("@vocab") {
+            if value.is_null() {
+                result.vocab = None;
+            } else {
+                let vocab = value
+                    .as_str()
+                    .ok_or_else(|| JsonLdError::processing(InvalidVocabMapping, value.dump()))?;
+                let iri = result
+                    .expand_iri(vocab, true, true)
+                    .filter(|iri| is_iri(iri) || is_bnode_id(iri))
+                    .ok_or_else(|| JsonLdError::processing(InvalidVocabMapping, vocab))?;
+                result.vocab = Some(iri);
+            }
+        }
+        if let Some(value) = context.get("@language") {
+            if value.is_null() {
+                result.language = None;
+            } else {
+                let lang = value
+                    .as_str()
+                    .ok_or_else(|| JsonLdError::processing(InvalidDefaultLanguage, value.dump()))?;
+                result.language = Some(lang.to_ascii_lowercase());
+            }
+        }
+        if let Some(value) = context.get("@direction") {
+            if self.is_10() {
+                return Err(JsonLdError::processing(
+                    InvalidContextEntry,
+                    "@direction in JSON-LD 1.0 mode",
+                ));
+            }
+            if value.is_null() {
+                result.direction = None;
+            } else {
+                result.direction = Some(parse_direction(value)?);
+            }
+        }
+        if let Some(value) = context.get("@propagate") {
+            if self.is_10() {
+                return Err(JsonLdError::processing(
+                    InvalidContextEntry,
+                    "@propagate in JSON-LD 1.0 mode",
+                ));
+            }
+            if !value.is_boolean() {
+                return Err(JsonLdError::processing(InvalidPropagateValue, value.dump()));
+            }
+        }
+        let protected = match context.get("@protected") {
+            None => false,
+            Some(value) => value
+                .as_bool()
+                .ok_or_else(|| JsonLdError::processing(InvalidProtectedValue, value.dump()))?,
+        };
+        let scope = TermScope {
+            local: context,
+            base_url,
+            protected,
+            override_protected,
+            remote_contexts,
+        };
+        let mut defined = HashMap::new();
+        for (key, _) in context.iter() {
+            match key {
+                "@base" | "@direction" | "@import" | "@language" | "@propagate" | "@protected"
+                | "@version" | "@vocab" => {}
+                _ => self.create_term_definition(result, &scope, key, &mut defined)?,
+            }
+        }
+        Ok(())
+    }
+
+    /// IRI expansion, creating the term definitions of the local context as required.
+    fn expand_iri_defining(
+        &self,
+        result: &mut Context,
+        scope: &TermScope,
+        defined: &mut HashMap<String, bool>,
+        value: &str,
+        document_relative: bool,
+        vocab: bool,
+    ) -> Result<Option<String>, JsonLdError> {
+        if is_keyword(value) {
+            return Ok(Some(value.to_string()));
+        }
+        if has_keyword_form(value) {
+            return Ok(None);
+        }
+        if scope.local.get(value).is_some() && defined.get(value) != Some(&true) {
+            self.create_term_definition(result, scope, value, defined)?;
+        }
+        if let Some(def) = result.get(value) {
+            if let Some(iri) = &def.iri {
+                if is_keyword(iri) {
+                    return Ok(Some(iri.clone()));
+                }
+            }
+            if vocab {
+                return Ok(def.iri.clone());
+            }
+        }
+        if let Some(pos) = value.get(1..).and_then(|v| v.find(':')).map(|p| p + 1) {
+            let prefix = &value[..pos];
+            if scope.local.get(prefix).is_some() && defined.get(prefix) != Some(&true) {
+                self.create_term_definition(result, scope, prefix, defined)?;
+            }
+        }
+        Ok(result.expand_iri(value, document_relative, vocab))
+    }
+
+    /// The [create term definition] algorithm.
+    ///
+    /// [create term definition]: https://www.w3.org/TR/json-ld11-api/#create-term-definition
+    fn create_term_definition(
+        &self,
+        active: &mut Context,
+        scope: &TermScope,
+        term: &str,
+        defined: &mut HashMap<String, bool>,
+    ) -> Result<(), JsonLdError> {
+        match defined.get(term) {
+            Some(true) => return Ok(()),
+            Some(false) => return Err(JsonLdError::processing(CyclicIriMapping, term)),
+            None => {}
+        }
+        if term.is_empty() {
+            return Err(JsonLdError::processing(InvalidTermDefinition, "empty term"));
+        }
+        defined.insert(term.to_string(), false);
+        let value = scope.local.get(term).unwrap();
+
+        if term == "@type" && !self.is_10() {
+            let ok = match value {
+                JsonValue::Object(obj) => {
+                    !obj.is_empty()
+                        && obj.iter().all(|(k, v)| match k {
+                            "@container" => v == "@set",
+                            "@protected" => true,
+                            _ => false,
+                        })
+                }
+                _ => false,
+            };
+            if !ok {
+                return Err(JsonLdError::processing(KeywordRedefinition, term));
+            }
+        } else if is_keyword(term) {
+            return Err(JsonLdError::processing(KeywordRedefinition, term));
+        } else if has_keyword_form(term) {
+            // ignored, as recommended by the spec
+            defined.insert(term.to_string(), true);
+            return Ok(());
+        }
+
+        let previous = active.terms.remove(term);
+        let (value, simple_term) = match value {
+            JsonValue::Null => {
+                let mut obj = Object::new();
+                obj.insert("@id", JsonValue::Null);
+                (obj, false)
+            }
+            JsonValue::String(_) | JsonValue::Short(_) => {
+                let mut obj = Object::new();
+                obj.insert("@id", value.clone());
+                (obj, true)
+            }
+            JsonValue::Object(obj) => (obj.clone(), false),
+            _ => {
+                return Err(JsonLdError::processing(
+                    InvalidTermDefinition,
+                    format!("{}: {}", term, value.dump()),
+                ))
+            }
+        };
+
+        let mut definition = TermDefinition::default();
+        if let Some(protected) = value.get("@protected") {
+            if self.is_10() {
+                return Err(JsonLdError::processing(
+                    InvalidTermDefinition,
+                    "@protected in JSON-LD 1.0 mode",
+                ));
+            }
+            definition.protected = protected
+                .as_bool()
+                .ok_or_else(|| JsonLdError::processing(InvalidProtectedValue, protected.dump()))?;
+        } else {
+            definition.protected = scope.protected;
+        }
+
+        if let Some(typ) = value.get("@type") {
+            let typ = typ
+                .as_str()
+                .ok_or_else(|| JsonLdError::processing(InvalidTypeMapping, typ.dump()))?;
+            let typ = self
+                .expand_iri_defining(active, scope, defined, typ, false, true)?
+                .ok_or_else(|| JsonLdError::processing(InvalidTypeMapping, typ))?;
+            let ok = match typ.as_str() {
+                "@json" | "@none" => !self.is_10(),
+                "@id" | "@vocab" => true,
+                iri => is_iri(iri),
+            };
+            if !ok {
+                return Err(JsonLdError::processing(InvalidTypeMapping, typ));
+            }
+            definition.type_mapping = Some(typ);
+        }
+
+        if let Some(reverse) = value.get("@reverse") {
+            if value.get("@id").is_some() || value.get("@nest").is_some() {
+                return Err(JsonLdError::processing(InvalidReverseProperty, term));
+            }
+            let reverse = reverse
+                .as_str()
+                .ok_or_else(|| JsonLdError::processing(InvalidIriMapping, reverse.dump()))?;
+            if has_keyword_form(reverse) {
+                defined.insert(term.to_string(), true);
+                return Ok(());
+            }
+            let iri = self
+                .expand_iri_defining(active, scope, defined, reverse, false, true)?
+                .filter(|iri| is_iri(iri) || is_bnode_id(iri))
+                .ok_or_else(|| JsonLdError::processing(InvalidIriMapping, reverse))?;
+            definition.iri = Some(iri);
+            if let Some(container) = value.get("@container") {
+                match container.as_str() {
+                    _ if container.is_null() => {}
+                    Some("@set") | Some("@index") => {
+                        definition.container = vec![container.as_str().unwrap().to_string()]
+                    }
+                    _ => {
+                        return Err(JsonLdError::processing(
+                            InvalidReverseProperty,
+                            container.dump(),
+                        ))
+                    }
+                }
+            }
+            definition.reverse = true;
+            active.terms.insert(term.to_string(), definition);
+            defined.insert(term.to_string(), true);
+            return Ok(());
+        }
+
+        let id = value.get("@id").filter(|id| id.as_str() != Some(term));
+        if let Some(id) = id {
+            if !id.is_null() {
+                let id = id
+                    .as_str()
+                    .ok_or_else(|| JsonLdError::processing(InvalidIriMapping, id.dump()))?;
+                if !is_keyword(id) && has_keyword_form(id) {
+                    defined.insert(term.to_string(), true);
+                    return Ok(());
+                }
+                let iri = self
+                    .expand_iri_defining(active, scope, defined, id, false, true)?
+                    .filter(|iri| is_keyword(iri) || is_iri(iri) || is_bnode_id(iri))
+                    .ok_or_else(|| JsonLdError::processing(InvalidIriMapping, id))?;
+                if iri == "@context" {
+                    return Err(JsonLdError::processing(InvalidKeywordAlias, term));
+                }
+                let inner_colon = term
+                    .get(1..term.len().saturating_sub(1))
+                    .map(|t| t.contains(':'))
+                    .unwrap_or(false);
+                if inner_colon || term.contains('/') {
+                    defined.insert(term.to_string(), true);
+                    let expanded =
+                        self.expand_iri_defining(active, scope, defined, term, false, true)?;
+                    if expanded.as_deref() != Some(iri.as_str()) {
+                        return Err(JsonLdError::processing(
+                            InvalidIriMapping,
+                            format!("{} does not expand to {}", term, iri),
+                        ));
+                    }
+                }
+                if !term.contains(':')
+                    && !term.contains('/')
+                    && simple_term
+                    && (is_bnode_id(&iri) || iri.ends_with([':', '/', '?', '#', '[', ']', '@']))
+                {
+                    definition.prefix = true;
+                }
+                definition.iri = Some(iri);
+            }
+        } else if let Some(pos) = term.get(1..).and_then(|t| t.find(':')).map(|p| p + 1) {
+            let (prefix, suffix) = (&term[..pos], &term[pos + 1..]);
+            if scope.local.get(prefix).is_some() {
+                self.create_term_definition(active, scope, prefix, defined)?;
+            }
+            match active.get(prefix).and_then(|def| def.iri.as_ref()) {
+                Some(iri) if !suffix.starts_with("//") => {
+                    definition.iri = Some(format!("{}{}", iri, suffix));
+                }
+                _ => definition.iri = Some(term.to_string()),
+            }
+        } else if term.contains('/') {
+            let iri = active
+                .expand_iri(term, false, true)
+                .filter(|iri| is_iri(iri))
+                .ok_or_else(|| JsonLdError::processing(InvalidIriMapping, term))?;
+            definition.iri = Some(iri);
+        } else if term == "@type" {
+            definition.iri = Some("@type".to_string());
+        } else if let Some(vocab) = &active.vocab {
+            definition.iri = Some(format!("{}{}", vocab, term));
+        } else {
+            return Err(JsonLdError::processing(
+                InvalidIriMapping,
+                format!("{} can not be expanded (no @vocab)", term),
+            ));
+        }
+
+        if let Some(container) = value.get("@container") {
+            definition.container = self.check_container(container)?;
+            if definition.has_container("@type") {
+                match definition.type_mapping.as_deref() {
+                    None => definition.type_mapping = Some("@id".to_string()),
+                    Some("@id") | Some("@vocab") => {}
+                    Some(typ) => return Err(JsonLdError::processing(InvalidTypeMapping, typ)),
+                }
+            }
+        }
+
+        if let Some(index) = value.get("@index") {
+            if self.is_10() || !definition.has_container("@index") {
+                return Err(JsonLdError::processing(
+                    InvalidTermDefinition,
+                    format!("{}: @index without @index container", term),
+                ));
+            }
+            let index = index
+                .as_str()
+                .ok_or_else(|| JsonLdError::processing(InvalidTermDefinition, index.dump()))?;
+            match active.expand_iri(index, false, true) {
+                Some(iri) if is_iri(&iri) => {}
+                _ => return Err(JsonLdError::processing(InvalidTermDefinition, index)),
+            }
+            definition.index = Some(index.to_string());
+        }
+
+        if let Some(context) = value.get("@context") {
+            if self.is_10() {
+                return Err(JsonLdError::processing(
+                    InvalidTermDefinition,
+                    "@context in term definition in JSON-LD 1.0 mode",
+                ));
+            }
+            self.process(
+                active,
+                context,
+                scope.base_url,
+                scope.remote_contexts,
+                true,
+                true,
+                false,
+            )
+            .map_err(|e| JsonLdError::processing(InvalidScopedContext, e))?;
+            definition.context = Some(context.clone());
+            definition.base_url = scope.base_url.map(str::to_string);
+        }
+
+        if value.get("@type").is_none() {
+            if let Some(language) = value.get("@language") {
+                if language.is_null() {
+                    definition.language = Some(None);
+                } else {
+                    let language = language.as_str().ok_or_else(|| {
+                        JsonLdError::processing(InvalidLanguageMapping, language.dump())
+                    })?;
+                    definition.language = Some(Some(language.to_ascii_lowercase()));
+                }
+            }
+            if let Some(direction) = value.get("@direction") {
+                if direction.is_null() {
+                    definition.direction = Some(None);
+                } else {
+                    definition.direction = Some(Some(parse_direction(direction)?));
+                }
+            }
+        }
+
+        if let Some(nest) = value.get("@nest") {
+            if self.is_10() {
+                return Err(JsonLdError::processing(
+                    InvalidTermDefinition,
+                    "@nest in JSON-LD 1.0 mode",
+                ));
+            }
+            let nest = nest
+                .as_str()
+                .filter(|n| *n == "@nest" || !is_keyword(n))
+                .ok_or_else(|| JsonLdError::processing(InvalidNestValue, nest.dump()))?;
+            definition.nest = Some(nest.to_string());
+        }
+
+        if let Some(prefix) = value.get("@prefix") {
+            if self.is_10() || term.contains(':') || term.contains('/') {
+                return Err(JsonLdError::processing(
+                    InvalidTermDefinition,
+                    format!("{}: @prefix not allowed", term),
+                ));
+            }
+            definition.prefix = prefix
+                .as_bool()
+                .ok_or_else(|| JsonLdError::processing(InvalidPrefixValue, prefix.dump()))?;
+            if definition.prefix && definition.iri.as_deref().map(is_keyword).unwrap_or(false) {
+                return Err(JsonLdError::processing(
+                    InvalidTermDefinition,
+                    format!("{}: a keyword alias can not be a prefix", term),
+                ));
+            }
+        }
+
+        for (key, _) in value.iter() {
+            match key {
+                "@id" | "@reverse" | "@container" | "@context" | "@direction" | "@index"
+                | "@language" | "@nest" | "@prefix" | "@protected" | "@type" => {}
+                _ => {
+                    return Err(JsonLdError::processing(
+                        InvalidTermDefinition,
+                        format!("{}: unexpected entry {}", term, key),
+                    ))
+                }
+            }
+        }
+
+        if let Some(previous) = previous {
+            if !scope.override_protected && previous.protected {
+                if !definition.same_as(&previous) {
+                    return Err(JsonLdError::processing(ProtectedTermRedefinition, term));
+                }
+                definition = previous;
+            }
+        }
+        active.terms.insert(term.to_string(), definition);
+        defined.insert(term.to_string(), true);
+        Ok(())
+    }
+
+    /// Check the value of `@container` in a term definition,
+    /// and return it as a list of containers.
+    fn check_container(&self, container: &JsonValue) -> Result<Vec<String>, JsonLdError> {
+        let err = || JsonLdError::processing(InvalidContainerMapping, container.dump());
+        let mut containers = vec![];
+        for c in as_slice(container) {
+            containers.push(c.as_str().ok_or_else(err)?.to_string());
+        }
+        if self.is_10() {
+            return match (container.as_str(), containers.len()) {
+                (Some("@list" | "@set" | "@index" | "@language"), 1) => Ok(containers),
+                _ => Err(err()),
+            };
+        }
+        let has = |c: &str| containers.iter().any(|x| x == c);
+        let valid = match containers.len() {
+            1 => matches!(
+                containers[0].as_str(),
+                "@graph" | "@id" | "@index" | "@language" | "@list" | "@set" | "@type"
+            ),
+            _ if has("@list") => false,
+            2 if has("@graph") => has("@id") || has("@index") || has("@set"),
+            3 if has("@graph") => has("@set") && (has("@id") || has("@index")),
+            2 if has("@set") => containers.iter().all(|c| {
+                matches!(
+                    c.as_str(),
+                    "@set" | "@index" | "@graph" | "@id" | "@type" | "@language"
+                )
+            }),
+            _ => false,
+        };
+        if valid {
+            Ok(containers)
+        } else {
+            Err(err())
+        }
+    }
+}
+
+/// Parse the value of `@direction`.
+pub fn parse_direction(value: &JsonValue) -> Result<String, JsonLdError> {
+    match value.as_str() {
+        Some(d @ ("ltr" | "rtl")) => Ok(d.to_string()),
+        _ => Err(JsonLdError::processing(InvalidBaseDirection, value.dump())),
+    }
+}
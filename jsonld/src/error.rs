@@ -1,6 +1,8 @@
 //! JSON-LD errors.
 
 use crate::config::JsonLdSpecVersion;
+use sophia_api::parser::LimitExceeded;
+use std::fmt;
 
 /// JSON-LD error
 #[derive(Debug, thiserror::Error)]
@@ -14,7 +16,151 @@ pub enum JsonLdError {
     #[error("IO Error: {0}")]
     IoError(#[from] std::io::Error),
 
+    /// One of the [limits](crate::JsonLdConfig::limits) of the parser was exceeded.
+    #[error("{0}")]
+    LimitExceeded(#[from] LimitExceeded),
+
     /// An unsupported JSON-LD version
     #[error("unsupported version: {0:?}")]
     UnsupportedVersion(JsonLdSpecVersion),
+
+    /// An error raised by one of the [JSON-LD algorithms],
+    /// with the corresponding error code and a message.
+    ///
+    /// [JSON-LD algorithms]: https://www.w3.org/TR/json-ld11-api/#jsonlderrorcode
+    #[error("{0}: {1}")]
+    Processing(JsonLdErrorCode, String),
+}
+
+impl JsonLdError {
+    /// Build a [`JsonLdError::Processing`] error.
+    pub(crate) fn processing<T: fmt::Display>(code: JsonLdErrorCode, message: T) -> Self {
+        JsonLdError::Processing(code, message.to_string())
+    }
+}
+
+/// The error codes defined by the [JSON-LD 1.1 Processing Algorithms and API]
+/// and by [JSON-LD 1.1 Framing].
+///
+/// They are displayed as in the specification (e.g. `invalid @id value`).
+///
+/// [JSON-LD 1.1 Processing Algorithms and API]: https://www.w3.org/TR/json-ld11-api/#jsonlderrorcode
+/// [JSON-LD 1.1 Framing]: https://www.w3.org/TR/json-ld11-framing/#error-handling
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+#[non_exhaustive]
+#[allow(missing_docs)]
+pub enum JsonLdErrorCode {
+    CollidingKeywords,
+    ConflictingIndexes,
+    ContextOverflow,
+    CyclicIriMapping,
+    InvalidIdValue,
+    InvalidImportValue,
+    InvalidIncludedValue,
+    InvalidIndexValue,
+    InvalidNestValue,
+    InvalidPrefixValue,
+    InvalidPropagateValue,
+    InvalidProtectedValue,
+    InvalidReverseValue,
+    InvalidVersionValue,
+    InvalidBaseDirection,
+    InvalidBaseIri,
+    InvalidContainerMapping,
+    InvalidContextEntry,
+    InvalidContextNullification,
+    InvalidDefaultLanguage,
+    InvalidEmbedValue,
+    InvalidFrame,
+    InvalidIriMapping,
+    InvalidJsonLiteral,
+    InvalidKeywordAlias,
+    InvalidLanguageMapValue,
+    InvalidLanguageMapping,
+    InvalidLanguageTaggedString,
+    InvalidLanguageTaggedValue,
+    InvalidLocalContext,
+    InvalidRemoteContext,
+    InvalidReverseProperty,
+    InvalidReversePropertyMap,
+    InvalidReversePropertyValue,
+    InvalidScopedContext,
+    InvalidSetOrListObject,
+    InvalidTermDefinition,
+    InvalidTypeMapping,
+    InvalidTypeValue,
+    InvalidTypedValue,
+    InvalidValueObject,
+    InvalidValueObjectValue,
+    InvalidVocabMapping,
+    IriConfusedWithPrefix,
+    KeywordRedefinition,
+    LoadingDocumentFailed,
+    LoadingRemoteContextFailed,
+    ProcessingModeConflict,
+    ProtectedTermRedefinition,
+}
+
+impl JsonLdErrorCode {
+    /// The error code, as it appears in the specification.
+    pub fn as_str(&self) -> &'static str {
+        use JsonLdErrorCode::*;
+        match self {
+            CollidingKeywords => "colliding keywords",
+            ConflictingIndexes => "conflicting indexes",
+            ContextOverflow => "context overflow",
+            CyclicIriMapping => "cyclic IRI mapping",
+            InvalidIdValue => "invalid @id value",
+            InvalidImportValue => "invalid @import value",
+            InvalidIncludedValue => "invalid @included value",
+            InvalidIndexValue => "invalid @index value",
+            InvalidNestValue => "invalid @nest value",
+            InvalidPrefixValue => "invalid @prefix value",
+            InvalidPropagateValue => "invalid @propagate value",
+            InvalidProtectedValue => "invalid @protected value",
+            InvalidReverseValue => "invalid @reverse value",
+            InvalidVersionValue => "invalid @version value",
+            InvalidBaseDirection => "invalid base direction",
+            InvalidBaseIri => "invalid base IRI",
+            InvalidContainerMapping => "invalid container mapping",
+            InvalidContextEntry => "invalid context entry",
+            InvalidContextNullification => "invalid context nullification",
+            InvalidDefaultLanguage => "invalid default language",
+            InvalidEmbedValue => "invalid @embed value",
+            InvalidFrame => "invalid frame",
+            InvalidIriMapping => "invalid IRI mapping",
+            InvalidJsonLiteral => "invalid JSON literal",
+            InvalidKeywordAlias => "invalid keyword alias",
+            InvalidLanguageMapValue => "invalid language map value",
+            InvalidLanguageMapping => "invalid language mapping",
+            InvalidLanguageTaggedString => "invalid language-tagged string",
+            InvalidLanguageTaggedValue => "invalid language-tagged value",
+            InvalidLocalContext => "invalid local context",
+            InvalidRemoteContext => "invalid remote context",
+            InvalidReverseProperty => "invalid reverse property",
+            InvalidReversePropertyMap => "invalid reverse property map",
+            InvalidReversePropertyValue => "invalid reverse property value",
+            InvalidScopedContext => "invalid scoped context",
+            InvalidSetOrListObject => "invalid set or list object",
+            InvalidTermDefinition => "invalid term definition",
+            InvalidTypeMapping => "invalid type mapping",
+            InvalidTypeValue => "invalid type value",
+            InvalidTypedValue => "invalid typed value",
+            InvalidValueObject => "invalid value object",
+            InvalidValueObjectValue => "invalid value object value",
+            InvalidVocabMapping => "invalid vocab mapping",
+            IriConfusedWithPrefix => "IRI confused with prefix",
+            KeywordRedefinition => "keyword redefinition",
+            LoadingDocumentFailed => "loading document failed",
+            LoadingRemoteContextFailed => "loading remote context failed",
+            ProcessingModeConflict => "processing mode conflict",
+            ProtectedTermRedefinition => "protected term redefinition",
+        }
+    }
+}
+
+impl fmt::Display for JsonLdErrorCode {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
 }
@@ -0,0 +1,830 @@
+//! The [expansion algorithms] of JSON-LD.
+//!
+//! [expansion algorithms]: https://www.w3.org/TR/json-ld11-api/#expansion-algorithms
+use crate::context::*;
+use crate::error::{JsonLdError, JsonLdErrorCode::*};
+use crate::loader::DocumentLoader;
+use json::object::Object;
+use json::JsonValue;
+use std::rc::Rc;
+
+/// Implements the expansion algorithms.
+pub struct Expander<'p, 'a, L: ?Sized> {
+    processor: &'p ContextProcessor<'a, L>,
+    frame_expansion: bool,
+}
+
+impl<'p, 'a, L: DocumentLoader + ?Sized> Expander<'p, 'a, L> {
+    /// Build a new expander, using the given context processor.
+    pub fn new(processor: &'p ContextProcessor<'a, L>) -> Self {
+        Expander {
+            processor,
+            frame_expansion: false,
+        }
+    }
+
+    /// Build a new expander for [frames], using the given context processor.
+    ///
+    /// This sets the `frameExpansion` flag of the expansion algorithm,
+    /// which relaxes the constraints on some keywords, and keeps framing keywords.
+    ///
+    /// [frames]: https://www.w3.org/TR/json-ld11-framing/#dfn-frame
+    pub fn new_for_frame(processor: &'p ContextProcessor<'a, L>) -> Self {
+        Expander {
+            processor,
+            frame_expansion: true,
+        }
+    }
+
+    /// Expand a whole document, as per the [`expand()`] method.
+    ///
+    /// [`expand()`]: https://www.w3.org/TR/json-ld11-api/#dom-jsonldprocessor-expand
+    pub fn expand_document(
+        &self,
+        active: &Rc<Context>,
+        document: &JsonValue,
+        base_url: Option<&str>,
+    ) -> Result<Vec<JsonValue>, JsonLdError> {
+        let expanded = self.expand(active, None, document, base_url, false)?;
+        Ok(match expanded {
+            JsonValue::Object(mut obj) if obj.len() == 1 && obj.get("@graph").is_some() => {
+                as_array(obj.remove("@graph").unwrap())
+            }
+            JsonValue::Null => vec![],
+            expanded => as_array(expanded),
+        })
+    }
+
+    /// The [expansion algorithm].
+    ///
+    /// [expansion algorithm]: https://www.w3.org/TR/json-ld11-api/#expansion-algorithm
+    pub fn expand(
+        &self,
+        active: &Rc<Context>,
+        active_property: Option<&str>,
+        element: &JsonValue,
+        base_url: Option<&str>,
+        from_map: bool,
+    ) -> Result<JsonValue, JsonLdError> {
+        let property_def = active_property.and_then(|p| active.get(p));
+        let property_scoped = property_def.and_then(|def| def.context.as_ref());
+        match element {
+            JsonValue::Null => Ok(JsonValue::Null),
+            JsonValue::Array(items) => {
+                let is_list = active_property
+                    .map(|p| active.has_container(p, "@list"))
+                    .unwrap_or(false);
+                let mut result = vec![];
+                for item in items {
+                    let expanded =
+                        self.expand(active, active_property, item, base_url, from_map)?;
+                    match expanded {
+                        JsonValue::Array(v) if is_list => result.push(json::object! {"@list": v}),
+                        JsonValue::Array(v) => result.extend(v),
+                        JsonValue::Null => {}
+                        expanded => result.push(expanded),
+                    }
+                }
+                Ok(JsonValue::Array(result))
+            }
+            JsonValue::Object(obj) => {
+                let mut active = active.clone();
+                if let Some(previous) = &active.previous {
+                    if !from_map && !self.has_value_or_single_id(&active, obj) {
+                        active = previous.clone();
+                    }
+                }
+                if let Some(ctx) = property_scoped {
+                    let def = property_def.unwrap();
+                    active = Rc::new(self.processor.process(
+                        &active,
+                        ctx,
+                        def.base_url.as_deref(),
+                        &[],
+                        true,
+                        true,
+                        true,
+                    )?);
+                }
+                self.expand_object(active, active_property, obj, base_url)
+            }
+            _ => {
+                // scalar
+                if matches!(active_property, None | Some("@graph")) {
+                    return Ok(JsonValue::Null);
+                }
+                let active = match property_scoped {
+                    None => active.clone(),
+                    Some(ctx) => Rc::new(self.processor.process(
+                        active,
+                        ctx,
+                        property_def.unwrap().base_url.as_deref(),
+                        &[],
+                        false,
+                        true,
+                        true,
+                    )?),
+                };
+                Ok(self.expand_value(&active, active_property, element))
+            }
+        }
+    }
+
+    /// Whether `obj` contains an entry expanding to `@value`,
+    /// or consists of a single entry expanding to `@id`.
+    fn has_value_or_single_id(&self, active: &Context, obj: &Object) -> bool {
+        let expands_to =
+            |key: &str, kw: &str| active.expand_iri(key, false, true).as_deref() == Some(kw);
+        obj.iter().any(|(k, _)| expands_to(k, "@value"))
+            || (obj.len() == 1 && obj.iter().all(|(k, _)| expands_to(k, "@id")))
+    }
+
+    /// Steps 9 to 20 of the expansion algorithm.
+    fn expand_object(
+        &self,
+        mut active: Rc<Context>,
+        active_property: Option<&str>,
+        element: &Object,
+        base_url: Option<&str>,
+    ) -> Result<JsonValue, JsonLdError> {
+        if let Some(ctx) = element.get("@context") {
+            active =
+                Rc::new(
+                    self.processor
+                        .process(&active, ctx, base_url, &[], false, true, true)?,
+                );
+        }
+        let type_scoped = active.clone();
+        let mut input_type = None;
+        for key in sorted_keys(element) {
+            if active.expand_iri(key, false, true).as_deref() != Some("@type") {
+                continue;
+            }
+            let mut types: Vec<&str> = as_slice(&element[key])
+                .iter()
+                .filter_map(|t| t.as_str())
+                .collect();
+            if input_type.is_none() {
+                input_type = types.last().and_then(|t| active.expand_iri(t, false, true));
+            }
+            types.sort_unstable();
+            for t in types {
+                if let Some(def) = type_scoped.get(t) {
+                    if let Some(ctx) = &def.context {
+                        active = Rc::new(self.processor.process(
+                            &active,
+                            ctx,
+                            def.base_url.as_deref(),
+                            &[],
+                            false,
+                            false,
+                            true,
+                        )?);
+                    }
+                }
+            }
+        }
+
+        let mut result = Object::new();
+        self.expand_entries(
+            &active,
+            &type_scoped,
+            active_property,
+            element,
+            base_url,
+            input_type.as_deref(),
+            &mut result,
+        )?;
+
+        if self.frame_expansion {
+            if let Some(typ) = result.get_mut("@type") {
+                if !typ.is_array() {
+                    *typ = JsonValue::Array(vec![typ.take()]);
+                }
+            }
+            return Ok(JsonValue::Object(result));
+        }
+
+        let mut result = if result.get("@value").is_some() {
+            let invalid = result.iter().any(|(k, _)| {
+                !matches!(
+                    k,
+                    "@direction" | "@index" | "@language" | "@type" | "@value"
+                )
+            }) || (result.get("@type").is_some()
+                && (result.get("@language").is_some() || result.get("@direction").is_some()));
+            if invalid {
+                return Err(JsonLdError::processing(
+                    InvalidValueObject,
+                    JsonValue::Object(result).dump(),
+                ));
+            }
+            let typ = result.get("@type");
+            if typ.map(|t| t == "@json").unwrap_or(false) {
+                // no further validation
+            } else if result["@value"].is_null() {
+                return Ok(JsonValue::Null);
+            } else if !result["@value"].is_string() && result.get("@language").is_some() {
+                return Err(JsonLdError::processing(
+                    InvalidLanguageTaggedValue,
+                    result["@value"].dump(),
+                ));
+            } else if let Some(typ) = typ {
+                if !typ.as_str().map(is_iri).unwrap_or(false) {
+                    return Err(JsonLdError::processing(InvalidTypedValue, typ.dump()));
+                }
+            }
+            JsonValue::Object(result)
+        } else if let Some(typ) = result.get_mut("@type") {
+            if !typ.is_array() {
+                *typ = JsonValue::Array(vec![typ.take()]);
+            }
+            JsonValue::Object(result)
+        } else if result.get("@set").is_some() || result.get("@list").is_some() {
+            if result.len() > 2 || (result.len() == 2 && result.get("@index").is_none()) {
+                return Err(JsonLdError::processing(
+                    InvalidSetOrListObject,
+                    JsonValue::Object(result).dump(),
+                ));
+            }
+            match result.remove("@set") {
+                Some(set) => set,
+                None => JsonValue::Object(result),
+            }
+        } else {
+            JsonValue::Object(result)
+        };
+
+        if let JsonValue::Object(obj) = &mut result {
+            if obj.len() == 1 && obj.get("@language").is_some() {
+                return Ok(JsonValue::Null);
+            }
+            if matches!(active_property, None | Some("@graph"))
+                && (obj.is_empty()
+                    || obj.get("@value").is_some()
+                    || obj.get("@list").is_some()
+                    || (obj.len() == 1 && obj.get("@id").is_some()))
+            {
+                return Ok(JsonValue::Null);
+            }
+        }
+        Ok(result)
+    }
+
+    /// Steps 13 and 14 of the expansion algorithm.
+    #[allow(clippy::too_many_arguments)]
+    fn expand_entries(
+        &self,
+        active: &Rc<Context>,
+        type_scoped: &Rc<Context>,
+        active_property: Option<&str>,
+        element: &Object,
+        base_url: Option<&str>,
+        input_type: Option<&str>,
+        result: &mut Object,
+    ) -> Result<(), JsonLdError> {
+        let is_10 = self.processor.is_10();
+        let mut nests: Vec<&str> = vec![];
+        for key in sorted_keys(element) {
+            let value = &element[key];
+            if key == "@context" {
+                continue;
+            }
+            let expanded_property = match active.expand_iri(key, false, true) {
+                Some(p) if p.contains(':') || is_keyword(&p) => p,
+                _ => continue,
+            };
+            let ep = expanded_property.as_str();
+            if is_keyword(ep) {
+                if active_property == Some("@reverse") {
+                    return Err(JsonLdError::processing(InvalidReversePropertyMap, key));
+                }
+                if result.get(ep).is_some() && (is_10 || !matches!(ep, "@included" | "@type")) {
+                    return Err(JsonLdError::processing(CollidingKeywords, ep));
+                }
+                let expanded_value = match ep {
+                    "@id" if self.frame_expansion => {
+                        let mut ids = vec![];
+                        for id in as_slice(value) {
+                            ids.push(match id {
+                                JsonValue::Object(obj) if obj.is_empty() => id.clone(),
+                                JsonValue::Short(_) | JsonValue::String(_) => active
+                                    .expand_iri(id.as_str().unwrap(), true, false)
+                                    .map(JsonValue::from)
+                                    .unwrap_or(JsonValue::Null),
+                                _ => {
+                                    return Err(JsonLdError::processing(InvalidIdValue, id.dump()))
+                                }
+                            });
+                        }
+                        JsonValue::Array(ids)
+                    }
+                    "@id" => {
+                        let id = value
+                            .as_str()
+                            .ok_or_else(|| JsonLdError::processing(InvalidIdValue, value.dump()))?;
+                        active
+                            .expand_iri(id, true, false)
+                            .map(JsonValue::from)
+                            .unwrap_or(JsonValue::Null)
+                    }
+                    "@type" => {
+                        let mut types = vec![];
+                        for t in as_slice(value) {
+                            if self.frame_expansion && t.is_object() {
+                                types.push(self.expand_frame_type(type_scoped, t)?);
+                                continue;
+                            }
+                            let t = t.as_str().ok_or_else(|| {
+                                JsonLdError::processing(InvalidTypeValue, value.dump())
+                            })?;
+                            if let Some(t) = type_scoped.expand_iri(t, true, true) {
+                                types.push(JsonValue::from(t));
+                            }
+                        }
+                        match result.remove("@type") {
+                            Some(previous) => {
+                                let mut v = as_array(previous);
+                                v.extend(types);
+                                JsonValue::Array(v)
+                            }
+                            None if value.is_string() => types.pop().unwrap_or(JsonValue::Null),
+                            None => JsonValue::Array(types),
+                        }
+                    }
+                    "@graph" => JsonValue::Array(as_array(self.expand(
+                        active,
+                        Some("@graph"),
+                        value,
+                        base_url,
+                        false,
+                    )?)),
+                    "@included" => {
+                        if is_10 {
+                            continue;
+                        }
+                        let included = as_array(self.expand(active, None, value, base_url, false)?);
+                        if !included.iter().all(is_node_object) {
+                            return Err(JsonLdError::processing(
+                                InvalidIncludedValue,
+                                value.dump(),
+                            ));
+                        }
+                        let included = match result.remove("@included") {
+                            Some(previous) => {
+                                let mut v = as_array(previous);
+                                v.extend(included);
+                                v
+                            }
+                            None => included,
+                        };
+                        JsonValue::Array(included)
+                    }
+                    "@value" | "@language" | "@direction" if self.frame_expansion => {
+                        let values = as_slice(value).iter().map(|v| match v.as_str() {
+                            Some(txt) if ep == "@language" => txt.to_ascii_lowercase().into(),
+                            _ => v.clone(),
+                        });
+                        JsonValue::Array(values.collect())
+                    }
+                    "@default" if self.frame_expansion => {
+                        if value == "@null" {
+                            JsonValue::Array(vec![value.clone()])
+                        } else {
+                            let expanded =
+                                self.expand(active, active_property, value, base_url, false)?;
+                            JsonValue::Array(as_array(expanded))
+                        }
+                    }
+                    "@embed" | "@explicit" | "@omitDefault" | "@requireAll"
+                        if self.frame_expansion =>
+                    {
+                        JsonValue::Array(as_slice(value).to_vec())
+                    }
+                    "@value" => {
+                        if input_type == Some("@json") {
+                            if is_10 {
+                                return Err(JsonLdError::processing(
+                                    InvalidValueObjectValue,
+                                    "@json in JSON-LD 1.0 mode",
+                                ));
+                            }
+                            value.clone()
+                        } else if value.is_null() {
+                            result.insert("@value", JsonValue::Null);
+                            continue;
+                        } else if is_scalar(value) {
+                            value.clone()
+                        } else {
+                            return Err(JsonLdError::processing(
+                                InvalidValueObjectValue,
+                                value.dump(),
+                            ));
+                        }
+                    }
+                    "@language" => {
+                        let lang = value.as_str().ok_or_else(|| {
+                            JsonLdError::processing(InvalidLanguageTaggedString, value.dump())
+                        })?;
+                        JsonValue::from(lang.to_ascii_lowercase())
+                    }
+                    "@direction" => {
+                        if is_10 {
+                            continue;
+                        }
+                        JsonValue::from(parse_direction(value)?)
+                    }
+                    "@index" => {
+                        if !value.is_string() {
+                            return Err(JsonLdError::processing(InvalidIndexValue, value.dump()));
+                        }
+                        value.clone()
+                    }
+                    "@list" => {
+                        if matches!(active_property, None | Some("@graph")) {
+                            continue;
+                        }
+                        JsonValue::Array(as_array(self.expand(
+                            active,
+                            active_property,
+                            value,
+                            base_url,
+                            false,
+                        )?))
+                    }
+                    "@set" => self.expand(active, active_property, value, base_url, false)?,
+                    "@reverse" => {
+                        if !value.is_object() {
+                            return Err(JsonLdError::processing(InvalidReverseValue, value.dump()));
+                        }
+                        let expanded =
+                            self.expand(active, Some("@reverse"), value, base_url, false)?;
+                        if let JsonValue::Object(mut expanded) = expanded {
+                            if let Some(JsonValue::Object(reverse)) = expanded.remove("@reverse") {
+                                for (property, item) in reverse.iter() {
+                                    add_value(result, property, item.clone(), true);
+                                }
+                            }
+                            if !expanded.is_empty() {
+                                let mut reverse_map = match result.remove("@reverse") {
+                                    Some(JsonValue::Object(map)) => map,
+                                    _ => Object::new(),
+                                };
+                                for (property, items) in expanded.iter() {
+                                    for item in as_slice(items) {
+                                        if is_value_object(item) || is_list_object(item) {
+                                            return Err(JsonLdError::processing(
+                                                InvalidReversePropertyValue,
+                                                item.dump(),
+                                            ));
+                                        }
+                                        add_value(&mut reverse_map, property, item.clone(), true);
+                                    }
+                                }
+                                result.insert("@reverse", JsonValue::Object(reverse_map));
+                            }
+                        }
+                        continue;
+                    }
+                    "@nest" => {
+                        if !nests.contains(&key) {
+                            nests.push(key);
+                        }
+                        continue;
+                    }
+                    _ => continue,
+                };
+                if !expanded_value.is_null() || (ep == "@value" && input_type == Some("@json")) {
+                    result.insert(ep, expanded_value);
+                }
+                continue;
+            }
+
+            let def = active.get(key);
+            let has_container = |c: &str| def.map(|d| d.has_container(c)).unwrap_or(false);
+            let expanded_value = if def.and_then(|d| d.type_mapping.as_deref()) == Some("@json") {
+                json::object! {"@value": value.clone(), "@type": "@json"}
+            } else if has_container("@language") && value.is_object() {
+                let direction = match def.and_then(|d| d.direction.as_ref()) {
+                    Some(d) => d.clone(),
+                    None => active.direction.clone(),
+                };
+                let mut expanded = vec![];
+                for language in sorted_keys(value_object(value)) {
+                    for item in as_slice(&value[language]) {
+                        if item.is_null() {
+                            continue;
+                        }
+                        if !item.is_string() {
+                            return Err(JsonLdError::processing(
+                                InvalidLanguageMapValue,
+                                item.dump(),
+                            ));
+                        }
+                        let mut v = json::object! {"@value": item.clone()};
+                        if language != "@none"
+                            && active.expand_iri(language, false, true).as_deref() != Some("@none")
+                        {
+                            v["@language"] = language.to_ascii_lowercase().into();
+                        }
+                        if let Some(d) = &direction {
+                            v["@direction"] = d.as_str().into();
+                        }
+                        expanded.push(v);
+                    }
+                }
+                JsonValue::Array(expanded)
+            } else if (has_container("@index") || has_container("@type") || has_container("@id"))
+                && value.is_object()
+            {
+                self.expand_index_map(active, key, value_object(value), base_url)?
+            } else {
+                self.expand(active, Some(key), value, base_url, false)?
+            };
+            if expanded_value.is_null() {
+                continue;
+            }
+            let expanded_value = if has_container("@list") && !is_list_object(&expanded_value) {
+                json::object! {"@list": JsonValue::Array(as_array(expanded_value))}
+            } else {
+                expanded_value
+            };
+            let expanded_value =
+                if has_container("@graph") && !has_container("@id") && !has_container("@index") {
+                    JsonValue::Array(
+                        as_array(expanded_value)
+                            .into_iter()
+                            .map(|ev| json::object! {"@graph": JsonValue::Array(as_array(ev))})
+                            .collect(),
+                    )
+                } else {
+                    expanded_value
+                };
+            if def.map(|d| d.reverse).unwrap_or(false) {
+                let mut reverse_map = match result.remove("@reverse") {
+                    Some(JsonValue::Object(map)) => map,
+                    _ => Object::new(),
+                };
+                for item in as_array(expanded_value) {
+                    if is_value_object(&item) || is_list_object(&item) {
+                        return Err(JsonLdError::processing(
+                            InvalidReversePropertyValue,
+                            item.dump(),
+                        ));
+                    }
+                    add_value(&mut reverse_map, ep, item, true);
+                }
+                result.insert("@reverse", JsonValue::Object(reverse_map));
+            } else {
+                add_value(result, ep, expanded_value, true);
+            }
+        }
+
+        for nesting_key in nests {
+            for nested in as_slice(&element[nesting_key]) {
+                let nested = match nested {
+                    JsonValue::Object(obj)
+                        if !obj.iter().any(|(k, _)| {
+                            active.expand_iri(k, false, true).as_deref() == Some("@value")
+                        }) =>
+                    {
+                        obj
+                    }
+                    _ => return Err(JsonLdError::processing(InvalidNestValue, nested.dump())),
+                };
+                self.expand_entries(
+                    active,
+                    type_scoped,
+                    active_property,
+                    nested,
+                    base_url,
+                    input_type,
+                    result,
+                )?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Expand a map used as a value of `@type` in a frame:
+    /// either a wildcard (`{}`) or a default object.
+    fn expand_frame_type(
+        &self,
+        active: &Context,
+        value: &JsonValue,
+    ) -> Result<JsonValue, JsonLdError> {
+        match value {
+            JsonValue::Object(obj) if obj.is_empty() => Ok(value.clone()),
+            JsonValue::Object(obj) if obj.len() == 1 => match obj["@default"].as_str() {
+                Some(default) => Ok(json::object! {
+                    "@default": active.expand_iri(default, true, true)
+                }),
+                None => Err(JsonLdError::processing(InvalidTypeValue, value.dump())),
+            },
+            _ => Err(JsonLdError::processing(InvalidTypeValue, value.dump())),
+        }
+    }
+
+    /// Step 13.8 of the expansion algorithm (index, type and id maps).
+    fn expand_index_map(
+        &self,
+        active: &Rc<Context>,
+        key: &str,
+        value: &Object,
+        base_url: Option<&str>,
+    ) -> Result<JsonValue, JsonLdError> {
+        let def = active.get(key).unwrap();
+        let index_key = def.index.as_deref().unwrap_or("@index");
+        let mut expanded = vec![];
+        for index in sorted_keys(value) {
+            let index_value = &value[index];
+            let mut map_context = active.clone();
+            if def.has_container("@type") {
+                if let Some(previous) = &active.previous {
+                    map_context = previous.clone();
+                }
+                if let Some(index_def) = map_context.get(index) {
+                    if let Some(ctx) = &index_def.context {
+                        map_context = Rc::new(self.processor.process(
+                            &map_context,
+                            ctx,
+                            index_def.base_url.as_deref(),
+                            &[],
+                            false,
+                            true,
+                            true,
+                        )?);
+                    }
+                }
+            }
+            let expanded_index = active.expand_iri(index, false, true);
+            let is_none = expanded_index.as_deref() == Some("@none");
+            let index_value = JsonValue::Array(as_slice(index_value).to_vec());
+            let items = self.expand(&map_context, Some(key), &index_value, base_url, true)?;
+            for mut item in as_array(items) {
+                if def.has_container("@graph") && !is_graph_object(&item) {
+                    item = json::object! {"@graph": JsonValue::Array(as_array(item))};
+                }
+                if def.has_container("@index") && index_key != "@index" && !is_none {
+                    let re_expanded =
+                        self.expand_value(active, Some(index_key), &JsonValue::from(index));
+                    let expanded_index_key = active
+                        .expand_iri(index_key, false, true)
+                        .unwrap_or_default();
+                    let mut values = vec![re_expanded];
+                    if let JsonValue::Object(obj) = &mut item {
+                        if let Some(previous) = obj.remove(&expanded_index_key) {
+                            values.extend(as_array(previous));
+                        }
+                    }
+                    item[expanded_index_key.as_str()] = JsonValue::Array(values);
+                    if is_value_object(&item) {
+                        return Err(JsonLdError::processing(InvalidValueObject, item.dump()));
+                    }
+                } else if def.has_container("@index") && item["@index"].is_null() && !is_none {
+                    item["@index"] = index.into();
+                } else if def.has_container("@id") && item["@id"].is_null() && !is_none {
+                    item["@id"] = active
+                        .expand_iri(index, true, false)
+                        .map(JsonValue::from)
+                        .unwrap_or(JsonValue::Null);
+                } else if def.has_container("@type") && !is_none {
+                    let mut types = vec![JsonValue::from(expanded_index.clone())];
+                    if let JsonValue::Object(obj) = &mut item {
+                        if let Some(previous) = obj.remove("@type") {
+                            types.extend(as_array(previous));
+                        }
+                    }
+                    item["@type"] = JsonValue::Array(types);
+                }
+                expanded.push(item);
+            }
+        }
+        Ok(JsonValue::Array(expanded))
+    }
+
+    /// The [value expansion] algorithm.
+    ///
+    /// [value expansion]: https://www.w3.org/TR/json-ld11-api/#value-expansion
+    pub fn expand_value(
+        &self,
+        active: &Context,
+        active_property: Option<&str>,
+        value: &JsonValue,
+    ) -> JsonValue {
+        let def = active_property.and_then(|p| active.get(p));
+        let type_mapping = def.and_then(|d| d.type_mapping.as_deref());
+        if let Some(txt) = value.as_str() {
+            let iri = match type_mapping {
+                Some("@id") => Some(active.expand_iri(txt, true, false)),
+                Some("@vocab") => Some(active.expand_iri(txt, true, true)),
+                _ => None,
+            };
+            if let Some(iri) = iri {
+                return json::object! {"@id": iri.map(JsonValue::from).unwrap_or(JsonValue::Null)};
+            }
+        }
+        let mut result = json::object! {"@value": value.clone()};
+        match type_mapping {
+            Some("@id" | "@vocab" | "@none") => {}
+            Some(typ) => {
+                result["@type"] = typ.into();
+                return result;
+            }
+            None => {}
+        }
+        if value.is_string() {
+            let language = match def.and_then(|d| d.language.as_ref()) {
+                Some(lang) => lang.as_deref(),
+                None => active.language.as_deref(),
+            };
+            let direction = match def.and_then(|d| d.direction.as_ref()) {
+                Some(dir) => dir.as_deref(),
+                None => active.direction.as_deref(),
+            };
+            if let Some(language) = language {
+                result["@language"] = language.into();
+            }
+            if let Some(direction) = direction {
+                result["@direction"] = direction.into();
+            }
+        }
+        result
+    }
+}
+
+/// The keys of `obj`, in lexicographical order.
+pub fn sorted_keys(obj: &Object) -> Vec<&str> {
+    let mut keys: Vec<&str> = obj.iter().map(|(k, _)| k).collect();
+    keys.sort_unstable();
+    keys
+}
+
+/// Add `value` to the entry `key` of `obj`, as per the [add value] algorithm.
+///
+/// [add value]: https://www.w3.org/TR/json-ld11-api/#dfn-add-value
+pub fn add_value(obj: &mut Object, key: &str, value: JsonValue, as_array_: bool) {
+    if as_array_ && obj.get(key).is_none() {
+        obj.insert(key, JsonValue::Array(vec![]));
+    }
+    match value {
+        JsonValue::Array(values) => {
+            for v in values {
+                add_value(obj, key, v, false);
+            }
+        }
+        value => match obj.get_mut(key) {
+            None => obj.insert(key, value),
+            Some(JsonValue::Array(values)) => values.push(value),
+            Some(previous) => {
+                let previous = previous.take();
+                obj.insert(key, JsonValue::Array(vec![previous, value]));
+            }
+        },
+    }
+}
+
+fn value_object(value: &JsonValue) -> &Object {
+    match value {
+        JsonValue::Object(obj) => obj,
+        _ => unreachable!(),
+    }
+}
+
+/// Whether `value` is a scalar (string, number or boolean).
+pub fn is_scalar(value: &JsonValue) -> bool {
+    value.is_string() || value.is_number() || value.is_boolean()
+}
+
+/// Whether `value` is a [value object](https://www.w3.org/TR/json-ld11/#dfn-value-object).
+pub fn is_value_object(value: &JsonValue) -> bool {
+    value.is_object() && value.has_key("@value")
+}
+
+/// Whether `value` is a [list object](https://www.w3.org/TR/json-ld11/#dfn-list-object).
+pub fn is_list_object(value: &JsonValue) -> bool {
+    value.is_object() && value.has_key("@list")
+}
+
+/// Whether `value` is a [graph object](https://www.w3.org/TR/json-ld11/#dfn-graph-object).
+pub fn is_graph_object(value: &JsonValue) -> bool {
+    match value {
+        JsonValue::Object(obj) => {
+            obj.get("@graph").is_some()
+                && obj
+                    .iter()
+                    .all(|(k, _)| matches!(k, "@graph" | "@id" | "@index" | "@context"))
+        }
+        _ => false,
+    }
+}
+
+/// Whether `value` is a [node object](https://www.w3.org/TR/json-ld11/#dfn-node-object).
+pub fn is_node_object(value: &JsonValue) -> bool {
+    match value {
+        JsonValue::Object(obj) => {
+            obj.get("@value").is_none() && obj.get("@list").is_none() && obj.get("@set").is_none()
+        }
+        _ => false,
+    }
+}
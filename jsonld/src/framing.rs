@@ -0,0 +1,556 @@
+//! The [framing algorithms] of JSON-LD 1.1.
+//!
+//! [framing algorithms]: https://www.w3.org/TR/json-ld11-framing/#framing-algorithms
+use crate::compaction::{with_context, Compactor};
+use crate::context::*;
+use crate::error::{JsonLdError, JsonLdErrorCode::*};
+use crate::expansion::{add_value, is_list_object, sorted_keys, Expander};
+use crate::loader::DocumentLoader;
+use crate::node_map::*;
+use json::object::Object;
+use json::JsonValue;
+use std::collections::{HashMap, HashSet};
+use std::rc::Rc;
+
+/// Frame the expanded document `expanded` with `frame`, and compact the result,
+/// as per the [`frame()`] method.
+///
+/// If `frame` has no `@context`, `default_context` is used to compact the result.
+///
+/// [`frame()`]: https://www.w3.org/TR/json-ld11-framing/#dom-jsonldprocessor-frame
+pub fn frame_document<L: DocumentLoader + ?Sized>(
+    processor: &ContextProcessor<L>,
+    base: Option<&str>,
+    expanded: &JsonValue,
+    frame: &JsonValue,
+    default_context: Option<&JsonValue>,
+) -> Result<JsonValue, JsonLdError> {
+    let frame_obj = match frame {
+        JsonValue::Object(obj) => obj,
+        _ => return Err(JsonLdError::processing(InvalidFrame, frame.dump())),
+    };
+    let context = match (frame_obj.get("@context"), default_context) {
+        (Some(ctx), _) | (None, Some(ctx)) => unwrap_context(ctx).clone(),
+        (None, None) => JsonValue::Null,
+    };
+    let initial = Rc::new(Context::new(base.map(str::to_string)));
+    let active = Rc::new(processor.process(&initial, &context, base, &[], false, true, true)?);
+
+    let expanded_frame =
+        Expander::new_for_frame(processor).expand_document(&initial, frame, base)?;
+    let expanded_frame = match &expanded_frame[..] {
+        [] => Object::new(),
+        [JsonValue::Object(obj)] => obj.clone(),
+        _ => return Err(JsonLdError::processing(InvalidFrame, frame.dump())),
+    };
+    let frame_default = frame_obj
+        .iter()
+        .any(|(key, _)| active.expand_iri(key, false, true).as_deref() == Some("@graph"));
+
+    let (mut graph_map, _) = generate_node_map(expanded)?;
+    let merged = merge_node_maps(&graph_map);
+    graph_map.insert(MERGED.to_string(), merged);
+    let graph = if frame_default { DEFAULT } else { MERGED };
+    let mut state = FramingState {
+        flags: Flags {
+            embed: Embed::Once,
+            explicit: false,
+            require_all: false,
+            omit_default: false,
+        },
+        graph_map,
+        graph: graph.to_string(),
+        subject_stack: vec![],
+        embedded: HashSet::new(),
+    };
+    let subjects: Vec<String> = state.graph_map[graph].keys().cloned().collect();
+    let mut results = JsonValue::Array(vec![]);
+    state.frame(&subjects, &expanded_frame, &mut results, None)?;
+
+    let is_10 = processor.is_10();
+    if !is_10 {
+        let mut counts = HashMap::new();
+        count_bnodes(&results, &mut counts);
+        prune_bnodes(&mut results, &counts);
+    }
+    let compactor = Compactor::new(processor);
+    let compacted = if is_10 {
+        let graph = compactor.compact(&active, Some("@graph"), &results)?;
+        let mut obj = Object::new();
+        obj.insert(
+            &compactor.compact_iri(&active, "@graph", None, true, false)?,
+            graph,
+        );
+        with_context(&context, obj)
+    } else {
+        compactor.compact_document(&active, &context, &results)?
+    };
+    Ok(cleanup(compacted))
+}
+
+/// If `context` is a document with a `@context` entry, return the value of that entry.
+pub fn unwrap_context(context: &JsonValue) -> &JsonValue {
+    match context {
+        JsonValue::Object(obj) if obj.get("@context").is_some() => &obj["@context"],
+        ctx => ctx,
+    }
+}
+
+/// The possible values of the [`@embed`] flag.
+///
+/// [`@embed`]: https://www.w3.org/TR/json-ld11-framing/#dom-jsonldembed
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Embed {
+    Always,
+    Once,
+    Never,
+}
+
+/// The framing flags, as found in a frame or inherited from the state.
+#[derive(Clone, Copy, Debug)]
+struct Flags {
+    embed: Embed,
+    explicit: bool,
+    require_all: bool,
+    omit_default: bool,
+}
+
+impl Flags {
+    /// The flags of `frame`, defaulting to `self`.
+    fn of(&self, frame: &Object) -> Result<Flags, JsonLdError> {
+        let embed = match frame.get("@embed").and_then(|v| as_slice(v).first()) {
+            None => self.embed,
+            Some(JsonValue::Boolean(true)) => Embed::Once,
+            Some(JsonValue::Boolean(false)) => Embed::Never,
+            Some(value) => match value.as_str() {
+                Some("@always") => Embed::Always,
+                Some("@once") => Embed::Once,
+                Some("@never") => Embed::Never,
+                _ => return Err(JsonLdError::processing(InvalidEmbedValue, value.dump())),
+            },
+        };
+        let flag = |key: &str, default: bool| {
+            frame
+                .get(key)
+                .and_then(|v| as_slice(v).first())
+                .and_then(JsonValue::as_bool)
+                .unwrap_or(default)
+        };
+        Ok(Flags {
+            embed,
+            explicit: flag("@explicit", self.explicit),
+            require_all: flag("@requireAll", self.require_all),
+            omit_default: flag("@omitDefault", self.omit_default),
+        })
+    }
+
+    /// A frame carrying these flags, used where no explicit frame is given.
+    fn implicit_frame(&self) -> Object {
+        let embed = match self.embed {
+            Embed::Always => "@always",
+            Embed::Once => "@once",
+            Embed::Never => "@never",
+        };
+        let mut frame = Object::new();
+        frame.insert("@embed", JsonValue::Array(vec![embed.into()]));
+        frame.insert("@explicit", JsonValue::Array(vec![self.explicit.into()]));
+        frame.insert(
+            "@requireAll",
+            JsonValue::Array(vec![self.require_all.into()]),
+        );
+        frame
+    }
+}
+
+/// The state of the [framing algorithm].
+///
+/// [framing algorithm]: https://www.w3.org/TR/json-ld11-framing/#framing-algorithm
+struct FramingState {
+    flags: Flags,
+    graph_map: NodeMap,
+    graph: String,
+    subject_stack: Vec<(String, String)>,
+    embedded: HashSet<(String, String)>,
+}
+
+impl FramingState {
+    /// The [framing algorithm], adding to `parent` the nodes among `subjects` matching `frame`.
+    ///
+    /// [framing algorithm]: https://www.w3.org/TR/json-ld11-framing/#framing-algorithm
+    fn frame(
+        &mut self,
+        subjects: &[String],
+        frame: &Object,
+        parent: &mut JsonValue,
+        property: Option<&str>,
+    ) -> Result<(), JsonLdError> {
+        let flags = self.flags.of(frame)?;
+        for id in self.filter_subjects(subjects, frame, flags.require_all)? {
+            let node = self.graph_map[&self.graph][&id].clone();
+            if property.is_none() {
+                self.embedded.clear();
+            }
+            let mut output = json::object! {"@id": id.as_str()};
+            let key = (self.graph.clone(), id.clone());
+            if flags.embed == Embed::Never
+                || self.subject_stack.contains(&key)
+                || (flags.embed == Embed::Once && self.embedded.contains(&key))
+            {
+                add_frame_output(parent, property, output);
+                continue;
+            }
+            self.embedded.insert(key.clone());
+            self.subject_stack.push(key);
+
+            if id != DEFAULT && id != MERGED && self.graph_map.contains_key(&id) {
+                let (recurse, subframe) = match frame.get("@graph") {
+                    None => (self.graph != MERGED, Object::new()),
+                    Some(subframe) => (true, first_object(subframe)),
+                };
+                if recurse {
+                    let graph_subjects: Vec<String> = self.graph_map[&id].keys().cloned().collect();
+                    let previous = std::mem::replace(&mut self.graph, id.clone());
+                    self.frame(&graph_subjects, &subframe, &mut output, Some("@graph"))?;
+                    self.graph = previous;
+                }
+            }
+
+            if let Some(included) = frame.get("@included") {
+                self.frame(
+                    subjects,
+                    &first_object(included),
+                    &mut output,
+                    Some("@included"),
+                )?;
+            }
+
+            for prop in sorted_keys(&node) {
+                let objects = &node[prop];
+                if prop == "@id" {
+                    continue;
+                }
+                if is_keyword(prop) {
+                    output[prop] = objects.clone();
+                    continue;
+                }
+                if flags.explicit && frame.get(prop).is_none() {
+                    continue;
+                }
+                let subframe = match frame.get(prop) {
+                    Some(subframe) if !as_slice(subframe).is_empty() => first_object(subframe),
+                    _ => flags.implicit_frame(),
+                };
+                for o in as_slice(objects) {
+                    if is_list_object(o) {
+                        let list_frame = match subframe.get("@list") {
+                            Some(list_frame) => first_object(list_frame),
+                            None => subframe.clone(),
+                        };
+                        let mut list = json::object! {"@list": []};
+                        for item in as_slice(&o["@list"]) {
+                            match node_reference(item) {
+                                Some(item_id) => {
+                                    let item_id = [item_id.to_string()];
+                                    self.frame(&item_id, &list_frame, &mut list, Some("@list"))?;
+                                }
+                                None => add_frame_output(&mut list, Some("@list"), item.clone()),
+                            }
+                        }
+                        add_frame_output(&mut output, Some(prop), list);
+                    } else if let Some(o_id) = node_reference(o) {
+                        self.frame(&[o_id.to_string()], &subframe, &mut output, Some(prop))?;
+                    } else if value_match(&subframe, o) {
+                        add_frame_output(&mut output, Some(prop), o.clone());
+                    }
+                }
+            }
+
+            for prop in sorted_keys(frame) {
+                if prop == "@type" {
+                    let default = as_slice(&frame[prop])
+                        .first()
+                        .filter(|t| t.has_key("@default"))
+                        .map(|t| &t["@default"]);
+                    if let (Some(default), false) = (default, output.has_key("@type")) {
+                        output["@type"] = JsonValue::Array(vec![default.clone()]);
+                    }
+                    continue;
+                }
+                if is_keyword(prop) {
+                    continue;
+                }
+                let next = first_object(&frame[prop]);
+                let omit_default = flags.of(&next)?.omit_default;
+                if !omit_default && !output.has_key(prop) {
+                    let preserve = match next.get("@default") {
+                        Some(default) => as_array(default.clone()),
+                        None => vec!["@null".into()],
+                    };
+                    let mut default = Object::new();
+                    default.insert("@preserve", JsonValue::Array(preserve));
+                    output[prop] = JsonValue::Array(vec![JsonValue::Object(default)]);
+                }
+            }
+
+            if let Some(JsonValue::Object(reverse_frame)) = frame.get("@reverse") {
+                output["@reverse"] = JsonValue::Object(Object::new());
+                for reverse_prop in sorted_keys(reverse_frame) {
+                    let subframe = first_object(&reverse_frame[reverse_prop]);
+                    let referrers: Vec<String> = self.graph_map[&self.graph]
+                        .iter()
+                        .filter(|(_, n)| {
+                            n.get(reverse_prop)
+                                .map(|values| {
+                                    as_slice(values)
+                                        .iter()
+                                        .any(|v| v["@id"].as_str() == Some(id.as_str()))
+                                })
+                                .unwrap_or(false)
+                        })
+                        .map(|(referrer, _)| referrer.clone())
+                        .collect();
+                    let reverse = &mut output["@reverse"];
+                    self.frame(&referrers, &subframe, reverse, Some(reverse_prop))?;
+                }
+            }
+
+            add_frame_output(parent, property, output);
+            self.subject_stack.pop();
+        }
+        Ok(())
+    }
+
+    /// The identifiers in `subjects` whose node matches `frame`, in order.
+    fn filter_subjects(
+        &self,
+        subjects: &[String],
+        frame: &Object,
+        require_all: bool,
+    ) -> Result<Vec<String>, JsonLdError> {
+        let graph = &self.graph_map[&self.graph];
+        let mut matches = vec![];
+        for id in subjects {
+            if let Some(node) = graph.get(id) {
+                if self.filter_subject(node, frame, require_all)? {
+                    matches.push(id.clone());
+                }
+            }
+        }
+        Ok(matches)
+    }
+
+    /// The [frame matching] algorithm, for a single node.
+    ///
+    /// [frame matching]: https://www.w3.org/TR/json-ld11-framing/#frame-matching-algorithm
+    fn filter_subject(
+        &self,
+        node: &Object,
+        frame: &Object,
+        require_all: bool,
+    ) -> Result<bool, JsonLdError> {
+        let mut wildcard = true;
+        let mut matches_some = false;
+        for key in sorted_keys(frame) {
+            let frame_values = as_slice(&frame[key]);
+            let match_this = match key {
+                "@id" => {
+                    wildcard = false;
+                    let id = &node["@id"];
+                    frame_values.iter().any(|v| v == id || is_empty_object(v))
+                }
+                "@type" => {
+                    wildcard = false;
+                    let node_types = node.get("@type").map(as_slice).unwrap_or_default();
+                    match frame_values.first() {
+                        None => node_types.is_empty(),
+                        Some(t) if is_empty_object(t) => !node_types.is_empty(),
+                        Some(JsonValue::Object(t)) if t.get("@default").is_some() => true,
+                        _ => frame_values.iter().any(|t| node_types.contains(t)),
+                    }
+                }
+                _ if is_keyword(key) => continue,
+                _ => {
+                    wildcard = false;
+                    let node_values = node.get(key).map(as_slice).unwrap_or_default();
+                    let this_frame = frame_values.first();
+                    let has_default = this_frame.map(|f| f.has_key("@default")).unwrap_or(false);
+                    if node_values.is_empty() && has_default {
+                        continue;
+                    }
+                    match this_frame {
+                        None => node_values.is_empty(),
+                        Some(f) if is_list_object(f) => {
+                            let list_frame = first_object(&f["@list"]);
+                            let mut list_match = false;
+                            for v in node_values.iter().filter(|v| is_list_object(v)) {
+                                for item in as_slice(&v["@list"]) {
+                                    list_match |= match node_reference(item) {
+                                        Some(_) => self.node_match(&list_frame, item)?,
+                                        None => value_match(&list_frame, item),
+                                    };
+                                }
+                            }
+                            list_match
+                        }
+                        Some(f) if is_value_pattern(f) => {
+                            let pattern = first_object(f);
+                            node_values.iter().any(|v| value_match(&pattern, v))
+                        }
+                        Some(JsonValue::Object(f)) if is_node_pattern(f) => {
+                            let mut node_match = false;
+                            for v in node_values {
+                                node_match |= self.node_match(f, v)?;
+                            }
+                            node_match
+                        }
+                        Some(_) => !node_values.is_empty(),
+                    }
+                }
+            };
+            if matches!(key, "@id" | "@type") && !require_all {
+                // an explicit @id or @type takes precedence over other properties
+                return Ok(match_this);
+            }
+            if !match_this && require_all {
+                return Ok(false);
+            }
+            matches_some |= match_this;
+        }
+        Ok(wildcard || matches_some)
+    }
+
+    /// Whether the node referenced by `value` matches the node pattern `pattern`.
+    fn node_match(&self, pattern: &Object, value: &JsonValue) -> Result<bool, JsonLdError> {
+        let node = match node_reference(value).and_then(|id| self.graph_map[&self.graph].get(id)) {
+            Some(node) => node,
+            None => return Ok(false),
+        };
+        let require_all = self.flags.of(pattern)?.require_all;
+        self.filter_subject(node, pattern, require_all)
+    }
+}
+
+/// Add `output` to `parent`, either as an array item, or as a value of `property`.
+fn add_frame_output(parent: &mut JsonValue, property: Option<&str>, output: JsonValue) {
+    match (parent, property) {
+        (JsonValue::Array(items), _) => items.push(output),
+        (JsonValue::Object(obj), Some(property)) => add_value(obj, property, output, true),
+        _ => unreachable!(),
+    }
+}
+
+/// The first map in `value` (or an empty map if there is none).
+fn first_object(value: &JsonValue) -> Object {
+    match as_slice(value).first() {
+        Some(JsonValue::Object(obj)) => obj.clone(),
+        _ => Object::new(),
+    }
+}
+
+fn is_empty_object(value: &JsonValue) -> bool {
+    matches!(value, JsonValue::Object(obj) if obj.is_empty())
+}
+
+/// The identifier of `value` if it is a node reference.
+fn node_reference(value: &JsonValue) -> Option<&str> {
+    match value {
+        JsonValue::Object(obj) if obj.len() == 1 => obj.get("@id").and_then(JsonValue::as_str),
+        _ => None,
+    }
+}
+
+fn is_value_pattern(value: &JsonValue) -> bool {
+    value.has_key("@value") || value.has_key("@type") || value.has_key("@language")
+}
+
+fn is_node_pattern(frame: &Object) -> bool {
+    frame
+        .iter()
+        .any(|(key, _)| !is_keyword(key) || key == "@id" || key == "@type")
+}
+
+/// The [value pattern matching] algorithm.
+///
+/// [value pattern matching]: https://www.w3.org/TR/json-ld11-framing/#value-matching
+fn value_match(pattern: &Object, value: &JsonValue) -> bool {
+    let component_match = |key: &str, ignore_case: bool| match pattern.get(key) {
+        None => true,
+        Some(expected) => {
+            let expected = as_slice(expected);
+            match &value[key] {
+                JsonValue::Null => expected.is_empty(),
+                actual => expected.iter().any(|e| {
+                    is_empty_object(e)
+                        || e == actual
+                        || (ignore_case
+                            && matches!((e.as_str(), actual.as_str()), (Some(e), Some(a)) if e.eq_ignore_ascii_case(a)))
+                }),
+            }
+        }
+    };
+    component_match("@value", false)
+        && component_match("@type", false)
+        && component_match("@language", true)
+}
+
+/// Count the occurrences of each blank node identifier used as `@id` in `value`.
+fn count_bnodes(value: &JsonValue, counts: &mut HashMap<String, usize>) {
+    match value {
+        JsonValue::Array(items) => items.iter().for_each(|item| count_bnodes(item, counts)),
+        JsonValue::Object(obj) => {
+            if let Some(id) = obj.get("@id").and_then(JsonValue::as_str) {
+                if is_bnode_id(id) {
+                    *counts.entry(id.to_string()).or_default() += 1;
+                }
+            }
+            obj.iter().for_each(|(_, v)| count_bnodes(v, counts));
+        }
+        _ => {}
+    }
+}
+
+/// Remove the `@id` of node objects whose blank node identifier is used only once.
+fn prune_bnodes(value: &mut JsonValue, counts: &HashMap<String, usize>) {
+    match value {
+        JsonValue::Array(items) => items.iter_mut().for_each(|item| prune_bnodes(item, counts)),
+        JsonValue::Object(obj) => {
+            let prune = obj
+                .get("@id")
+                .and_then(JsonValue::as_str)
+                .map(|id| is_bnode_id(id) && counts.get(id) == Some(&1))
+                .unwrap_or(false);
+            if prune && obj.len() > 1 {
+                obj.remove("@id");
+            }
+            obj.iter_mut().for_each(|(_, v)| prune_bnodes(v, counts));
+        }
+        _ => {}
+    }
+}
+
+/// Replace `@preserve` objects with their value, and `@null` with `null`,
+/// removing nulls from arrays.
+fn cleanup(value: JsonValue) -> JsonValue {
+    match value {
+        JsonValue::Array(items) => JsonValue::Array(
+            items
+                .into_iter()
+                .map(cleanup)
+                .filter(|item| !item.is_null())
+                .collect(),
+        ),
+        JsonValue::Object(mut obj) => {
+            if let Some(preserve) = obj.remove("@preserve") {
+                return cleanup(preserve);
+            }
+            for (key, v) in obj.iter_mut() {
+                if key != "@context" {
+                    *v = cleanup(v.take());
+                }
+            }
+            JsonValue::Object(obj)
+        }
+        value if value == "@null" => JsonValue::Null,
+        value => value,
+    }
+}
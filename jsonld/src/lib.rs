@@ -3,9 +3,10 @@
 //!
 //! Serializer and parser for the [JSON-LD] concrete syntax of RDF.
 //!
-//! NB: this implementation only supports the [expanded document form] of [JSON-LD].
-//!
-//! NB2: currently, only the serializer part is implemented.
+//! By default, the serializer produces the [expanded document form] of [JSON-LD];
+//! it can also compact or frame its output (see [`JsonLdConfig`]).
+//! The parser accepts any form.
+//! Both retrieve remote contexts through a [`DocumentLoader`].
 //!
 //! [Sophia]: https://docs.rs/sophia/latest/sophia/
 //! [RDF]: https://www.w3.org/TR/rdf-primer/
@@ -15,10 +16,20 @@
 
 #![deny(missing_docs)]
 
+mod compaction;
+mod context;
+mod expansion;
+mod framing;
+mod node_map;
+
 pub mod config;
 pub use config::*;
 pub mod error;
 pub use error::*;
+pub mod loader;
+pub use loader::*;
+pub mod parser;
+pub use parser::*;
 pub mod serializer;
 pub use serializer::*;
 mod util_traits;
@@ -0,0 +1,197 @@
+//! Document loaders, used by the JSON-LD parser to retrieve remote contexts.
+//!
+//! This crate does not perform any network access by itself.
+//! Instead, remote documents are retrieved through an implementation of [`DocumentLoader`],
+//! which can be provided to the [`JsonLdParser`](crate::parser::JsonLdParser).
+//! The loaders provided here work offline:
+//! [`NoLoader`] refuses to load anything,
+//! [`StaticLoader`] serves documents stored in memory,
+//! and [`FsLoader`] serves documents from the local file system.
+
+use crate::error::{JsonLdError, JsonLdErrorCode::LoadingDocumentFailed};
+use json::JsonValue;
+use std::collections::HashMap;
+use std::fs;
+use std::path::PathBuf;
+
+/// A document retrieved by a [`DocumentLoader`].
+#[derive(Clone, Debug)]
+pub struct RemoteDocument {
+    /// The final URL of the document (after following redirections, if any),
+    /// used as the base URL of the document.
+    pub document_url: String,
+    /// The parsed content of the document.
+    pub document: JsonValue,
+}
+
+/// A document loader retrieves JSON documents given their URL.
+///
+/// See the [`loader`](self) module for the provided implementations.
+pub trait DocumentLoader {
+    /// Retrieve and parse the document at the given (absolute) URL.
+    fn load_document(&self, url: &str) -> Result<RemoteDocument, JsonLdError>;
+}
+
+impl<L: DocumentLoader + ?Sized> DocumentLoader for &L {
+    fn load_document(&self, url: &str) -> Result<RemoteDocument, JsonLdError> {
+        (**self).load_document(url)
+    }
+}
+
+impl<L: DocumentLoader + ?Sized> DocumentLoader for Box<L> {
+    fn load_document(&self, url: &str) -> Result<RemoteDocument, JsonLdError> {
+        (**self).load_document(url)
+    }
+}
+
+/// A [`DocumentLoader`] that fails to load any document.
+///
+/// This is the default loader of [`JsonLdParser`](crate::parser::JsonLdParser).
+#[derive(Clone, Copy, Debug, Default)]
+pub struct NoLoader;
+
+impl DocumentLoader for NoLoader {
+    fn load_document(&self, url: &str) -> Result<RemoteDocument, JsonLdError> {
+        Err(JsonLdError::processing(
+            LoadingDocumentFailed,
+            format!("no document loader available for <{}>", url),
+        ))
+    }
+}
+
+/// A [`DocumentLoader`] serving documents stored in memory.
+///
+/// ```
+/// use sophia_jsonld::loader::*;
+///
+/// let loader = StaticLoader::new().with_document(
+///     "http://example.org/context.jsonld",
+///     json::object! { "@context": { "name": "http://schema.org/name" } },
+/// );
+/// assert!(loader.load_document("http://example.org/context.jsonld").is_ok());
+/// assert!(loader.load_document("http://example.org/other.jsonld").is_err());
+/// ```
+#[derive(Clone, Debug, Default)]
+pub struct StaticLoader {
+    documents: HashMap<String, JsonValue>,
+}
+
+impl StaticLoader {
+    /// Build an empty `StaticLoader`.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add a document to this loader.
+    pub fn with_document<U: Into<String>>(mut self, url: U, document: JsonValue) -> Self {
+        self.insert(url, document);
+        self
+    }
+
+    /// Add a document to this loader,
+    /// replacing any document previously stored with the same URL.
+    pub fn insert<U: Into<String>>(&mut self, url: U, document: JsonValue) {
+        self.documents.insert(url.into(), document);
+    }
+}
+
+impl DocumentLoader for StaticLoader {
+    fn load_document(&self, url: &str) -> Result<RemoteDocument, JsonLdError> {
+        match self.documents.get(url) {
+            Some(document) => Ok(RemoteDocument {
+                document_url: url.to_string(),
+                document: document.clone(),
+            }),
+            None => NoLoader.load_document(url),
+        }
+    }
+}
+
+/// A [`DocumentLoader`] serving documents from the local file system.
+///
+/// Each URL starting with one of the registered prefixes
+/// is mapped to a file in the corresponding directory.
+///
+/// ```
+/// use sophia_jsonld::loader::FsLoader;
+///
+/// let loader = FsLoader::new().with_mapping("http://example.org/", "./contexts");
+/// // http://example.org/foo/bar.jsonld will be read from ./contexts/foo/bar.jsonld
+/// ```
+///
+/// URLs whose path contains empty, `.` or `..` segments are rejected,
+/// so that no file outside the mapped directories can be read.
+#[derive(Clone, Debug, Default)]
+pub struct FsLoader {
+    mappings: Vec<(String, PathBuf)>,
+}
+
+impl FsLoader {
+    /// Build an `FsLoader` without any mapping.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add a mapping from URL `prefix` to directory `path`.
+    pub fn with_mapping<U: Into<String>, P: Into<PathBuf>>(mut self, prefix: U, path: P) -> Self {
+        self.mappings.push((prefix.into(), path.into()));
+        self
+    }
+}
+
+impl DocumentLoader for FsLoader {
+    fn load_document(&self, url: &str) -> Result<RemoteDocument, JsonLdError> {
+        for (prefix, dir) in &self.mappings {
+            if let Some(suffix) = url.strip_prefix(prefix.as_str()) {
+                let suffix = suffix.split(&['?', '#'][..]).next().unwrap();
+                // prevent URLs from escaping the directory
+                if suffix
+                    .split('/')
+                    .any(|seg| matches!(seg, "" | "." | "..") || seg.contains('\\'))
+                {
+                    return Err(JsonLdError::processing(
+                        LoadingDocumentFailed,
+                        format!("<{}>: invalid path for {}", url, dir.display()),
+                    ));
+                }
+                let mut path = dir.clone();
+                path.extend(suffix.split('/'));
+                let txt = fs::read_to_string(&path).map_err(|e| {
+                    JsonLdError::processing(LoadingDocumentFailed, format!("<{}>: {}", url, e))
+                })?;
+                let document = json::parse(&txt).map_err(|e| {
+                    JsonLdError::processing(LoadingDocumentFailed, format!("<{}>: {}", url, e))
+                })?;
+                return Ok(RemoteDocument {
+                    document_url: url.to_string(),
+                    document,
+                });
+            }
+        }
+        NoLoader.load_document(url)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn fs_loader_stays_in_directory() {
+        let dir = std::env::temp_dir().join("sophia_jsonld_fs_loader");
+        fs::create_dir_all(dir.join("sub")).unwrap();
+        fs::write(dir.join("sub").join("ctx.jsonld"), r#"{"@context": {}}"#).unwrap();
+        let loader = FsLoader::new().with_mapping("http://example.org/", dir.join("sub"));
+        assert!(loader
+            .load_document("http://example.org/ctx.jsonld#frag")
+            .is_ok());
+        for url in [
+            "http://example.org/../sub/ctx.jsonld",
+            "http://example.org/./ctx.jsonld",
+            "http://example.org//ctx.jsonld",
+            "http://example.org/..\\sub\\ctx.jsonld",
+        ] {
+            assert!(loader.load_document(url).is_err(), "{}", url);
+        }
+    }
+}
@@ -0,0 +1,320 @@
+//! The [node map generation] algorithm of JSON-LD,
+//! used both to convert expanded documents to RDF and to frame them.
+//!
+//! [node map generation]: https://www.w3.org/TR/json-ld11-api/#node-map-generation
+
+use crate::context::{as_slice, is_bnode_id, is_keyword};
+use crate::error::{JsonLdError, JsonLdErrorCode::*};
+use crate::expansion::{add_value, sorted_keys};
+use json::object::Object;
+use json::JsonValue;
+use std::collections::{BTreeMap, HashMap};
+
+/// Graph name of the default graph in the node map.
+pub const DEFAULT: &str = "@default";
+
+/// Graph name of the merged graph in the node map (see [`merge_node_maps`]).
+pub const MERGED: &str = "@merged";
+
+/// Maps graph names to subject identifiers to nodes.
+pub type NodeMap = BTreeMap<String, BTreeMap<String, Object>>;
+
+/// Issues new blank node identifiers, as per the [blank node identifier generation] algorithm.
+///
+/// [blank node identifier generation]: https://www.w3.org/TR/json-ld11-api/#generate-blank-node-identifier
+#[derive(Default)]
+pub struct BnodeIssuer {
+    map: HashMap<String, String>,
+    counter: usize,
+}
+
+impl BnodeIssuer {
+    pub fn issue(&mut self, id: Option<&str>) -> String {
+        if let Some(issued) = id.and_then(|id| self.map.get(id)) {
+            return issued.clone();
+        }
+        let issued = format!("_:b{}", self.counter);
+        self.counter += 1;
+        if let Some(id) = id {
+            self.map.insert(id.to_string(), issued.clone());
+        }
+        issued
+    }
+}
+
+/// The active subject of the node map generation algorithm.
+enum Subject<'s> {
+    None,
+    Id(&'s str),
+    /// A reference to the node holding a reverse property.
+    Reverse(&'s JsonValue),
+}
+
+#[derive(Default)]
+struct NodeMapGenerator {
+    issuer: BnodeIssuer,
+}
+
+impl NodeMapGenerator {
+    /// The [node map generation] algorithm.
+    ///
+    /// [node map generation]: https://www.w3.org/TR/json-ld11-api/#node-map-generation
+    fn generate(
+        &mut self,
+        element: &JsonValue,
+        node_map: &mut NodeMap,
+        active_graph: &str,
+        active_subject: &Subject,
+        active_property: Option<&str>,
+        mut list: Option<&mut Vec<JsonValue>>,
+    ) -> Result<(), JsonLdError> {
+        let element = match element {
+            JsonValue::Array(items) => {
+                for item in items {
+                    self.generate(
+                        item,
+                        node_map,
+                        active_graph,
+                        active_subject,
+                        active_property,
+                        list.as_deref_mut(),
+                    )?;
+                }
+                return Ok(());
+            }
+            JsonValue::Object(obj) => obj,
+            _ => return Ok(()),
+        };
+        let types: Vec<JsonValue> = element
+            .get("@type")
+            .map(|types| {
+                as_slice(types)
+                    .iter()
+                    .map(|t| match t.as_str() {
+                        Some(t) if is_bnode_id(t) => self.issuer.issue(Some(t)).into(),
+                        _ => t.clone(),
+                    })
+                    .collect()
+            })
+            .unwrap_or_default();
+
+        if element.get("@value").is_some() {
+            let mut element = element.clone();
+            if !types.is_empty() {
+                element.insert("@type", types.into_iter().next().unwrap());
+            }
+            let element = JsonValue::Object(element);
+            match list {
+                None => self.add_to_subject(
+                    node_map,
+                    active_graph,
+                    active_subject,
+                    active_property,
+                    element,
+                ),
+                Some(list) => list.push(element),
+            }
+        } else if let Some(items) = element.get("@list") {
+            let mut result = vec![];
+            self.generate(
+                items,
+                node_map,
+                active_graph,
+                active_subject,
+                active_property,
+                Some(&mut result),
+            )?;
+            let result = json::object! {"@list": result};
+            match list {
+                None => {
+                    if let (Subject::Id(subject), Some(property)) =
+                        (active_subject, active_property)
+                    {
+                        let node = node_map
+                            .get_mut(active_graph)
+                            .and_then(|graph| graph.get_mut(*subject))
+                            .unwrap();
+                        add_value(node, property, result, true);
+                    }
+                }
+                Some(list) => list.push(result),
+            }
+        } else {
+            let id = match element.get("@id").and_then(JsonValue::as_str) {
+                Some(id) if is_bnode_id(id) => self.issuer.issue(Some(id)),
+                Some(id) => id.to_string(),
+                None => self.issuer.issue(None),
+            };
+            node_map
+                .entry(active_graph.to_string())
+                .or_default()
+                .entry(id.clone())
+                .or_insert_with(|| {
+                    let mut node = Object::new();
+                    node.insert("@id", id.clone().into());
+                    node
+                });
+
+            match active_subject {
+                Subject::Reverse(reference) => {
+                    let node = &mut node_map
+                        .get_mut(active_graph)
+                        .unwrap()
+                        .get_mut(&id)
+                        .unwrap();
+                    add_unique(node, active_property.unwrap(), (*reference).clone());
+                }
+                _ if active_property.is_some() => {
+                    let reference = json::object! {"@id": id.clone()};
+                    match list {
+                        None => self.add_to_subject(
+                            node_map,
+                            active_graph,
+                            active_subject,
+                            active_property,
+                            reference,
+                        ),
+                        Some(list) => list.push(reference),
+                    }
+                }
+                _ => {}
+            }
+
+            let node = node_map
+                .get_mut(active_graph)
+                .unwrap()
+                .get_mut(&id)
+                .unwrap();
+            for t in types {
+                add_unique(node, "@type", t);
+            }
+            if let Some(index) = element.get("@index") {
+                match node.get("@index") {
+                    Some(previous) if previous != index => {
+                        return Err(JsonLdError::processing(ConflictingIndexes, &id));
+                    }
+                    _ => node.insert("@index", index.clone()),
+                }
+            }
+            if let Some(JsonValue::Object(reverse_map)) = element.get("@reverse") {
+                let referenced = json::object! {"@id": id.clone()};
+                for property in sorted_keys(reverse_map) {
+                    for value in as_slice(&reverse_map[property]) {
+                        self.generate(
+                            value,
+                            node_map,
+                            active_graph,
+                            &Subject::Reverse(&referenced),
+                            Some(property),
+                            None,
+                        )?;
+                    }
+                }
+            }
+            if let Some(graph) = element.get("@graph") {
+                node_map.entry(id.clone()).or_default();
+                self.generate(graph, node_map, &id, &Subject::None, None, None)?;
+            }
+            if let Some(included) = element.get("@included") {
+                self.generate(included, node_map, active_graph, &Subject::None, None, None)?;
+            }
+            for property in sorted_keys(element) {
+                if matches!(
+                    property,
+                    "@id" | "@type" | "@index" | "@reverse" | "@graph" | "@included" | "@context"
+                ) {
+                    continue;
+                }
+                let property_id = if is_bnode_id(property) {
+                    self.issuer.issue(Some(property))
+                } else {
+                    property.to_string()
+                };
+                let node = node_map
+                    .get_mut(active_graph)
+                    .unwrap()
+                    .get_mut(&id)
+                    .unwrap();
+                if node.get(&property_id).is_none() {
+                    node.insert(&property_id, JsonValue::Array(vec![]));
+                }
+                self.generate(
+                    &element[property],
+                    node_map,
+                    active_graph,
+                    &Subject::Id(&id),
+                    Some(&property_id),
+                    None,
+                )?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Add `value` to the `active_property` of the `active_subject` node, unless already present.
+    fn add_to_subject(
+        &self,
+        node_map: &mut NodeMap,
+        active_graph: &str,
+        active_subject: &Subject,
+        active_property: Option<&str>,
+        value: JsonValue,
+    ) {
+        if let (Subject::Id(subject), Some(property)) = (active_subject, active_property) {
+            let node = node_map
+                .get_mut(active_graph)
+                .and_then(|graph| graph.get_mut(*subject))
+                .unwrap();
+            add_unique(node, property, value);
+        }
+    }
+}
+
+/// Add `value` to the array in the `key` entry of `node`, unless it is already present.
+pub fn add_unique(node: &mut Object, key: &str, value: JsonValue) {
+    match node.get_mut(key) {
+        Some(JsonValue::Array(values)) => {
+            if !values.contains(&value) {
+                values.push(value);
+            }
+        }
+        _ => node.insert(key, JsonValue::Array(vec![value])),
+    }
+}
+
+/// Generate the node map of an expanded document.
+///
+/// The returned [`BnodeIssuer`] can be used to issue further blank node identifiers.
+pub fn generate_node_map(expanded: &JsonValue) -> Result<(NodeMap, BnodeIssuer), JsonLdError> {
+    let mut gen = NodeMapGenerator::default();
+    let mut node_map = NodeMap::new();
+    node_map.insert(DEFAULT.to_string(), BTreeMap::new());
+    gen.generate(expanded, &mut node_map, DEFAULT, &Subject::None, None, None)?;
+    Ok((node_map, gen.issuer))
+}
+
+/// The [merge node maps] algorithm, merging all graphs of `node_map` into a single graph.
+///
+/// [merge node maps]: https://www.w3.org/TR/json-ld11-api/#merge-node-maps
+pub fn merge_node_maps(node_map: &NodeMap) -> BTreeMap<String, Object> {
+    let mut result = BTreeMap::<String, Object>::new();
+    for graph in node_map.values() {
+        for (id, node) in graph {
+            let merged = result.entry(id.clone()).or_insert_with(|| {
+                let mut merged = Object::new();
+                merged.insert("@id", id.as_str().into());
+                merged
+            });
+            for (property, values) in node.iter() {
+                if property != "@type" && is_keyword(property) {
+                    merged.insert(property, values.clone());
+                } else {
+                    for value in as_slice(values) {
+                        add_unique(merged, property, value.clone());
+                    }
+                }
+            }
+        }
+    }
+    result
+}
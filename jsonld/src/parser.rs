@@ -0,0 +1,231 @@
+//! A JSON-LD parser implementing the [`expand()`] and [`toRdf()`] methods
+//! of the JSON-LD API.
+//!
+//! [`expand()`]: https://www.w3.org/TR/json-ld11-api/#dom-jsonldprocessor-expand
+//! [`toRdf()`]: https://www.w3.org/TR/json-ld11-api/#dom-jsonldprocessor-tordf
+
+use crate::config::*;
+use crate::context::{Context, ContextProcessor};
+use crate::error::*;
+use crate::expansion::Expander;
+use crate::loader::{DocumentLoader, NoLoader};
+use json::JsonValue;
+use sophia_api::parser::{Limit, LimitExceeded, ParserLimits, QuadParser};
+use sophia_term::BoxTerm;
+use std::io::{self, BufRead, Read};
+use std::rc::Rc;
+
+#[cfg(test)]
+mod test;
+mod to_rdf;
+
+/// The type of quads produced by [`JsonLdParser`].
+pub type JsonLdQuad = ([BoxTerm; 3], Option<BoxTerm>);
+
+/// The [`QuadSource`](sophia_api::quad::stream::QuadSource) produced by [`JsonLdParser`].
+pub type JsonLdQuadSource = std::vec::IntoIter<Result<JsonLdQuad, JsonLdError>>;
+
+/// A JSON-LD parser.
+///
+/// Remote contexts are retrieved through the [`DocumentLoader`] `L`.
+/// By default, [`NoLoader`] is used, so that only documents with local contexts can be parsed.
+///
+/// ```
+/// use sophia_api::parser::QuadParser;
+/// use sophia_api::quad::stream::QuadSource;
+/// use sophia_jsonld::{JsonLdParser, JsonLdQuad, StaticLoader};
+///
+/// let loader = StaticLoader::new().with_document(
+///     "http://example.org/context.jsonld",
+///     json::object! { "@context": { "name": "http://schema.org/name" } },
+/// );
+/// let parser = JsonLdParser::new().with_loader(loader);
+/// let data = r#"{
+///     "@context": "http://example.org/context.jsonld",
+///     "@id": "http://example.org/alice",
+///     "name": "Alice"
+/// }"#;
+/// let quads: Vec<JsonLdQuad> = parser.parse_str(data).collect_quads()?;
+/// assert_eq!(quads.len(), 1);
+/// # Ok::<(), Box<dyn std::error::Error>>(())
+/// ```
+#[derive(Clone, Debug, Default)]
+pub struct JsonLdParser<L = NoLoader> {
+    config: JsonLdConfig,
+    loader: L,
+}
+
+impl JsonLdParser<NoLoader> {
+    /// Build a new JSON-LD parser with the default config.
+    #[inline]
+    pub fn new() -> Self {
+        Self::new_with_config(JsonLdConfig::default())
+    }
+
+    /// Build a new JSON-LD parser with the given config.
+    pub fn new_with_config(config: JsonLdConfig) -> Self {
+        JsonLdParser {
+            config,
+            loader: NoLoader,
+        }
+    }
+}
+
+impl<L> JsonLdParser<L> {
+    /// Use `loader` to retrieve remote contexts.
+    pub fn with_loader<L2: DocumentLoader>(self, loader: L2) -> JsonLdParser<L2> {
+        JsonLdParser {
+            config: self.config,
+            loader,
+        }
+    }
+
+    /// Borrow this parser's configuration.
+    pub fn config(&self) -> &JsonLdConfig {
+        &self.config
+    }
+
+    /// Borrow this parser's document loader.
+    pub fn loader(&self) -> &L {
+        &self.loader
+    }
+}
+
+impl<L: DocumentLoader> JsonLdParser<L> {
+    /// Apply the [expansion algorithm] to `document`,
+    /// returning the expanded form of the document.
+    ///
+    /// [expansion algorithm]: https://www.w3.org/TR/json-ld11-api/#expansion-algorithm
+    pub fn expand(&self, document: &JsonValue) -> Result<Vec<JsonValue>, JsonLdError> {
+        let processor = ContextProcessor::new(&self.loader, self.config.spec_version);
+        let base = self.config.base.as_deref();
+        let mut active = Context::new(base.map(str::to_string));
+        if let Some(ctx) = &self.config.expand_context {
+            let ctx = match ctx {
+                JsonValue::Object(obj) if obj.get("@context").is_some() => &obj["@context"],
+                ctx => ctx,
+            };
+            active = processor.process(&active, ctx, base, &[], false, true, true)?;
+        }
+        Expander::new(&processor).expand_document(&Rc::new(active), document, base)
+    }
+
+    /// Convert `document` to a list of quads,
+    /// as per the [`toRdf()`] method of the JSON-LD API.
+    ///
+    /// The [limits](ParserLimits) on the number of quads and on the length of terms
+    /// are enforced while the quads are generated.
+    ///
+    /// [`toRdf()`]: https://www.w3.org/TR/json-ld11-api/#dom-jsonldprocessor-tordf
+    pub fn to_quads(&self, document: &JsonValue) -> Result<Vec<JsonLdQuad>, JsonLdError> {
+        let expanded = self.expand(document)?;
+        to_rdf::to_quads(&self.config, JsonValue::Array(expanded))
+    }
+}
+
+impl<B: BufRead, L: DocumentLoader> QuadParser<B> for JsonLdParser<L> {
+    type Source = JsonLdQuadSource;
+
+    fn parse(&self, data: B) -> Self::Source {
+        let mut bytes = vec![];
+        let res = data
+            .take(self.read_limit())
+            .read_to_end(&mut bytes)
+            .map(|_| bytes);
+        self.parse_bytes(res)
+    }
+}
+
+impl<L: DocumentLoader> JsonLdParser<L> {
+    /// Parse `data` asynchronously (requires the `async` feature).
+    ///
+    /// Only reading the document is asynchronous;
+    /// remote contexts are still retrieved synchronously through the [`DocumentLoader`].
+    #[cfg(feature = "async")]
+    pub async fn parse_async<R>(&self, data: R) -> JsonLdQuadSource
+    where
+        R: futures_util::io::AsyncRead + Unpin,
+    {
+        use futures_util::io::AsyncReadExt;
+        let mut bytes = vec![];
+        let res = data
+            .take(self.read_limit())
+            .read_to_end(&mut bytes)
+            .await
+            .map(|_| bytes);
+        self.parse_bytes(res)
+    }
+
+    /// The maximum number of bytes to read,
+    /// one more than the input size limit so that exceeding it can be detected.
+    fn read_limit(&self) -> u64 {
+        match self.config.limits.max_input_bytes {
+            Some(max) => max as u64 + 1,
+            None => u64::MAX,
+        }
+    }
+
+    fn parse_bytes(&self, bytes: io::Result<Vec<u8>>) -> JsonLdQuadSource {
+        let limits = &self.config.limits;
+        let quads = bytes
+            .map_err(JsonLdError::from)
+            .and_then(|bytes| {
+                limits.check(Limit::InputBytes, bytes.len())?;
+                String::from_utf8(bytes)
+                    .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e).into())
+            })
+            .and_then(|txt| {
+                check_json_depth(&txt, limits)?;
+                json::parse(&txt)
+                    .map_err(|e| JsonLdError::processing(JsonLdErrorCode::LoadingDocumentFailed, e))
+            })
+            .and_then(|document| self.to_quads(&document));
+        match quads {
+            Ok(quads) => quads.into_iter().map(Ok).collect::<Vec<_>>(),
+            Err(err) => vec![Err(err)],
+        }
+        .into_iter()
+    }
+}
+
+/// Check the nesting depth of the arrays and objects in the JSON text `txt`,
+/// before actually parsing it.
+fn check_json_depth(txt: &str, limits: &ParserLimits) -> Result<(), LimitExceeded> {
+    if limits.max_nesting_depth.is_none() {
+        return Ok(());
+    }
+    let mut depth = 0;
+    let mut in_string = false;
+    let mut escaped = false;
+    for b in txt.bytes() {
+        if in_string {
+            match b {
+                _ if escaped => escaped = false,
+                b'\\' => escaped = true,
+                b'"' => in_string = false,
+                _ => (),
+            }
+        } else {
+            match b {
+                b'"' => in_string = true,
+                b'[' | b'{' => {
+                    depth += 1;
+                    limits.check(Limit::NestingDepth, depth)?;
+                }
+                b']' | b'}' => depth = depth.saturating_sub(1),
+                _ => (),
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Convenience function for parsing a BufRead with the default parser.
+pub fn parse_bufread<B: BufRead>(bufread: B) -> JsonLdQuadSource {
+    JsonLdParser::new().parse(bufread)
+}
+
+/// Convenience function for parsing a str with the default parser.
+pub fn parse_str(txt: &str) -> JsonLdQuadSource {
+    JsonLdParser::new().parse_str(txt)
+}
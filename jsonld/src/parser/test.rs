@@ -0,0 +1,329 @@
+use crate::loader::*;
+use crate::parser::*;
+use crate::test_util::*;
+use sophia_api::dataset::isomorphic_datasets;
+use sophia_api::dataset::Dataset;
+use sophia_api::parser::QuadParser;
+use sophia_api::quad::stream::QuadSource;
+use sophia_api::serializer::{QuadSerializer, Stringifier};
+use sophia_turtle::serializer::nq::NqSerializer;
+use std::path::Path;
+
+type QuadSet = Vec<JsonLdQuad>;
+
+fn parse_nq(nq: &str) -> QuadSet {
+    sophia_turtle::parser::nq::parse_str(nq)
+        .collect_quads()
+        .unwrap()
+}
+
+fn assert_to_rdf<L: DocumentLoader>(parser: &JsonLdParser<L>, jsonld: &str, nq: &str) {
+    let got: QuadSet = parser.parse_str(jsonld).collect_quads().unwrap();
+    let exp = parse_nq(nq);
+    assert!(
+        isomorphic_datasets(&got, &exp).unwrap(),
+        "got:\n{}\nexpected:\n{}",
+        NqSerializer::new_stringifier()
+            .serialize_quads(got.quads())
+            .unwrap()
+            .to_string(),
+        nq
+    );
+}
+
+#[test]
+fn w3c_test_suite() {
+    let mpath = Path::new("..")
+        .join("json-ld-api")
+        .join("tests")
+        .join("toRdf-manifest.jsonld");
+    let manifest = Manifest::new(&mpath);
+    let (failed, skipped, passed) = manifest.perform_all_tests(true);
+    assert_eq!(0, failed, "{}/{}", failed, failed + skipped + passed);
+}
+
+#[test]
+fn contexts_and_coercion() {
+    assert_to_rdf(
+        &JsonLdParser::new(),
+        r#"{
+            "@context": {
+                "@vocab": "http://schema.org/",
+                "@base": "http://example.org/",
+                "knows": {"@type": "@id"},
+                "age": {"@type": "http://www.w3.org/2001/XMLSchema#integer"},
+                "label": {"@id": "http://www.w3.org/2000/01/rdf-schema#label", "@language": "en"}
+            },
+            "@id": "alice",
+            "@type": "Person",
+            "name": "Alice",
+            "label": "Alice A.",
+            "knows": "bob",
+            "age": "42",
+            "height": 1.65,
+            "married": true
+        }"#,
+        r#"
+            <http://example.org/alice> <http://www.w3.org/1999/02/22-rdf-syntax-ns#type> <http://schema.org/Person>.
+            <http://example.org/alice> <http://schema.org/name> "Alice".
+            <http://example.org/alice> <http://www.w3.org/2000/01/rdf-schema#label> "Alice A."@en.
+            <http://example.org/alice> <http://schema.org/knows> <http://example.org/bob>.
+            <http://example.org/alice> <http://schema.org/age> "42"^^<http://www.w3.org/2001/XMLSchema#integer>.
+            <http://example.org/alice> <http://schema.org/height> "1.65E0"^^<http://www.w3.org/2001/XMLSchema#double>.
+            <http://example.org/alice> <http://schema.org/married> "true"^^<http://www.w3.org/2001/XMLSchema#boolean>.
+        "#,
+    );
+}
+
+#[test]
+fn graphs_lists_and_reverse() {
+    assert_to_rdf(
+        &JsonLdParser::new(),
+        r#"{
+            "@context": {
+                "ex": "http://example.org/",
+                "items": {"@id": "ex:items", "@container": "@list"},
+                "parent": {"@reverse": "ex:child"}
+            },
+            "@id": "ex:g",
+            "@graph": [
+                {
+                    "@id": "ex:a",
+                    "items": ["x", {"@id": "ex:b"}],
+                    "parent": {"@id": "ex:p"}
+                }
+            ]
+        }"#,
+        r#"
+            <http://example.org/a> <http://example.org/items> _:l1 <http://example.org/g>.
+            _:l1 <http://www.w3.org/1999/02/22-rdf-syntax-ns#first> "x" <http://example.org/g>.
+            _:l1 <http://www.w3.org/1999/02/22-rdf-syntax-ns#rest> _:l2 <http://example.org/g>.
+            _:l2 <http://www.w3.org/1999/02/22-rdf-syntax-ns#first> <http://example.org/b> <http://example.org/g>.
+            _:l2 <http://www.w3.org/1999/02/22-rdf-syntax-ns#rest> <http://www.w3.org/1999/02/22-rdf-syntax-ns#nil> <http://example.org/g>.
+            <http://example.org/p> <http://example.org/child> <http://example.org/a> <http://example.org/g>.
+        "#,
+    );
+}
+
+#[test]
+fn rdf_direction() {
+    let jsonld = r#"{
+        "@id": "http://example.org/a",
+        "http://example.org/p": {"@value": "hello", "@language": "en", "@direction": "ltr"}
+    }"#;
+    let config = JsonLdConfig::new().rdf_direction(Some(RdfDirectionMode::I18nDatatype));
+    assert_to_rdf(
+        &JsonLdParser::new_with_config(config),
+        jsonld,
+        r#"<http://example.org/a> <http://example.org/p> "hello"^^<https://www.w3.org/ns/i18n#en_ltr>."#,
+    );
+    let config = JsonLdConfig::new().rdf_direction(Some(RdfDirectionMode::CompoundLiteral));
+    assert_to_rdf(
+        &JsonLdParser::new_with_config(config),
+        jsonld,
+        r#"
+            <http://example.org/a> <http://example.org/p> _:c.
+            _:c <http://www.w3.org/1999/02/22-rdf-syntax-ns#value> "hello".
+            _:c <http://www.w3.org/1999/02/22-rdf-syntax-ns#language> "en".
+            _:c <http://www.w3.org/1999/02/22-rdf-syntax-ns#direction> "ltr".
+        "#,
+    );
+    assert_to_rdf(
+        &JsonLdParser::new(),
+        jsonld,
+        r#"<http://example.org/a> <http://example.org/p> "hello"@en."#,
+    );
+}
+
+#[test]
+fn remote_context() {
+    let loader = StaticLoader::new().with_document(
+        "http://example.org/ctx.jsonld",
+        json::object! {"@context": {"name": "http://schema.org/name"}},
+    );
+    let jsonld = r#"{
+        "@context": "http://example.org/ctx.jsonld",
+        "@id": "http://example.org/a",
+        "name": "A"
+    }"#;
+    assert_to_rdf(
+        &JsonLdParser::new().with_loader(loader),
+        jsonld,
+        r#"<http://example.org/a> <http://schema.org/name> "A"."#,
+    );
+
+    let res: Result<QuadSet, _> = JsonLdParser::new().parse_str(jsonld).collect_quads();
+    assert!(matches!(
+        res,
+        Err(sophia_api::triple::stream::SourceError(
+            JsonLdError::Processing(JsonLdErrorCode::LoadingRemoteContextFailed, _)
+        ))
+    ));
+}
+
+#[test]
+fn processing_errors() {
+    for (jsonld, code) in [
+        (r#"{"@id": 42}"#, JsonLdErrorCode::InvalidIdValue),
+        (
+            r#"{"http://example.org/p": {"@value": "x", "@language": "en", "@type": "http://example.org/t"}}"#,
+            JsonLdErrorCode::InvalidValueObject,
+        ),
+        (
+            r#"{"@context": {"@vocab": 42}}"#,
+            JsonLdErrorCode::InvalidVocabMapping,
+        ),
+    ] {
+        let res: Result<QuadSet, _> = parse_str(jsonld).collect_quads();
+        match res {
+            Err(sophia_api::triple::stream::SourceError(JsonLdError::Processing(got, _))) => {
+                assert_eq!(got, code, "{}", jsonld)
+            }
+            res => panic!("unexpected result for {}: {:?}", jsonld, res),
+        }
+    }
+}
+
+#[test]
+fn limits() {
+    use sophia_api::parser::{Limit, LimitExceeded, ParserLimits};
+
+    let jsonld = r#"{
+        "@id": "http://example.org/a",
+        "http://example.org/p": ["short", "a [bit] longer", {"@id": "http://example.org/b"}]
+    }"#;
+    let exceeded = |limits: ParserLimits| {
+        let parser = JsonLdParser::new_with_config(JsonLdConfig::new().limits(limits));
+        let res: Result<QuadSet, _> = parser.parse_str(jsonld).collect_quads();
+        match res {
+            Ok(_) => None,
+            Err(sophia_api::triple::stream::SourceError(JsonLdError::LimitExceeded(
+                LimitExceeded { limit, .. },
+            ))) => Some(limit),
+            res => panic!("unexpected result: {:?}", res),
+        }
+    };
+    let none = ParserLimits::default();
+    assert_eq!(exceeded(none), None);
+    for (limits, limit) in [
+        (
+            ParserLimits {
+                max_input_bytes: Some(100),
+                ..none
+            },
+            Limit::InputBytes,
+        ),
+        (
+            ParserLimits {
+                max_triples: Some(2),
+                ..none
+            },
+            Limit::Triples,
+        ),
+        (
+            ParserLimits {
+                max_literal_len: Some(10),
+                ..none
+            },
+            Limit::LiteralLength,
+        ),
+        (
+            ParserLimits {
+                max_iri_len: Some(30),
+                ..none
+            },
+            Limit::IriLength,
+        ),
+        (
+            ParserLimits {
+                max_nesting_depth: Some(2),
+                ..none
+            },
+            Limit::NestingDepth,
+        ),
+    ] {
+        assert_eq!(exceeded(limits), Some(limit));
+    }
+    let limits = ParserLimits {
+        max_input_bytes: Some(jsonld.len()),
+        max_triples: Some(3),
+        max_literal_len: Some(14),
+        max_iri_len: Some(39),
+        max_nesting_depth: Some(3),
+    };
+    assert_eq!(exceeded(limits), None);
+}
+
+#[test]
+fn limits_enforced_while_generating() {
+    use sophia_api::parser::{Limit, LimitExceeded, ParserLimits};
+
+    let items: Vec<String> = (0..1000).map(|i| format!("\"item{}\"", i)).collect();
+    let jsonld = format!(
+        r#"{{"@id": "http://example.org/a", "http://example.org/p": [{}]}}"#,
+        items.join(",")
+    );
+    let limits = ParserLimits {
+        max_triples: Some(10),
+        ..ParserLimits::default()
+    };
+    let parser = JsonLdParser::new_with_config(JsonLdConfig::new().limits(limits));
+    let document = json::parse(&jsonld).unwrap();
+    match parser.to_quads(&document) {
+        Err(JsonLdError::LimitExceeded(LimitExceeded { limit, max })) => {
+            assert_eq!(limit, Limit::Triples);
+            assert_eq!(max, 10);
+        }
+        res => panic!("unexpected result: {:?}", res.map(|q| q.len())),
+    }
+}
+
+#[test]
+fn scoped_contexts_and_maps() {
+    assert_to_rdf(
+        &JsonLdParser::new(),
+        r#"{
+            "@context": {
+                "@vocab": "http://example.org/",
+                "Person": {"@context": {"name": "http://schema.org/name"}},
+                "labels": {"@container": "@language"},
+                "byId": {"@container": "@id"},
+                "data": {"@type": "@json"},
+                "meta": "@nest"
+            },
+            "@id": "http://example.org/a",
+            "@type": "Person",
+            "name": "A",
+            "labels": {"en": "Hello", "fr": ["Bonjour", "Salut"]},
+            "byId": {"http://example.org/b": {"http://example.org/p": "B"}},
+            "friend": {"@id": "http://example.org/c", "name": "C"},
+            "data": {"b": [1, 2.5], "a": null},
+            "meta": {"version": 2}
+        }"#,
+        r#"
+            <http://example.org/a> <http://www.w3.org/1999/02/22-rdf-syntax-ns#type> <http://example.org/Person>.
+            <http://example.org/a> <http://schema.org/name> "A".
+            <http://example.org/a> <http://example.org/labels> "Hello"@en.
+            <http://example.org/a> <http://example.org/labels> "Bonjour"@fr.
+            <http://example.org/a> <http://example.org/labels> "Salut"@fr.
+            <http://example.org/a> <http://example.org/byId> <http://example.org/b>.
+            <http://example.org/b> <http://example.org/p> "B".
+            <http://example.org/a> <http://example.org/friend> <http://example.org/c>.
+            <http://example.org/c> <http://example.org/name> "C".
+            <http://example.org/a> <http://example.org/data> "{\"a\":null,\"b\":[1,2.5]}"^^<http://www.w3.org/1999/02/22-rdf-syntax-ns#JSON>.
+            <http://example.org/a> <http://example.org/version> "2"^^<http://www.w3.org/2001/XMLSchema#integer>.
+        "#,
+    );
+}
+
+#[cfg(feature = "async")]
+#[test]
+fn parse_async() {
+    let jsonld = r#"{"@id": "http://example.org/a", "http://example.org/p": "A"}"#;
+    let quads: QuadSet =
+        futures_executor::block_on(JsonLdParser::new().parse_async(jsonld.as_bytes()))
+            .collect_quads()
+            .unwrap();
+    let expected = parse_nq(r#"<http://example.org/a> <http://example.org/p> "A"."#);
+    assert!(isomorphic_datasets(&quads, &expected).unwrap());
+}
@@ -0,0 +1,273 @@
+//! The [RDF serialization] algorithm of JSON-LD,
+//! converting an expanded document into RDF quads.
+//!
+//! [RDF serialization]: https://www.w3.org/TR/json-ld11-api/#deserialize-json-ld-to-rdf-algorithm
+
+use super::JsonLdQuad;
+use crate::config::*;
+use crate::context::{as_slice, is_bnode_id, is_iri, is_keyword};
+use crate::error::JsonLdError;
+use crate::expansion::{is_list_object, sorted_keys};
+use crate::node_map::*;
+use json::object::Object;
+use json::JsonValue;
+use sophia_api::ns::{rdf, xsd};
+use sophia_api::parser::{Limit, LimitExceeded};
+use sophia_api::term::{CopyTerm, TTerm};
+use sophia_term::BoxTerm;
+use std::collections::BTreeMap;
+
+/// Convert an expanded JSON-LD document to quads.
+///
+/// The limits on the number of quads and on the length of terms
+/// are checked as each quad is generated.
+pub fn to_quads(
+    config: &JsonLdConfig,
+    expanded: JsonValue,
+) -> Result<Vec<JsonLdQuad>, JsonLdError> {
+    let (node_map, issuer) = generate_node_map(&expanded)?;
+    let mut converter = RdfConverter {
+        config,
+        issuer,
+        quads: vec![],
+    };
+    for (graph_name, graph) in &node_map {
+        let graph_name = if graph_name == DEFAULT {
+            None
+        } else {
+            match node_term(graph_name) {
+                Some(term) => Some(term),
+                None => continue,
+            }
+        };
+        converter.graph_to_rdf(graph, &graph_name)?;
+    }
+    Ok(converter.quads)
+}
+
+/// Converts the node map into RDF quads.
+struct RdfConverter<'c> {
+    config: &'c JsonLdConfig,
+    issuer: BnodeIssuer,
+    quads: Vec<JsonLdQuad>,
+}
+
+impl<'c> RdfConverter<'c> {
+    /// Steps 1.3 and following of the [RDF serialization] algorithm, for a single graph.
+    ///
+    /// [RDF serialization]: https://www.w3.org/TR/json-ld11-api/#deserialize-json-ld-to-rdf-algorithm
+    fn graph_to_rdf(
+        &mut self,
+        graph: &BTreeMap<String, Object>,
+        graph_name: &Option<BoxTerm>,
+    ) -> Result<(), LimitExceeded> {
+        for (subject, node) in graph {
+            let subject = match node_term(subject) {
+                Some(term) => term,
+                None => continue,
+            };
+            for property in sorted_keys(node) {
+                let values = as_slice(&node[property]);
+                if property == "@type" {
+                    for t in values {
+                        if let Some(t) = t.as_str().and_then(node_term) {
+                            self.push(&subject, BoxTerm::copy(&rdf::type_), t, graph_name)?;
+                        }
+                    }
+                    continue;
+                }
+                if is_keyword(property)
+                    || (is_bnode_id(property) && !self.config.produce_generalized_rdf)
+                {
+                    continue;
+                }
+                let predicate = match node_term(property) {
+                    Some(term) => term,
+                    None => continue,
+                };
+                for item in values {
+                    let mut triples = vec![];
+                    if let Some(object) = self.object_to_rdf(item, &mut triples) {
+                        self.push(&subject, predicate.clone(), object, graph_name)?;
+                    }
+                    for [s, p, o] in triples {
+                        self.push(&s, p, o, graph_name)?;
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Add a quad, unless it exceeds the configured limits.
+    fn push(
+        &mut self,
+        s: &BoxTerm,
+        p: BoxTerm,
+        o: BoxTerm,
+        g: &Option<BoxTerm>,
+    ) -> Result<(), LimitExceeded> {
+        let limits = &self.config.limits;
+        limits.check(Limit::Triples, self.quads.len() + 1)?;
+        limits.check_term(s)?;
+        limits.check_term(&p)?;
+        limits.check_term(&o)?;
+        if let Some(g) = g {
+            limits.check_term(g)?;
+        }
+        self.quads.push(([s.clone(), p, o], g.clone()));
+        Ok(())
+    }
+
+    /// The [object to RDF] conversion algorithm.
+    ///
+    /// [object to RDF]: https://www.w3.org/TR/json-ld11-api/#object-to-rdf-conversion
+    fn object_to_rdf(
+        &mut self,
+        item: &JsonValue,
+        triples: &mut Vec<[BoxTerm; 3]>,
+    ) -> Option<BoxTerm> {
+        if item.has_key("@id") || !(item.has_key("@value") || item.has_key("@list")) {
+            return item["@id"].as_str().and_then(node_term);
+        }
+        if is_list_object(item) {
+            return Some(self.list_to_rdf(as_slice(&item["@list"]), triples));
+        }
+        let value = &item["@value"];
+        let mut datatype = item["@type"].as_str().map(str::to_string);
+        if let Some(dt) = &datatype {
+            if dt != "@json" && !is_iri(dt) {
+                return None;
+            }
+        }
+        let lexical = if datatype.as_deref() == Some("@json") {
+            datatype = Some(rdf::JSON.value().to_string());
+            canonical_json(value)
+        } else {
+            match value {
+                JsonValue::Boolean(b) => {
+                    datatype.get_or_insert_with(|| xsd::boolean.value().to_string());
+                    b.to_string()
+                }
+                JsonValue::Number(n) => {
+                    let f: f64 = (*n).into();
+                    let is_double = datatype.as_deref() == Some(xsd::double.value().as_ref());
+                    if f.fract() != 0.0 || f.abs() >= 1e21 || is_double {
+                        datatype.get_or_insert_with(|| xsd::double.value().to_string());
+                        canonical_double(f)
+                    } else {
+                        datatype.get_or_insert_with(|| xsd::integer.value().to_string());
+                        format!("{:.0}", f)
+                    }
+                }
+                value => value.as_str()?.to_string(),
+            }
+        };
+        let language = item["@language"].as_str();
+        if let (Some(direction), Some(mode)) =
+            (item["@direction"].as_str(), self.config.rdf_direction)
+        {
+            let lang = language.unwrap_or("").to_ascii_lowercase();
+            match mode {
+                RdfDirectionMode::I18nDatatype => {
+                    let dt = format!("https://www.w3.org/ns/i18n#{}_{}", lang, direction);
+                    return BoxTerm::new_literal_dt(lexical, BoxTerm::new_iri(dt).ok()?).ok();
+                }
+                RdfDirectionMode::CompoundLiteral => {
+                    let node = node_term(&self.issuer.issue(None))?;
+                    let value = BoxTerm::new_literal_dt(lexical, xsd::string).ok()?;
+                    triples.push([node.clone(), BoxTerm::copy(&rdf::value), value]);
+                    if language.is_some() {
+                        let lang = BoxTerm::new_literal_dt(lang, xsd::string).ok()?;
+                        triples.push([node.clone(), BoxTerm::copy(&rdf::language), lang]);
+                    }
+                    let direction = BoxTerm::new_literal_dt(direction, xsd::string).ok()?;
+                    triples.push([node.clone(), BoxTerm::copy(&rdf::direction), direction]);
+                    return Some(node);
+                }
+            }
+        }
+        match (language, datatype) {
+            (Some(lang), _) => BoxTerm::new_literal_lang(lexical, lang).ok(),
+            (None, Some(dt)) => BoxTerm::new_literal_dt(lexical, BoxTerm::new_iri(dt).ok()?).ok(),
+            (None, None) => BoxTerm::new_literal_dt(lexical, xsd::string).ok(),
+        }
+    }
+
+    /// The [list to RDF] conversion algorithm.
+    ///
+    /// [list to RDF]: https://www.w3.org/TR/json-ld11-api/#list-to-rdf-conversion
+    fn list_to_rdf(&mut self, list: &[JsonValue], triples: &mut Vec<[BoxTerm; 3]>) -> BoxTerm {
+        let nodes: Vec<BoxTerm> = list
+            .iter()
+            .map(|_| node_term(&self.issuer.issue(None)).unwrap())
+            .collect();
+        for (i, item) in list.iter().enumerate() {
+            let mut embedded = vec![];
+            if let Some(object) = self.object_to_rdf(item, &mut embedded) {
+                triples.push([nodes[i].clone(), BoxTerm::copy(&rdf::first), object]);
+            }
+            let rest = match nodes.get(i + 1) {
+                Some(next) => next.clone(),
+                None => BoxTerm::copy(&rdf::nil),
+            };
+            triples.push([nodes[i].clone(), BoxTerm::copy(&rdf::rest), rest]);
+            triples.extend(embedded);
+        }
+        nodes
+            .into_iter()
+            .next()
+            .unwrap_or_else(|| BoxTerm::copy(&rdf::nil))
+    }
+}
+
+/// Convert a node identifier (IRI or blank node identifier) into a term,
+/// or `None` if it is not well-formed.
+fn node_term(id: &str) -> Option<BoxTerm> {
+    if let Some(bnode_id) = id.strip_prefix("_:") {
+        BoxTerm::new_bnode(bnode_id).ok()
+    } else if is_iri(id) {
+        BoxTerm::new_iri(id).ok()
+    } else {
+        None
+    }
+}
+
+/// The canonical lexical form of an `xsd:double` (e.g. `1.1E0`).
+fn canonical_double(f: f64) -> String {
+    let txt = format!("{:E}", f);
+    match txt.find('E') {
+        Some(pos) if !txt[..pos].contains('.') => format!("{}.0{}", &txt[..pos], &txt[pos..]),
+        _ => txt,
+    }
+}
+
+/// The canonical form of a JSON value, as per [RFC 8785].
+///
+/// [RFC 8785]: https://www.rfc-editor.org/rfc/rfc8785
+fn canonical_json(value: &JsonValue) -> String {
+    match value {
+        JsonValue::Array(items) => {
+            let items: Vec<String> = items.iter().map(canonical_json).collect();
+            format!("[{}]", items.join(","))
+        }
+        JsonValue::Object(obj) => {
+            let mut keys: Vec<&str> = obj.iter().map(|(k, _)| k).collect();
+            keys.sort_unstable_by(|k1, k2| k1.encode_utf16().cmp(k2.encode_utf16()));
+            let entries: Vec<String> = keys
+                .into_iter()
+                .map(|k| format!("{}:{}", JsonValue::from(k).dump(), canonical_json(&obj[k])))
+                .collect();
+            format!("{{{}}}", entries.join(","))
+        }
+        JsonValue::Number(n) => {
+            let f: f64 = (*n).into();
+            if f.fract() == 0.0 && f.abs() < 1e21 {
+                format!("{:.0}", f)
+            } else {
+                JsonValue::from(f).dump()
+            }
+        }
+        value => value.dump(),
+    }
+}
@@ -2,23 +2,40 @@
 //! [`Serialize RDF as JSON-LD Algorithm`].
 //!
 //! [`Serialize RDF as JSON-LD Algorithm`]: https://www.w3.org/TR/json-ld11-api/#serialize-rdf-as-json-ld-algorithm
+//!
+//! The output is [compacted] if [`JsonLdConfig::compact_context`] is set,
+//! and [framed] if [`JsonLdConfig::frame`] is set.
+//!
+//! [compacted]: https://www.w3.org/TR/json-ld11-api/#compaction-algorithm
+//! [framed]: https://www.w3.org/TR/json-ld11-framing/#framing-algorithm
 
+use crate::compaction::Compactor;
 use crate::config::*;
+use crate::context::{Context, ContextProcessor};
 use crate::error::*;
+use crate::framing::{frame_document, unwrap_context};
+use crate::loader::{DocumentLoader, NoLoader};
 use json::JsonValue;
 use sophia_api::quad::stream::*;
 use sophia_api::serializer::*;
 use sophia_api::triple::stream::{SinkError, StreamResult};
+use std::rc::Rc;
 
 mod engine;
 mod rdf_object;
+mod streaming;
 #[cfg(test)]
 mod test;
 
 /// A JSON-LD serializer.
-pub struct JsonLdSerializer<W> {
+///
+/// Remote contexts (used for compaction or framing)
+/// are retrieved through the [`DocumentLoader`] `L`.
+/// By default, [`NoLoader`] is used, so that only local contexts can be used.
+pub struct JsonLdSerializer<W, L = NoLoader> {
     config: JsonLdConfig,
     target: W,
+    loader: L,
 }
 
 impl<W> JsonLdSerializer<W> {
@@ -30,14 +47,31 @@ impl<W> JsonLdSerializer<W> {
 
     /// Build a new JSON-LD serializer writing to `write`, with the given config.
     pub fn new_with_config(target: W, config: JsonLdConfig) -> JsonLdSerializer<W> {
-        JsonLdSerializer { config, target }
+        JsonLdSerializer {
+            config,
+            target,
+            loader: NoLoader,
+        }
+    }
+}
+
+impl<W, L> JsonLdSerializer<W, L> {
+    /// Use `loader` to retrieve remote contexts.
+    pub fn with_loader<L2: DocumentLoader>(self, loader: L2) -> JsonLdSerializer<W, L2> {
+        JsonLdSerializer {
+            config: self.config,
+            target: self.target,
+            loader,
+        }
     }
 
     /// Borrow this serializer's configuration.
     pub fn config(&self) -> &JsonLdConfig {
         &self.config
     }
+}
 
+impl<W, L: DocumentLoader> JsonLdSerializer<W, L> {
     /// Convert a quad stream into a Json object
     fn convert_quads<QS>(&mut self, source: QS) -> StreamResult<JsonValue, QS::Error, JsonLdError>
     where
@@ -45,13 +79,82 @@ impl<W> JsonLdSerializer<W> {
     {
         let mut engine = engine::Engine::new_with_config(self.config.clone());
         engine.process_quads(source)?;
-        engine.into_json().map_err(SinkError)
+        let expanded = engine.into_json().map_err(SinkError)?;
+        self.compact_or_frame(expanded).map_err(SinkError)
+    }
+
+    /// Convert a quad stream into a JSON string, indented as per the configuration
+    fn convert_quads_to_string<QS>(
+        &mut self,
+        source: QS,
+    ) -> StreamResult<String, QS::Error, JsonLdError>
+    where
+        QS: QuadSource,
+    {
+        let result = self.convert_quads(source)?;
+        Ok(match self.config.spaces {
+            0 => json::stringify(result),
+            x => json::stringify_pretty(result, x),
+        })
+    }
+
+    /// Frame and/or compact `expanded`, depending on the configuration.
+    fn compact_or_frame(&self, expanded: JsonValue) -> Result<JsonValue, JsonLdError> {
+        let processor = ContextProcessor::new(&self.loader, self.config.spec_version);
+        let base = self.config.base.as_deref();
+        let compact_context = self.config.compact_context.as_ref();
+        let mut output = if let Some(frame) = &self.config.frame {
+            frame_document(&processor, base, &expanded, frame, compact_context)?
+        } else {
+            let context = match compact_context {
+                Some(context) => unwrap_context(context),
+                None => return Ok(expanded),
+            };
+            let initial = Context::new(base.map(str::to_string));
+            let active = processor.process(&initial, context, base, &[], false, true, true)?;
+            Compactor::new(&processor).compact_document(&Rc::new(active), context, &expanded)?
+        };
+        if let Some(base) = base {
+            declare_base(&mut output, base);
+        }
+        Ok(output)
+    }
+}
+
+/// Declare `base` with `@base` in the context of `output` (if any),
+/// so that the relative IRIs produced by compaction can be resolved.
+///
+/// Nothing is done if the context already declares a base IRI.
+fn declare_base(output: &mut JsonValue, base: &str) {
+    let context = match output {
+        JsonValue::Object(obj) => match obj.get_mut("@context") {
+            Some(context) => context,
+            None => return,
+        },
+        _ => return,
+    };
+    match context {
+        JsonValue::Object(obj) => {
+            if obj.get("@base").is_none() {
+                obj.insert("@base", base.into());
+            }
+        }
+        JsonValue::Array(items) => {
+            if !items.iter().any(|item| item.has_key("@base")) {
+                items.push(json::object! {"@base": base});
+            }
+        }
+        _ => {
+            let remote = context.take();
+            *context = json::array![remote, json::object! {"@base": base}];
+        }
     }
 }
 
-impl<W> QuadSerializer for JsonLdSerializer<W>
+impl<W, L> QuadSerializer for JsonLdSerializer<W, L>
 where
     W: std::io::Write,
+    L: DocumentLoader,
 {
     type Error = JsonLdError;
 
@@ -59,11 +162,16 @@ where
     where
         QS: QuadSource,
     {
-        let result = self.convert_quads(source)?;
-        let json_txt = match self.config.spaces {
-            0 => json::stringify(result),
-            x => json::stringify_pretty(result, x),
-        };
+        if self.config.streaming
+            && self.config.compact_context.is_none()
+            && self.config.frame.is_none()
+        {
+            let mut writer = streaming::StreamingWriter::new(self.config.clone(), &mut self.target);
+            writer.process_quads(source)?;
+            writer.finish().map_err(SinkError)?;
+            return Ok(self);
+        }
+        let json_txt = self.convert_quads_to_string(source)?;
         self.target
             .write(json_txt.as_bytes())
             .map_err(|e| SinkError(e.into()))?;
@@ -71,6 +179,45 @@ where
     }
 }
 
+#[cfg(feature = "async")]
+impl<W, L> JsonLdSerializer<W, L>
+where
+    W: futures_util::io::AsyncWrite + Unpin,
+    L: DocumentLoader,
+{
+    /// Asynchronous counterpart of [`QuadSerializer::serialize_quads`]
+    /// (requires the `async` feature).
+    ///
+    /// The output is built in memory (even in [streaming](JsonLdConfig::streaming) mode),
+    /// then written asynchronously.
+    pub async fn serialize_quads_async<QS>(
+        &mut self,
+        source: QS,
+    ) -> StreamResult<&mut Self, QS::Error, JsonLdError>
+    where
+        QS: QuadSource,
+    {
+        use futures_util::io::AsyncWriteExt;
+        let mut buffer = vec![];
+        if self.config.streaming
+            && self.config.compact_context.is_none()
+            && self.config.frame.is_none()
+        {
+            let mut writer = streaming::StreamingWriter::new(self.config.clone(), &mut buffer);
+            writer.process_quads(source)?;
+            writer.finish().map_err(SinkError)?;
+        } else {
+            buffer = self.convert_quads_to_string(source)?.into_bytes();
+        }
+        self.target
+            .write_all(&buffer)
+            .await
+            .map_err(|e| SinkError(e.into()))?;
+        self.target.flush().await.map_err(|e| SinkError(e.into()))?;
+        Ok(self)
+    }
+}
+
 /// A utility type alias of [`JsonLdSerializer`] with `[JsonTarget]` as its target.
 ///
 /// [`JsonLdSerializer`]: struct.JsonLdSerializer.html
@@ -98,7 +245,9 @@ impl Jsonifier {
     pub fn new_jsonifier_with_config(config: JsonLdConfig) -> Self {
         JsonLdSerializer::new_with_config(JsonTarget(JsonValue::Null), config)
     }
+}
 
+impl<L> JsonLdSerializer<JsonTarget, L> {
     /// Get a reference to the converted JsonValue
     #[inline]
     pub fn as_json(&self) -> &JsonValue {
@@ -106,7 +255,7 @@ impl Jsonifier {
     }
 }
 
-impl QuadSerializer for Jsonifier {
+impl<L: DocumentLoader> QuadSerializer for JsonLdSerializer<JsonTarget, L> {
     type Error = JsonLdError;
 
     fn serialize_quads<QS>(&mut self, source: QS) -> StreamResult<&mut Self, QS::Error, Self::Error>
@@ -136,7 +285,7 @@ impl JsonLdStringifier {
     }
 }
 
-impl Stringifier for JsonLdStringifier {
+impl<L: DocumentLoader> Stringifier for JsonLdSerializer<Vec<u8>, L> {
     fn as_utf8(&self) -> &[u8] {
         &self.target[..]
     }
@@ -153,7 +153,8 @@ impl Engine {
     fn mark_list_node(&mut self, inode: usize) {
         let (g_id, s_id) = &self.gs_id[inode];
         debug_assert!(s_id.starts_with("_:"), "{}", s_id);
-        if let Some((iparent, pp)) = &self.unique_parent[s_id] {
+        // NB: a node may have no parent at all
+        if let Some(Some((iparent, pp))) = self.unique_parent.get(s_id) {
             if self.config.spec_version == JsonLd10 && pp == RDF_FIRST {
                 return;
             }
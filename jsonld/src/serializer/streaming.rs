@@ -0,0 +1,231 @@
+//! Streaming mode of the JSON-LD serializer (see [`JsonLdConfig::streaming`]).
+
+use super::engine::Engine;
+use crate::config::*;
+use crate::context::as_array;
+use crate::error::*;
+use crate::parser::JsonLdQuad;
+use crate::util_traits::*;
+use json::JsonValue;
+use sophia_api::ns::rdf;
+use sophia_api::quad::{stream::*, Quad};
+use sophia_api::term::{term_eq, CopyTerm};
+use sophia_api::triple::stream::{SinkError, SourceError, StreamResult};
+use sophia_term::BoxTerm;
+use std::collections::HashMap;
+use std::convert::Infallible;
+use std::io::Write;
+
+/// Writes the expanded form of a quad stream incrementally.
+///
+/// Consecutive quads sharing the same subject form a *group*.
+/// A group whose subject is a blank node that may be a list node or a compound literal
+/// (i.e. it has an `rdf:first`, `rdf:rest` or, if relevant, `rdf:direction` property)
+/// may be inlined in the group referring to it,
+/// so both groups are buffered until the end of the stream.
+/// A group referring to a blank node whose group has not been seen yet
+/// is held until that blank node is settled,
+/// i.e. until its group ends (or until the end of the stream).
+/// Every other group is converted and written as soon as the next one starts.
+pub struct StreamingWriter<'w, W> {
+    config: JsonLdConfig,
+    target: &'w mut W,
+    buffer: Engine,
+    group: Vec<JsonLdQuad>,
+    /// Whether the subject of the current group may be a list node or a compound literal
+    group_inlinable: bool,
+    /// The blank nodes whose group has ended, and whether they may be inlined
+    settled: HashMap<String, bool>,
+    /// The groups waiting for some blank nodes to be settled,
+    /// with the number of such blank nodes
+    held: HashMap<usize, (Vec<JsonLdQuad>, usize)>,
+    /// The held groups waiting for each unsettled blank node
+    waiting: HashMap<String, Vec<usize>>,
+    next_held: usize,
+    written: usize,
+}
+
+impl<'w, W: Write> StreamingWriter<'w, W> {
+    pub fn new(config: JsonLdConfig, target: &'w mut W) -> Self {
+        StreamingWriter {
+            buffer: Engine::new_with_config(config.clone()),
+            config,
+            target,
+            group: vec![],
+            group_inlinable: false,
+            settled: HashMap::new(),
+            held: HashMap::new(),
+            waiting: HashMap::new(),
+            next_held: 0,
+            written: 0,
+        }
+    }
+
+    pub fn process_quads<QS>(&mut self, mut source: QS) -> StreamResult<(), QS::Error, JsonLdError>
+    where
+        QS: QuadSource,
+    {
+        if self.config.spec_version > JsonLdSpecVersion::JsonLd11 {
+            return Err(SinkError(JsonLdError::UnsupportedVersion(
+                self.config.spec_version,
+            )));
+        }
+        source.try_for_each_quad(|q| {
+            if !q.is_jsonld() {
+                return Ok(());
+            }
+            if let Some((group_spo, _)) = self.group.first() {
+                if !term_eq(&group_spo[0], q.s()) {
+                    self.end_group()?;
+                }
+            }
+            self.group_inlinable |= q.s().is_bnode()
+                && (&rdf::first == q.p()
+                    || &rdf::rest == q.p()
+                    || (self.config.rdf_direction == Some(RdfDirectionMode::CompoundLiteral)
+                        && &rdf::direction == q.p()));
+            self.group.push((
+                [
+                    BoxTerm::copy(q.s()),
+                    BoxTerm::copy(q.p()),
+                    BoxTerm::copy(q.o()),
+                ],
+                q.g().map(BoxTerm::copy),
+            ));
+            Ok(())
+        })
+    }
+
+    /// Write the held and buffered groups, and terminate the output.
+    pub fn finish(mut self) -> Result<(), JsonLdError> {
+        self.end_group()?;
+        // the blank nodes still unsettled have no group, so they can not be inlined
+        let mut held: Vec<_> = std::mem::take(&mut self.held).into_iter().collect();
+        held.sort_unstable_by_key(|(id, _)| *id);
+        for (_, (group, _)) in held {
+            self.write_group(group)?;
+        }
+        let buffer = std::mem::replace(
+            &mut self.buffer,
+            Engine::new_with_config(JsonLdConfig::new()),
+        );
+        for item in as_array(buffer.into_json()?) {
+            self.write_item(item)?;
+        }
+        let end = match (self.written, self.config.spaces) {
+            (0, _) => "[]",
+            (_, 0) => "]",
+            _ => "\n]",
+        };
+        self.target.write_all(end.as_bytes())?;
+        Ok(())
+    }
+
+    /// Write, buffer or hold the current group, then settle its subject.
+    fn end_group(&mut self) -> Result<(), JsonLdError> {
+        let group = std::mem::take(&mut self.group);
+        let inlinable = std::mem::take(&mut self.group_inlinable);
+        let subject = match group.first() {
+            Some((spo, _)) if spo[0].is_bnode() => Some(spo[0].as_id()),
+            Some(_) => None,
+            None => return Ok(()),
+        };
+        // NB: a blank node is settled only once,
+        // even if its quads are not consecutive
+        let mut buffered = match &subject {
+            Some(id) => *self.settled.entry(id.clone()).or_insert(inlinable),
+            None => false,
+        };
+        let mut unsettled = vec![];
+        for (spo, _) in &group {
+            if spo[2].is_bnode() {
+                let id = spo[2].as_id();
+                match self.settled.get(&id) {
+                    Some(inlinable) => buffered |= inlinable,
+                    None if !unsettled.contains(&id) => unsettled.push(id),
+                    None => (),
+                }
+            }
+        }
+        if buffered {
+            self.buffer_group(group)?;
+        } else if unsettled.is_empty() {
+            self.write_group(group)?;
+        } else {
+            let id = self.next_held;
+            self.next_held += 1;
+            self.held.insert(id, (group, unsettled.len()));
+            for bnode in unsettled {
+                self.waiting.entry(bnode).or_default().push(id);
+            }
+        }
+        if let Some(subject) = subject {
+            self.release(&subject)?;
+        }
+        Ok(())
+    }
+
+    /// Release the groups held because of the (now settled) blank node `bnode`.
+    fn release(&mut self, bnode: &str) -> Result<(), JsonLdError> {
+        let inlinable = self.settled[bnode];
+        for id in self.waiting.remove(bnode).unwrap_or_default() {
+            let count = match self.held.get_mut(&id) {
+                Some((_, count)) => count,
+                None => continue, // already released
+            };
+            *count -= 1;
+            if inlinable {
+                let (group, _) = self.held.remove(&id).unwrap();
+                self.buffer_group(group)?;
+            } else if *count == 0 {
+                let (group, _) = self.held.remove(&id).unwrap();
+                self.write_group(group)?;
+            }
+        }
+        Ok(())
+    }
+
+    fn buffer_group(&mut self, group: Vec<JsonLdQuad>) -> Result<(), JsonLdError> {
+        let group = group.into_iter().map(Ok::<_, Infallible>);
+        self.buffer.process_quads(group).map_err(infallible_source)
+    }
+
+    fn write_group(&mut self, group: Vec<JsonLdQuad>) -> Result<(), JsonLdError> {
+        let group = group.into_iter().map(Ok::<_, Infallible>);
+        let mut engine = Engine::new_with_config(self.config.clone());
+        engine.process_quads(group).map_err(infallible_source)?;
+        for item in as_array(engine.into_json()?) {
+            self.write_item(item)?;
+        }
+        Ok(())
+    }
+
+    fn write_item(&mut self, item: JsonValue) -> Result<(), JsonLdError> {
+        let spaces = self.config.spaces;
+        let separator = match (self.written, spaces) {
+            (0, 0) => "[",
+            (0, _) => "[\n",
+            (_, 0) => ",",
+            _ => ",\n",
+        };
+        self.target.write_all(separator.as_bytes())?;
+        if spaces == 0 {
+            item.write(self.target)?;
+        } else {
+            // JSON strings can not contain raw newlines, so this only indents the structure
+            let indent = " ".repeat(spaces as usize);
+            let txt = item.pretty(spaces).replace('\n', &format!("\n{}", indent));
+            self.target.write_all(indent.as_bytes())?;
+            self.target.write_all(txt.as_bytes())?;
+        }
+        self.written += 1;
+        Ok(())
+    }
+}
+
+fn infallible_source(err: StreamError<Infallible, JsonLdError>) -> JsonLdError {
+    match err {
+        SourceError(never) => match never {},
+        SinkError(err) => err,
+    }
+}
@@ -1,4 +1,8 @@
+use crate::loader::*;
+use crate::serializer::*;
 use crate::test_util::*;
+use json::JsonValue;
+use sophia_api::quad::stream::QuadSource;
 use std::path::Path;
 
 #[test]
@@ -11,3 +15,395 @@ fn w3c_test_suite() {
     let (failed, skipped, passed) = manifest.perform_all_tests(true);
     assert_eq!(0, failed, "{}/{}", failed, failed + skipped + passed);
 }
+
+#[test]
+fn w3c_compact_test_suite() {
+    let mpath = Path::new("..")
+        .join("json-ld-api")
+        .join("tests")
+        .join("compact-manifest.jsonld");
+    let manifest = Manifest::new(&mpath);
+    let (failed, skipped, passed) = manifest.perform_all_tests(true);
+    assert_eq!(0, failed, "{}/{}", failed, failed + skipped + passed);
+}
+
+/// NB: the framing test suite is not part of the json-ld-api repository,
+/// this requires a checkout of <https://github.com/w3c/json-ld-framing> next to it.
+#[test]
+fn w3c_frame_test_suite() {
+    let mpath = Path::new("..")
+        .join("json-ld-framing")
+        .join("tests")
+        .join("frame-manifest.jsonld");
+    let manifest = Manifest::new(&mpath);
+    let (failed, skipped, passed) = manifest.perform_all_tests(true);
+    assert_eq!(0, failed, "{}/{}", failed, failed + skipped + passed);
+}
+
+const DATA: &str = r#"
+    <http://example.org/alice> <http://www.w3.org/1999/02/22-rdf-syntax-ns#type> <http://schema.org/Person>.
+    <http://example.org/alice> <http://schema.org/name> "Alice".
+    <http://example.org/alice> <http://www.w3.org/2000/01/rdf-schema#label> "Alice"@en.
+    <http://example.org/alice> <http://schema.org/knows> <http://example.org/bob>.
+    <http://example.org/alice> <http://schema.org/address> _:addr.
+    _:addr <http://schema.org/addressLocality> "Paris".
+    <http://example.org/bob> <http://schema.org/name> "Bob".
+"#;
+
+fn context() -> JsonValue {
+    json::object! {
+        "@vocab": "http://schema.org/",
+        "ex": "http://example.org/",
+        "knows": {"@type": "@id"},
+        "label": {"@id": "http://www.w3.org/2000/01/rdf-schema#label", "@language": "en"},
+    }
+}
+
+fn serialize<L: DocumentLoader>(serializer: &mut JsonLdSerializer<JsonTarget, L>) -> JsonValue {
+    let quads = sophia_turtle::parser::nq::parse_str(DATA);
+    serializer.serialize_quads(quads).unwrap();
+    serializer.as_json().clone()
+}
+
+fn assert_json_eq(got: JsonValue, expected: JsonValue) {
+    assert_eq!(
+        got,
+        expected,
+        "got:\n{}\nexpected:\n{}",
+        got.pretty(2),
+        expected.pretty(2)
+    );
+}
+
+#[test]
+fn compaction() {
+    let config = JsonLdConfig::new().compact_context(context());
+    let got = serialize(&mut Jsonifier::new_jsonifier_with_config(config));
+    assert_json_eq(
+        got,
+        json::object! {
+            "@context": context(),
+            "@graph": [
+                {
+                    "@id": "ex:alice",
+                    "@type": "Person",
+                    "address": {"@id": "_:addr"},
+                    "knows": "ex:bob",
+                    "name": "Alice",
+                    "label": "Alice",
+                },
+                {"@id": "ex:bob", "name": "Bob"},
+                {"@id": "_:addr", "addressLocality": "Paris"},
+            ],
+        },
+    );
+}
+
+#[test]
+fn compaction_with_containers() {
+    let data = r#"
+        <http://example.org/a> <http://example.org/label> "Hello"@en.
+        <http://example.org/a> <http://example.org/label> "Bonjour"@fr.
+        <http://example.org/a> <http://example.org/items> _:l1.
+        _:l1 <http://www.w3.org/1999/02/22-rdf-syntax-ns#first> "x".
+        _:l1 <http://www.w3.org/1999/02/22-rdf-syntax-ns#rest> _:l2.
+        _:l2 <http://www.w3.org/1999/02/22-rdf-syntax-ns#first> <http://example.org/b>.
+        _:l2 <http://www.w3.org/1999/02/22-rdf-syntax-ns#rest> <http://www.w3.org/1999/02/22-rdf-syntax-ns#nil>.
+        <http://example.org/a> <http://example.org/tag> "t1".
+    "#;
+    let context = json::object! {
+        "@vocab": "http://example.org/",
+        "labels": {"@id": "http://example.org/label", "@container": "@language"},
+        "items": {"@container": "@list"},
+        "tags": {"@id": "http://example.org/tag", "@container": "@set"},
+    };
+    let config = JsonLdConfig::new()
+        .base("http://example.org/")
+        .compact_context(context.clone());
+    let mut expected_context = context;
+    expected_context["@base"] = "http://example.org/".into();
+    let mut jsonifier = Jsonifier::new_jsonifier_with_config(config);
+    jsonifier
+        .serialize_quads(sophia_turtle::parser::nq::parse_str(data))
+        .unwrap();
+    assert_json_eq(
+        jsonifier.as_json().clone(),
+        json::object! {
+            "@context": expected_context,
+            "@id": "a",
+            "items": ["x", {"@id": "b"}],
+            "labels": {"en": "Hello", "fr": "Bonjour"},
+            "tags": ["t1"],
+        },
+    );
+}
+
+#[test]
+fn compaction_with_base() {
+    let data = r#"
+        <http://example.org/doc/a> <http://example.org/p> <http://example.org/b>.
+        <http://example.org/doc/a> <http://example.org/p> <http://example.org/doc/#c>.
+        <http://example.org/doc/a> <http://example.org/p> <http://example.org/doc/>.
+        <http://example.org/doc/a> <http://example.org/p> <http://example.com/d>.
+    "#;
+    let loader = StaticLoader::new().with_document(
+        "http://example.org/context.jsonld",
+        json::object! {"@context": {"@vocab": "http://example.org/"}},
+    );
+    let config = JsonLdConfig::new()
+        .base("http://example.org/doc/")
+        .compact_context(json::object! {"@context": "http://example.org/context.jsonld"});
+    let mut jsonifier = Jsonifier::new_jsonifier_with_config(config).with_loader(loader);
+    jsonifier
+        .serialize_quads(sophia_turtle::parser::nq::parse_str(data))
+        .unwrap();
+    assert_json_eq(
+        jsonifier.as_json().clone(),
+        json::object! {
+            "@context": [
+                "http://example.org/context.jsonld",
+                {"@base": "http://example.org/doc/"},
+            ],
+            "@id": "a",
+            "p": [
+                {"@id": "../b"},
+                {"@id": "#c"},
+                {"@id": ""},
+                {"@id": "http://example.com/d"},
+            ],
+        },
+    );
+}
+
+#[test]
+fn compaction_with_remote_context() {
+    let loader = StaticLoader::new().with_document(
+        "http://example.org/context.jsonld",
+        json::object! {"@context": context()},
+    );
+    let config = JsonLdConfig::new()
+        .compact_context(json::object! {"@context": "http://example.org/context.jsonld"});
+    let got =
+        serialize(&mut Jsonifier::new_jsonifier_with_config(config.clone()).with_loader(loader));
+    assert_eq!(got["@context"], "http://example.org/context.jsonld");
+    assert_eq!(
+        got["@graph"][1],
+        json::object! {"@id": "ex:bob", "name": "Bob"}
+    );
+
+    let quads = sophia_turtle::parser::nq::parse_str(DATA);
+    let mut jsonifier = Jsonifier::new_jsonifier_with_config(config);
+    assert!(matches!(
+        jsonifier.serialize_quads(quads).map(|_| ()),
+        Err(sophia_api::triple::stream::SinkError(
+            JsonLdError::Processing(JsonLdErrorCode::LoadingRemoteContextFailed, _)
+        ))
+    ));
+}
+
+#[test]
+fn framing() {
+    let frame = json::object! {"@context": context(), "@type": "Person"};
+    let config = JsonLdConfig::new().frame(frame);
+    let got = serialize(&mut Jsonifier::new_jsonifier_with_config(config));
+    assert_json_eq(
+        got,
+        json::object! {
+            "@context": context(),
+            "@id": "ex:alice",
+            "@type": "Person",
+            "address": {"addressLocality": "Paris"},
+            "knows": {"@id": "ex:bob", "name": "Bob"},
+            "label": "Alice",
+            "name": "Alice",
+        },
+    );
+}
+
+#[test]
+fn framing_flags_and_defaults() {
+    let frame = json::object! {
+        "@type": "http://schema.org/Person",
+        "@explicit": true,
+        "http://schema.org/name": {},
+        "http://schema.org/knows": {"@embed": "@never"},
+        "http://schema.org/age": {"@default": 42},
+        "http://schema.org/email": {},
+    };
+    let config = JsonLdConfig::new()
+        .frame(frame)
+        .compact_context(json::object! {"@vocab": "http://schema.org/"});
+    let got = serialize(&mut Jsonifier::new_jsonifier_with_config(config));
+    assert_json_eq(
+        got,
+        json::object! {
+            "@context": {"@vocab": "http://schema.org/"},
+            "@id": "http://example.org/alice",
+            "@type": "Person",
+            "age": 42,
+            "email": null,
+            "knows": {"@id": "http://example.org/bob"},
+            "name": "Alice",
+        },
+    );
+
+    let config = JsonLdConfig::new().frame(json::object! {"@embed": "@sometimes"});
+    let quads = sophia_turtle::parser::nq::parse_str(DATA);
+    let mut jsonifier = Jsonifier::new_jsonifier_with_config(config);
+    assert!(matches!(
+        jsonifier.serialize_quads(quads).map(|_| ()),
+        Err(sophia_api::triple::stream::SinkError(
+            JsonLdError::Processing(JsonLdErrorCode::InvalidEmbedValue, _)
+        ))
+    ));
+}
+
+#[test]
+fn stringifier_with_frame() {
+    let frame = json::object! {
+        "@context": {"@vocab": "http://schema.org/"},
+        "@id": "http://example.org/bob",
+    };
+    let config = JsonLdConfig::new().frame(frame);
+    let quads = sophia_turtle::parser::nq::parse_str(DATA);
+    let mut stringifier = JsonLdStringifier::new_stringifier_with_config(config);
+    let txt = stringifier.serialize_quads(quads).unwrap().to_string();
+    assert_eq!(
+        json::parse(&txt).unwrap(),
+        json::object! {
+            "@context": {"@vocab": "http://schema.org/"},
+            "@id": "http://example.org/bob",
+            "name": "Bob",
+        }
+    );
+}
+
+#[test]
+fn streaming() {
+    let data = r#"
+        <http://example.org/a> <http://example.org/p> "a1".
+        <http://example.org/a> <http://example.org/p> "a2" <http://example.org/g>.
+        <http://example.org/b> <http://example.org/items> _:l1.
+        <http://example.org/c> <http://example.org/p> <http://example.org/a>.
+        _:l1 <http://www.w3.org/1999/02/22-rdf-syntax-ns#first> "x".
+        _:l1 <http://www.w3.org/1999/02/22-rdf-syntax-ns#rest> <http://www.w3.org/1999/02/22-rdf-syntax-ns#nil>.
+    "#;
+    for spaces in [0, 2] {
+        let config = JsonLdConfig::new().streaming(true).spaces(spaces);
+        let mut stringifier = JsonLdStringifier::new_stringifier_with_config(config);
+        let txt = stringifier
+            .serialize_quads(sophia_turtle::parser::nq::parse_str(data))
+            .unwrap()
+            .to_string();
+        let got = json::parse(&txt).unwrap();
+        assert_json_eq(
+            got,
+            json::array![
+                {"@id": "http://example.org/a", "http://example.org/p": [{"@value": "a1"}]},
+                {"@id": "http://example.org/g", "@graph": [
+                    {"@id": "http://example.org/a", "http://example.org/p": [{"@value": "a2"}]},
+                ]},
+                {"@id": "http://example.org/c", "http://example.org/p": [{"@id": "http://example.org/a"}]},
+                {"@id": "http://example.org/b", "http://example.org/items": [{"@list": [{"@value": "x"}]}]},
+            ],
+        );
+        let quads: Vec<crate::JsonLdQuad> = crate::parser::parse_str(&txt).collect_quads().unwrap();
+        let expected: Vec<crate::JsonLdQuad> = sophia_turtle::parser::nq::parse_str(data)
+            .collect_quads()
+            .unwrap();
+        assert!(sophia_api::dataset::isomorphic_datasets(&quads, &expected).unwrap());
+    }
+
+    let config = JsonLdConfig::new().streaming(true);
+    let mut stringifier = JsonLdStringifier::new_stringifier_with_config(config);
+    let txt = stringifier
+        .serialize_quads(sophia_turtle::parser::nq::parse_str(""))
+        .unwrap()
+        .to_string();
+    assert_eq!(txt, "[]");
+}
+
+#[test]
+fn streaming_holds_only_what_may_be_inlined() {
+    let data = r#"
+        <http://example.org/a> <http://example.org/p> _:x.
+        _:x <http://example.org/p> "x".
+        <http://example.org/b> <http://example.org/items> _:l1.
+        <http://example.org/c> <http://example.org/p> _:y.
+        <http://example.org/d> <http://example.org/p> "d".
+        _:l1 <http://www.w3.org/1999/02/22-rdf-syntax-ns#first> "1".
+        _:l1 <http://www.w3.org/1999/02/22-rdf-syntax-ns#rest> <http://www.w3.org/1999/02/22-rdf-syntax-ns#nil>.
+    "#;
+    let config = JsonLdConfig::new().streaming(true);
+    let mut stringifier = JsonLdStringifier::new_stringifier_with_config(config);
+    let txt = stringifier
+        .serialize_quads(sophia_turtle::parser::nq::parse_str(data))
+        .unwrap()
+        .to_string();
+    let got = json::parse(&txt).unwrap();
+    let ids: Vec<_> = got.members().map(|item| item["@id"].to_string()).collect();
+    // a is written as soon as _:x is known not to be inlined,
+    // c is held until the end, because _:y is never described,
+    // b is buffered until the end, because _:l1 is a list
+    assert_eq!(
+        ids,
+        [
+            txt.split('"').find(|s| s.starts_with("_:")).unwrap(),
+            "http://example.org/a",
+            "http://example.org/d",
+            "http://example.org/c",
+            "http://example.org/b",
+        ]
+    );
+    assert_eq!(
+        got[4],
+        json::object! {
+            "@id": "http://example.org/b",
+            "http://example.org/items": [{"@list": [{"@value": "1"}]}],
+        }
+    );
+    let quads: Vec<crate::JsonLdQuad> = crate::parser::parse_str(&txt).collect_quads().unwrap();
+    let expected: Vec<crate::JsonLdQuad> = sophia_turtle::parser::nq::parse_str(data)
+        .collect_quads()
+        .unwrap();
+    assert!(sophia_api::dataset::isomorphic_datasets(&quads, &expected).unwrap());
+}
+
+#[test]
+fn list_node_without_parent() {
+    let data = r#"
+        _:l1 <http://www.w3.org/1999/02/22-rdf-syntax-ns#first> "1".
+        _:l1 <http://www.w3.org/1999/02/22-rdf-syntax-ns#rest> <http://www.w3.org/1999/02/22-rdf-syntax-ns#nil>.
+    "#;
+    for streaming in [false, true] {
+        let config = JsonLdConfig::new().streaming(streaming);
+        let mut stringifier = JsonLdStringifier::new_stringifier_with_config(config);
+        let txt = stringifier
+            .serialize_quads(sophia_turtle::parser::nq::parse_str(data))
+            .unwrap()
+            .to_string();
+        let quads: Vec<crate::JsonLdQuad> = crate::parser::parse_str(&txt).collect_quads().unwrap();
+        assert_eq!(quads.len(), 2);
+    }
+}
+
+#[cfg(feature = "async")]
+#[test]
+fn serialize_async() {
+    let data = r#"<http://example.org/a> <http://example.org/p> "a1" .
+        <http://example.org/a> <http://example.org/p> "a2" <http://example.org/g> .
+    "#;
+    for streaming in [false, true] {
+        let config = JsonLdConfig::new().streaming(streaming);
+        let expected = JsonLdStringifier::new_stringifier_with_config(config.clone())
+            .serialize_quads(sophia_turtle::parser::nq::parse_str(data))
+            .unwrap()
+            .to_string();
+        let mut serializer = JsonLdSerializer::new_with_config(vec![], config);
+        futures_executor::block_on(
+            serializer.serialize_quads_async(sophia_turtle::parser::nq::parse_str(data)),
+        )
+        .unwrap();
+        assert_eq!(String::from_utf8(serializer.target).unwrap(), expected);
+    }
+}
@@ -2,19 +2,40 @@
 
 #![allow(missing_docs)]
 
+use crate::compaction::Compactor;
 use crate::config::*;
+use crate::context::{Context, ContextProcessor};
 use crate::error::*;
+use crate::framing::{frame_document, unwrap_context};
+use crate::loader::FsLoader;
+use crate::parser::{JsonLdParser, JsonLdQuad};
 use crate::serializer::Jsonifier;
 use json::JsonValue;
-use sophia_api::dataset::MutableDataset;
+use sophia_api::dataset::{isomorphic_datasets, MutableDataset};
+use sophia_api::parser::QuadParser;
+use sophia_api::quad::stream::QuadSource;
 use sophia_api::serializer::QuadSerializer;
-use sophia_api::triple::stream::SinkError;
+use sophia_api::term::TTerm;
+use sophia_api::triple::stream::{SinkError, SourceError};
 use sophia_iri::resolve::Resolve;
 use sophia_term::iri::Iri;
 use sophia_term::BoxTerm;
 use std::collections::HashSet;
 use std::fs;
 use std::path::{Path, PathBuf};
+use std::rc::Rc;
+
+/// The test options supported by [`Test::config`].
+const OPTIONS: &[&str] = &[
+    "base",
+    "expandContext",
+    "processingMode",
+    "produceGeneralizedRdf",
+    "rdfDirection",
+    "specVersion",
+    "useNativeTypes",
+    "useRdfType",
+];
 
 pub struct Manifest {
     base: PathBuf,
@@ -100,6 +121,28 @@ impl<'a> Test<'a> {
         fs::read_to_string(path).expect("test input could not be read")
     }
 
+    /// The IRI of the input document, used as its base IRI.
+    pub fn input_iri(&self) -> String {
+        let mut buffer = String::new();
+        let input_iri = Iri::<&str>::new(self.json["input"].as_str().unwrap())
+            .expect("test input has invalid IRI");
+        let input_iri: Iri<Box<str>> = self
+            .manifest
+            .iri()
+            .parse_components(&mut buffer)
+            .resolve(&input_iri)
+            .map_into();
+        let value = input_iri.value().to_string();
+        value
+    }
+
+    /// A document loader serving the files of the test suite from the local file system.
+    pub fn loader(&self) -> FsLoader {
+        let manifest_iri = self.manifest.iri().value();
+        let prefix = &manifest_iri[..manifest_iri.rfind('/').unwrap() + 1];
+        FsLoader::new().with_mapping(prefix, self.manifest.base.clone())
+    }
+
     pub fn input_dataset(&self) -> HashSet<([BoxTerm; 3], Option<BoxTerm>)> {
         let nq = self.input();
         let quads = sophia_turtle::parser::nq::parse_str(&nq);
@@ -116,6 +159,16 @@ impl<'a> Test<'a> {
         fs::read_to_string(path).expect("test expect could not be read")
     }
 
+    /// Parse the JSON file referred to by the `key` entry of the test (e.g. `context`).
+    pub fn json_file(&self, key: &str) -> JsonValue {
+        let mut path = self.manifest.base.clone();
+        for part in self.json[key].as_str().unwrap().split('/') {
+            path.push(part);
+        }
+        let txt = fs::read_to_string(path).expect("test file could not be read");
+        json::parse(&txt).expect("test file could not be parsed")
+    }
+
     pub fn expected_json(&self) -> JsonValue {
         let src = self.expected();
         json::parse(&src).expect("test expect could not be parsed")
@@ -129,6 +182,28 @@ impl<'a> Test<'a> {
         let mut config = JsonLdConfig::new();
         for (key, val) in self.json["option"].entries() {
             match key {
+                "base" => {
+                    config.base = val.as_str().map(str::to_string);
+                }
+                "expandContext" => {
+                    let mut path = self.manifest.base.clone();
+                    for part in val.as_str().unwrap().split('/') {
+                        path.push(part);
+                    }
+                    let txt = fs::read_to_string(path).expect("expandContext could not be read");
+                    config.expand_context =
+                        Some(json::parse(&txt).expect("expandContext could not be parsed"));
+                }
+                "processingMode" => {
+                    config.spec_version = match val.as_str().unwrap() {
+                        "json-ld-1.0" => JsonLdSpecVersion::JsonLd10,
+                        "json-ld-1.1" => JsonLdSpecVersion::JsonLd11,
+                        _ => panic!("Unknown processingMode {}", val),
+                    };
+                }
+                "produceGeneralizedRdf" => {
+                    config.produce_generalized_rdf = val.as_bool().unwrap();
+                }
                 "rdfDirection" => {
                     config.rdf_direction = val.as_str().map(|val| match val {
                         "i18n-datatype" => RdfDirectionMode::I18nDatatype,
@@ -160,9 +235,22 @@ impl<'a> Test<'a> {
         self.json["option"]["specVersion"] == "json-ld-1.0"
     }
 
+    /// Whether this test uses options not supported by [`Test::config`].
+    pub fn has_unsupported_options(&self) -> bool {
+        self.json["option"]
+            .entries()
+            .any(|(key, _)| !OPTIONS.contains(&key))
+    }
+
     pub fn perform(&self, verbose: bool) -> TestResult {
         if self.json["@type"].contains("jld:FromRDFTest") {
             perform_from_rdf(self, verbose)
+        } else if self.json["@type"].contains("jld:ToRDFTest") {
+            perform_to_rdf(self, verbose)
+        } else if self.json["@type"].contains("jld:CompactTest") {
+            perform_compact(self, verbose)
+        } else if self.json["@type"].contains("jld:FrameTest") {
+            perform_frame(self, verbose)
         } else {
             if verbose {
                 println!("testing {} .....SKIP (unrecognized)", self.id());
@@ -232,6 +320,122 @@ pub fn perform_from_rdf(test: &Test, verbose: bool) -> TestResult {
     }
 }
 
+pub fn perform_to_rdf(test: &Test, verbose: bool) -> TestResult {
+    if verbose {
+        print!("testing {} .....\t", test.id());
+    }
+    if test.skip() || test.json["@type"].contains("jld:HttpTest") {
+        if verbose {
+            println!("SKIP");
+        }
+        return TestResult::Skip;
+    }
+    let mut config = test.config();
+    if config.base.is_none() {
+        config.base = Some(test.input_iri());
+    }
+    let parser = JsonLdParser::new_with_config(config).with_loader(test.loader());
+    let res: Result<Vec<JsonLdQuad>, _> = parser.parse_str(&test.input()).collect_quads();
+    let passed = if test.positive() {
+        match (&res, test.json.has_key("expect")) {
+            (Ok(got), true) => {
+                let exp: Vec<JsonLdQuad> = sophia_turtle::parser::nq::parse_str(&test.expected())
+                    .collect_quads()
+                    .expect("test expect could not be parsed");
+                isomorphic_datasets(got, &exp).unwrap()
+            }
+            (Ok(_), false) => true,
+            (Err(_), _) => false,
+        }
+    } else {
+        let exp = test.expected_error();
+        matches!(&res, Err(SourceError(e)) if format!("{}", e).starts_with(exp))
+    };
+    if passed {
+        if verbose {
+            println!("PASS");
+        }
+        TestResult::Pass
+    } else {
+        if verbose {
+            println!("FAIL\n  {:?}", res.map(|_| ()));
+        }
+        TestResult::Fail
+    }
+}
+
+pub fn perform_compact(test: &Test, verbose: bool) -> TestResult {
+    perform_compact_or_frame(test, verbose, |processor, base, expanded| {
+        let context = test.json_file("context");
+        let context = unwrap_context(&context);
+        let initial = Context::new(base.map(str::to_string));
+        let active = processor.process(&initial, context, base, &[], false, true, true)?;
+        Compactor::new(processor).compact_document(&Rc::new(active), context, expanded)
+    })
+}
+
+pub fn perform_frame(test: &Test, verbose: bool) -> TestResult {
+    perform_compact_or_frame(test, verbose, |processor, base, expanded| {
+        frame_document(processor, base, expanded, &test.json_file("frame"), None)
+    })
+}
+
+/// Expand the input of `test`, then pass it to `process`,
+/// and compare the result to the expected output (or error).
+fn perform_compact_or_frame<F>(test: &Test, verbose: bool, process: F) -> TestResult
+where
+    F: FnOnce(
+        &ContextProcessor<FsLoader>,
+        Option<&str>,
+        &JsonValue,
+    ) -> Result<JsonValue, JsonLdError>,
+{
+    if verbose {
+        print!("testing {} .....\t", test.id());
+    }
+    if test.skip() || test.json["@type"].contains("jld:HttpTest") || test.has_unsupported_options()
+    {
+        if verbose {
+            println!("SKIP");
+        }
+        return TestResult::Skip;
+    }
+    let mut config = test.config();
+    if config.base.is_none() {
+        config.base = Some(test.input_iri());
+    }
+    let input = json::parse(&test.input()).expect("test input could not be parsed");
+    let parser = JsonLdParser::new_with_config(config.clone()).with_loader(test.loader());
+    let res = parser.expand(&input).and_then(|expanded| {
+        let processor = ContextProcessor::new(parser.loader(), config.spec_version);
+        process(
+            &processor,
+            config.base.as_deref(),
+            &JsonValue::Array(expanded),
+        )
+    });
+    let passed = if test.positive() {
+        matches!(&res, Ok(got) if jsonld_cmp(got, &test.expected_json(), ""))
+    } else {
+        let exp = test.expected_error();
+        matches!(&res, Err(e) if format!("{}", e).starts_with(exp))
+    };
+    if passed {
+        if verbose {
+            println!("PASS");
+        }
+        TestResult::Pass
+    } else {
+        if verbose {
+            match &res {
+                Ok(got) => println!("FAIL\n    {}", json::stringify_pretty(got.clone(), 4)),
+                Err(e) => println!("FAIL\n  {:?}", e),
+            }
+        }
+        TestResult::Fail
+    }
+}
+
 pub fn jsonld_cmp(v1: &JsonValue, v2: &JsonValue, orig: &str) -> bool {
     use JsonValue::*;
     match (v1, v2) {
@@ -163,6 +163,137 @@ where
     }
 }
 
+/// TripleSource / QuadSource adapter for RIO TripleParser / QuadParser,
+/// that can optionally skip the statements that the parser fails to parse.
+///
+/// This only makes sense for parsers that are able to resume after an error,
+/// such as the line-based N-Triples and N-Quads parsers (which skip the faulty line).
+///
+/// Only syntax errors (i.e. errors with a [textual position](ParseError::textual_position))
+/// are skipped; other errors (typically I/O errors) are returned,
+/// after which the source produces nothing more.
+pub struct RioSource<T, E> {
+    parser: T,
+    lenient: bool,
+    errors: Vec<E>,
+    failed: bool,
+}
+
+impl<T, E> RioSource<T, E> {
+    /// Wrap a RIO parser.
+    ///
+    /// If `lenient` is false, this source behaves like [`StrictRioSource`],
+    /// i.e. it stops at the first error.
+    /// Otherwise, syntax errors are not returned, but recorded in [`errors`](RioSource::errors).
+    pub fn new(parser: T, lenient: bool) -> Self {
+        RioSource {
+            parser,
+            lenient,
+            errors: vec![],
+            failed: false,
+        }
+    }
+
+    /// The errors skipped so far (in lenient mode).
+    pub fn errors(&self) -> &[E] {
+        &self.errors
+    }
+
+    /// Remove and return the errors skipped so far (in lenient mode).
+    ///
+    /// This can be used to report errors on the fly, while parsing a large input.
+    pub fn take_errors(&mut self) -> Vec<E> {
+        std::mem::take(&mut self.errors)
+    }
+}
+
+impl<T, E: ParseError + 'static> RioSource<T, E> {
+    fn skip_error<EF: Error>(
+        &mut self,
+        res: StreamResult<bool, E, EF>,
+    ) -> StreamResult<bool, E, EF> {
+        match res {
+            Err(SourceError(err)) if self.lenient && err.textual_position().is_some() => {
+                self.errors.push(err);
+                Ok(true)
+            }
+            Err(err) => {
+                // the parser may not be able to recover (e.g. from an I/O error)
+                self.failed = true;
+                Err(err)
+            }
+            res => res,
+        }
+    }
+}
+
+impl<T, E> TripleSource for RioSource<T, E>
+where
+    T: TriplesParser<Error = E>,
+    E: ParseError + 'static,
+{
+    type Error = E;
+    type Triple = ScopedRioSourceTriple;
+
+    fn try_for_some_triple<F, EF>(&mut self, f: &mut F) -> StreamResult<bool, E, EF>
+    where
+        F: FnMut(StreamedTriple<Self::Triple>) -> Result<(), EF>,
+        EF: Error,
+    {
+        if self.failed || self.parser.is_end() {
+            return Ok(false);
+        }
+        let res = self
+            .parser
+            .parse_step(&mut |t| -> StdResult<(), MyStreamError<E, EF>> {
+                f(StreamedTriple::scoped([
+                    RioTermWrapper(t.subject.into()),
+                    RioTermWrapper(t.predicate.into()),
+                    RioTermWrapper(t.object),
+                ]))
+                .map_err(MyStreamError::from_sink_error)
+            })
+            .map_err(|e| e.into_stream_error())
+            .and(Ok(true));
+        self.skip_error(res)
+    }
+}
+
+impl<T, E> QuadSource for RioSource<T, E>
+where
+    T: QuadsParser<Error = E>,
+    E: ParseError + 'static,
+{
+    type Error = E;
+    type Quad = ScopedRioSourceQuad;
+
+    fn try_for_some_quad<F, EF>(&mut self, f: &mut F) -> StreamResult<bool, E, EF>
+    where
+        F: FnMut(StreamedQuad<Self::Quad>) -> Result<(), EF>,
+        EF: Error,
+    {
+        if self.failed || self.parser.is_end() {
+            return Ok(false);
+        }
+        let res = self
+            .parser
+            .parse_step(&mut |q| -> StdResult<(), MyStreamError<E, EF>> {
+                f(StreamedQuad::scoped((
+                    [
+                        RioTermWrapper(q.subject.into()),
+                        RioTermWrapper(q.predicate.into()),
+                        RioTermWrapper(q.object),
+                    ],
+                    q.graph_name.map(|g| RioTermWrapper(g.into())),
+                )))
+                .map_err(MyStreamError::from_sink_error)
+            })
+            .map_err(|e| e.into_stream_error())
+            .and(Ok(true));
+        self.skip_error(res)
+    }
+}
+
 /// A Generalized RIO source quad.
 pub type GRioSourceQuad<'a> = ([GRioTermWrapper<'a>; 3], Option<GRioTermWrapper<'a>>);
 sophia_api::make_scoped_quad_streaming_mode!(
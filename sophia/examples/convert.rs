@@ -1,15 +1,12 @@
 //! Convert RDF on stdin from one format to another
-use resiter::map::Map;
+use sophia::format::{self, Format};
 use sophia::iri::Iri;
-use sophia::parser::TripleParser;
 use sophia::prefix::Prefix;
 use sophia::serializer::turtle::TurtleConfig;
 use sophia::serializer::TripleSerializer;
-use sophia::term::{BoxTerm, CopyTerm};
-use sophia::triple::{stream::TripleSource, Triple};
+use sophia::term::BoxTerm;
 use std::convert::Infallible;
 use std::io;
-use std::iter::IntoIterator;
 
 fn main() -> Result<(), Box<dyn std::error::Error>> {
     let args: Vec<_> = std::env::args().collect();
@@ -70,53 +67,36 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
             );
         }
         _ => {
-            exit_printing_usage(&args, "unknown output format", Status::InputFormat);
+            exit_printing_usage(&args, "unknown output format", Status::OutputFormat);
         }
     }
     Ok(())
 }
 
 fn get_triples(args: &[String]) -> MyTripleSource {
+    let format = match args[1].parse::<Format>() {
+        Ok(format) if format.is_available() => format,
+        Ok(_) => exit_printing_usage(
+            args,
+            "input format not supported (recompile with the corresponding feature)",
+            Status::UnsupportedXml,
+        ),
+        Err(_) => exit_printing_usage(args, "unknown input format", Status::InputFormat),
+    };
     let bufread = io::BufReader::new(io::stdin());
-    match args[1].as_str() {
-        "nt" => wrap(sophia::parser::nt::parse_bufread(bufread)),
-        "turtle" => {
-            let p = sophia::parser::turtle::TurtleParser {
-                base: Some("x-stdin:".to_string()),
-            };
-            wrap(p.parse(bufread))
-        }
-        #[cfg(feature = "xml")]
-        "xml" => wrap(sophia::parser::xml::parse_bufread(bufread)),
-        #[cfg(not(feature = "xml"))]
-        "xml" => {
-            exit_printing_usage(
-                &args,
-                "RDF/XML not supported (recompile with --feature xml)",
-                Status::UnsupportedXml,
-            );
-        }
-        _ => {
-            exit_printing_usage(args, "unknown output format", Status::OutputFormat);
-        }
-    }
-}
-
-fn wrap<TS: TripleSource + 'static>(ts: TS) -> MyTripleSource {
-    Box::new(
-        ts.map_triples(|t| {
-            [
-                BoxTerm::copy(t.s()),
-                BoxTerm::copy(t.p()),
-                BoxTerm::copy(t.o()),
-            ]
-        })
-        .into_iter()
-        .map_err(|e| {
+    let quads = format::parse(format, bufread, Some("x-stdin:")).unwrap_or_else(|e| {
+        eprintln!("error while parsing: {}", e);
+        std::process::exit(Status::ParseError as i32)
+    });
+    // only keep the default graph
+    Box::new(quads.filter_map(|res| match res {
+        Ok((spo, None)) => Some(Ok(spo)),
+        Ok(_) => None,
+        Err(e) => {
             eprintln!("error while parsing: {}", e);
             std::process::exit(Status::ParseError as i32)
-        }),
-    )
+        }
+    }))
 }
 
 type MyTripleSource = Box<dyn Iterator<Item = Result<[BoxTerm; 3], Infallible>>>;
@@ -127,7 +107,11 @@ fn exit_printing_usage(args: &[String], message: &str, status: Status) -> ! {
     }
     eprintln!("usage: {} <format-in> <format-out>", args[0]);
     eprintln!("  convert RDF on STDIN from one format to another,");
-    eprintln!("  where format can be:");
+    eprintln!("  where format-in can be any name, extension or media type of:");
+    for format in Format::ALL.iter().filter(|f| f.is_available()) {
+        eprintln!("    - {} ({})", format, format.extensions().join(", "));
+    }
+    eprintln!("  and format-out can be:");
     eprintln!("    - nt");
     eprintln!("    - turtle");
     #[cfg(feature = "xml")]
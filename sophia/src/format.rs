@@ -0,0 +1,969 @@
+//! A registry of the RDF concrete syntaxes supported by Sophia.
+//!
+//! Each [`Format`] knows its media types and file extensions,
+//! and can be guessed from the content of a document ([`Format::sniff`]),
+//! or negotiated against an HTTP `Accept` header ([`Format::negotiate`]).
+//!
+//! The functions [`parse`], [`parse_file`] and [`serialize_to`]
+//! pick the appropriate parser or serializer for a given format.
+//! Parsing always produces a [`DynQuadSource`];
+//! triples from triple-only formats are in the default graph.
+//!
+//! ```
+//! use sophia::dataset::{inmem::FastDataset, Dataset};
+//! use sophia::format::{self, Format};
+//! use sophia::quad::stream::QuadSource;
+//!
+//! let data = "<tag:s> <tag:p> <tag:o> <tag:g> .\n";
+//! let format = Format::sniff(data.as_bytes()).unwrap();
+//! assert_eq!(format, Format::NQuads);
+//!
+//! let dataset: FastDataset = format::parse(format, data.as_bytes(), None)?.collect_quads()?;
+//! let mut output = vec![];
+//! let accepted = Format::negotiate("text/turtle;q=0.5, application/trig", &Format::ALL).unwrap();
+//! format::serialize_to(accepted, dataset.quads(), &mut output)?;
+//! assert_eq!(accepted.media_type(), "application/trig");
+//! # Ok::<(), Box<dyn std::error::Error>>(())
+//! ```
+//!
+//! RDF/XML and TriX require the `xml` feature, and JSON-LD requires the `jsonld` feature;
+//! when they are disabled, these formats can still be detected,
+//! but parsing or serializing them fails with [`FormatError::Unavailable`].
+
+use sophia_api::parser::{QuadParser, TripleParser};
+use sophia_api::quad::stream::QuadSource;
+use sophia_api::quad::Quad;
+use sophia_api::serializer::{QuadSerializer, TripleSerializer};
+use sophia_api::term::CopyTerm;
+use sophia_api::triple::stream::{SinkError, SourceError, StreamError, StreamResult, TripleSource};
+use sophia_api::triple::Triple;
+use sophia_binary::parser::BinaryParser;
+use sophia_binary::serializer::BinarySerializer;
+use sophia_term::BoxTerm;
+use sophia_turtle::parser::hext::HextParser;
+use sophia_turtle::parser::{nq::NQuadsParser, nt::NTriplesParser};
+use sophia_turtle::parser::{trig::TriGParser, turtle::TurtleParser};
+use sophia_turtle::serializer::hext::HextSerializer;
+use sophia_turtle::serializer::{nq::NqSerializer, nt::NtSerializer};
+use sophia_turtle::serializer::{trig::TrigSerializer, turtle::TurtleSerializer};
+use std::cmp::Ordering;
+use std::error::Error;
+use std::fmt;
+use std::fs::File;
+use std::io::{self, BufRead, BufReader};
+use std::path::Path;
+use std::str::FromStr;
+use thiserror::Error;
+
+/// The magic number starting documents in the [binary format](sophia_binary).
+const BINARY_MAGIC: &[u8] = b"SRDF";
+
+/// An RDF concrete syntax.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+#[non_exhaustive]
+pub enum Format {
+    /// [N-Triples](https://www.w3.org/TR/n-triples/)
+    NTriples,
+    /// [N-Quads](https://www.w3.org/TR/n-quads/)
+    NQuads,
+    /// [Turtle](https://www.w3.org/TR/turtle/)
+    Turtle,
+    /// [TriG](https://www.w3.org/TR/trig/)
+    TriG,
+    /// [RDF/XML](https://www.w3.org/TR/rdf-syntax-grammar/) (requires the `xml` feature)
+    RdfXml,
+    /// [JSON-LD](https://www.w3.org/TR/json-ld11/) (requires the `jsonld` feature)
+    JsonLd,
+    /// [HexTuples](https://github.com/ontola/hextuples)
+    HexTuples,
+    /// [TriX](https://www.hpl.hp.com/techreports/2004/HPL-2004-56.html) (requires the `xml` feature)
+    TriX,
+    /// The compact [binary format](sophia_binary) of Sophia
+    Binary,
+}
+
+struct FormatInfo {
+    format: Format,
+    label: &'static str,
+    /// short names accepted by [`Format::from_str`], besides extensions and media types
+    names: &'static [&'static str],
+    /// the first one is the preferred one
+    media_types: &'static [&'static str],
+    /// the first one is the preferred one
+    extensions: &'static [&'static str],
+    datasets: bool,
+}
+
+const REGISTRY: &[FormatInfo] = &[
+    FormatInfo {
+        format: Format::NTriples,
+        label: "N-Triples",
+        names: &["ntriples"],
+        media_types: &["application/n-triples"],
+        extensions: &["nt"],
+        datasets: false,
+    },
+    FormatInfo {
+        format: Format::NQuads,
+        label: "N-Quads",
+        names: &["nquads"],
+        media_types: &["application/n-quads"],
+        extensions: &["nq"],
+        datasets: true,
+    },
+    FormatInfo {
+        format: Format::Turtle,
+        label: "Turtle",
+        names: &["turtle"],
+        media_types: &["text/turtle", "application/x-turtle"],
+        extensions: &["ttl"],
+        datasets: false,
+    },
+    FormatInfo {
+        format: Format::TriG,
+        label: "TriG",
+        names: &["trig"],
+        media_types: &["application/trig", "application/x-trig"],
+        extensions: &["trig"],
+        datasets: true,
+    },
+    FormatInfo {
+        format: Format::RdfXml,
+        label: "RDF/XML",
+        names: &["rdfxml", "xml"],
+        media_types: &["application/rdf+xml"],
+        extensions: &["rdf", "owl"],
+        datasets: false,
+    },
+    FormatInfo {
+        format: Format::JsonLd,
+        label: "JSON-LD",
+        names: &["json-ld"],
+        media_types: &["application/ld+json"],
+        extensions: &["jsonld"],
+        datasets: true,
+    },
+    FormatInfo {
+        format: Format::HexTuples,
+        label: "HexTuples",
+        names: &["hextuples"],
+        media_types: &["application/hex+x-ndjson"],
+        extensions: &["hext"],
+        datasets: true,
+    },
+    FormatInfo {
+        format: Format::TriX,
+        label: "TriX",
+        names: &["trix"],
+        media_types: &["application/trix"],
+        extensions: &["trix"],
+        datasets: true,
+    },
+    FormatInfo {
+        format: Format::Binary,
+        label: "Sophia binary RDF",
+        names: &["binary"],
+        media_types: &["application/x-sophia-srdf"],
+        extensions: &["srdf"],
+        datasets: true,
+    },
+];
+
+impl Format {
+    /// All the formats known to this registry.
+    pub const ALL: [Format; 9] = [
+        Format::NTriples,
+        Format::NQuads,
+        Format::Turtle,
+        Format::TriG,
+        Format::RdfXml,
+        Format::JsonLd,
+        Format::HexTuples,
+        Format::TriX,
+        Format::Binary,
+    ];
+
+    fn info(self) -> &'static FormatInfo {
+        REGISTRY.iter().find(|i| i.format == self).unwrap()
+    }
+
+    /// The human-readable name of this format.
+    pub fn label(self) -> &'static str {
+        self.info().label
+    }
+
+    /// The preferred media type of this format.
+    pub fn media_type(self) -> &'static str {
+        self.info().media_types[0]
+    }
+
+    /// All the media types of this format, the preferred one first.
+    pub fn media_types(self) -> &'static [&'static str] {
+        self.info().media_types
+    }
+
+    /// The preferred file extension of this format (without the leading dot).
+    pub fn extension(self) -> &'static str {
+        self.info().extensions[0]
+    }
+
+    /// All the file extensions of this format (without the leading dot), the preferred one first.
+    pub fn extensions(self) -> &'static [&'static str] {
+        self.info().extensions
+    }
+
+    /// Whether this format can hold named graphs.
+    pub fn supports_datasets(self) -> bool {
+        self.info().datasets
+    }
+
+    /// Whether this format can be parsed and serialized,
+    /// given the features enabled in this crate.
+    #[allow(clippy::match_like_matches_macro)] // cfg! expands to a literal
+    pub fn is_available(self) -> bool {
+        match self {
+            Format::RdfXml | Format::TriX => cfg!(feature = "xml"),
+            Format::JsonLd => cfg!(feature = "jsonld"),
+            _ => true,
+        }
+    }
+
+    /// The format with the given media type, if any.
+    ///
+    /// Media type parameters (e.g. `charset`) are ignored.
+    pub fn from_media_type(media_type: &str) -> Option<Format> {
+        let media_type = media_type.split(';').next()?.trim();
+        REGISTRY
+            .iter()
+            .find(|i| {
+                i.media_types
+                    .iter()
+                    .any(|mt| mt.eq_ignore_ascii_case(media_type))
+            })
+            .map(|i| i.format)
+    }
+
+    /// The format with the given file extension (with or without the leading dot), if any.
+    pub fn from_extension(extension: &str) -> Option<Format> {
+        let extension = extension.strip_prefix('.').unwrap_or(extension);
+        REGISTRY
+            .iter()
+            .find(|i| {
+                i.extensions
+                    .iter()
+                    .any(|e| e.eq_ignore_ascii_case(extension))
+            })
+            .map(|i| i.format)
+    }
+
+    /// The format corresponding to the extension of `path`, if any.
+    pub fn from_path<P: AsRef<Path>>(path: P) -> Option<Format> {
+        Format::from_extension(path.as_ref().extension()?.to_str()?)
+    }
+
+    /// Guess the format of a document from its first bytes.
+    ///
+    /// This is a heuristic, which only looks at the first statement of the document
+    /// (after comments and whitespace).
+    /// Note that a Turtle document may be guessed as N-Triples,
+    /// as N-Triples is a subset of Turtle.
+    pub fn sniff(data: &[u8]) -> Option<Format> {
+        if data.starts_with(BINARY_MAGIC) {
+            return Some(Format::Binary);
+        }
+        // the sample may have been cut in the middle of a character
+        let text = match std::str::from_utf8(data) {
+            Ok(text) => text,
+            Err(err) => std::str::from_utf8(&data[..err.valid_up_to()]).unwrap(),
+        };
+        let text = skip_comments(text.trim_start_matches('\u{feff}'));
+        if text.starts_with("<?xml") || text.starts_with("<rdf:RDF") || text.starts_with("<TriX") {
+            return if xml_root(text).starts_with("<TriX") {
+                Some(Format::TriX)
+            } else {
+                Some(Format::RdfXml)
+            };
+        }
+        if let Some(rest) = text.strip_prefix('{') {
+            let rest = rest.trim_start();
+            return if rest.is_empty() || rest.starts_with('"') || rest.starts_with('}') {
+                Some(Format::JsonLd)
+            } else {
+                Some(Format::TriG)
+            };
+        }
+        if let Some(rest) = text.strip_prefix('[') {
+            let rest = rest.trim_start();
+            if rest.is_empty() || rest.starts_with('{') || rest.starts_with(']') {
+                return Some(Format::JsonLd);
+            }
+            if rest.starts_with('"') {
+                return Some(Format::HexTuples);
+            }
+        }
+        if text.is_empty() {
+            return None;
+        }
+        match count_nquads_terms(text) {
+            Some(3) => Some(Format::NTriples),
+            Some(4) => Some(Format::NQuads),
+            _ if looks_like_trig(text) => Some(Format::TriG),
+            _ => Some(Format::Turtle),
+        }
+    }
+
+    /// Select, among `candidates`, the format best matching an HTTP `Accept` header.
+    ///
+    /// Formats are ranked by the quality value of their most specific matching media range,
+    /// then by the specificity of that range (exact media type, `type/*`, then `*/*`),
+    /// then by the position of that range in `accept`;
+    /// remaining ties are broken by the order of `candidates`.
+    /// Return `None` if no candidate is acceptable.
+    ///
+    /// To only consider formats which can be serialized, pass
+    /// `&Format::ALL.into_iter().filter(|f| f.is_available()).collect::<Vec<_>>()`.
+    pub fn negotiate(accept: &str, candidates: &[Format]) -> Option<Format> {
+        let ranges = parse_accept(accept);
+        let mut best: Option<(AcceptMatch, Format)> = None;
+        for format in candidates {
+            for media_type in format.media_types() {
+                let m = match accept_match(&ranges, media_type) {
+                    Some(m) if m.q > 0.0 => m,
+                    _ => continue,
+                };
+                if best
+                    .map(|(best_m, _)| m.is_better_than(&best_m))
+                    .unwrap_or(true)
+                {
+                    best = Some((m, *format));
+                }
+            }
+        }
+        best.map(|(_, format)| format)
+    }
+}
+
+impl fmt::Display for Format {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.label().fmt(f)
+    }
+}
+
+impl FromStr for Format {
+    type Err = FormatError;
+
+    /// Accept a media type, a file extension, or a short name
+    /// such as `turtle` or `ntriples` (case-insensitive).
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Format::from_media_type(s)
+            .or_else(|| Format::from_extension(s))
+            .or_else(|| {
+                REGISTRY
+                    .iter()
+                    .find(|i| i.names.iter().any(|n| n.eq_ignore_ascii_case(s)))
+                    .map(|i| i.format)
+            })
+            .ok_or_else(|| FormatError::UnknownFormat(s.to_string()))
+    }
+}
+
+/// An error raised by the functions of this module.
+#[derive(Debug, Error)]
+pub enum FormatError {
+    /// The format could not be determined.
+    #[error("unknown format: {0}")]
+    UnknownFormat(String),
+    /// The format is known, but the corresponding feature is not enabled.
+    #[error("{0} is not available (the corresponding feature is not enabled)")]
+    Unavailable(Format),
+    /// Some quads in named graphs could not be serialized in a triple-only format.
+    #[error("{0} can not represent named graphs")]
+    NamedGraph(Format),
+    /// An I/O error.
+    #[error("{0}")]
+    Io(#[from] io::Error),
+    /// An error raised by a parser.
+    #[error("{0}")]
+    Parse(Box<dyn Error + Send + Sync>),
+    /// An error raised by a serializer.
+    #[error("{0}")]
+    Serialize(Box<dyn Error + Send + Sync>),
+}
+
+/// The type of quads produced by [`DynQuadSource`].
+pub type FormatQuad = ([BoxTerm; 3], Option<BoxTerm>);
+
+/// The quad source returned by the parsing functions of this module.
+pub type DynQuadSource = Box<dyn Iterator<Item = Result<FormatQuad, FormatError>>>;
+
+/// Parse `data` in the given format,
+/// using `base` (if any) to resolve relative IRIs.
+pub fn parse<B: BufRead + 'static>(
+    format: Format,
+    data: B,
+    base: Option<&str>,
+) -> Result<DynQuadSource, FormatError> {
+    let base = base.map(str::to_string);
+    Ok(match format {
+        Format::NTriples => boxed_triples(NTriplesParser::default().parse(data)),
+        Format::NQuads => boxed_quads(NQuadsParser::default().parse(data)),
+        Format::HexTuples => boxed_quads(HextParser::default().parse(data)),
+        Format::Binary => boxed_quads_lossy(QuadParser::parse(&BinaryParser::default(), data)),
+        Format::Turtle => boxed_triples(
+            TurtleParser {
+                base,
+                ..TurtleParser::default()
+            }
+            .parse(data),
+        ),
+        Format::TriG => boxed_quads(
+            TriGParser {
+                base,
+                ..TriGParser::default()
+            }
+            .parse(data),
+        ),
+        #[cfg(feature = "xml")]
+        Format::RdfXml => boxed_triples(
+            sophia_xml::parser::RdfXmlParser {
+                base,
+                ..Default::default()
+            }
+            .parse(data),
+        ),
+        #[cfg(feature = "xml")]
+        Format::TriX => {
+            boxed_quads_lossy(sophia_xml::parser::trix::TrixParser::default().parse(data))
+        }
+        #[cfg(feature = "jsonld")]
+        Format::JsonLd => {
+            let mut config = sophia_jsonld::JsonLdConfig::new();
+            config.base = base;
+            boxed_quads(sophia_jsonld::JsonLdParser::new_with_config(config).parse(data))
+        }
+        #[allow(unreachable_patterns)]
+        _ => return Err(FormatError::Unavailable(format)),
+    })
+}
+
+/// Parse the file at `path`.
+///
+/// The format is determined by the file extension,
+/// or guessed from the content of the file if the extension is unknown.
+pub fn parse_file<P: AsRef<Path>>(path: P) -> Result<DynQuadSource, FormatError> {
+    let path = path.as_ref();
+    let mut data = BufReader::new(File::open(path)?);
+    let format = match Format::from_path(path) {
+        Some(format) => format,
+        None => Format::sniff(data.fill_buf()?)
+            .ok_or_else(|| FormatError::UnknownFormat(path.display().to_string()))?,
+    };
+    parse(format, data, None)
+}
+
+/// Serialize `source` in the given format into `write`.
+///
+/// If `format` can not represent named graphs,
+/// the quads of the default graph are serialized,
+/// and [`FormatError::NamedGraph`] is returned if any quad was in a named graph.
+pub fn serialize_to<QS, W>(
+    format: Format,
+    source: QS,
+    write: W,
+) -> StreamResult<(), QS::Error, FormatError>
+where
+    QS: QuadSource,
+    W: io::Write,
+{
+    if !format.is_available() {
+        return Err(SinkError(FormatError::Unavailable(format)));
+    }
+    if format.supports_datasets() {
+        return serialize_quads(format, source, write);
+    }
+    let mut named = false;
+    let triples = source.filter_map_quads(|q| {
+        if q.g().is_some() {
+            named = true;
+            return None;
+        }
+        Some([
+            BoxTerm::copy(q.s()),
+            BoxTerm::copy(q.p()),
+            BoxTerm::copy(q.o()),
+        ])
+    });
+    serialize_triples(format, triples, write)?;
+    if named {
+        return Err(SinkError(FormatError::NamedGraph(format)));
+    }
+    Ok(())
+}
+
+fn serialize_quads<QS, W>(
+    format: Format,
+    source: QS,
+    write: W,
+) -> StreamResult<(), QS::Error, FormatError>
+where
+    QS: QuadSource,
+    W: io::Write,
+{
+    match format {
+        Format::NQuads => NqSerializer::new(write)
+            .serialize_quads(source)
+            .map(|_| ())
+            .map_err(serialize_error),
+        Format::TriG => TrigSerializer::new(write)
+            .serialize_quads(source)
+            .map(|_| ())
+            .map_err(serialize_error),
+        Format::HexTuples => HextSerializer::new(write)
+            .serialize_quads(source)
+            .map(|_| ())
+            .map_err(serialize_error),
+        Format::Binary => BinarySerializer::new(write)
+            .serialize_quads(source)
+            .map(|_| ())
+            .map_err(serialize_error),
+        #[cfg(feature = "xml")]
+        Format::TriX => sophia_xml::serializer::trix::TrixSerializer::new(write)
+            .serialize_quads(source)
+            .map(|_| ())
+            .map_err(serialize_error),
+        #[cfg(feature = "jsonld")]
+        Format::JsonLd => sophia_jsonld::JsonLdSerializer::new(write)
+            .serialize_quads(source)
+            .map(|_| ())
+            .map_err(serialize_error),
+        _ => Err(SinkError(FormatError::Unavailable(format))),
+    }
+}
+
+fn serialize_triples<TS, W>(
+    format: Format,
+    source: TS,
+    write: W,
+) -> StreamResult<(), TS::Error, FormatError>
+where
+    TS: TripleSource,
+    W: io::Write,
+{
+    match format {
+        Format::NTriples => NtSerializer::new(write)
+            .serialize_triples(source)
+            .map(|_| ())
+            .map_err(serialize_error),
+        Format::Turtle => TurtleSerializer::new(write)
+            .serialize_triples(source)
+            .map(|_| ())
+            .map_err(serialize_error),
+        #[cfg(feature = "xml")]
+        Format::RdfXml => sophia_xml::serializer::RdfXmlSerializer::new(write)
+            .serialize_triples(source)
+            .map(|_| ())
+            .map_err(serialize_error),
+        _ => Err(SinkError(FormatError::Unavailable(format))),
+    }
+}
+
+fn serialize_error<E1, E2>(err: StreamError<E1, E2>) -> StreamError<E1, FormatError>
+where
+    E1: Error + 'static,
+    E2: Error + Send + Sync + 'static,
+{
+    match err {
+        SourceError(err) => SourceError(err),
+        SinkError(err) => SinkError(FormatError::Serialize(Box::new(err))),
+    }
+}
+
+fn boxed_triples<TS>(source: TS) -> DynQuadSource
+where
+    TS: TripleSource + 'static,
+    TS::Error: Send + Sync,
+{
+    Box::new(
+        source
+            .map_triples(|t| {
+                (
+                    [
+                        BoxTerm::copy(t.s()),
+                        BoxTerm::copy(t.p()),
+                        BoxTerm::copy(t.o()),
+                    ],
+                    None,
+                )
+            })
+            .into_iter()
+            .map(|res| res.map_err(|err| FormatError::Parse(Box::new(err)))),
+    )
+}
+
+fn boxed_quads<QS>(source: QS) -> DynQuadSource
+where
+    QS: QuadSource + 'static,
+    QS::Error: Send + Sync,
+{
+    boxed_quads_with(source, |err| FormatError::Parse(Box::new(err)))
+}
+
+/// Like [`boxed_quads`], for parsers whose errors are not `Send + Sync`
+/// (e.g. because they wrap a [`TermError`](sophia_term::TermError)):
+/// their errors are only kept as a message.
+fn boxed_quads_lossy<QS>(source: QS) -> DynQuadSource
+where
+    QS: QuadSource + 'static,
+{
+    boxed_quads_with(source, |err| FormatError::Parse(err.to_string().into()))
+}
+
+fn boxed_quads_with<QS, F>(source: QS, convert: F) -> DynQuadSource
+where
+    QS: QuadSource + 'static,
+    F: Fn(QS::Error) -> FormatError + 'static,
+{
+    Box::new(
+        source
+            .map_quads(|q| {
+                (
+                    [
+                        BoxTerm::copy(q.s()),
+                        BoxTerm::copy(q.p()),
+                        BoxTerm::copy(q.o()),
+                    ],
+                    q.g().map(BoxTerm::copy),
+                )
+            })
+            .into_iter()
+            .map(move |res| res.map_err(&convert)),
+    )
+}
+
+/// Skip leading whitespace and comments.
+fn skip_comments(mut text: &str) -> &str {
+    loop {
+        text = text.trim_start();
+        match text.strip_prefix('#') {
+            Some(comment) => text = comment.find('\n').map(|i| &comment[i..]).unwrap_or(""),
+            None => return text,
+        }
+    }
+}
+
+/// Skip the XML declaration, processing instructions, comments and doctype
+/// preceding the root element of an XML document.
+fn xml_root(mut text: &str) -> &str {
+    loop {
+        text = text.trim_start();
+        let end = if text.starts_with("<?") {
+            text.find("?>").map(|i| i + 2)
+        } else if text.starts_with("<!--") {
+            text.find("-->").map(|i| i + 3)
+        } else if text.starts_with("<!") {
+            // a doctype, possibly with an internal subset
+            match (text.find('['), text.find('>')) {
+                (Some(i), Some(j)) if i < j => text.find("]>").map(|i| i + 2),
+                (_, j) => j.map(|j| j + 1),
+            }
+        } else {
+            return text;
+        };
+        match end {
+            Some(end) => text = &text[end..],
+            None => return "",
+        }
+    }
+}
+
+/// If `text` starts with an N-Triples or N-Quads statement, return its number of terms.
+fn count_nquads_terms(text: &str) -> Option<usize> {
+    let line = text.lines().next()?;
+    let mut rest = line;
+    let mut count = 0;
+    loop {
+        rest = rest.trim_start();
+        if let Some(after) = rest.strip_prefix('.') {
+            let after = after.trim_start();
+            return (after.is_empty() || after.starts_with('#')).then_some(count);
+        } else if let Some(iri) = rest.strip_prefix('<') {
+            rest = &iri[iri.find('>')? + 1..];
+        } else if let Some(label) = rest.strip_prefix("_:") {
+            let end = label
+                .find(|c: char| c.is_whitespace() || c == '<' || c == '"')
+                .unwrap_or(label.len());
+            // a label can contain dots, but can not end with one
+            let end = label[..end].trim_end_matches('.').len();
+            rest = &label[end..];
+        } else if let Some(literal) = rest.strip_prefix('"') {
+            rest = skip_string(literal)?;
+            if let Some(tag) = rest.strip_prefix('@') {
+                let end = tag
+                    .find(|c: char| !c.is_ascii_alphanumeric() && c != '-')
+                    .unwrap_or(tag.len());
+                rest = &tag[end..];
+            } else if let Some(dt) = rest.strip_prefix("^^<") {
+                rest = &dt[dt.find('>')? + 1..];
+            }
+        } else {
+            return None;
+        }
+        count += 1;
+    }
+}
+
+/// Skip the rest of a double-quoted string (after the opening quote).
+fn skip_string(text: &str) -> Option<&str> {
+    let mut chars = text.char_indices();
+    while let Some((i, c)) = chars.next() {
+        match c {
+            '\\' => {
+                chars.next();
+            }
+            '"' => return Some(&text[i + 1..]),
+            _ => (),
+        }
+    }
+    None
+}
+
+/// Whether a Turtle-like document contains graph blocks.
+fn looks_like_trig(text: &str) -> bool {
+    text.split(|c: char| c.is_whitespace())
+        .any(|word| word.eq_ignore_ascii_case("GRAPH") || word.starts_with('{'))
+        || text.contains("> {")
+}
+
+/// Parse an `Accept` header into (lower-cased media range, quality) pairs.
+fn parse_accept(accept: &str) -> Vec<(String, f32)> {
+    accept
+        .split(',')
+        .filter_map(|item| {
+            let mut parts = item.split(';');
+            let range = parts.next()?.trim().to_ascii_lowercase();
+            if range.is_empty() {
+                return None;
+            }
+            let q = parts
+                .filter_map(|p| p.trim().strip_prefix("q="))
+                .next()
+                .map(|q| q.trim().parse().unwrap_or(0.0))
+                .unwrap_or(1.0);
+            Some((range, q))
+        })
+        .collect()
+}
+
+/// How a media type matches an `Accept` header.
+#[derive(Clone, Copy, Debug)]
+struct AcceptMatch {
+    /// The quality value of the most specific matching range.
+    q: f32,
+    /// 2 if that range is the exact media type, 1 if it is `type/*`, 0 if it is `*/*`.
+    specificity: u8,
+    /// The position of that range in the header.
+    index: usize,
+}
+
+impl AcceptMatch {
+    fn is_better_than(&self, other: &AcceptMatch) -> bool {
+        self.q
+            .partial_cmp(&other.q)
+            .unwrap_or(Ordering::Equal)
+            .then(self.specificity.cmp(&other.specificity))
+            .then(other.index.cmp(&self.index))
+            == Ordering::Greater
+    }
+}
+
+/// Match `media_type` against its most specific range in `ranges`, if any.
+fn accept_match(ranges: &[(String, f32)], media_type: &str) -> Option<AcceptMatch> {
+    let main_type = media_type.split('/').next().unwrap_or("");
+    let mut best: Option<AcceptMatch> = None;
+    for (index, (range, q)) in ranges.iter().enumerate() {
+        let specificity = if range == media_type {
+            2
+        } else if range.strip_suffix("/*") == Some(main_type) {
+            1
+        } else if range == "*/*" {
+            0
+        } else {
+            continue;
+        };
+        if best.map(|m| specificity > m.specificity).unwrap_or(true) {
+            best = Some(AcceptMatch {
+                q: *q,
+                specificity,
+                index,
+            });
+        }
+    }
+    best
+}
+
+// ---------------------------------------------------------------------------------
+//                                      tests
+// ---------------------------------------------------------------------------------
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::dataset::{inmem::FastDataset, isomorphic_datasets, Dataset};
+
+    #[test]
+    fn lookup() {
+        assert_eq!(Format::from_media_type("text/turtle"), Some(Format::Turtle));
+        assert_eq!(
+            Format::from_media_type("Application/N-Quads; charset=utf-8"),
+            Some(Format::NQuads)
+        );
+        assert_eq!(Format::from_media_type("text/html"), None);
+        assert_eq!(Format::from_extension(".TTL"), Some(Format::Turtle));
+        assert_eq!(Format::from_path("a/b.jsonld"), Some(Format::JsonLd));
+        assert_eq!(Format::from_path("a/b.hext"), Some(Format::HexTuples));
+        assert_eq!(Format::from_path("a/b.trix"), Some(Format::TriX));
+        assert_eq!(Format::from_path("a/b.srdf"), Some(Format::Binary));
+        assert_eq!(
+            Format::from_media_type("application/trix"),
+            Some(Format::TriX)
+        );
+        assert_eq!(Format::from_path("a/b"), None);
+        assert_eq!("xml".parse::<Format>().unwrap(), Format::RdfXml);
+        assert_eq!("nt".parse::<Format>().unwrap(), Format::NTriples);
+        assert!("foo".parse::<Format>().is_err());
+        for format in Format::ALL {
+            assert_eq!(Format::from_media_type(format.media_type()), Some(format));
+            assert_eq!(Format::from_extension(format.extension()), Some(format));
+        }
+    }
+
+    #[test]
+    fn sniff() {
+        let cases = [
+            ("<s> <p> <o> .\n<s> <p> <o2> .", Some(Format::NTriples)),
+            (
+                "# comment\n_:b <p> \"x\\\"y\"@en-US .",
+                Some(Format::NTriples),
+            ),
+            ("<s> <p> \"1\"^^<dt> <g> .", Some(Format::NQuads)),
+            ("\u{feff}@prefix : <x:>.\n:s :p :o.", Some(Format::Turtle)),
+            ("<s> <p> <o>, <o2> .", Some(Format::Turtle)),
+            ("[ <p> <o> ] .", Some(Format::Turtle)),
+            ("PREFIX : <x:>\nGRAPH :g { :s :p :o }", Some(Format::TriG)),
+            ("<g> { <s> <p> <o> }", Some(Format::TriG)),
+            ("{ <s> <p> <o> }", Some(Format::TriG)),
+            ("<?xml version=\"1.0\"?>\n<rdf:RDF/>", Some(Format::RdfXml)),
+            (
+                "<?xml version=\"1.0\"?>\n<!-- c -->\n<TriX xmlns=\"x:\"/>",
+                Some(Format::TriX),
+            ),
+            (
+                "<?xml version=\"1.0\"?>\n<!-- <TriX> -->",
+                Some(Format::RdfXml),
+            ),
+            ("{\n  \"@id\": \"x:s\" }", Some(Format::JsonLd)),
+            ("[ { \"@id\": \"x:s\" } ]", Some(Format::JsonLd)),
+            (
+                "[\"x:s\", \"x:p\", \"x:o\", \"globalId\", \"\", \"\"]",
+                Some(Format::HexTuples),
+            ),
+            ("  \n# only a comment\n", None),
+        ];
+        for (data, expected) in cases {
+            assert_eq!(Format::sniff(data.as_bytes()), expected, "{:?}", data);
+        }
+        assert_eq!(Format::sniff(b"SRDF\x01\x01\x00"), Some(Format::Binary));
+    }
+
+    #[test]
+    fn negotiate() {
+        let all = &Format::ALL;
+        assert_eq!(Format::negotiate("text/turtle", all), Some(Format::Turtle));
+        assert_eq!(
+            Format::negotiate("text/turtle;q=0.5, application/ld+json", all),
+            Some(Format::JsonLd)
+        );
+        assert_eq!(
+            Format::negotiate("text/*;q=0.3, */*;q=0.1", all),
+            Some(Format::Turtle)
+        );
+        assert_eq!(Format::negotiate("*/*", all), Some(Format::NTriples));
+        assert_eq!(
+            Format::negotiate("*/*, application/n-triples;q=0", all),
+            Some(Format::NQuads)
+        );
+        assert_eq!(
+            Format::negotiate("application/x-trig", all),
+            Some(Format::TriG)
+        );
+        assert_eq!(Format::negotiate("text/html", all), None);
+        assert_eq!(
+            Format::negotiate("text/turtle, application/trig;q=0.9", &[Format::TriG]),
+            Some(Format::TriG)
+        );
+        // equal quality: more specific ranges first, then the order of the header
+        let candidates = &[Format::Turtle, Format::NTriples];
+        assert_eq!(
+            Format::negotiate("text/*, application/n-triples;q=1", candidates),
+            Some(Format::NTriples)
+        );
+        assert_eq!(
+            Format::negotiate("*/*, application/n-triples", candidates),
+            Some(Format::NTriples)
+        );
+        assert_eq!(
+            Format::negotiate("application/n-triples, text/turtle", candidates),
+            Some(Format::NTriples)
+        );
+        assert_eq!(
+            Format::negotiate(
+                "text/turtle, application/n-triples",
+                &[Format::NTriples, Format::Turtle]
+            ),
+            Some(Format::Turtle)
+        );
+        // quality still comes first
+        assert_eq!(
+            Format::negotiate("application/n-triples;q=0.5, text/*", candidates),
+            Some(Format::Turtle)
+        );
+    }
+
+    #[test]
+    fn round_trip() -> Result<(), Box<dyn std::error::Error>> {
+        let trig = "<x:s> <x:p> <x:o>. <x:g> { <x:s> <x:p> \"lit\"@en, _:b }";
+        let dataset: FastDataset = parse(Format::TriG, trig.as_bytes(), None)?.collect_quads()?;
+        assert_eq!(dataset.quads().count(), 3);
+        for format in Format::ALL.into_iter().filter(|f| f.is_available()) {
+            let mut output = vec![];
+            let res = serialize_to(format, dataset.quads(), &mut output);
+            let expected: FastDataset = if format.supports_datasets() {
+                assert!(res.is_ok(), "{}: {:?}", format, res);
+                dataset.quads().collect_quads()?
+            } else {
+                assert!(matches!(res, Err(SinkError(FormatError::NamedGraph(_)))));
+                dataset
+                    .quads()
+                    .filter_quads(|q| q.g().is_none())
+                    .collect_quads()?
+            };
+            assert!(Format::sniff(&output).is_some());
+            let got: FastDataset = parse(format, io::Cursor::new(output), None)?.collect_quads()?;
+            assert!(isomorphic_datasets(&got, &expected)?, "{}", format);
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn parse_file() -> Result<(), Box<dyn std::error::Error>> {
+        let dir = std::env::temp_dir();
+        let path = dir.join(format!("sophia-format-test-{}", std::process::id()));
+        std::fs::write(&path, "<x:s> <x:p> <x:o> <x:g> .\n")?;
+        let res = super::parse_file(&path).and_then(|source| source.collect::<Result<Vec<_>, _>>());
+        std::fs::remove_file(&path)?;
+        let quads = res?;
+        assert_eq!(quads.len(), 1);
+        assert!(quads[0].1.is_some());
+        Ok(())
+    }
+}
@@ -61,6 +61,7 @@
 
 #![deny(missing_docs)]
 
+pub mod format;
 pub mod query;
 
 /// This module re-exports symbols from
@@ -89,11 +90,14 @@ pub mod ns {
 }
 /// This module re-exports symbols from
 /// [`sophia_api::parser`] and [`sophia_turtle::parser`].
-/// If the `xml` feature is enabled, it also re-exports
+/// It also re-exports [`sophia_binary::parser`] as [`binary`](crate::parser::binary),
+/// and, if the `xml` feature is enabled,
 /// [`sophia_xml::parser`] as [`xml`](crate::parser::xml).
 pub mod parser {
     pub use sophia_api::parser::*;
+    pub use sophia_binary::parser as binary;
     pub use sophia_turtle::parser::gtrig;
+    pub use sophia_turtle::parser::hext;
     pub use sophia_turtle::parser::nq;
     pub use sophia_turtle::parser::nt;
     pub use sophia_turtle::parser::trig;
@@ -116,10 +120,13 @@ pub mod quad {
 }
 /// This module re-exports symbols from
 /// [`sophia_api::serializer`] and [`sophia_turtle::serializer`].
-/// If the `xml` feature is enabled, it also re-exports
+/// It also re-exports [`sophia_binary::serializer`] as [`binary`](crate::serializer::binary),
+/// and, if the `xml` feature is enabled,
 /// [`sophia_xml::serializer`] as [`xml`](crate::serializer::xml).
 pub mod serializer {
     pub use sophia_api::serializer::*;
+    pub use sophia_binary::serializer as binary;
+    pub use sophia_turtle::serializer::hext;
     pub use sophia_turtle::serializer::nq;
     pub use sophia_turtle::serializer::nt;
     pub use sophia_turtle::serializer::trig;
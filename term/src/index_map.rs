@@ -2,6 +2,7 @@
 
 use crate::factory::{FTerm, TermFactory};
 use crate::RefTerm;
+use thiserror::Error;
 
 /// A bidirectional mapping between [`Term`]s and *indexes* of a smaller type.
 ///
@@ -25,11 +26,16 @@ pub trait TermIndexMap: Default {
     /// Return the index associated to the given term, if it exists.
     fn get_index(&self, t: &RefTerm) -> Option<Self::Index>;
     /// Return the index associated to the given term, creating it if required, and increasing its ref count.
-    fn make_index(&mut self, t: &RefTerm) -> Self::Index;
+    ///
+    /// Fails if no index is available for a new term,
+    /// or if the ref count of an existing index can not be increased anymore.
+    fn make_index(&mut self, t: &RefTerm) -> Result<Self::Index, TermIndexFullError>;
     /// Return the term associated to the given index, if it exists.
     fn get_term(&self, i: Self::Index) -> Option<&FTerm<Self::Factory>>;
     /// Increase the reference count of a given index (or do nothing if i is the null index).
-    fn inc_ref(&mut self, i: Self::Index);
+    ///
+    /// Fails if the ref count can not be increased anymore.
+    fn inc_ref(&mut self, i: Self::Index) -> Result<(), TermIndexFullError>;
     /// Decrease the reference count of a given index (or do nothing if i is the null index).
     fn dec_ref(&mut self, i: Self::Index);
     /// Shrinks the capacity of the TermIndexMap as much as possible.
@@ -45,9 +51,12 @@ pub trait TermIndexMap: Default {
         }
     }
     /// Return the index associated to the given graph name, creating it if required, and increasing its ref count.
-    fn make_index_for_graph_name(&mut self, g: Option<&RefTerm>) -> Self::Index {
+    fn make_index_for_graph_name(
+        &mut self,
+        g: Option<&RefTerm>,
+    ) -> Result<Self::Index, TermIndexFullError> {
         match g {
-            None => Self::NULL_INDEX,
+            None => Ok(Self::NULL_INDEX),
             Some(t) => self.make_index(t),
         }
     }
@@ -68,6 +77,19 @@ pub trait TermIndexMap: Default {
     }
 }
 
+/// This error is raised when a [`TermIndexMap`] has exhausted its [`Index`](TermIndexMap::Index) type,
+/// either because no index is left for a new term,
+/// or because the ref count of an index has reached its maximum value.
+#[derive(Clone, Copy, Debug, Error, PartialEq, Eq)]
+#[error("The term index map is full (its index type is exhausted)")]
+pub struct TermIndexFullError;
+
+impl From<std::convert::Infallible> for TermIndexFullError {
+    fn from(x: std::convert::Infallible) -> TermIndexFullError {
+        match x {}
+    }
+}
+
 #[allow(clippy::cognitive_complexity)]
 /// Takes an empty TermIndexMap, and checks that it behaves as expected.
 ///
@@ -78,7 +100,7 @@ pub fn assert_term_index_map_works<T: TermIndexMap>(ti: &mut T) {
 
     // insert term, then remove it
 
-    let it = ti.make_index(&t);
+    let it = ti.make_index(&t).unwrap();
     assert!(ti.get_index(&t).is_some());
     assert!(ti.get_index(&t).unwrap() == it);
     assert!(ti.get_term(it).is_some());
@@ -90,13 +112,13 @@ pub fn assert_term_index_map_works<T: TermIndexMap>(ti: &mut T) {
 
     // insert term twice, then remove it
 
-    let it = ti.make_index(&t);
+    let it = ti.make_index(&t).unwrap();
     assert!(ti.get_index(&t).is_some());
     assert!(ti.get_index(&t).unwrap() == it);
     assert!(ti.get_term(it).is_some());
     assert!(ti.get_term(it).unwrap() == &t);
 
-    let it2 = ti.make_index(&t);
+    let it2 = ti.make_index(&t).unwrap();
     assert!(it == it2);
 
     ti.dec_ref(it);
@@ -111,13 +133,13 @@ pub fn assert_term_index_map_works<T: TermIndexMap>(ti: &mut T) {
 
     // insert term, incref it, then remove it
 
-    let it = ti.make_index(&t);
+    let it = ti.make_index(&t).unwrap();
     assert!(ti.get_index(&t).is_some());
     assert!(ti.get_index(&t).unwrap() == it);
     assert!(ti.get_term(it).is_some());
     assert!(ti.get_term(it).unwrap() == &t);
 
-    ti.inc_ref(it);
+    ti.inc_ref(it).unwrap();
 
     ti.dec_ref(it);
     assert!(ti.get_index(&t).is_some());
@@ -133,8 +155,8 @@ pub fn assert_term_index_map_works<T: TermIndexMap>(ti: &mut T) {
 
     let t1 = t;
     let t2 = RefTerm::new_iri("http://example.org/2").unwrap();
-    let it1 = ti.make_index(&t1);
-    let it2 = ti.make_index(&t2);
+    let it1 = ti.make_index(&t1).unwrap();
+    let it2 = ti.make_index(&t2).unwrap();
     assert!(it1 != it2);
 
     ti.dec_ref(it2);
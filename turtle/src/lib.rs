@@ -2,7 +2,7 @@
 //! an [RDF] and [Linked Data] toolkit in Rust.
 //!
 //! Parsers and serializers for the Turtle-familt of RDF concrete syntaxes,
-//! based on [`rio_turtle`](https://docs.rs/rio_turtle/).
+//! partly based on [`rio_turtle`](https://docs.rs/rio_turtle/).
 //!
 //! [Sophia]: https://docs.rs/sophia/latest/sophia/
 //! [RDF]: https://www.w3.org/TR/rdf-primer/
@@ -1,8 +1,22 @@
-//! Parsers for the Turtle-familt of RDF concrete syntaxes,
-//! based on [`rio_turtle`].
+//! Parsers for the Turtle-familt of RDF concrete syntaxes.
+//!
+//! The [Turtle](turtle) and [TriG](trig) parsers are native to Sophia;
+//! they report the [location](sophia_api::parser::WithLocation) of every error,
+//! and can optionally recover from errors (see [`TurtleParser::recovery`](turtle::TurtleParser::recovery)).
+//! The [HexTuples](hext) parser is also native;
+//! the other parsers are based on [`rio_turtle`].
+
+mod _error;
+pub use _error::*;
+mod _native;
+
+#[cfg(feature = "async")]
+pub mod asynchronous;
 
 pub mod gtrig;
+pub mod hext;
 pub mod nq;
 pub mod nt;
+pub mod parallel;
 pub mod trig;
 pub mod turtle;
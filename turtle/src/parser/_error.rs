@@ -0,0 +1,84 @@
+// this module is transparently re-exported by its parent `parser`
+use sophia_api::parser::{LimitExceeded, Location, WithLocation};
+use std::fmt;
+use std::io;
+use std::ops::Range;
+use thiserror::Error;
+
+/// A position in a parsed document.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub struct TextPosition {
+    /// The line number, starting at 1.
+    pub line: usize,
+    /// The column number (in characters), starting at 1.
+    pub column: usize,
+    /// The byte offset, starting at 0.
+    pub offset: usize,
+}
+
+impl Default for TextPosition {
+    fn default() -> Self {
+        TextPosition {
+            line: 1,
+            column: 1,
+            offset: 0,
+        }
+    }
+}
+
+impl fmt::Display for TextPosition {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}:{}", self.line, self.column)
+    }
+}
+
+/// An error raised by the [Turtle](super::turtle) and [TriG](super::trig) parsers.
+#[derive(Debug, Error)]
+pub enum TurtleParseError {
+    /// The underlying reader failed.
+    #[error("{0}")]
+    Io(#[from] io::Error),
+    /// The document is not valid.
+    #[error("{message} at {start}")]
+    Syntax {
+        /// A description of the error.
+        message: String,
+        /// The start of the faulty text.
+        start: TextPosition,
+        /// The end of the faulty text.
+        end: TextPosition,
+    },
+    /// One of the [limits](sophia_api::parser::ParserLimits) of the parser was exceeded.
+    #[error("{error} at {position}")]
+    Limit {
+        /// The exceeded limit.
+        error: LimitExceeded,
+        /// Where the limit was exceeded.
+        position: TextPosition,
+    },
+}
+
+impl TurtleParseError {
+    /// The byte offsets of the faulty text, if known.
+    pub fn byte_range(&self) -> Option<Range<usize>> {
+        match self {
+            TurtleParseError::Io(_) => None,
+            TurtleParseError::Syntax { start, end, .. } => Some(start.offset..end.offset),
+            TurtleParseError::Limit { position, .. } => Some(position.offset..position.offset),
+        }
+    }
+}
+
+impl WithLocation for TurtleParseError {
+    fn location(&self) -> Location {
+        match self {
+            TurtleParseError::Io(_) => Location::Unknown,
+            TurtleParseError::Syntax { start, end, .. } => {
+                Location::from_licos(start.line, start.column, end.line, end.column)
+            }
+            TurtleParseError::Limit { position, .. } => {
+                Location::from_lico(position.line, position.column)
+            }
+        }
+    }
+}
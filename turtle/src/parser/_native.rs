@@ -0,0 +1,1319 @@
+//! The native parser shared by [`turtle`](super::turtle) and [`trig`](super::trig).
+//!
+//! It is a hand-written recursive-descent parser,
+//! reading its input incrementally and producing the triples of each statement
+//! once the statement is complete.
+//! In recovery mode, a faulty statement is skipped and its error recorded,
+//! and parsing resumes with the next statement.
+//! Exceeding one of the [`ParserLimits`] is always fatal.
+
+use super::{TextPosition, TurtleParseError};
+use sophia_api::ns::{rdf, xsd};
+use sophia_api::parser::{Limit, LimitExceeded, ParserLimits};
+use sophia_api::prefix::PrefixBox;
+use sophia_api::term::CopyTerm;
+use sophia_iri::resolve::{IriParsed, Resolve};
+use sophia_iri::IriBox;
+use sophia_term::BoxTerm;
+use std::collections::{HashMap, VecDeque};
+use std::io::{self, BufRead};
+
+/// The quads produced by the native parser.
+pub(crate) type NativeQuad = ([BoxTerm; 3], Option<BoxTerm>);
+
+type Result<T> = std::result::Result<T, TurtleParseError>;
+
+/// The state of a native Turtle or TriG parser.
+pub(crate) struct NativeParser<B> {
+    input: Input<B>,
+    trig: bool,
+    recovery: bool,
+    limits: ParserLimits,
+    base: Option<String>,
+    /// the declared prefixes, in order of first declaration
+    prefixes: Vec<(PrefixBox, IriBox)>,
+    /// the index of each prefix in `prefixes`
+    prefix_index: HashMap<String, usize>,
+    /// prefix of generated blank node identifiers, unlikely to collide with explicit ones
+    bnode_prefix: String,
+    bnode_count: usize,
+    /// the number of quads of completed statements
+    /// (triples of discarded statements do not count)
+    quad_count: usize,
+    /// the nesting depth of collections and blank node property lists
+    depth: usize,
+    /// `Some(g)` when inside a wrapped graph (TriG only)
+    graph: Option<Option<BoxTerm>>,
+    /// triples of the statement being parsed
+    pending: Vec<NativeQuad>,
+    /// triples of completed statements
+    ready: VecDeque<NativeQuad>,
+    errors: Vec<TurtleParseError>,
+    /// set when a statement was interrupted at the end of a line, where recovery should resume
+    resume_at_line: bool,
+    /// set when recovery is skipping the rest of a faulty statement
+    skipping: bool,
+    done: bool,
+}
+
+impl<B> NativeParser<B> {
+    /// The underlying reader.
+    #[cfg(feature = "async")]
+    pub(crate) fn reader_mut(&mut self) -> &mut B {
+        &mut self.input.read
+    }
+
+    /// The number of bytes kept by the parser since the start of the current statement.
+    #[cfg(feature = "async")]
+    pub(crate) fn buffered(&self) -> usize {
+        self.input.buffer.len() - self.input.mark.0
+    }
+
+    /// The prefixes declared so far.
+    pub(crate) fn prefixes(&self) -> &[(PrefixBox, IriBox)] {
+        &self.prefixes
+    }
+
+    /// The current base IRI.
+    pub(crate) fn base(&self) -> Option<&str> {
+        self.base.as_deref()
+    }
+
+    /// The errors skipped so far in recovery mode.
+    pub(crate) fn errors(&self) -> &[TurtleParseError] {
+        &self.errors
+    }
+}
+
+impl<B: BufRead> NativeParser<B> {
+    pub(crate) fn new(
+        read: B,
+        base: Option<String>,
+        trig: bool,
+        recovery: bool,
+        limits: ParserLimits,
+    ) -> Self {
+        NativeParser {
+            input: Input::new(read, limits.max_input_bytes),
+            trig,
+            recovery,
+            limits,
+            base,
+            prefixes: vec![],
+            prefix_index: HashMap::new(),
+            bnode_prefix: random_bnode_prefix(),
+            bnode_count: 0,
+            quad_count: 0,
+            depth: 0,
+            graph: None,
+            pending: vec![],
+            ready: VecDeque::new(),
+            errors: vec![],
+            resume_at_line: false,
+            skipping: false,
+            done: false,
+        }
+    }
+
+    /// Return the next quad, or the next error in strict mode.
+    pub(crate) fn next_quad(&mut self) -> Option<Result<NativeQuad>> {
+        loop {
+            if let Some(quad) = self.ready.pop_front() {
+                return Some(Ok(quad));
+            }
+            if self.done {
+                return None;
+            }
+            if self.skipping {
+                if let Err(err) = self.skip_statement() {
+                    return self.io_error(err);
+                }
+                self.skipping = false;
+            }
+            self.input.mark();
+            self.depth = 0;
+            match self.parse_statement() {
+                Ok(true) => {
+                    self.quad_count += self.pending.len();
+                    self.ready.extend(self.pending.drain(..));
+                }
+                Ok(false) => self.done = true,
+                Err(TurtleParseError::Io(err)) => {
+                    self.pending.clear();
+                    return self.io_error(err);
+                }
+                Err(err) => {
+                    self.pending.clear();
+                    if !self.recovery || matches!(err, TurtleParseError::Limit { .. }) {
+                        self.done = true;
+                        return Some(Err(err));
+                    }
+                    self.errors.push(err);
+                    if !std::mem::take(&mut self.resume_at_line) {
+                        self.input.mark();
+                        self.skipping = true;
+                    }
+                }
+            }
+        }
+    }
+
+    /// Handle an IO error.
+    ///
+    /// [`WouldBlock`](io::ErrorKind::WouldBlock) errors are not fatal:
+    /// the input is rewound to the start of the interrupted statement,
+    /// so that parsing can resume once more data is available.
+    fn io_error(&mut self, err: io::Error) -> Option<Result<NativeQuad>> {
+        if err.kind() == io::ErrorKind::WouldBlock {
+            self.input.reset();
+            return Some(Err(err.into()));
+        }
+        self.done = true;
+        let limit = err
+            .get_ref()
+            .and_then(|inner| inner.downcast_ref::<LimitExceeded>());
+        Some(Err(match limit {
+            Some(error) => TurtleParseError::Limit {
+                error: *error,
+                position: self.input.position(),
+            },
+            None => err.into(),
+        }))
+    }
+
+    // statements
+
+    /// Parse a statement, return false at the end of the input.
+    fn parse_statement(&mut self) -> Result<bool> {
+        self.skip_ws()?;
+        let c = match self.input.peek()? {
+            None if self.graph.is_some() => return Err(self.error("expected '}'")),
+            None => return Ok(false),
+            Some(c) => c,
+        };
+        if self.graph.is_some() {
+            if c == b'}' {
+                self.input.advance();
+                self.graph = None;
+            } else {
+                self.parse_triples()?;
+            }
+            return Ok(true);
+        }
+        if c == b'@' {
+            self.parse_directive()?;
+        } else if self.at_keyword("PREFIX")? {
+            self.parse_prefix(false)?;
+        } else if self.at_keyword("BASE")? {
+            self.parse_base(false)?;
+        } else if !self.trig {
+            self.parse_triples()?;
+        } else if self.at_keyword("GRAPH")? {
+            self.skip_ws()?;
+            let name = match self.input.peek()? {
+                Some(b'[') => self.parse_anon()?,
+                _ => self.parse_iri_or_bnode()?,
+            };
+            self.skip_ws()?;
+            self.expect(b'{')?;
+            self.graph = Some(Some(name));
+        } else if c == b'{' {
+            self.input.advance();
+            self.graph = Some(None);
+        } else if c == b'[' || c == b'(' {
+            if c == b'[' && self.at_anon()? {
+                let name = self.parse_anon()?;
+                self.skip_ws()?;
+                if self.input.peek()? == Some(b'{') {
+                    self.input.advance();
+                    self.graph = Some(Some(name));
+                } else {
+                    self.parse_predicate_object_list(&name)?;
+                    self.end_triples()?;
+                }
+            } else {
+                self.parse_triples()?;
+            }
+        } else {
+            let subject = self.parse_iri_or_bnode()?;
+            self.skip_ws()?;
+            if self.input.peek()? == Some(b'{') {
+                self.input.advance();
+                self.graph = Some(Some(subject));
+            } else {
+                self.parse_predicate_object_list(&subject)?;
+                self.end_triples()?;
+            }
+        }
+        Ok(true)
+    }
+
+    fn parse_directive(&mut self) -> Result<()> {
+        let start = self.input.position();
+        self.input.advance();
+        let mut name = String::new();
+        while let Some(c) = self.input.peek()? {
+            if !c.is_ascii_alphabetic() {
+                break;
+            }
+            name.push(c as char);
+            self.input.advance();
+        }
+        match name.as_str() {
+            "prefix" => self.parse_prefix(true),
+            "base" => self.parse_base(true),
+            _ => Err(self.error_from(start, format!("unknown directive '@{}'", name))),
+        }
+    }
+
+    fn parse_prefix(&mut self, turtle_style: bool) -> Result<()> {
+        self.skip_ws()?;
+        let start = self.input.position();
+        let prefix = self.read_pn_prefix()?;
+        if self.input.peek()? != Some(b':') {
+            return Err(self.error_from(start, "expected prefix declaration"));
+        }
+        self.input.advance();
+        self.skip_ws()?;
+        let iri = self.read_iriref()?;
+        if turtle_style {
+            self.skip_ws()?;
+            self.expect(b'.')?;
+        }
+        let iri = IriBox::new_unchecked(iri.into());
+        match self.prefix_index.get(&prefix) {
+            Some(&i) => self.prefixes[i].1 = iri,
+            None => {
+                self.prefix_index
+                    .insert(prefix.clone(), self.prefixes.len());
+                self.prefixes
+                    .push((PrefixBox::new_unchecked(prefix.into()), iri));
+            }
+        }
+        Ok(())
+    }
+
+    fn parse_base(&mut self, turtle_style: bool) -> Result<()> {
+        self.skip_ws()?;
+        let iri = self.read_iriref()?;
+        if turtle_style {
+            self.skip_ws()?;
+            self.expect(b'.')?;
+        }
+        self.base = Some(iri);
+        Ok(())
+    }
+
+    /// Parse a `triples` production, and the '.' terminating it.
+    fn parse_triples(&mut self) -> Result<()> {
+        let subject = match self.input.peek()? {
+            Some(b'[') => {
+                let subject = self.parse_blank_node_property_list()?;
+                self.skip_ws()?;
+                if self.at_end_of_triples()? {
+                    return self.end_triples();
+                }
+                subject
+            }
+            Some(b'(') => self.parse_collection()?,
+            _ => self.parse_iri_or_bnode()?,
+        };
+        self.parse_predicate_object_list(&subject)?;
+        self.end_triples()
+    }
+
+    fn at_end_of_triples(&mut self) -> Result<bool> {
+        Ok(match self.input.peek()? {
+            Some(b'.') => true,
+            Some(b'}') => self.graph.is_some(),
+            _ => false,
+        })
+    }
+
+    fn end_triples(&mut self) -> Result<()> {
+        self.skip_ws()?;
+        match self.input.peek()? {
+            Some(b'.') => {
+                self.input.advance();
+                Ok(())
+            }
+            // the last triples of a wrapped graph need no '.'
+            Some(b'}') if self.graph.is_some() => Ok(()),
+            _ => Err(self.unexpected("expected '.'")),
+        }
+    }
+
+    fn parse_predicate_object_list(&mut self, subject: &BoxTerm) -> Result<()> {
+        loop {
+            self.skip_ws()?;
+            let predicate = self.parse_verb()?;
+            self.parse_object_list(subject, &predicate)?;
+            self.skip_ws()?;
+            if self.input.peek()? != Some(b';') {
+                return Ok(());
+            }
+            while self.input.peek()? == Some(b';') {
+                self.input.advance();
+                self.skip_ws()?;
+            }
+            match self.input.peek()? {
+                None | Some(b'.') | Some(b']') | Some(b'}') => return Ok(()),
+                _ => (),
+            }
+        }
+    }
+
+    fn parse_verb(&mut self) -> Result<BoxTerm> {
+        if self.input.peek()? == Some(b'a') {
+            // 'a' is the keyword only if it can not start a prefixed name,
+            // whose prefix may contain (but not end with) dots, e.g. `a.b:p`
+            let mut i = 1;
+            while self.input.peek_at(i)? == Some(b'.') {
+                i += 1;
+            }
+            let next = self.input.peek_char_at(i)?;
+            let pname = match next {
+                Some(c) if is_pn_chars(c) => true,
+                Some(':') => i == 1,
+                _ => false,
+            };
+            if !pname {
+                self.input.advance();
+                return Ok(BoxTerm::copy(&rdf::type_));
+            }
+        }
+        let start = self.input.position();
+        match self.input.peek()? {
+            Some(b'<') | Some(b':') => self.parse_iri(),
+            Some(_) if self.at_pname_start()? => self.parse_iri(),
+            _ => Err(self.error_from(start, "expected predicate")),
+        }
+    }
+
+    fn parse_object_list(&mut self, subject: &BoxTerm, predicate: &BoxTerm) -> Result<()> {
+        loop {
+            self.skip_ws()?;
+            let object = self.parse_object()?;
+            self.emit(subject.clone(), predicate.clone(), object)?;
+            self.skip_ws()?;
+            if self.input.peek()? != Some(b',') {
+                return Ok(());
+            }
+            self.input.advance();
+        }
+    }
+
+    fn parse_object(&mut self) -> Result<BoxTerm> {
+        match self.input.peek()? {
+            Some(b'[') => self.parse_blank_node_property_list(),
+            Some(b'(') => self.parse_collection(),
+            Some(b'"') | Some(b'\'') => self.parse_rdf_literal(),
+            Some(c) if c.is_ascii_digit() || c == b'+' || c == b'-' => self.parse_numeric(),
+            Some(b'.') if matches!(self.input.peek_at(1)?, Some(c) if c.is_ascii_digit()) => {
+                self.parse_numeric()
+            }
+            Some(_) if self.at_keyword("true")? => {
+                Ok(BoxTerm::new_literal_dt_unchecked("true", xsd::boolean))
+            }
+            Some(_) if self.at_keyword("false")? => {
+                Ok(BoxTerm::new_literal_dt_unchecked("false", xsd::boolean))
+            }
+            Some(b'<') | Some(b'_') | Some(b':') => self.parse_iri_or_bnode(),
+            Some(_) if self.at_pname_start()? => self.parse_iri(),
+            _ => Err(self.unexpected("expected object")),
+        }
+    }
+
+    fn parse_blank_node_property_list(&mut self) -> Result<BoxTerm> {
+        if self.at_anon()? {
+            return self.parse_anon();
+        }
+        self.expect(b'[')?;
+        self.enter()?;
+        let bnode = self.new_bnode();
+        self.parse_predicate_object_list(&bnode)?;
+        self.skip_ws()?;
+        self.expect(b']')?;
+        self.depth -= 1;
+        Ok(bnode)
+    }
+
+    fn parse_collection(&mut self) -> Result<BoxTerm> {
+        self.expect(b'(')?;
+        self.enter()?;
+        let mut head: Option<BoxTerm> = None;
+        let mut last: Option<BoxTerm> = None;
+        loop {
+            self.skip_ws()?;
+            if self.input.peek()? == Some(b')') {
+                self.input.advance();
+                break;
+            }
+            let item = self.parse_object()?;
+            let cell = self.new_bnode();
+            match last.take() {
+                None => head = Some(cell.clone()),
+                Some(previous) => self.emit(previous, BoxTerm::copy(&rdf::rest), cell.clone())?,
+            }
+            self.emit(cell.clone(), BoxTerm::copy(&rdf::first), item)?;
+            last = Some(cell);
+        }
+        let collection = match (head, last) {
+            (Some(head), Some(last)) => {
+                self.emit(last, BoxTerm::copy(&rdf::rest), BoxTerm::copy(&rdf::nil))?;
+                head
+            }
+            _ => BoxTerm::copy(&rdf::nil),
+        };
+        self.depth -= 1;
+        Ok(collection)
+    }
+
+    fn parse_rdf_literal(&mut self) -> Result<BoxTerm> {
+        let txt = self.read_string()?;
+        match self.input.peek()? {
+            Some(b'@') => {
+                let start = self.input.position();
+                let tag = self.read_langtag()?;
+                BoxTerm::new_literal_lang(txt, tag)
+                    .map_err(|err| self.error_from(start, err.to_string()))
+            }
+            Some(b'^') if self.input.peek_at(1)? == Some(b'^') => {
+                self.input.advance();
+                self.input.advance();
+                let dt = self.parse_iri()?;
+                Ok(BoxTerm::new_literal_dt_unchecked(txt, dt))
+            }
+            _ => Ok(BoxTerm::new_literal_dt_unchecked(txt, xsd::string)),
+        }
+    }
+
+    fn parse_numeric(&mut self) -> Result<BoxTerm> {
+        let start = self.input.position();
+        let mut txt = String::new();
+        if let Some(c @ (b'+' | b'-')) = self.input.peek()? {
+            txt.push(c as char);
+            self.input.advance();
+        }
+        let integer_digits = self.read_digits(&mut txt)?;
+        let mut datatype = xsd::integer;
+        if self.input.peek()? == Some(b'.') {
+            match self.input.peek_at(1)? {
+                Some(c) if c.is_ascii_digit() => {
+                    txt.push('.');
+                    self.input.advance();
+                    self.read_digits(&mut txt)?;
+                    datatype = xsd::decimal;
+                }
+                Some(b'e' | b'E') if integer_digits > 0 && self.at_exponent(2)? => {
+                    txt.push('.');
+                    self.input.advance();
+                }
+                _ => (),
+            }
+        }
+        if matches!(self.input.peek()?, Some(b'e' | b'E')) && self.at_exponent(1)? {
+            txt.push(self.input.peek()?.unwrap() as char);
+            self.input.advance();
+            if let Some(c @ (b'+' | b'-')) = self.input.peek()? {
+                txt.push(c as char);
+                self.input.advance();
+            }
+            self.read_digits(&mut txt)?;
+            datatype = xsd::double;
+        }
+        if !txt.bytes().any(|c| c.is_ascii_digit()) {
+            return Err(self.error_from(start, "invalid numeric literal"));
+        }
+        Ok(BoxTerm::new_literal_dt_unchecked(txt, datatype))
+    }
+
+    /// Whether an exponent starts at `i` bytes after the 'e' or 'E' preceding it.
+    fn at_exponent(&mut self, i: usize) -> Result<bool> {
+        Ok(match self.input.peek_at(i)? {
+            Some(b'+' | b'-') => {
+                matches!(self.input.peek_at(i + 1)?, Some(c) if c.is_ascii_digit())
+            }
+            Some(c) => c.is_ascii_digit(),
+            None => false,
+        })
+    }
+
+    fn read_digits(&mut self, txt: &mut String) -> Result<usize> {
+        let mut count = 0;
+        while let Some(c) = self.input.peek()? {
+            if !c.is_ascii_digit() {
+                break;
+            }
+            txt.push(c as char);
+            self.input.advance();
+            count += 1;
+            self.check_limit(Limit::LiteralLength, txt.len())?;
+        }
+        Ok(count)
+    }
+
+    // terms
+
+    fn parse_iri_or_bnode(&mut self) -> Result<BoxTerm> {
+        match self.input.peek()? {
+            Some(b'_') if self.input.peek_at(1)? == Some(b':') => self.parse_bnode_label(),
+            Some(b'<') | Some(b':') => self.parse_iri(),
+            Some(_) if self.at_pname_start()? => self.parse_iri(),
+            _ => Err(self.unexpected("expected IRI or blank node")),
+        }
+    }
+
+    fn parse_iri(&mut self) -> Result<BoxTerm> {
+        let start = self.input.position();
+        let iri = if self.input.peek()? == Some(b'<') {
+            self.read_iriref()?
+        } else {
+            let prefix = self.read_pn_prefix()?;
+            if self.input.peek()? != Some(b':') {
+                return Err(self.unexpected("expected ':'"));
+            }
+            self.input.advance();
+            let local = self.read_pn_local()?;
+            match self.prefix_index.get(&prefix) {
+                Some(&i) => {
+                    let iri = format!("{}{}", &*self.prefixes[i].1, local);
+                    self.check_limit(Limit::IriLength, iri.len())?;
+                    iri
+                }
+                None => {
+                    return Err(self.error_from(start, format!("undefined prefix '{}:'", prefix)))
+                }
+            }
+        };
+        BoxTerm::new_iri(iri).map_err(|err| self.error_from(start, err.to_string()))
+    }
+
+    fn parse_bnode_label(&mut self) -> Result<BoxTerm> {
+        let start = self.input.position();
+        self.input.advance();
+        self.input.advance();
+        let mut label = String::new();
+        match self.input.peek_char()? {
+            Some(c) if is_pn_chars_u(c) || c.is_ascii_digit() => {
+                label.push(c);
+                self.input.advance_char(c);
+            }
+            _ => return Err(self.error_from(start, "invalid blank node label")),
+        }
+        self.read_name_tail(&mut label, is_pn_chars)?;
+        Ok(BoxTerm::new_bnode_unchecked(label))
+    }
+
+    fn at_anon(&mut self) -> Result<bool> {
+        let mut i = 1;
+        loop {
+            match self.input.peek_at(i)? {
+                Some(b' ' | b'\t' | b'\r' | b'\n') => i += 1,
+                Some(b']') => return Ok(true),
+                _ => return Ok(false),
+            }
+        }
+    }
+
+    fn parse_anon(&mut self) -> Result<BoxTerm> {
+        if !self.at_anon()? {
+            return Err(self.unexpected("expected '[]'"));
+        }
+        while self.input.peek()? != Some(b']') {
+            self.input.advance();
+        }
+        self.input.advance();
+        Ok(self.new_bnode())
+    }
+
+    fn new_bnode(&mut self) -> BoxTerm {
+        self.bnode_count += 1;
+        BoxTerm::new_bnode_unchecked(format!("{}{}", self.bnode_prefix, self.bnode_count))
+    }
+
+    fn emit(&mut self, s: BoxTerm, p: BoxTerm, o: BoxTerm) -> Result<()> {
+        self.check_limit(Limit::Triples, self.quad_count + self.pending.len() + 1)?;
+        let g = self.graph.clone().flatten();
+        self.pending.push(([s, p, o], g));
+        Ok(())
+    }
+
+    /// Enter a nested collection or blank node property list.
+    fn enter(&mut self) -> Result<()> {
+        self.depth += 1;
+        self.check_limit(Limit::NestingDepth, self.depth)
+    }
+
+    // tokens
+
+    fn skip_ws(&mut self) -> io::Result<()> {
+        while let Some(c) = self.input.peek()? {
+            match c {
+                b' ' | b'\t' | b'\r' | b'\n' => self.input.advance(),
+                b'#' => self.skip_comment()?,
+                _ => break,
+            }
+        }
+        Ok(())
+    }
+
+    fn skip_comment(&mut self) -> io::Result<()> {
+        while let Some(c) = self.input.peek()? {
+            if c == b'\n' || c == b'\r' {
+                break;
+            }
+            self.input.advance();
+        }
+        Ok(())
+    }
+
+    fn expect(&mut self, expected: u8) -> Result<()> {
+        if self.input.peek()? == Some(expected) {
+            self.input.advance();
+            Ok(())
+        } else {
+            Err(self.unexpected(&format!("expected '{}'", expected as char)))
+        }
+    }
+
+    /// If the input starts with `keyword` (case-insensitively) as a whole word, consume it.
+    fn at_keyword(&mut self, keyword: &str) -> Result<bool> {
+        for (i, k) in keyword.bytes().enumerate() {
+            match self.input.peek_at(i)? {
+                Some(c) if c.eq_ignore_ascii_case(&k) => (),
+                _ => return Ok(false),
+            }
+        }
+        let next = self.input.peek_char_at(keyword.len())?;
+        if next.map(|c| is_pn_chars(c) || c == ':').unwrap_or(false) {
+            return Ok(false);
+        }
+        for _ in 0..keyword.len() {
+            self.input.advance();
+        }
+        Ok(true)
+    }
+
+    fn at_pname_start(&mut self) -> Result<bool> {
+        Ok(matches!(self.input.peek_char()?, Some(c) if is_pn_chars_base(c)))
+    }
+
+    fn read_iriref(&mut self) -> Result<String> {
+        let start = self.input.position();
+        if self.input.peek()? != Some(b'<') {
+            return Err(self.unexpected("expected IRI"));
+        }
+        self.input.advance();
+        let mut bytes = vec![];
+        let mut error = None;
+        loop {
+            match self.input.peek()? {
+                None | Some(b'\n' | b'\r') => {
+                    return Err(self.error_from(start, "unterminated IRI"))
+                }
+                Some(b'>') => {
+                    self.input.advance();
+                    break;
+                }
+                Some(b'\\') => {
+                    let escape_start = self.input.position();
+                    self.input.advance();
+                    match self.input.peek()? {
+                        Some(b'u') => self.read_uchar(4, &mut bytes, escape_start, &mut error)?,
+                        Some(b'U') => self.read_uchar(8, &mut bytes, escape_start, &mut error)?,
+                        _ => {
+                            error.get_or_insert_with(|| {
+                                self.error_from(escape_start, "invalid escape sequence in IRI")
+                            });
+                        }
+                    }
+                }
+                Some(c) if c <= b' ' || b"<\"{}|^`".contains(&c) => {
+                    let char_start = self.input.position();
+                    self.input.advance();
+                    error.get_or_insert_with(|| {
+                        self.error_from(
+                            char_start,
+                            format!("invalid character {:?} in IRI", c as char),
+                        )
+                    });
+                }
+                Some(c) => {
+                    bytes.push(c);
+                    self.input.advance();
+                    self.check_limit(Limit::IriLength, bytes.len())?;
+                }
+            }
+        }
+        if let Some(error) = error {
+            return Err(error);
+        }
+        let iri = String::from_utf8(bytes).map_err(|_| self.error_from(start, "invalid UTF-8"))?;
+        let iri = self
+            .resolve(&iri)
+            .ok_or_else(|| self.error_from(start, format!("invalid IRI <{}>", iri)))?;
+        self.check_limit(Limit::IriLength, iri.len())?;
+        Ok(iri)
+    }
+
+    fn resolve(&self, iri: &str) -> Option<String> {
+        match &self.base {
+            Some(base) if !has_scheme(iri) => {
+                let base = IriParsed::new(base).ok()?;
+                base.resolve(iri).ok().map(|iri| iri.to_string())
+            }
+            _ => Some(iri.to_string()),
+        }
+    }
+
+    fn read_pn_prefix(&mut self) -> Result<String> {
+        let mut prefix = String::new();
+        if let Some(c) = self.input.peek_char()? {
+            if is_pn_chars_base(c) {
+                prefix.push(c);
+                self.input.advance_char(c);
+                self.read_name_tail(&mut prefix, is_pn_chars)?;
+            }
+        }
+        Ok(prefix)
+    }
+
+    fn read_pn_local(&mut self) -> Result<String> {
+        let mut local = String::new();
+        match self.input.peek_char()? {
+            Some(c) if is_pn_chars_u(c) || c == ':' || c.is_ascii_digit() => {
+                local.push(c);
+                self.input.advance_char(c);
+            }
+            Some('%' | '\\') => self.read_plx(&mut local)?,
+            _ => return Ok(local),
+        }
+        loop {
+            match self.input.peek_char()? {
+                Some('%' | '\\') => self.read_plx(&mut local)?,
+                Some(c) if is_pn_chars(c) || c == ':' => {
+                    local.push(c);
+                    self.input.advance_char(c);
+                }
+                Some('.') if self.dots_followed_by(is_pn_local_tail)? => {
+                    local.push('.');
+                    self.input.advance();
+                }
+                _ => return Ok(local),
+            }
+        }
+    }
+
+    fn read_plx(&mut self, local: &mut String) -> Result<()> {
+        let start = self.input.position();
+        if self.input.peek()? == Some(b'%') {
+            let hex = (self.input.peek_at(1)?, self.input.peek_at(2)?);
+            match hex {
+                (Some(h1), Some(h2)) if h1.is_ascii_hexdigit() && h2.is_ascii_hexdigit() => {
+                    local.push('%');
+                    local.push(h1 as char);
+                    local.push(h2 as char);
+                    for _ in 0..3 {
+                        self.input.advance();
+                    }
+                    Ok(())
+                }
+                _ => {
+                    self.input.advance();
+                    Err(self.error_from(start, "invalid percent-encoding"))
+                }
+            }
+        } else {
+            self.input.advance();
+            match self.input.peek()? {
+                Some(c) if b"_~.-!$&'()*+,;=/?#@%".contains(&c) => {
+                    local.push(c as char);
+                    self.input.advance();
+                    Ok(())
+                }
+                _ => Err(self.error_from(start, "invalid escape sequence in local name")),
+            }
+        }
+    }
+
+    /// Read characters satisfying `pred`, or dots if they are followed by such characters.
+    fn read_name_tail(&mut self, name: &mut String, pred: fn(char) -> bool) -> Result<()> {
+        loop {
+            match self.input.peek_char()? {
+                Some(c) if pred(c) => {
+                    name.push(c);
+                    self.input.advance_char(c);
+                }
+                Some('.') if self.dots_followed_by(pred)? => {
+                    name.push('.');
+                    self.input.advance();
+                }
+                _ => return Ok(()),
+            }
+        }
+    }
+
+    /// Whether the dots starting the input are followed by a character satisfying `pred`.
+    fn dots_followed_by(&mut self, pred: fn(char) -> bool) -> Result<bool> {
+        let mut i = 0;
+        while self.input.peek_at(i)? == Some(b'.') {
+            i += 1;
+        }
+        Ok(matches!(self.input.peek_char_at(i)?, Some(c) if pred(c)))
+    }
+
+    fn read_langtag(&mut self) -> Result<String> {
+        let start = self.input.position();
+        self.input.advance();
+        let mut tag = String::new();
+        let mut subtag_len = 0;
+        while let Some(c) = self.input.peek()? {
+            if c.is_ascii_alphabetic() || (c.is_ascii_digit() && tag.contains('-')) {
+                subtag_len += 1;
+            } else if c == b'-' && subtag_len > 0 {
+                subtag_len = 0;
+            } else {
+                break;
+            }
+            tag.push(c as char);
+            self.input.advance();
+        }
+        if subtag_len == 0 {
+            return Err(self.error_from(start, "invalid language tag"));
+        }
+        Ok(tag)
+    }
+
+    fn read_string(&mut self) -> Result<String> {
+        let start = self.input.position();
+        let quote = self.input.peek()?.unwrap_or(b'"');
+        let long = self.input.peek_at(1)? == Some(quote) && self.input.peek_at(2)? == Some(quote);
+        let quote_len = if long { 3 } else { 1 };
+        for _ in 0..quote_len {
+            self.input.advance();
+        }
+        let mut bytes = vec![];
+        let mut error = None;
+        loop {
+            match self.input.peek()? {
+                None => return Err(self.error_from(start, "unterminated string")),
+                Some(b'\n' | b'\r') if !long => {
+                    // the rest of the statement is probably garbled by the missing quote
+                    self.resume_at_line = true;
+                    return Err(self.error_from(start, "unterminated string"));
+                }
+                Some(c) if c == quote && !long => {
+                    self.input.advance();
+                    break;
+                }
+                Some(c) if c == quote && self.at_long_string_end(quote)? => {
+                    for _ in 0..3 {
+                        self.input.advance();
+                    }
+                    break;
+                }
+                Some(b'\\') => {
+                    let escape_start = self.input.position();
+                    self.input.advance();
+                    let c = self.input.peek()?;
+                    let unescaped = match c {
+                        Some(b't') => b'\t',
+                        Some(b'b') => 0x08,
+                        Some(b'n') => b'\n',
+                        Some(b'r') => b'\r',
+                        Some(b'f') => 0x0C,
+                        Some(b'"') => b'"',
+                        Some(b'\'') => b'\'',
+                        Some(b'\\') => b'\\',
+                        Some(b'u') => {
+                            self.read_uchar(4, &mut bytes, escape_start, &mut error)?;
+                            continue;
+                        }
+                        Some(b'U') => {
+                            self.read_uchar(8, &mut bytes, escape_start, &mut error)?;
+                            continue;
+                        }
+                        _ => {
+                            error.get_or_insert_with(|| {
+                                self.error_from(escape_start, "invalid escape sequence in string")
+                            });
+                            continue;
+                        }
+                    };
+                    bytes.push(unescaped);
+                    self.input.advance();
+                }
+                Some(c) => {
+                    bytes.push(c);
+                    self.input.advance();
+                }
+            }
+            self.check_limit(Limit::LiteralLength, bytes.len())?;
+        }
+        if let Some(error) = error {
+            return Err(error);
+        }
+        String::from_utf8(bytes).map_err(|_| self.error_from(start, "invalid UTF-8"))
+    }
+
+    /// Whether the three quotes starting the input end a long string
+    /// (i.e. they are not followed by another quote).
+    fn at_long_string_end(&mut self, quote: u8) -> Result<bool> {
+        Ok(self.input.peek_at(1)? == Some(quote)
+            && self.input.peek_at(2)? == Some(quote)
+            && self.input.peek_at(3)? != Some(quote))
+    }
+
+    /// Read the `len` hex digits of a `\u` or `\U` escape sequence (after the backslash).
+    ///
+    /// Errors are stored in `error` rather than returned,
+    /// so that the enclosing token can be read to its end.
+    fn read_uchar(
+        &mut self,
+        len: usize,
+        bytes: &mut Vec<u8>,
+        start: TextPosition,
+        error: &mut Option<TurtleParseError>,
+    ) -> Result<()> {
+        self.input.advance();
+        let mut code = 0;
+        for _ in 0..len {
+            match self.input.peek()?.and_then(|c| (c as char).to_digit(16)) {
+                Some(digit) => {
+                    code = code * 16 + digit;
+                    self.input.advance();
+                }
+                None => {
+                    error.get_or_insert_with(|| self.error_from(start, "invalid escape sequence"));
+                    return Ok(());
+                }
+            }
+        }
+        match char::from_u32(code) {
+            Some(c) => bytes.extend_from_slice(c.encode_utf8(&mut [0; 4]).as_bytes()),
+            None => {
+                error.get_or_insert_with(|| self.error_from(start, "invalid code point"));
+            }
+        }
+        Ok(())
+    }
+
+    // errors
+
+    fn check_limit(&self, limit: Limit, value: usize) -> Result<()> {
+        self.limits
+            .check(limit, value)
+            .map_err(|error| TurtleParseError::Limit {
+                error,
+                position: self.input.position(),
+            })
+    }
+
+    /// Build an error spanning from `start` to the current position.
+    fn error_from<M: Into<String>>(&self, start: TextPosition, message: M) -> TurtleParseError {
+        TurtleParseError::Syntax {
+            message: message.into(),
+            start,
+            end: self.input.position(),
+        }
+    }
+
+    /// Build an error at the current position.
+    fn error<M: Into<String>>(&self, message: M) -> TurtleParseError {
+        self.error_from(self.input.position(), message)
+    }
+
+    /// Build an error spanning the next character.
+    fn unexpected(&mut self, message: &str) -> TurtleParseError {
+        let start = self.input.position();
+        let mut end = start;
+        let found = match self.input.peek_char() {
+            Ok(Some(c)) => {
+                end.column += 1;
+                end.offset += c.len_utf8();
+                format!("{:?}", c)
+            }
+            _ => "end of file".to_string(),
+        };
+        TurtleParseError::Syntax {
+            message: format!("{}, found {}", message, found),
+            start,
+            end,
+        }
+    }
+
+    // recovery
+
+    /// Skip the rest of the current statement, after an error.
+    ///
+    /// This stops after the next '.' that is followed by a space or the end of the input,
+    /// or after the '}' closing the current graph, if any.
+    /// Strings, IRIs and comments are skipped as a whole,
+    /// as they may contain those characters.
+    fn skip_statement(&mut self) -> io::Result<()> {
+        let mut nested_graph = false;
+        while let Some(c) = self.input.peek()? {
+            match c {
+                b'"' | b'\'' => self.skip_string(c)?,
+                b'<' => {
+                    self.input.advance();
+                    while let Some(c) = self.input.peek()? {
+                        if c <= b' ' {
+                            break;
+                        }
+                        self.input.advance();
+                        if c == b'>' {
+                            break;
+                        }
+                    }
+                }
+                b'#' => self.skip_comment()?,
+                b'.' if !nested_graph => {
+                    self.input.advance();
+                    match self.input.peek()? {
+                        None | Some(b' ' | b'\t' | b'\r' | b'\n' | b'#') => return Ok(()),
+                        _ => (),
+                    }
+                }
+                b'{' if self.trig && self.graph.is_none() => {
+                    // the error occurred in a graph name: skip the whole graph
+                    self.input.advance();
+                    nested_graph = true;
+                }
+                b'}' if nested_graph || self.graph.is_some() => {
+                    self.input.advance();
+                    self.graph = None;
+                    return Ok(());
+                }
+                _ => self.input.advance(),
+            }
+        }
+        Ok(())
+    }
+
+    fn skip_string(&mut self, quote: u8) -> io::Result<()> {
+        let long = self.input.peek_at(1)? == Some(quote) && self.input.peek_at(2)? == Some(quote);
+        if long {
+            for _ in 0..3 {
+                self.input.advance();
+            }
+        } else {
+            self.input.advance();
+        }
+        while let Some(c) = self.input.peek()? {
+            match c {
+                b'\\' => {
+                    self.input.advance();
+                    if self.input.peek()?.is_some() {
+                        self.input.advance();
+                    }
+                }
+                b'\n' | b'\r' if !long => return Ok(()),
+                c if c == quote && !long => {
+                    self.input.advance();
+                    return Ok(());
+                }
+                c if c == quote
+                    && self.input.peek_at(1)? == Some(quote)
+                    && self.input.peek_at(2)? == Some(quote) =>
+                {
+                    for _ in 0..3 {
+                        self.input.advance();
+                    }
+                    return Ok(());
+                }
+                _ => self.input.advance(),
+            }
+        }
+        Ok(())
+    }
+}
+
+/// A buffered reader with lookahead, keeping track of the current position.
+struct Input<B> {
+    read: B,
+    buffer: Vec<u8>,
+    index: usize,
+    eof: bool,
+    position: TextPosition,
+    /// the index and position to rewind to; data after it is kept in the buffer
+    mark: (usize, TextPosition),
+    /// the number of bytes read so far, and the maximum allowed
+    read_bytes: usize,
+    max_bytes: Option<usize>,
+}
+
+impl<B: BufRead> Input<B> {
+    fn new(read: B, max_bytes: Option<usize>) -> Self {
+        Input {
+            read,
+            buffer: vec![],
+            index: 0,
+            eof: false,
+            position: TextPosition::default(),
+            mark: (0, TextPosition::default()),
+            read_bytes: 0,
+            max_bytes,
+        }
+    }
+
+    fn position(&self) -> TextPosition {
+        self.position
+    }
+
+    fn mark(&mut self) {
+        self.mark = (self.index, self.position);
+    }
+
+    fn reset(&mut self) {
+        (self.index, self.position) = self.mark;
+    }
+
+    /// Ensure that at least `n` bytes are available, unless the input is exhausted.
+    fn fill(&mut self, n: usize) -> io::Result<bool> {
+        while self.buffer.len() - self.index < n && !self.eof {
+            let consumed = self.mark.0;
+            if consumed > 0 {
+                self.buffer.drain(..consumed);
+                self.index -= consumed;
+                self.mark.0 = 0;
+            }
+            let chunk = match self.read.fill_buf() {
+                Ok(chunk) => chunk,
+                Err(err) if err.kind() == io::ErrorKind::Interrupted => continue,
+                Err(err) => return Err(err),
+            };
+            if chunk.is_empty() {
+                self.eof = true;
+            } else {
+                let len = chunk.len();
+                self.buffer.extend_from_slice(chunk);
+                self.read.consume(len);
+                self.read_bytes += len;
+                if let Some(max) = self.max_bytes {
+                    if self.read_bytes > max {
+                        let limit = Limit::InputBytes;
+                        return Err(io::Error::new(
+                            io::ErrorKind::InvalidData,
+                            LimitExceeded { limit, max },
+                        ));
+                    }
+                }
+            }
+        }
+        Ok(self.buffer.len() - self.index >= n)
+    }
+
+    fn peek_at(&mut self, i: usize) -> io::Result<Option<u8>> {
+        Ok(if self.fill(i + 1)? {
+            Some(self.buffer[self.index + i])
+        } else {
+            None
+        })
+    }
+
+    fn peek(&mut self) -> io::Result<Option<u8>> {
+        self.peek_at(0)
+    }
+
+    /// Decode the character starting `i` bytes ahead, if it is valid UTF-8.
+    fn peek_char_at(&mut self, i: usize) -> io::Result<Option<char>> {
+        let first = match self.peek_at(i)? {
+            None => return Ok(None),
+            Some(c) if c < 0x80 => return Ok(Some(c as char)),
+            Some(c) => c,
+        };
+        let len = match first {
+            0xC0..=0xDF => 2,
+            0xE0..=0xEF => 3,
+            0xF0..=0xF7 => 4,
+            _ => return Ok(None),
+        };
+        if !self.fill(i + len)? {
+            return Ok(None);
+        }
+        let start = self.index + i;
+        Ok(std::str::from_utf8(&self.buffer[start..start + len])
+            .ok()
+            .and_then(|txt| txt.chars().next()))
+    }
+
+    fn peek_char(&mut self) -> io::Result<Option<char>> {
+        self.peek_char_at(0)
+    }
+
+    /// Consume one byte; must only be called after a successful peek.
+    fn advance(&mut self) {
+        let c = self.buffer[self.index];
+        self.index += 1;
+        self.position.offset += 1;
+        if c == b'\n' {
+            self.position.line += 1;
+            self.position.column = 1;
+        } else if c & 0xC0 != 0x80 {
+            self.position.column += 1;
+        }
+    }
+
+    /// Consume the (previously peeked) character `c`.
+    fn advance_char(&mut self, c: char) {
+        for _ in 0..c.len_utf8() {
+            self.advance();
+        }
+    }
+}
+
+fn random_bnode_prefix() -> String {
+    use std::collections::hash_map::RandomState;
+    use std::hash::{BuildHasher, Hasher};
+    format!("n{:016x}x", RandomState::new().build_hasher().finish())
+}
+
+/// Whether `iri` starts with a scheme, i.e. is not relative.
+fn has_scheme(iri: &str) -> bool {
+    match iri.find(':') {
+        Some(colon) => {
+            let scheme = &iri[..colon];
+            scheme.starts_with(|c: char| c.is_ascii_alphabetic())
+                && scheme
+                    .chars()
+                    .all(|c| c.is_ascii_alphanumeric() || c == '+' || c == '-' || c == '.')
+        }
+        None => false,
+    }
+}
+
+fn is_pn_chars_base(c: char) -> bool {
+    matches!(c,
+        'A'..='Z'
+        | 'a'..='z'
+        | '\u{C0}'..='\u{D6}'
+        | '\u{D8}'..='\u{F6}'
+        | '\u{F8}'..='\u{2FF}'
+        | '\u{370}'..='\u{37D}'
+        | '\u{37F}'..='\u{1FFF}'
+        | '\u{200C}'..='\u{200D}'
+        | '\u{2070}'..='\u{218F}'
+        | '\u{2C00}'..='\u{2FEF}'
+        | '\u{3001}'..='\u{D7FF}'
+        | '\u{F900}'..='\u{FDCF}'
+        | '\u{FDF0}'..='\u{FFFD}'
+        | '\u{10000}'..='\u{EFFFF}'
+    )
+}
+
+fn is_pn_chars_u(c: char) -> bool {
+    c == '_' || is_pn_chars_base(c)
+}
+
+fn is_pn_chars(c: char) -> bool {
+    is_pn_chars_u(c)
+        || matches!(c,
+            '-' | '0'..='9' | '\u{B7}' | '\u{300}'..='\u{36F}' | '\u{203F}'..='\u{2040}'
+        )
+}
+
+fn is_pn_local_tail(c: char) -> bool {
+    is_pn_chars(c) || c == ':' || c == '%' || c == '\\'
+}
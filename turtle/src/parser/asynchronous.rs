@@ -0,0 +1,594 @@
+//! Asynchronous parsing (requires the `async` feature).
+//!
+//! The parsers of this crate have a `parse_async` method,
+//! reading from an [`AsyncBufRead`] instead of a [`BufRead`].
+//! The returned sources can not implement [`TripleSource`](sophia_api::triple::stream::TripleSource)
+//! or [`QuadSource`](sophia_api::quad::stream::QuadSource), which are synchronous;
+//! instead, they provide async methods to get the next triple or quad,
+//! or to insert all of them into a graph or dataset.
+//!
+//! ```
+//! # use futures_executor::block_on;
+//! # use sophia_api::graph::Graph;
+//! # use sophia_inmem::graph::FastGraph;
+//! # use sophia_turtle::parser::turtle::TurtleParser;
+//! # block_on(async {
+//! let data = "<tag:s> <tag:p> <tag:o1>, <tag:o2>.".as_bytes(); // any AsyncBufRead
+//! let mut source = TurtleParser::default().parse_async(data);
+//! let mut g = FastGraph::new();
+//! assert_eq!(source.add_to_graph(&mut g).await.unwrap(), 2);
+//! # })
+//! ```
+//!
+//! The Turtle and TriG parsers never block on I/O:
+//! when a statement is not entirely available, parsing is resumed from its start
+//! once more data has been read.
+
+use super::_native::{NativeParser, NativeQuad};
+use super::nq::NQuadsParser;
+use super::nt::NTriplesParser;
+use super::parallel::locate_rio_error;
+use super::trig::TriGParser;
+use super::turtle::TurtleParser;
+use super::{TextPosition, TurtleParseError};
+use futures_util::io::{AsyncBufRead, AsyncBufReadExt};
+use rio_turtle::TurtleError;
+use sophia_api::dataset::MutableDataset;
+use sophia_api::graph::MutableGraph;
+use sophia_api::parser::{ParserLimits, QuadParser, TripleParser};
+use sophia_api::prefix::PrefixBox;
+use sophia_api::quad::{stream::QuadSource, Quad};
+use sophia_api::term::CopyTerm;
+use sophia_api::triple::stream::{SinkError, SourceError, StreamResult, TripleSource};
+use sophia_api::triple::Triple;
+use sophia_iri::IriBox;
+use sophia_term::BoxTerm;
+use std::collections::VecDeque;
+use std::io::{self, BufRead, Read};
+
+/// The minimum number of bytes read at once from the underlying reader.
+const MIN_READ: usize = 1 << 13;
+
+impl TurtleParser {
+    /// Parse `read` asynchronously.
+    pub fn parse_async<R: AsyncBufRead + Unpin>(&self, read: R) -> AsyncTurtleSource<R> {
+        AsyncTurtleSource(AsyncNative::new(
+            read,
+            self.base.clone(),
+            false,
+            self.recovery,
+            self.limits,
+        ))
+    }
+}
+
+impl TriGParser {
+    /// Parse `read` asynchronously.
+    pub fn parse_async<R: AsyncBufRead + Unpin>(&self, read: R) -> AsyncTriGSource<R> {
+        AsyncTriGSource(AsyncNative::new(
+            read,
+            self.base.clone(),
+            true,
+            self.recovery,
+            self.limits,
+        ))
+    }
+}
+
+impl NTriplesParser {
+    /// Parse `read` asynchronously.
+    pub fn parse_async<R: AsyncBufRead + Unpin>(&self, read: R) -> AsyncNTriplesSource<R> {
+        AsyncNTriplesSource(AsyncLines::new(read, self.lenient, parse_nt_lines))
+    }
+}
+
+impl NQuadsParser {
+    /// Parse `read` asynchronously.
+    pub fn parse_async<R: AsyncBufRead + Unpin>(&self, read: R) -> AsyncNQuadsSource<R> {
+        AsyncNQuadsSource(AsyncLines::new(read, self.lenient, parse_nq_lines))
+    }
+}
+
+/// Asynchronous counterpart of [`TurtleSource`](super::turtle::TurtleSource).
+pub struct AsyncTurtleSource<R>(AsyncNative<R>);
+
+impl<R: AsyncBufRead + Unpin> AsyncTurtleSource<R> {
+    /// Parse the next triple.
+    pub async fn next_triple(&mut self) -> Option<Result<[BoxTerm; 3], TurtleParseError>> {
+        self.0.next_quad().await.map(|res| res.map(|(spo, _)| spo))
+    }
+
+    /// Insert all remaining triples into `graph`.
+    ///
+    /// Return the number of triples parsed (not necessarily the number of triples inserted).
+    pub async fn add_to_graph<G: MutableGraph>(
+        &mut self,
+        graph: &mut G,
+    ) -> StreamResult<usize, TurtleParseError, G::MutationError> {
+        let mut count = 0;
+        while let Some(triple) = self.next_triple().await {
+            let [s, p, o] = triple.map_err(SourceError)?;
+            graph.insert(&s, &p, &o).map_err(SinkError)?;
+            count += 1;
+        }
+        Ok(count)
+    }
+
+    /// See [`TurtleSource::errors`](super::turtle::TurtleSource::errors).
+    pub fn errors(&self) -> &[TurtleParseError] {
+        self.0.parser.errors()
+    }
+
+    /// See [`TurtleSource::prefixes`](super::turtle::TurtleSource::prefixes).
+    pub fn prefixes(&self) -> &[(PrefixBox, IriBox)] {
+        self.0.parser.prefixes()
+    }
+
+    /// See [`TurtleSource::base`](super::turtle::TurtleSource::base).
+    pub fn base(&self) -> Option<&str> {
+        self.0.parser.base()
+    }
+}
+
+/// Asynchronous counterpart of [`TriGSource`](super::trig::TriGSource).
+pub struct AsyncTriGSource<R>(AsyncNative<R>);
+
+impl<R: AsyncBufRead + Unpin> AsyncTriGSource<R> {
+    /// Parse the next quad.
+    pub async fn next_quad(&mut self) -> Option<Result<NativeQuad, TurtleParseError>> {
+        self.0.next_quad().await
+    }
+
+    /// Insert all remaining quads into `dataset`.
+    ///
+    /// Return the number of quads parsed (not necessarily the number of quads inserted).
+    pub async fn add_to_dataset<D: MutableDataset>(
+        &mut self,
+        dataset: &mut D,
+    ) -> StreamResult<usize, TurtleParseError, D::MutationError> {
+        let mut count = 0;
+        while let Some(quad) = self.next_quad().await {
+            let ([s, p, o], g) = quad.map_err(SourceError)?;
+            dataset.insert(&s, &p, &o, g.as_ref()).map_err(SinkError)?;
+            count += 1;
+        }
+        Ok(count)
+    }
+
+    /// See [`TriGSource::errors`](super::trig::TriGSource::errors).
+    pub fn errors(&self) -> &[TurtleParseError] {
+        self.0.parser.errors()
+    }
+
+    /// See [`TriGSource::prefixes`](super::trig::TriGSource::prefixes).
+    pub fn prefixes(&self) -> &[(PrefixBox, IriBox)] {
+        self.0.parser.prefixes()
+    }
+
+    /// See [`TriGSource::base`](super::trig::TriGSource::base).
+    pub fn base(&self) -> Option<&str> {
+        self.0.parser.base()
+    }
+}
+
+/// Asynchronous N-Triples source.
+///
+/// In [lenient](NTriplesParser::lenient) mode,
+/// skipped errors are available through [`errors`](AsyncNTriplesSource::errors).
+pub struct AsyncNTriplesSource<R>(AsyncLines<R, [BoxTerm; 3]>);
+
+impl<R: AsyncBufRead + Unpin> AsyncNTriplesSource<R> {
+    /// Parse the next triple.
+    pub async fn next_triple(&mut self) -> Option<Result<[BoxTerm; 3], TurtleParseError>> {
+        self.0.next().await
+    }
+
+    /// Insert all remaining triples into `graph`.
+    ///
+    /// Return the number of triples parsed (not necessarily the number of triples inserted).
+    pub async fn add_to_graph<G: MutableGraph>(
+        &mut self,
+        graph: &mut G,
+    ) -> StreamResult<usize, TurtleParseError, G::MutationError> {
+        let mut count = 0;
+        while let Some(triple) = self.next_triple().await {
+            let [s, p, o] = triple.map_err(SourceError)?;
+            graph.insert(&s, &p, &o).map_err(SinkError)?;
+            count += 1;
+        }
+        Ok(count)
+    }
+
+    /// The errors skipped so far (in lenient mode).
+    pub fn errors(&self) -> &[TurtleParseError] {
+        &self.0.errors
+    }
+}
+
+/// Asynchronous N-Quads source.
+///
+/// In [lenient](NQuadsParser::lenient) mode,
+/// skipped errors are available through [`errors`](AsyncNQuadsSource::errors).
+pub struct AsyncNQuadsSource<R>(AsyncLines<R, NativeQuad>);
+
+impl<R: AsyncBufRead + Unpin> AsyncNQuadsSource<R> {
+    /// Parse the next quad.
+    pub async fn next_quad(&mut self) -> Option<Result<NativeQuad, TurtleParseError>> {
+        self.0.next().await
+    }
+
+    /// Insert all remaining quads into `dataset`.
+    ///
+    /// Return the number of quads parsed (not necessarily the number of quads inserted).
+    pub async fn add_to_dataset<D: MutableDataset>(
+        &mut self,
+        dataset: &mut D,
+    ) -> StreamResult<usize, TurtleParseError, D::MutationError> {
+        let mut count = 0;
+        while let Some(quad) = self.next_quad().await {
+            let ([s, p, o], g) = quad.map_err(SourceError)?;
+            dataset.insert(&s, &p, &o, g.as_ref()).map_err(SinkError)?;
+            count += 1;
+        }
+        Ok(count)
+    }
+
+    /// The errors skipped so far (in lenient mode).
+    pub fn errors(&self) -> &[TurtleParseError] {
+        &self.0.errors
+    }
+}
+
+// ---------------------------------------------------------------------------------
+//                                      inners
+// ---------------------------------------------------------------------------------
+
+/// A buffer filled asynchronously, and consumed by a synchronous parser.
+///
+/// When it is empty (but not at the end of the input), reading it fails with
+/// [`WouldBlock`](io::ErrorKind::WouldBlock).
+#[derive(Default)]
+struct Feed {
+    data: Vec<u8>,
+    index: usize,
+    eof: bool,
+}
+
+impl Read for Feed {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let available = self.fill_buf()?;
+        let len = available.len().min(buf.len());
+        buf[..len].copy_from_slice(&available[..len]);
+        self.consume(len);
+        Ok(len)
+    }
+}
+
+impl BufRead for Feed {
+    fn fill_buf(&mut self) -> io::Result<&[u8]> {
+        if self.index == self.data.len() && !self.eof {
+            return Err(io::ErrorKind::WouldBlock.into());
+        }
+        Ok(&self.data[self.index..])
+    }
+
+    fn consume(&mut self, amt: usize) {
+        self.index += amt;
+        if self.index == self.data.len() {
+            self.data.clear();
+            self.index = 0;
+        }
+    }
+}
+
+/// The native Turtle/TriG parser, fed asynchronously.
+struct AsyncNative<R> {
+    read: R,
+    parser: NativeParser<Feed>,
+}
+
+impl<R: AsyncBufRead + Unpin> AsyncNative<R> {
+    fn new(
+        read: R,
+        base: Option<String>,
+        trig: bool,
+        recovery: bool,
+        limits: ParserLimits,
+    ) -> Self {
+        AsyncNative {
+            read,
+            parser: NativeParser::new(Feed::default(), base, trig, recovery, limits),
+        }
+    }
+
+    async fn next_quad(&mut self) -> Option<Result<NativeQuad, TurtleParseError>> {
+        loop {
+            match self.parser.next_quad() {
+                Some(Err(TurtleParseError::Io(err))) if err.kind() == io::ErrorKind::WouldBlock => {
+                    // read at least as much as what is already buffered,
+                    // so that long statements are not re-parsed too many times
+                    let want = self.parser.buffered().max(MIN_READ);
+                    let feed = self.parser.reader_mut();
+                    if let Err(err) = read_into(&mut self.read, &mut feed.data, want).await {
+                        feed.eof = true;
+                        return Some(Err(err.into()));
+                    }
+                    if feed.index == feed.data.len() {
+                        feed.eof = true;
+                    }
+                }
+                other => return other,
+            }
+        }
+    }
+}
+
+/// Append at least `want` bytes from `read` to `data`, unless the end of the input is reached.
+async fn read_into<R: AsyncBufRead + Unpin>(
+    read: &mut R,
+    data: &mut Vec<u8>,
+    want: usize,
+) -> io::Result<()> {
+    let mut got = 0;
+    while got < want {
+        let buf = read.fill_buf().await?;
+        if buf.is_empty() {
+            break;
+        }
+        let len = buf.len();
+        data.extend_from_slice(buf);
+        read.consume_unpin(len);
+        got += len;
+    }
+    Ok(())
+}
+
+type LineParser<T> = fn(&[u8], bool) -> (Vec<T>, Vec<TurtleError>, Option<TurtleError>);
+
+/// A line-based parser, fed asynchronously with chunks of complete lines.
+struct AsyncLines<R, T> {
+    read: R,
+    lenient: bool,
+    parse: LineParser<T>,
+    position: TextPosition,
+    ready: VecDeque<T>,
+    errors: Vec<TurtleParseError>,
+    error: Option<TurtleParseError>,
+    done: bool,
+}
+
+impl<R: AsyncBufRead + Unpin, T> AsyncLines<R, T> {
+    fn new(read: R, lenient: bool, parse: LineParser<T>) -> Self {
+        AsyncLines {
+            read,
+            lenient,
+            parse,
+            position: TextPosition::default(),
+            ready: VecDeque::new(),
+            errors: vec![],
+            error: None,
+            done: false,
+        }
+    }
+
+    async fn next(&mut self) -> Option<Result<T, TurtleParseError>> {
+        loop {
+            if let Some(item) = self.ready.pop_front() {
+                return Some(Ok(item));
+            }
+            if let Some(err) = self.error.take() {
+                return Some(Err(err));
+            }
+            if self.done {
+                return None;
+            }
+            let mut data = vec![];
+            let res = match read_into(&mut self.read, &mut data, MIN_READ).await {
+                Ok(()) if data.last().map(|b| *b != b'\n').unwrap_or(false) => {
+                    self.read.read_until(b'\n', &mut data).await.map(|_| ())
+                }
+                res => res,
+            };
+            if let Err(err) = res {
+                self.done = true;
+                return Some(Err(err.into()));
+            }
+            if data.is_empty() {
+                self.done = true;
+                continue;
+            }
+            let (items, skipped, error) = (self.parse)(&data, self.lenient);
+            self.ready.extend(items);
+            for err in skipped {
+                self.errors
+                    .push(locate_rio_error(err, &data, self.position));
+            }
+            if let Some(err) = error {
+                self.error = Some(locate_rio_error(err, &data, self.position));
+                self.done = true;
+            }
+            self.position.line += data.iter().filter(|b| **b == b'\n').count();
+            self.position.offset += data.len();
+        }
+    }
+}
+
+fn parse_nt_lines(
+    data: &[u8],
+    lenient: bool,
+) -> (Vec<[BoxTerm; 3]>, Vec<TurtleError>, Option<TurtleError>) {
+    let mut triples = vec![];
+    let mut source = NTriplesParser { lenient }.parse(data);
+    let res = source.for_each_triple(|t| {
+        triples.push([
+            BoxTerm::copy(t.s()),
+            BoxTerm::copy(t.p()),
+            BoxTerm::copy(t.o()),
+        ])
+    });
+    (triples, source.take_errors(), res.err())
+}
+
+fn parse_nq_lines(
+    data: &[u8],
+    lenient: bool,
+) -> (Vec<NativeQuad>, Vec<TurtleError>, Option<TurtleError>) {
+    let mut quads = vec![];
+    let mut source = NQuadsParser { lenient }.parse(data);
+    let res = source.for_each_quad(|q| {
+        quads.push((
+            [
+                BoxTerm::copy(q.s()),
+                BoxTerm::copy(q.p()),
+                BoxTerm::copy(q.o()),
+            ],
+            q.g().map(BoxTerm::copy),
+        ))
+    });
+    (quads, source.take_errors(), res.err())
+}
+
+// ---------------------------------------------------------------------------------
+//                                      tests
+// ---------------------------------------------------------------------------------
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use futures_executor::block_on;
+    use futures_util::io::AsyncRead;
+    use sophia_api::dataset::isomorphic_datasets;
+    use sophia_api::graph::isomorphic_graphs;
+    use sophia_api::parser::{Location, WithLocation};
+    use sophia_inmem::dataset::FastDataset;
+    use sophia_inmem::graph::FastGraph;
+    use std::pin::Pin;
+    use std::task::{Context, Poll};
+
+    /// An AsyncRead returning at most `step` bytes at a time,
+    /// and returning `Pending` before each of them.
+    struct Trickle<'a> {
+        data: &'a [u8],
+        step: usize,
+        ready: bool,
+    }
+
+    impl<'a> Trickle<'a> {
+        fn new(data: &'a str, step: usize) -> futures_util::io::BufReader<Self> {
+            futures_util::io::BufReader::with_capacity(
+                step,
+                Trickle {
+                    data: data.as_bytes(),
+                    step,
+                    ready: false,
+                },
+            )
+        }
+    }
+
+    impl<'a> AsyncRead for Trickle<'a> {
+        fn poll_read(
+            mut self: Pin<&mut Self>,
+            cx: &mut Context<'_>,
+            buf: &mut [u8],
+        ) -> Poll<io::Result<usize>> {
+            if !self.ready {
+                self.ready = true;
+                cx.waker().wake_by_ref();
+                return Poll::Pending;
+            }
+            self.ready = false;
+            let len = self.step.min(buf.len()).min(self.data.len());
+            buf[..len].copy_from_slice(&self.data[..len]);
+            self.data = &self.data[len..];
+            Poll::Ready(Ok(len))
+        }
+    }
+
+    const TURTLE: &str = r#"
+        @base <http://example.org/> .
+        @prefix : <ns#> .
+        <#me> :knows [ a :Person ; :name "Alice" ], _:bob ;
+            :likes ( 1 2.0 3e0 ), """long
+            string""" .
+        _:bob :name "Bob"@en .
+    "#;
+
+    #[test]
+    fn turtle() -> Result<(), Box<dyn std::error::Error>> {
+        let expected: FastGraph = super::super::turtle::parse_str(TURTLE).collect_triples()?;
+        for step in [1, 7, 1000] {
+            let mut source = TurtleParser::default().parse_async(Trickle::new(TURTLE, step));
+            let mut g = FastGraph::new();
+            let count = block_on(source.add_to_graph(&mut g))?;
+            assert_eq!(count, 13);
+            assert!(isomorphic_graphs(&g, &expected)?);
+            assert_eq!(source.prefixes().len(), 1);
+            assert_eq!(source.base(), Some("http://example.org/"));
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn turtle_recovery() {
+        let turtle = "<x:a> <x:p> 1 .\n<x:b> <x:p> oops .\n<x:c> <x:p> 3 .\n";
+        let parser = TurtleParser {
+            recovery: true,
+            ..TurtleParser::default()
+        };
+        let mut source = parser.parse_async(Trickle::new(turtle, 3));
+        let count = block_on(source.add_to_graph(&mut FastGraph::new())).unwrap();
+        assert_eq!(count, 2);
+        assert_eq!(source.errors().len(), 1);
+        assert_eq!(
+            format!("{:?}", source.errors()[0].location()),
+            format!("{:?}", Location::from_licos(2, 17, 2, 18))
+        );
+    }
+
+    #[test]
+    fn trig() -> Result<(), Box<dyn std::error::Error>> {
+        let trig = "PREFIX : <x:>\n:g { :a :b :c, :d }\n{ :e :f :g }\nGRAPH :h { :i :j :k . }";
+        let expected: FastDataset = super::super::trig::parse_str(trig).collect_quads()?;
+        let mut source = TriGParser::default().parse_async(Trickle::new(trig, 2));
+        let mut d = FastDataset::new();
+        assert_eq!(block_on(source.add_to_dataset(&mut d))?, 4);
+        assert!(isomorphic_datasets(&d, &expected)?);
+        Ok(())
+    }
+
+    #[test]
+    fn nt() -> Result<(), Box<dyn std::error::Error>> {
+        let nt: String = (0..2000)
+            .map(|i| format!("<x:s> <x:p> \"{}\" .\n", i))
+            .collect();
+        let mut source = NTriplesParser::default().parse_async(Trickle::new(&nt, 100));
+        let mut g = FastGraph::new();
+        assert_eq!(block_on(source.add_to_graph(&mut g))?, 2000);
+
+        let nt = format!("{}<x:s> <x:p> oops .\n{}", nt, nt);
+        let mut source = NTriplesParser::default().parse_async(Trickle::new(&nt, 100));
+        let err = match block_on(source.add_to_graph(&mut FastGraph::new())) {
+            Err(SourceError(err)) => err,
+            _ => panic!("expected a source error"),
+        };
+        assert_eq!(
+            format!("{:?}", err.location()),
+            format!("{:?}", Location::from_licos(2001, 13, 2001, 14))
+        );
+
+        let mut source = NTriplesParser { lenient: true }.parse_async(Trickle::new(&nt, 100));
+        assert_eq!(block_on(source.add_to_graph(&mut FastGraph::new()))?, 4000);
+        assert_eq!(source.errors().len(), 1);
+        Ok(())
+    }
+
+    #[test]
+    fn nq() -> Result<(), Box<dyn std::error::Error>> {
+        let nq = "<x:s> <x:p> <x:o> .\n<x:s> <x:p> <x:o> <x:g> .\n";
+        let mut source = NQuadsParser::default().parse_async(Trickle::new(nq, 5));
+        let mut d = FastDataset::new();
+        assert_eq!(block_on(source.add_to_dataset(&mut d))?, 2);
+        Ok(())
+    }
+}
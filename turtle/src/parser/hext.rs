@@ -0,0 +1,224 @@
+//! Parser for the [HexTuples] concrete syntax of RDF datasets.
+//!
+//! Each line of a HexTuples document is a JSON array of 6 strings:
+//! subject, predicate, value, datatype, language and graph name.
+//! Blank nodes are prefixed with `_:`,
+//! and the default graph is identified by the empty string.
+//! Empty lines are ignored.
+//!
+//! [HexTuples]: https://github.com/ontola/hextuples
+
+use json::JsonValue;
+use sophia_api::ns::xsd;
+use sophia_api::parser::QuadParser;
+use sophia_term::iri::Iri;
+use sophia_term::literal::Literal;
+use sophia_term::{BoxTerm, TermError};
+use std::io::{self, BufRead};
+
+/// HexTuples parser.
+#[derive(Clone, Debug, Default)]
+pub struct HextParser {}
+
+impl<B: BufRead> QuadParser<B> for HextParser {
+    type Source = HextSource<B>;
+    fn parse(&self, data: B) -> Self::Source {
+        HextSource {
+            read: data,
+            line: 0,
+            buffer: String::new(),
+            done: false,
+        }
+    }
+}
+
+sophia_api::def_mod_functions_for_bufread_parser!(HextParser, QuadParser);
+
+/// The type of quads produced by [`HextParser`].
+pub type HextQuad = ([BoxTerm; 3], Option<BoxTerm>);
+
+/// The error raised when parsing a HexTuples document.
+#[derive(Debug, thiserror::Error)]
+pub enum HextError {
+    /// The underlying reader failed.
+    #[error("I/O error: {0}")]
+    Io(#[from] io::Error),
+    /// A line is not valid JSON.
+    #[error("Invalid JSON: {source} on line {line}")]
+    Json {
+        /// The error raised by the JSON parser.
+        source: json::Error,
+        /// The line of the error (starting at 1).
+        line: usize,
+    },
+    /// A line does not comply with the structure of HexTuples,
+    /// or contains an invalid term.
+    #[error("Invalid HexTuples: {message} on line {line}")]
+    Hext {
+        /// A description of the error.
+        message: String,
+        /// The line of the error (starting at 1).
+        line: usize,
+    },
+}
+
+/// The quad source returned by [`HextParser`].
+///
+/// The document is read line by line, so it is never fully loaded in memory.
+pub struct HextSource<B> {
+    read: B,
+    line: usize,
+    buffer: String,
+    done: bool,
+}
+
+impl<B: BufRead> Iterator for HextSource<B> {
+    type Item = Result<HextQuad, HextError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while !self.done {
+            self.buffer.clear();
+            let result = match self.read.read_line(&mut self.buffer) {
+                Ok(0) => {
+                    self.done = true;
+                    return None;
+                }
+                Ok(_) => {
+                    self.line += 1;
+                    if self.buffer.trim().is_empty() {
+                        continue;
+                    }
+                    self.parse_line()
+                }
+                Err(err) => Err(err.into()),
+            };
+            self.done = result.is_err();
+            return Some(result);
+        }
+        None
+    }
+}
+
+impl<B> HextSource<B> {
+    fn parse_line(&self) -> Result<HextQuad, HextError> {
+        let value = json::parse(&self.buffer).map_err(|source| HextError::Json {
+            source,
+            line: self.line,
+        })?;
+        let fields = match &value {
+            JsonValue::Array(fields) if fields.len() == 6 => fields,
+            _ => return Err(self.error("a line must be an array of 6 strings")),
+        };
+        let mut strings = [""; 6];
+        for (string, field) in strings.iter_mut().zip(fields) {
+            *string = field
+                .as_str()
+                .ok_or_else(|| self.error("a line must be an array of 6 strings"))?;
+        }
+        let [s, p, value, datatype, lang, g] = strings;
+        let s = self.node(s)?;
+        let p = BoxTerm::new_iri(p).map_err(|e| self.term_error(e))?;
+        let o = match datatype {
+            "globalId" => BoxTerm::new_iri(value),
+            "localId" => BoxTerm::new_bnode(value.strip_prefix("_:").unwrap_or(value)),
+            _ if !lang.is_empty() => BoxTerm::new_literal_lang(value, lang),
+            "" => BoxTerm::new_literal_dt(value, xsd::string),
+            _ => Iri::<Box<str>>::new(datatype).map(|dt| Literal::new_dt(value, dt).into()),
+        }
+        .map_err(|e| self.term_error(e))?;
+        let g = if g.is_empty() {
+            None
+        } else {
+            Some(self.node(g)?)
+        };
+        Ok(([s, p, o], g))
+    }
+
+    /// Build an IRI, or a blank node if `txt` starts with `_:`.
+    fn node(&self, txt: &str) -> Result<BoxTerm, HextError> {
+        match txt.strip_prefix("_:") {
+            Some(id) => BoxTerm::new_bnode(id),
+            None => BoxTerm::new_iri(txt),
+        }
+        .map_err(|e| self.term_error(e))
+    }
+
+    fn error<T: ToString>(&self, message: T) -> HextError {
+        HextError::Hext {
+            message: message.to_string(),
+            line: self.line,
+        }
+    }
+
+    fn term_error(&self, source: TermError) -> HextError {
+        self.error(source)
+    }
+}
+
+// ---------------------------------------------------------------------------------
+//                                      tests
+// ---------------------------------------------------------------------------------
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use sophia_api::dataset::isomorphic_datasets;
+    use sophia_api::quad::stream::QuadSource;
+    use sophia_inmem::dataset::FastDataset;
+
+    #[test]
+    fn parse() -> Result<(), Box<dyn std::error::Error>> {
+        let hext = r#"["http://example.org/alice", "http://www.w3.org/1999/02/22-rdf-syntax-ns#type", "http://example.org/Person", "globalId", "", ""]
+["http://example.org/alice", "http://example.org/name", "Alice", "http://www.w3.org/2001/XMLSchema#string", "", ""]
+
+["http://example.org/alice", "http://example.org/name", "Alicia", "http://www.w3.org/1999/02/22-rdf-syntax-ns#langString", "es", "http://example.org/g"]
+["_:b1", "http://example.org/knows", "_:b2", "localId", "", "_:g"]
+["_:b2", "http://example.org/age", "42", "http://www.w3.org/2001/XMLSchema#integer", "", "_:g"]
+["_:b2", "http://example.org/note", "line 1\nline \"2\"", "", "", "_:g"]
+"#;
+        let nq = r#"
+            <http://example.org/alice> <http://www.w3.org/1999/02/22-rdf-syntax-ns#type> <http://example.org/Person>.
+            <http://example.org/alice> <http://example.org/name> "Alice".
+            <http://example.org/alice> <http://example.org/name> "Alicia"@es <http://example.org/g>.
+            _:x <http://example.org/knows> _:y _:z.
+            _:y <http://example.org/age> "42"^^<http://www.w3.org/2001/XMLSchema#integer> _:z.
+            _:y <http://example.org/note> "line 1\nline \"2\"" _:z.
+        "#;
+        let d1: FastDataset = parse_str(hext).collect_quads()?;
+        let d2: FastDataset = crate::parser::nq::parse_str(nq).collect_quads()?;
+        assert!(isomorphic_datasets(&d1, &d2)?);
+        Ok(())
+    }
+
+    #[test]
+    fn errors() {
+        for (hext, line) in [
+            ("\n[\"tag:s\", \"tag:p\", \"tag:o\", \"globalId\", \"\"]", 2),
+            (
+                "[\"tag:s\", \"tag:p\", \"tag:o\", \"globalId\", \"\", 42]",
+                1,
+            ),
+            (
+                "[\"tag:s\", \"tag:p\", \"tag:o\", \"globalId\", \"\", \"\"",
+                1,
+            ),
+            (
+                "[\"tag:s\", \"tag:p\", \"a b\", \"globalId\", \"\", \"\"]",
+                1,
+            ),
+            (
+                "[\"tag:s\", \"tag:p\", \"o\", \"tag:dt\", \"not a tag\", \"\"]",
+                1,
+            ),
+        ] {
+            let quads: Vec<_> = parse_str(hext).collect();
+            assert_eq!(quads.len(), 1);
+            match &quads[0] {
+                Err(HextError::Json { line: l, .. }) | Err(HextError::Hext { line: l, .. }) => {
+                    assert_eq!(*l, line, "{}", hext)
+                }
+                other => panic!("unexpected result {:?} for {}", other, hext),
+            }
+        }
+    }
+}
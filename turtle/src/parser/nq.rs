@@ -9,12 +9,16 @@ use std::io::BufRead;
 
 /// N-Quads parser based on RIO.
 #[derive(Clone, Debug, Default)]
-pub struct NQuadsParser {}
+pub struct NQuadsParser {
+    /// If true, invalid lines are skipped rather than aborting the parsing;
+    /// their errors are recorded in the source (see [`RioSource::errors`]).
+    pub lenient: bool,
+}
 
 impl<B: BufRead> QuadParser<B> for NQuadsParser {
-    type Source = StrictRioSource<RioNQParser<B>, TurtleError>;
+    type Source = RioSource<RioNQParser<B>, TurtleError>;
     fn parse(&self, data: B) -> Self::Source {
-        StrictRioSource::Parser(RioNQParser::new(data))
+        RioSource::new(RioNQParser::new(data), self.lenient)
     }
 }
 
@@ -27,9 +31,10 @@ sophia_api::def_mod_functions_for_bufread_parser!(NQuadsParser, QuadParser);
 #[cfg(test)]
 mod test {
     use super::*;
-    use sophia_api::dataset::Dataset;
+    use sophia_api::dataset::{Dataset, MutableDataset};
     use sophia_api::ns::{rdf, xsd};
     use sophia_api::quad::stream::QuadSource;
+    use sophia_api::quad::Quad;
     use sophia_api::term::matcher::ANY;
     use sophia_inmem::dataset::FastDataset;
     use sophia_term::StaticTerm;
@@ -43,7 +48,7 @@ mod test {
         "#;
 
         let mut d = FastDataset::new();
-        let p = NQuadsParser {};
+        let p = NQuadsParser::default();
         let c = p.parse_str(turtle).add_to_dataset(&mut d)?;
         assert_eq!(c, 3);
         assert!(d
@@ -75,4 +80,28 @@ mod test {
             .is_some());
         Ok(())
     }
+
+    #[test]
+    fn test_lenient() -> std::result::Result<(), Box<dyn std::error::Error>> {
+        let nq = r#"<http://example.org/a> <http://example.org/p> "1" <http://example.org/g> .
+<http://example.org/b> <http://example.org/p> "2" "not a graph name" .
+<http://example.org/c> <http://example.org/p> "3" .
+"#;
+        assert!(NQuadsParser::default()
+            .parse_str(nq)
+            .for_each_quad(|_| ())
+            .is_err());
+
+        let p = NQuadsParser { lenient: true };
+        let mut source = p.parse_str(nq);
+        let mut d = FastDataset::new();
+        source.for_each_quad(|q| {
+            d.insert(q.s(), q.p(), q.o(), q.g()).unwrap();
+        })?;
+        assert_eq!(d.quads().count(), 2);
+        assert_eq!(source.errors().len(), 1);
+        assert_eq!(source.take_errors().len(), 1);
+        assert!(source.errors().is_empty());
+        Ok(())
+    }
 }
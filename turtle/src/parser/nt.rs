@@ -9,12 +9,16 @@ use std::io::BufRead;
 
 /// N-Triples parser based on RIO.
 #[derive(Clone, Debug, Default)]
-pub struct NTriplesParser {}
+pub struct NTriplesParser {
+    /// If true, invalid lines are skipped rather than aborting the parsing;
+    /// their errors are recorded in the source (see [`RioSource::errors`]).
+    pub lenient: bool,
+}
 
 impl<B: BufRead> TripleParser<B> for NTriplesParser {
-    type Source = StrictRioSource<RioNTParser<B>, TurtleError>;
+    type Source = RioSource<RioNTParser<B>, TurtleError>;
     fn parse(&self, data: B) -> Self::Source {
-        StrictRioSource::Parser(RioNTParser::new(data))
+        RioSource::new(RioNTParser::new(data), self.lenient)
     }
 }
 
@@ -27,10 +31,12 @@ sophia_api::def_mod_functions_for_bufread_parser!(NTriplesParser, TripleParser);
 #[cfg(test)]
 mod test {
     use super::*;
-    use sophia_api::graph::Graph;
+    use rio_api::parser::ParseError;
+    use sophia_api::graph::{Graph, MutableGraph};
     use sophia_api::ns::{rdf, xsd};
     use sophia_api::term::matcher::ANY;
     use sophia_api::triple::stream::TripleSource;
+    use sophia_api::triple::Triple;
     use sophia_inmem::graph::FastGraph;
     use sophia_term::StaticTerm;
 
@@ -43,7 +49,7 @@ mod test {
         "#;
 
         let mut g = FastGraph::new();
-        let p = NTriplesParser {};
+        let p = NTriplesParser::default();
         let c = p.parse_str(turtle).add_to_graph(&mut g)?;
         assert_eq!(c, 3);
         assert!(g
@@ -72,4 +78,50 @@ mod test {
             .is_some());
         Ok(())
     }
+
+    #[test]
+    fn test_lenient() -> std::result::Result<(), Box<dyn std::error::Error>> {
+        let nt = r#"<http://example.org/a> <http://example.org/p> "1" .
+<http://example.org/b> <http://example.org/p> oops .
+<http://example.org/c> <http://example.org/p> "3" .
+<http://example.org/d> <http://example.org/p> "unterminated .
+<http://example.org/e> <http://example.org/p> "5" .
+"#;
+        assert!(NTriplesParser::default()
+            .parse_str(nt)
+            .for_each_triple(|_| ())
+            .is_err());
+
+        let p = NTriplesParser { lenient: true };
+        let mut source = p.parse_str(nt);
+        let mut g = FastGraph::new();
+        source.for_each_triple(|t| {
+            g.insert(t.s(), t.p(), t.o()).unwrap();
+        })?;
+        assert_eq!(g.triples().count(), 3);
+        let lines: Vec<_> = source
+            .errors()
+            .iter()
+            .map(|err| err.textual_position().unwrap().line_number())
+            .collect();
+        assert_eq!(lines, vec![2, 4]);
+        Ok(())
+    }
+
+    #[test]
+    fn test_lenient_io_error() {
+        struct Failing;
+        impl std::io::Read for Failing {
+            fn read(&mut self, _: &mut [u8]) -> std::io::Result<usize> {
+                Err(std::io::Error::other("failing"))
+            }
+        }
+
+        let p = NTriplesParser { lenient: true };
+        let mut source = p.parse(std::io::BufReader::new(Failing));
+        // I/O errors are not skipped, and stop the source
+        assert!(source.for_each_triple(|_| ()).is_err());
+        assert!(source.errors().is_empty());
+        assert!(matches!(source.for_each_triple(|_| ()), Ok(())));
+    }
 }
@@ -0,0 +1,497 @@
+//! Multi-threaded parser for [N-Triples] and [N-Quads].
+//!
+//! As these formats are line-based,
+//! the input can be split into chunks at line boundaries,
+//! and each chunk parsed independently on a pool of threads.
+//! Parsed chunks are then handed, as batches of triples or quads,
+//! to the calling thread (see [`ParallelParser`]).
+//!
+//! [N-Triples]: https://www.w3.org/TR/n-triples/
+//! [N-Quads]: https://www.w3.org/TR/n-quads/
+
+use super::nq::NQuadsParser;
+use super::nt::NTriplesParser;
+use super::{TextPosition, TurtleParseError};
+use rio_api::parser::ParseError;
+use rio_turtle::TurtleError;
+use sophia_api::dataset::MutableDataset;
+use sophia_api::graph::MutableGraph;
+use sophia_api::parser::{QuadParser, TripleParser};
+use sophia_api::quad::{stream::QuadSource, Quad};
+use sophia_api::term::CopyTerm;
+use sophia_api::triple::stream::{SinkError, SourceError, StreamResult, TripleSource};
+use sophia_api::triple::Triple;
+use sophia_term::BoxTerm;
+use std::collections::BTreeMap;
+use std::error::Error;
+use std::io::{self, BufRead};
+use std::sync::mpsc::{channel, sync_channel, Receiver};
+use std::sync::{Arc, Mutex};
+use std::thread;
+
+/// A batch of triples, as produced by [`ParallelParser::parse_nt`].
+pub type TripleBatch = Vec<[BoxTerm; 3]>;
+
+/// A batch of quads, as produced by [`ParallelParser::parse_nq`].
+pub type QuadBatch = Vec<([BoxTerm; 3], Option<BoxTerm>)>;
+
+/// Multi-threaded N-Triples / N-Quads parser.
+///
+/// ```
+/// # use sophia_api::graph::Graph;
+/// # use sophia_inmem::graph::FastGraph;
+/// # use sophia_turtle::parser::parallel::ParallelParser;
+/// let nt = "<x:s> <x:p> <x:o1>.\n<x:s> <x:p> <x:o2>.\n";
+/// let mut g = FastGraph::new();
+/// let count = ParallelParser::default().add_nt_to_graph(nt.as_bytes(), &mut g).unwrap();
+/// assert_eq!(count, 2);
+/// ```
+#[derive(Clone, Debug)]
+pub struct ParallelParser {
+    /// The approximate size (in bytes) of each chunk (defaults to 1MiB).
+    ///
+    /// Chunks are extended to the end of their last line.
+    pub chunk_size: usize,
+    /// The number of parsing threads (defaults to the available parallelism).
+    pub threads: usize,
+    /// If true, batches are handed over in the order of the input;
+    /// otherwise, they are handed over as soon as they are parsed (default).
+    ///
+    /// NB: in ordered mode, batches parsed ahead of a slow chunk are kept in memory.
+    pub ordered: bool,
+}
+
+impl Default for ParallelParser {
+    fn default() -> Self {
+        ParallelParser {
+            chunk_size: 1 << 20,
+            threads: thread::available_parallelism()
+                .map(|n| n.get())
+                .unwrap_or(1),
+            ordered: false,
+        }
+    }
+}
+
+impl ParallelParser {
+    /// Parse `read` as N-Triples, passing each batch of triples to `f`.
+    ///
+    /// Return the total number of triples.
+    pub fn parse_nt<R, F, E>(&self, read: R, mut f: F) -> StreamResult<usize, TurtleParseError, E>
+    where
+        R: BufRead + Send,
+        F: FnMut(TripleBatch) -> Result<(), E>,
+        E: Error,
+    {
+        self.parse_chunks(read, parse_nt_chunk, |batch| {
+            let len = batch.len();
+            f(batch).map(|_| len)
+        })
+    }
+
+    /// Parse `read` as N-Quads, passing each batch of quads to `f`.
+    ///
+    /// Return the total number of quads.
+    pub fn parse_nq<R, F, E>(&self, read: R, mut f: F) -> StreamResult<usize, TurtleParseError, E>
+    where
+        R: BufRead + Send,
+        F: FnMut(QuadBatch) -> Result<(), E>,
+        E: Error,
+    {
+        self.parse_chunks(read, parse_nq_chunk, |batch| {
+            let len = batch.len();
+            f(batch).map(|_| len)
+        })
+    }
+
+    /// Parse `read` as N-Triples, and insert all triples into `graph`.
+    ///
+    /// Return the number of triples parsed (not necessarily the number of triples inserted).
+    pub fn add_nt_to_graph<R, G>(
+        &self,
+        read: R,
+        graph: &mut G,
+    ) -> StreamResult<usize, TurtleParseError, G::MutationError>
+    where
+        R: BufRead + Send,
+        G: MutableGraph,
+    {
+        self.parse_nt(read, |batch| {
+            for [s, p, o] in batch {
+                graph.insert(&s, &p, &o)?;
+            }
+            Ok(())
+        })
+    }
+
+    /// Parse `read` as N-Quads, and insert all quads into `dataset`.
+    ///
+    /// Return the number of quads parsed (not necessarily the number of quads inserted).
+    pub fn add_nq_to_dataset<R, D>(
+        &self,
+        read: R,
+        dataset: &mut D,
+    ) -> StreamResult<usize, TurtleParseError, D::MutationError>
+    where
+        R: BufRead + Send,
+        D: MutableDataset,
+    {
+        self.parse_nq(read, |batch| {
+            for ([s, p, o], g) in batch {
+                dataset.insert(&s, &p, &o, g.as_ref())?;
+            }
+            Ok(())
+        })
+    }
+
+    /// Split `read` into chunks (on a dedicated thread),
+    /// parse them with `parse` (on `self.threads` threads),
+    /// and pass the results to `f` (on the calling thread).
+    fn parse_chunks<R, T, P, F, E>(
+        &self,
+        read: R,
+        parse: P,
+        mut f: F,
+    ) -> StreamResult<usize, TurtleParseError, E>
+    where
+        R: BufRead + Send,
+        T: Send,
+        P: Fn(&[u8]) -> Result<T, TurtleError> + Sync,
+        F: FnMut(T) -> Result<usize, E>,
+        E: Error,
+    {
+        let threads = self.threads.max(1);
+        let chunk_size = self.chunk_size.max(1);
+        thread::scope(|scope| {
+            // the number of pending chunks is bounded, to keep memory usage under control
+            let (chunk_tx, chunk_rx) = sync_channel::<Chunk>(threads * 2);
+            let chunk_rx = Arc::new(Mutex::new(chunk_rx));
+            let (result_tx, result_rx) = channel::<(usize, Result<T, TurtleParseError>)>();
+
+            let reader_tx = result_tx.clone();
+            scope.spawn(move || {
+                let mut splitter = Splitter::new(read, chunk_size);
+                loop {
+                    match splitter.next_chunk() {
+                        Ok(Some(chunk)) => {
+                            if chunk_tx.send(chunk).is_err() {
+                                return;
+                            }
+                        }
+                        Ok(None) => return,
+                        Err(err) => {
+                            let _ = reader_tx.send((splitter.index, Err(err.into())));
+                            return;
+                        }
+                    }
+                }
+            });
+            for _ in 0..threads {
+                let chunk_rx = Arc::clone(&chunk_rx);
+                let result_tx = result_tx.clone();
+                let parse = &parse;
+                scope.spawn(move || loop {
+                    let chunk = match next_chunk(&chunk_rx) {
+                        Some(chunk) => chunk,
+                        None => return,
+                    };
+                    let result = parse(&chunk.data).map_err(|err| chunk.locate(err));
+                    if result_tx.send((chunk.index, result)).is_err() {
+                        return;
+                    }
+                });
+            }
+            drop(result_tx);
+
+            let mut count = 0;
+            let mut handle = |result: Result<T, TurtleParseError>| match result {
+                Ok(batch) => {
+                    count += f(batch).map_err(SinkError)?;
+                    Ok(())
+                }
+                Err(err) => Err(SourceError(err)),
+            };
+            if self.ordered {
+                let mut next = 0;
+                let mut pending = BTreeMap::new();
+                for (index, result) in result_rx {
+                    pending.insert(index, result);
+                    while let Some(result) = pending.remove(&next) {
+                        handle(result)?;
+                        next += 1;
+                    }
+                }
+            } else {
+                for (_, result) in result_rx {
+                    handle(result)?;
+                }
+            }
+            Ok(count)
+        })
+    }
+}
+
+fn next_chunk(chunk_rx: &Mutex<Receiver<Chunk>>) -> Option<Chunk> {
+    chunk_rx.lock().ok()?.recv().ok()
+}
+
+fn parse_nt_chunk(data: &[u8]) -> Result<TripleBatch, TurtleError> {
+    let mut batch = vec![];
+    NTriplesParser::default().parse(data).for_each_triple(|t| {
+        batch.push([
+            BoxTerm::copy(t.s()),
+            BoxTerm::copy(t.p()),
+            BoxTerm::copy(t.o()),
+        ])
+    })?;
+    Ok(batch)
+}
+
+fn parse_nq_chunk(data: &[u8]) -> Result<QuadBatch, TurtleError> {
+    let mut batch = vec![];
+    NQuadsParser::default().parse(data).for_each_quad(|q| {
+        batch.push((
+            [
+                BoxTerm::copy(q.s()),
+                BoxTerm::copy(q.p()),
+                BoxTerm::copy(q.o()),
+            ],
+            q.g().map(BoxTerm::copy),
+        ))
+    })?;
+    Ok(batch)
+}
+
+/// A set of complete lines from the input.
+struct Chunk {
+    index: usize,
+    /// the position of the first byte of the chunk in the input
+    start: TextPosition,
+    data: Vec<u8>,
+}
+
+impl Chunk {
+    /// Convert an error raised by RIO, whose position is relative to the chunk.
+    fn locate(&self, err: TurtleError) -> TurtleParseError {
+        locate_rio_error(err, &self.data, self.start)
+    }
+}
+
+/// Convert an error raised by RIO while parsing `data`,
+/// assuming that `data` starts at position `start` in the whole input.
+pub(crate) fn locate_rio_error(
+    err: TurtleError,
+    data: &[u8],
+    start: TextPosition,
+) -> TurtleParseError {
+    let (line, byte) = match err.textual_position() {
+        Some(pos) => (pos.line_number() as usize, pos.byte_number() as usize),
+        None => return TurtleParseError::Io(io::Error::other(err)),
+    };
+    let message = err.to_string();
+    let message = match message.rfind(" on line ") {
+        Some(i) => message[..i].to_string(),
+        None => message,
+    };
+    let line_start = data
+        .split_inclusive(|b| *b == b'\n')
+        .take(line.saturating_sub(1))
+        .map(|l| l.len())
+        .sum::<usize>();
+    let line_end = data[line_start..]
+        .iter()
+        .position(|b| *b == b'\n')
+        .map(|i| line_start + i)
+        .unwrap_or(data.len());
+    let in_line = byte.saturating_sub(1).min(line_end - line_start);
+    let prefix = &data[line_start..line_start + in_line];
+    let column = 1 + prefix.iter().filter(|b| *b & 0xC0 != 0x80).count();
+    let chunk_start = start;
+    let start = TextPosition {
+        line: chunk_start.line + line - 1,
+        column: if line == 1 {
+            chunk_start.column + column - 1
+        } else {
+            column
+        },
+        offset: chunk_start.offset + line_start + in_line,
+    };
+    let end = TextPosition {
+        column: start.column + 1,
+        offset: start.offset + 1,
+        ..start
+    };
+    TurtleParseError::Syntax {
+        message,
+        start,
+        end,
+    }
+}
+
+/// Split the input into chunks of complete lines.
+struct Splitter<R> {
+    read: R,
+    chunk_size: usize,
+    index: usize,
+    position: TextPosition,
+}
+
+impl<R: BufRead> Splitter<R> {
+    fn new(read: R, chunk_size: usize) -> Self {
+        Splitter {
+            read,
+            chunk_size,
+            index: 0,
+            position: TextPosition::default(),
+        }
+    }
+
+    fn next_chunk(&mut self) -> io::Result<Option<Chunk>> {
+        let mut data = Vec::with_capacity(self.chunk_size);
+        while data.len() < self.chunk_size {
+            let buf = match self.read.fill_buf() {
+                Ok(buf) => buf,
+                Err(err) if err.kind() == io::ErrorKind::Interrupted => continue,
+                Err(err) => return Err(err),
+            };
+            if buf.is_empty() {
+                break;
+            }
+            let len = buf.len().min(self.chunk_size - data.len());
+            data.extend_from_slice(&buf[..len]);
+            self.read.consume(len);
+        }
+        if data.last().map(|b| *b != b'\n').unwrap_or(false) {
+            self.read.read_until(b'\n', &mut data)?;
+        }
+        if data.is_empty() {
+            return Ok(None);
+        }
+        let chunk = Chunk {
+            index: self.index,
+            start: self.position,
+            data,
+        };
+        self.index += 1;
+        let lines = chunk.data.iter().filter(|b| **b == b'\n').count();
+        self.position.line += lines;
+        self.position.offset += chunk.data.len();
+        // all chunks but the last end with a newline
+        self.position.column = 1;
+        Ok(Some(chunk))
+    }
+}
+
+// ---------------------------------------------------------------------------------
+//                                      tests
+// ---------------------------------------------------------------------------------
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use sophia_api::dataset::{isomorphic_datasets, Dataset};
+    use sophia_api::graph::isomorphic_graphs;
+    use sophia_api::parser::{Location, WithLocation};
+    use sophia_api::term::TTerm;
+    use sophia_inmem::dataset::FastDataset;
+    use sophia_inmem::graph::FastGraph;
+    use std::convert::Infallible;
+
+    fn nt_data(n: usize) -> String {
+        (0..n)
+            .map(|i| {
+                format!(
+                    "<http://example.org/s{}> <http://example.org/p> \"été {}\"@fr .\n",
+                    i % 7,
+                    i
+                )
+            })
+            .collect()
+    }
+
+    #[test]
+    fn graph() -> Result<(), Box<dyn std::error::Error>> {
+        let nt = nt_data(1000);
+        let expected: FastGraph = crate::parser::nt::parse_str(&nt).collect_triples()?;
+        for threads in [1, 3] {
+            let parser = ParallelParser {
+                chunk_size: 100,
+                threads,
+                ordered: false,
+            };
+            let mut g = FastGraph::new();
+            let count = parser.add_nt_to_graph(nt.as_bytes(), &mut g)?;
+            assert_eq!(count, 1000);
+            assert!(isomorphic_graphs(&g, &expected)?);
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn dataset() -> Result<(), Box<dyn std::error::Error>> {
+        let nq: String = (0..500)
+            .map(|i| {
+                format!(
+                    "<x:s{}> <x:p> <x:o> <x:g{}> .\n<x:s> <x:p> _:b{} .\n",
+                    i,
+                    i % 3,
+                    i
+                )
+            })
+            .collect();
+        let expected: FastDataset = crate::parser::nq::parse_str(&nq).collect_quads()?;
+        let parser = ParallelParser {
+            chunk_size: 64,
+            ..ParallelParser::default()
+        };
+        let mut d = FastDataset::new();
+        let count = parser.add_nq_to_dataset(nq.as_bytes(), &mut d)?;
+        assert_eq!(count, 1000);
+        assert_eq!(d.quads().count(), 1000);
+        assert!(isomorphic_datasets(&d, &expected)?);
+        Ok(())
+    }
+
+    #[test]
+    fn ordered_batches() -> Result<(), Box<dyn std::error::Error>> {
+        let nt = nt_data(1000);
+        let parser = ParallelParser {
+            chunk_size: 50,
+            threads: 4,
+            ordered: true,
+        };
+        let mut objects = vec![];
+        parser.parse_nt(nt.as_bytes(), |batch| {
+            objects.extend(batch.into_iter().map(|[_, _, o]| o.value().to_string()));
+            Ok(()) as Result<(), Infallible>
+        })?;
+        let expected: Vec<_> = (0..1000).map(|i| format!("été {}", i)).collect();
+        assert_eq!(objects, expected);
+        Ok(())
+    }
+
+    #[test]
+    fn error_location() {
+        let mut nt = nt_data(100);
+        nt.push_str("<x:s> <x:p> oops .\n");
+        nt.push_str(&nt_data(100));
+        let parser = ParallelParser {
+            chunk_size: 100,
+            threads: 2,
+            ordered: true,
+        };
+        let err = parser
+            .add_nt_to_graph(nt.as_bytes(), &mut FastGraph::new())
+            .unwrap_err();
+        let err = match err {
+            SourceError(err) => err,
+            SinkError(err) => panic!("unexpected sink error {}", err),
+        };
+        assert_eq!(
+            format!("{:?}", err.location()),
+            format!("{:?}", Location::from_licos(101, 13, 101, 14))
+        );
+        let offset = err.byte_range().unwrap().start;
+        assert_eq!(&nt[offset..offset + 4], "oops");
+    }
+}
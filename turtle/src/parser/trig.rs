@@ -1,23 +1,72 @@
-//! Adapter for the TriG parser from [RIO](https://github.com/Tpt/rio/blob/master/turtle/src/turtle.rs)
+//! Native TriG parser.
 
-use rio_turtle::{TriGParser as RioTriGParser, TurtleError};
-use sophia_api::parser::QuadParser;
-use sophia_rio::parser::*;
+use super::_native::NativeParser;
+use super::TurtleParseError;
+use sophia_api::parser::{ParserLimits, QuadParser};
+use sophia_api::prefix::PrefixBox;
+use sophia_iri::IriBox;
+use sophia_term::BoxTerm;
 use std::io::BufRead;
 
-/// TriG parser based on RIO.
+/// TriG parser.
 #[derive(Clone, Debug, Default)]
 pub struct TriGParser {
     /// The base IRI used by this parser to resolve relative IRI-references.
     pub base: Option<String>,
+    /// If true, a faulty statement does not stop the parsing:
+    /// it is skipped, and its error is recorded in [`TriGSource::errors`].
+    pub recovery: bool,
+    /// Limits on the resources consumed by this parser (none by default).
+    ///
+    /// The input size is measured in bytes read from the underlying reader,
+    /// and the nesting depth is that of collections and blank node property lists.
+    pub limits: ParserLimits,
 }
 
 impl<B: BufRead> QuadParser<B> for TriGParser {
-    type Source = StrictRioSource<RioTriGParser<B>, TurtleError>;
+    type Source = TriGSource<B>;
     fn parse(&self, data: B) -> Self::Source {
-        // TODO issue TurtleError if base can not be parsed
-        let base = self.base.clone().and_then(|b| oxiri::Iri::parse(b).ok());
-        StrictRioSource::Parser(RioTriGParser::new(data, base))
+        TriGSource(NativeParser::new(
+            data,
+            self.base.clone(),
+            true,
+            self.recovery,
+            self.limits,
+        ))
+    }
+}
+
+/// The [`QuadSource`](sophia_api::quad::stream::QuadSource) returned by [`TriGParser`].
+///
+/// See [`TurtleSource`](super::turtle::TurtleSource) about retrieving errors in recovery mode.
+pub struct TriGSource<B>(NativeParser<B>);
+
+impl<B> TriGSource<B> {
+    /// The prefixes declared so far in the parsed document.
+    ///
+    /// The returned slice implements [`PrefixMap`](sophia_api::prefix::PrefixMap),
+    /// so it can be passed to a serializer
+    /// (e.g. with [`TurtleConfig::with_prefix_map`](crate::serializer::turtle::TurtleConfig::with_prefix_map)).
+    pub fn prefixes(&self) -> &[(PrefixBox, IriBox)] {
+        self.0.prefixes()
+    }
+
+    /// The base IRI currently in effect,
+    /// i.e. the last one declared in the parsed document, or the one given to the parser.
+    pub fn base(&self) -> Option<&str> {
+        self.0.base()
+    }
+
+    /// The errors encountered so far in [recovery](TriGParser::recovery) mode.
+    pub fn errors(&self) -> &[TurtleParseError] {
+        self.0.errors()
+    }
+}
+
+impl<B: BufRead> Iterator for TriGSource<B> {
+    type Item = Result<([BoxTerm; 3], Option<BoxTerm>), TurtleParseError>;
+    fn next(&mut self) -> Option<Self::Item> {
+        self.0.next_quad()
     }
 }
 
@@ -53,6 +102,7 @@ mod test {
         let mut d = FastDataset::new();
         let p = TriGParser {
             base: Some("http://localhost/ex".into()),
+            ..TriGParser::default()
         };
         let c = p.parse_str(trig).add_to_dataset(&mut d)?;
         assert_eq!(c, 3);
@@ -85,4 +135,36 @@ mod test {
             .is_some());
         Ok(())
     }
+
+    #[test]
+    fn test_recovery() -> std::result::Result<(), Box<dyn std::error::Error>> {
+        let trig = r#"
+            PREFIX : <http://example.org/>
+            :g1 {
+                :a :p :o1 .
+                :b :p :o2 :o3 .
+                :c :p :o4
+            }
+            <bad iri> { :d :p :o5 }
+            GRAPH :g2 { :e :p :o6 . :f :p }
+            { :g :p :o7 }
+        "#;
+        let parser = TriGParser {
+            recovery: true,
+            ..TriGParser::default()
+        };
+        let mut source = parser.parse_str(trig);
+        let mut d = FastDataset::new();
+        let c = (&mut source).add_to_dataset(&mut d)?;
+        assert_eq!(c, 4);
+        assert_eq!(source.errors().len(), 3);
+        let g1 = StaticTerm::new_iri("http://example.org/g1").unwrap();
+        assert_eq!(d.quads_matching(&ANY, &ANY, &ANY, &Some(&g1)).count(), 2);
+        assert_eq!(
+            d.quads_matching(&ANY, &ANY, &ANY, &None as &Option<&StaticTerm>)
+                .count(),
+            1
+        );
+        Ok(())
+    }
 }
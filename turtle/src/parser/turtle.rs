@@ -1,26 +1,90 @@
-//! Adapter for the Turtle parser from [RIO](https://github.com/Tpt/rio/blob/master/turtle/src/turtle.rs)
+//! Native Turtle parser.
 
 use std::io::BufRead;
 
+use super::_native::NativeParser;
+use super::TurtleParseError;
 use rio_api::parser::ParseError;
-use rio_turtle::{TurtleError, TurtleParser as RioTurtleParser};
-use sophia_api::parser::{Location, TripleParser, WithLocation};
-use sophia_rio::parser::*;
+use rio_turtle::TurtleError;
+use sophia_api::parser::{Location, ParserLimits, TripleParser, WithLocation};
+use sophia_api::prefix::PrefixBox;
+use sophia_iri::IriBox;
+use sophia_term::BoxTerm;
 use thiserror::Error;
 
-/// Turtle parser based on RIO.
+/// Turtle parser.
 #[derive(Clone, Debug, Default)]
 pub struct TurtleParser {
     /// The base IRI used by this parser to resolve relative IRI-references.
     pub base: Option<String>,
+    /// If true, a faulty statement does not stop the parsing:
+    /// it is skipped, and its error is recorded in [`TurtleSource::errors`].
+    pub recovery: bool,
+    /// Limits on the resources consumed by this parser (none by default).
+    ///
+    /// The input size is measured in bytes read from the underlying reader,
+    /// and the nesting depth is that of collections and blank node property lists.
+    pub limits: ParserLimits,
 }
 
 impl<B: BufRead> TripleParser<B> for TurtleParser {
-    type Source = StrictRioSource<RioTurtleParser<B>, TurtleError>;
+    type Source = TurtleSource<B>;
     fn parse(&self, data: B) -> Self::Source {
-        // TODO issue TurtleError if base can not be parsed
-        let base = self.base.clone().and_then(|b| oxiri::Iri::parse(b).ok());
-        StrictRioSource::Parser(RioTurtleParser::new(data, base))
+        TurtleSource(NativeParser::new(
+            data,
+            self.base.clone(),
+            false,
+            self.recovery,
+            self.limits,
+        ))
+    }
+}
+
+/// The [`TripleSource`](sophia_api::triple::stream::TripleSource) returned by [`TurtleParser`].
+///
+/// In [recovery](TurtleParser::recovery) mode,
+/// the errors are not yielded by the source, but can be retrieved with [`errors`](TurtleSource::errors);
+/// to do so after consuming the source, use a `&mut` reference to it:
+/// ```
+/// # use sophia_api::parser::TripleParser;
+/// # use sophia_api::triple::stream::TripleSource;
+/// # use sophia_inmem::graph::FastGraph;
+/// # use sophia_turtle::parser::turtle::TurtleParser;
+/// let parser = TurtleParser { recovery: true, ..TurtleParser::default() };
+/// let mut source = parser.parse_str("<x:s> <x:p> <x:o1>. <x:s> oops. <x:s> <x:p> <x:o2>.");
+/// let mut g = FastGraph::new();
+/// let count = (&mut source).add_to_graph(&mut g).unwrap();
+/// assert_eq!(count, 2);
+/// assert_eq!(source.errors().len(), 1);
+/// ```
+pub struct TurtleSource<B>(NativeParser<B>);
+
+impl<B> TurtleSource<B> {
+    /// The prefixes declared so far in the parsed document.
+    ///
+    /// The returned slice implements [`PrefixMap`](sophia_api::prefix::PrefixMap),
+    /// so it can be passed to a serializer
+    /// (e.g. with [`TurtleConfig::with_prefix_map`](crate::serializer::turtle::TurtleConfig::with_prefix_map)).
+    pub fn prefixes(&self) -> &[(PrefixBox, IriBox)] {
+        self.0.prefixes()
+    }
+
+    /// The base IRI currently in effect,
+    /// i.e. the last one declared in the parsed document, or the one given to the parser.
+    pub fn base(&self) -> Option<&str> {
+        self.0.base()
+    }
+
+    /// The errors encountered so far in [recovery](TurtleParser::recovery) mode.
+    pub fn errors(&self) -> &[TurtleParseError] {
+        self.0.errors()
+    }
+}
+
+impl<B: BufRead> Iterator for TurtleSource<B> {
+    type Item = Result<[BoxTerm; 3], TurtleParseError>;
+    fn next(&mut self) -> Option<Self::Item> {
+        self.0.next_quad().map(|res| res.map(|(spo, _)| spo))
     }
 }
 
@@ -52,10 +116,11 @@ mod test {
     use super::*;
     use sophia_api::graph::Graph;
     use sophia_api::ns::{rdf, xsd};
+    use sophia_api::parser::Position;
     use sophia_api::term::matcher::ANY;
     use sophia_api::triple::stream::TripleSource;
     use sophia_inmem::graph::FastGraph;
-    use sophia_term::StaticTerm;
+    use sophia_term::{BoxTerm, StaticTerm};
 
     #[test]
     fn test_simple_turtle_string() -> std::result::Result<(), Box<dyn std::error::Error>> {
@@ -68,6 +133,7 @@ mod test {
         let mut g = FastGraph::new();
         let p = TurtleParser {
             base: Some("http://localhost/ex".into()),
+            ..TurtleParser::default()
         };
         let c = p.parse_str(turtle).add_to_graph(&mut g)?;
         assert_eq!(c, 3);
@@ -97,4 +163,281 @@ mod test {
             .is_some());
         Ok(())
     }
+
+    #[test]
+    fn test_turtle_grammar() -> std::result::Result<(), Box<dyn std::error::Error>> {
+        let turtle = r#"
+            @base <http://localhost/> .
+            PREFIX : <ns/>
+            prefix ex: <http://example.org/>
+            :s :p <o>, ex:o\.1.x, _:b.c, [], [ :q 1 ], ( 1 -2.5 +3e1 .4E-1 ), () ;
+               :lit 'a', "b"@en-GB, """c"
+"d""""", '''e''', "f"^^ex:dt, true, false ;;
+               a :C .
+            [ :p :o ] .
+            ( :a ) :p :o .
+            :é\~%41 :p :o # comment
+            .
+        "#;
+        let g: FastGraph = TurtleParser::default()
+            .parse_str(turtle)
+            .collect_triples()?;
+        assert_eq!(g.triples().count(), 29);
+        let s = StaticTerm::new_iri("http://localhost/ns/s").unwrap();
+        let lit = StaticTerm::new_iri("http://localhost/ns/lit").unwrap();
+        for o in [
+            BoxTerm::new_iri("http://localhost/o").unwrap(),
+            BoxTerm::new_iri("http://example.org/o.1.x").unwrap(),
+            BoxTerm::new_bnode("b.c").unwrap(),
+            BoxTerm::new_iri("http://www.w3.org/1999/02/22-rdf-syntax-ns#nil").unwrap(),
+        ] {
+            assert!(g.triples_matching(&s, &ANY, &o).next().is_some(), "{}", o);
+        }
+        for o in [
+            BoxTerm::new_literal_dt("a", xsd::string).unwrap(),
+            BoxTerm::new_literal_lang("b", "en-GB").unwrap(),
+            BoxTerm::new_literal_dt("c\"\n\"d\"\"", xsd::string).unwrap(),
+            BoxTerm::new_literal_dt("e", xsd::string).unwrap(),
+            BoxTerm::new_literal_dt("f", BoxTerm::new_iri("http://example.org/dt").unwrap())
+                .unwrap(),
+            BoxTerm::new_literal_dt("true", xsd::boolean).unwrap(),
+            BoxTerm::new_literal_dt("false", xsd::boolean).unwrap(),
+        ] {
+            assert!(g.triples_matching(&s, &lit, &o).next().is_some(), "{}", o);
+        }
+        for o in [
+            BoxTerm::new_literal_dt("1", xsd::integer).unwrap(),
+            BoxTerm::new_literal_dt("-2.5", xsd::decimal).unwrap(),
+            BoxTerm::new_literal_dt("+3e1", xsd::double).unwrap(),
+            BoxTerm::new_literal_dt(".4E-1", xsd::double).unwrap(),
+        ] {
+            assert!(g.triples_matching(&ANY, &ANY, &o).next().is_some(), "{}", o);
+        }
+        assert!(g
+            .triples_matching(
+                &StaticTerm::new_iri("http://localhost/ns/é~%41").unwrap(),
+                &ANY,
+                &ANY
+            )
+            .next()
+            .is_some());
+        Ok(())
+    }
+
+    #[test]
+    fn test_a_as_prefix() -> std::result::Result<(), Box<dyn std::error::Error>> {
+        let turtle = r#"
+            @prefix a.b: <http://ex.org/ab/>.
+            @prefix a: <http://ex.org/a/>.
+            <http://s> a.b:p <http://o>.
+            <http://s> a:p <http://o>.
+            <http://s> a <http://C>.
+            <http://s> a a:C.
+        "#;
+        let g: FastGraph = TurtleParser::default()
+            .parse_str(turtle)
+            .collect_triples()?;
+        assert_eq!(g.triples().count(), 4);
+        for p in ["http://ex.org/ab/p", "http://ex.org/a/p"] {
+            let p = BoxTerm::new_iri(p).unwrap();
+            assert!(g.triples_matching(&ANY, &p, &ANY).next().is_some(), "{}", p);
+        }
+        let n = g.triples_matching(&ANY, &rdf::type_, &ANY).count();
+        assert_eq!(n, 2);
+        Ok(())
+    }
+
+    #[test]
+    fn test_error_location() {
+        let turtle = "@prefix : <http://example.org/> .\n:s :p \"é\" ;\n   :q oops:o .\n";
+        let err = TurtleParser::default()
+            .parse_str(turtle)
+            .for_each_triple(|_| ())
+            .unwrap_err();
+        assert_eq!(
+            format!("{:?}", err.location()),
+            format!("{:?}", Location::from_licos(3, 7, 3, 13))
+        );
+        assert_eq!(err.byte_range(), Some(53..59));
+        assert_eq!(&turtle[53..59], "oops:o");
+    }
+
+    #[test]
+    fn test_recovery() -> std::result::Result<(), Box<dyn std::error::Error>> {
+        let turtle = r#"
+            @prefix : <http://example.org/> .
+            :a :p :o1 .
+            :b :p "unterminated .
+            :c :p :o2, <bad iri> .
+            :d :p "a. b", :o3 .
+            :e :p nope:o4 ; :q :o5 .
+            :f :p """long
+            . string""" .
+        "#;
+        let parser = TurtleParser {
+            recovery: true,
+            ..TurtleParser::default()
+        };
+        let mut source = parser.parse_str(turtle);
+        let mut g = FastGraph::new();
+        let c = (&mut source).add_to_graph(&mut g)?;
+        assert_eq!(c, 4);
+        let lines: Vec<_> = source
+            .errors()
+            .iter()
+            .map(|err| match err.location() {
+                Location::Span(Position::LiCo(line, _), _) => line,
+                _ => panic!("unexpected location {:?}", err.location()),
+            })
+            .collect();
+        assert_eq!(lines, vec![4, 5, 7]);
+        Ok(())
+    }
+
+    #[test]
+    fn test_prefixes_and_base() -> std::result::Result<(), Box<dyn std::error::Error>> {
+        use crate::serializer::turtle::{TurtleConfig, TurtleSerializer};
+        use sophia_api::prefix::PrefixMap;
+        use sophia_api::serializer::{Stringifier, TripleSerializer};
+
+        let turtle = r#"
+            @prefix : <http://example.org/ns/> .
+            :a :b :c .
+            @base <http://example.org/> .
+            PREFIX foaf: <http://xmlns.com/foaf/0.1/>
+            PREFIX : <ns2/>
+            <#me> a foaf:Person .
+        "#;
+        let mut source = TurtleParser::default().parse_str(turtle);
+        source.next().unwrap()?;
+        assert_eq!(source.prefixes().len(), 1);
+        assert_eq!(source.base(), None);
+
+        let mut g = FastGraph::new();
+        (&mut source).add_to_graph(&mut g)?;
+        let prefixes = source.prefixes();
+        assert_eq!(prefixes.len(), 2);
+        assert_eq!(
+            &*prefixes.get_namespace("").unwrap(),
+            "http://example.org/ns2/"
+        );
+        assert_eq!(
+            &*prefixes.get_namespace("foaf").unwrap(),
+            "http://xmlns.com/foaf/0.1/"
+        );
+        assert_eq!(source.base(), Some("http://example.org/"));
+
+        let config = TurtleConfig::new()
+            .with_pretty(true)
+            .with_prefix_map(prefixes);
+        let out = TurtleSerializer::new_stringifier_with_config(config)
+            .serialize_graph(&g)?
+            .to_string();
+        assert!(out.contains("foaf:Person"), "{}", out);
+        Ok(())
+    }
+
+    #[test]
+    fn test_limits() {
+        use sophia_api::parser::{Limit, LimitExceeded};
+
+        let turtle = r#"
+            @prefix : <http://example.org/> .
+            :s :p "short", "a bit longer" .
+            :s :p ( 1 ( 2 [ :p ( 3 ) ] ) ) .
+            :s :p :a, :b, :c, :d .
+        "#;
+        let exceeded = |limits: ParserLimits| {
+            let parser = TurtleParser {
+                limits,
+                recovery: true,
+                ..TurtleParser::default()
+            };
+            let mut source = parser.parse_str(turtle);
+            let err = loop {
+                match source.next() {
+                    Some(Ok(_)) => (),
+                    Some(Err(err)) => break err,
+                    None => return None,
+                }
+            };
+            assert!(source.next().is_none());
+            match err {
+                TurtleParseError::Limit {
+                    error: LimitExceeded { limit, .. },
+                    position,
+                } => Some((limit, position.line)),
+                err => panic!("unexpected error {:?}", err),
+            }
+        };
+        let none = ParserLimits::default();
+        assert_eq!(exceeded(none), None);
+
+        let cases = [
+            (
+                ParserLimits {
+                    max_input_bytes: Some(100),
+                    ..none
+                },
+                Limit::InputBytes,
+            ),
+            (
+                ParserLimits {
+                    max_triples: Some(14),
+                    ..none
+                },
+                Limit::Triples,
+            ),
+            (
+                ParserLimits {
+                    max_literal_len: Some(10),
+                    ..none
+                },
+                Limit::LiteralLength,
+            ),
+            (
+                ParserLimits {
+                    max_iri_len: Some(19),
+                    ..none
+                },
+                Limit::IriLength,
+            ),
+            (
+                ParserLimits {
+                    max_nesting_depth: Some(3),
+                    ..none
+                },
+                Limit::NestingDepth,
+            ),
+        ];
+        let lines = [1, 5, 3, 3, 4];
+        for ((limits, limit), line) in cases.into_iter().zip(lines) {
+            assert_eq!(exceeded(limits), Some((limit, line)), "{:?}", limit);
+        }
+        // limits that are not exceeded
+        let limits = ParserLimits {
+            max_input_bytes: Some(turtle.len()),
+            max_triples: Some(18),
+            max_literal_len: Some(12),
+            max_iri_len: Some(20),
+            max_nesting_depth: Some(4),
+        };
+        assert_eq!(exceeded(limits), None);
+
+        // triples of discarded statements do not count
+        let parser = TurtleParser {
+            limits: ParserLimits {
+                max_triples: Some(2),
+                ..none
+            },
+            recovery: true,
+            ..TurtleParser::default()
+        };
+        let mut source = parser.parse_str("<a:s> <a:p> <a:o1>, <a:o2>, ! .\n<a:s> <a:p> <a:o3> .");
+        let results: Vec<_> = source.by_ref().collect();
+        assert_eq!(results.len(), 1, "{:?}", results);
+        let [_, _, o] = results[0].as_ref().unwrap();
+        assert_eq!(sophia_api::term::TTerm::value(o), "a:o3");
+        assert_eq!(source.errors().len(), 1);
+    }
 }
@@ -1,6 +1,9 @@
 //! Serializers for the Turtle-familt of RDF concrete syntaxes,
 //! based on [`rio_turtle`].
 
+#[cfg(feature = "async")]
+pub mod asynchronous;
+pub mod hext;
 pub mod nq;
 pub mod nt;
 pub mod trig;
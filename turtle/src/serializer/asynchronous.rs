@@ -0,0 +1,453 @@
+//! Asynchronous serialization (requires the `async` feature).
+//!
+//! The functions of this module write to an [`AsyncWrite`].
+//! Triples (resp. quads) are serialized synchronously in batches into a memory buffer,
+//! which is then written asynchronously.
+//! The serialization state is kept from one batch to the next,
+//! so the output is identical to that of the corresponding synchronous serializer.
+//!
+//! ```
+//! # use futures_executor::block_on;
+//! # use sophia_turtle::serializer::asynchronous::serialize_nt;
+//! # use sophia_turtle::serializer::nt::NtConfig;
+//! # block_on(async {
+//! let triples = vec![["tag:s", "tag:p", "tag:o"].map(sophia_term::BoxTerm::new_iri_unchecked)];
+//! let mut output = vec![]; // any AsyncWrite
+//! serialize_nt(triples.into_iter().map(Ok::<_, std::convert::Infallible>), &mut output, &NtConfig::default())
+//!     .await
+//!     .unwrap();
+//! assert_eq!(output, b"<tag:s> <tag:p> <tag:o>.\n");
+//! # })
+//! ```
+//!
+//! Note that, in [pretty](super::turtle::TurtleConfig::pretty) mode,
+//! the Turtle and TriG serializers need the whole graph (resp. dataset),
+//! so they consume the whole source before writing anything.
+//! The same goes for the N-Triples and N-Quads serializers
+//! when [sorted](super::nt::NtConfig::sorted) output is required.
+
+use super::nq::{NqConfig, NqSerializer};
+use super::nt::{NtConfig, NtSerializer};
+use super::trig::{QuadStreamer, TrigConfig, TrigSerializer};
+use super::turtle::{write_base, write_prefixes, Streamer, TurtleConfig, TurtleSerializer};
+use futures_util::io::{AsyncWrite, AsyncWriteExt};
+use sophia_api::quad::stream::QuadSource;
+use sophia_api::quad::Quad;
+use sophia_api::serializer::{QuadSerializer, TripleSerializer};
+use sophia_api::term::CopyTerm;
+use sophia_api::triple::stream::{SinkError, SourceError, StreamError, StreamResult, TripleSource};
+use sophia_api::triple::Triple;
+use sophia_term::BoxTerm;
+use std::convert::Infallible;
+use std::io;
+
+/// The number of triples (resp. quads) serialized in each batch.
+const BATCH_SIZE: usize = 1 << 10;
+
+type BoxQuad = ([BoxTerm; 3], Option<BoxTerm>);
+
+/// Serialize `source` as N-Triples into `write`.
+pub async fn serialize_nt<TS, W>(
+    source: TS,
+    write: &mut W,
+    config: &NtConfig,
+) -> StreamResult<(), TS::Error, io::Error>
+where
+    TS: TripleSource,
+    W: AsyncWrite + Unpin,
+{
+    // sorting must be done over the whole source, not batch per batch
+    let batch_size = if config.sorted() {
+        usize::MAX
+    } else {
+        BATCH_SIZE
+    };
+    write_triple_batches(source, write, batch_size, |buffer, batch| {
+        NtSerializer::new_with_config(buffer, config.clone())
+            .serialize_triples(batch)
+            .map(|_| ())
+    })
+    .await
+}
+
+/// Serialize `source` as N-Quads into `write`.
+pub async fn serialize_nq<QS, W>(
+    source: QS,
+    write: &mut W,
+    config: &NqConfig,
+) -> StreamResult<(), QS::Error, io::Error>
+where
+    QS: QuadSource,
+    W: AsyncWrite + Unpin,
+{
+    // sorting must be done over the whole source, not batch per batch
+    let batch_size = if config.sorted() {
+        usize::MAX
+    } else {
+        BATCH_SIZE
+    };
+    write_quad_batches(source, write, batch_size, |buffer, batch| {
+        NqSerializer::new_with_config(buffer, config.clone())
+            .serialize_quads(batch)
+            .map(|_| ())
+    })
+    .await
+}
+
+/// Serialize `source` as Turtle into `write`.
+pub async fn serialize_turtle<TS, W>(
+    source: TS,
+    write: &mut W,
+    config: &TurtleConfig,
+) -> StreamResult<(), TS::Error, io::Error>
+where
+    TS: TripleSource,
+    W: AsyncWrite + Unpin,
+{
+    if config.pretty {
+        write_triple_batches(source, write, usize::MAX, |buffer, batch| {
+            TurtleSerializer::new_with_config(buffer, config.clone())
+                .serialize_triples(batch)
+                .map(|_| ())
+        })
+        .await
+    } else {
+        write_header(write, config).await.map_err(SinkError)?;
+        let mut streamer = Streamer::new(config, "");
+        let mut buffer = vec![];
+        let mut source = source;
+        let mut batch = vec![];
+        loop {
+            let more =
+                fill_triple_batch(&mut source, &mut batch, BATCH_SIZE).map_err(SourceError)?;
+            for [s, p, o] in batch.drain(..) {
+                streamer
+                    .write_triple(&mut buffer, &s, &p, &o)
+                    .map_err(SinkError)?;
+            }
+            if !more {
+                streamer.finish(&mut buffer).map_err(SinkError)?;
+            }
+            write.write_all(&buffer).await.map_err(SinkError)?;
+            buffer.clear();
+            if !more {
+                return write.flush().await.map_err(SinkError);
+            }
+        }
+    }
+}
+
+/// Serialize `source` as TriG into `write`.
+pub async fn serialize_trig<QS, W>(
+    source: QS,
+    write: &mut W,
+    config: &TrigConfig,
+) -> StreamResult<(), QS::Error, io::Error>
+where
+    QS: QuadSource,
+    W: AsyncWrite + Unpin,
+{
+    if config.pretty {
+        write_quad_batches(source, write, usize::MAX, |buffer, batch| {
+            TrigSerializer::new_with_config(buffer, config.clone())
+                .serialize_quads(batch)
+                .map(|_| ())
+        })
+        .await
+    } else {
+        write_header(write, config).await.map_err(SinkError)?;
+        let mut streamer = QuadStreamer::new(config);
+        let mut buffer = vec![];
+        let mut source = source;
+        let mut batch = vec![];
+        loop {
+            let more = fill_quad_batch(&mut source, &mut batch, BATCH_SIZE).map_err(SourceError)?;
+            for q in batch.drain(..) {
+                streamer.write_quad(&mut buffer, &q).map_err(SinkError)?;
+            }
+            if !more {
+                streamer.finish(&mut buffer).map_err(SinkError)?;
+            }
+            write.write_all(&buffer).await.map_err(SinkError)?;
+            buffer.clear();
+            if !more {
+                return write.flush().await.map_err(SinkError);
+            }
+        }
+    }
+}
+
+/// Write the base IRI and prefixes of `config` into `write`.
+async fn write_header<W>(write: &mut W, config: &TurtleConfig) -> io::Result<()>
+where
+    W: AsyncWrite + Unpin,
+{
+    let mut buffer = vec![];
+    write_base(&mut buffer, config.base())?;
+    write_prefixes(&mut buffer, config.prefix_map())?;
+    write.write_all(&buffer).await
+}
+
+/// Copy up to `batch_size` triples from `source` into `batch`.
+///
+/// Return `false` if the source is exhausted.
+fn fill_triple_batch<TS: TripleSource>(
+    source: &mut TS,
+    batch: &mut Vec<[BoxTerm; 3]>,
+    batch_size: usize,
+) -> Result<bool, TS::Error> {
+    while batch.len() < batch_size {
+        let more = source
+            .try_for_some_triple(&mut |t| {
+                batch.push([
+                    BoxTerm::copy(t.s()),
+                    BoxTerm::copy(t.p()),
+                    BoxTerm::copy(t.o()),
+                ]);
+                Ok::<_, Infallible>(())
+            })
+            .map_err(StreamError::unwrap_source_error)?;
+        if !more {
+            return Ok(false);
+        }
+    }
+    Ok(true)
+}
+
+/// Copy up to `batch_size` quads from `source` into `batch`.
+///
+/// Return `false` if the source is exhausted.
+fn fill_quad_batch<QS: QuadSource>(
+    source: &mut QS,
+    batch: &mut Vec<BoxQuad>,
+    batch_size: usize,
+) -> Result<bool, QS::Error> {
+    while batch.len() < batch_size {
+        let more = source
+            .try_for_some_quad(&mut |q| {
+                batch.push((
+                    [
+                        BoxTerm::copy(q.s()),
+                        BoxTerm::copy(q.p()),
+                        BoxTerm::copy(q.o()),
+                    ],
+                    q.g().map(BoxTerm::copy),
+                ));
+                Ok::<_, Infallible>(())
+            })
+            .map_err(StreamError::unwrap_source_error)?;
+        if !more {
+            return Ok(false);
+        }
+    }
+    Ok(true)
+}
+
+async fn write_triple_batches<TS, W, F>(
+    mut source: TS,
+    write: &mut W,
+    batch_size: usize,
+    mut serialize: F,
+) -> StreamResult<(), TS::Error, io::Error>
+where
+    TS: TripleSource,
+    W: AsyncWrite + Unpin,
+    F: FnMut(&mut Vec<u8>, TripleBatch) -> StreamResult<(), Infallible, io::Error>,
+{
+    let mut batch = vec![];
+    let mut buffer = vec![];
+    loop {
+        let more = fill_triple_batch(&mut source, &mut batch, batch_size).map_err(SourceError)?;
+        if !batch.is_empty() {
+            serialize(&mut buffer, batch.drain(..).map(Ok))
+                .map_err(StreamError::unwrap_sink_error)
+                .map_err(SinkError)?;
+            write.write_all(&buffer).await.map_err(SinkError)?;
+            buffer.clear();
+        }
+        if !more {
+            return write.flush().await.map_err(SinkError);
+        }
+    }
+}
+
+async fn write_quad_batches<QS, W, F>(
+    mut source: QS,
+    write: &mut W,
+    batch_size: usize,
+    mut serialize: F,
+) -> StreamResult<(), QS::Error, io::Error>
+where
+    QS: QuadSource,
+    W: AsyncWrite + Unpin,
+    F: FnMut(&mut Vec<u8>, QuadBatch) -> StreamResult<(), Infallible, io::Error>,
+{
+    let mut batch = vec![];
+    let mut buffer = vec![];
+    loop {
+        let more = fill_quad_batch(&mut source, &mut batch, batch_size).map_err(SourceError)?;
+        if !batch.is_empty() {
+            serialize(&mut buffer, batch.drain(..).map(Ok))
+                .map_err(StreamError::unwrap_sink_error)
+                .map_err(SinkError)?;
+            write.write_all(&buffer).await.map_err(SinkError)?;
+            buffer.clear();
+        }
+        if !more {
+            return write.flush().await.map_err(SinkError);
+        }
+    }
+}
+
+type TripleBatch<'a> = std::iter::Map<
+    std::vec::Drain<'a, [BoxTerm; 3]>,
+    fn([BoxTerm; 3]) -> Result<[BoxTerm; 3], Infallible>,
+>;
+
+type QuadBatch<'a> =
+    std::iter::Map<std::vec::Drain<'a, BoxQuad>, fn(BoxQuad) -> Result<BoxQuad, Infallible>>;
+
+// ---------------------------------------------------------------------------------
+//                                      tests
+// ---------------------------------------------------------------------------------
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::parser::{trig, turtle};
+    use futures_executor::block_on;
+    use sophia_api::dataset::{isomorphic_datasets, Dataset, MutableDataset};
+    use sophia_api::graph::{isomorphic_graphs, Graph};
+    use sophia_api::prefix::PrefixBox;
+    use sophia_api::serializer::Stringifier;
+    use sophia_inmem::dataset::FastDataset;
+    use sophia_inmem::graph::FastGraph;
+    use sophia_iri::IriBox;
+
+    fn graph(n: usize) -> FastGraph {
+        let ttl: String = (0..n)
+            .map(|i| format!("<http://ex.org/s{}> <http://ex.org/p> \"{}\" .\n", i % 7, i))
+            .collect();
+        turtle::parse_str(&ttl).collect_triples().unwrap()
+    }
+
+    #[test]
+    fn nt_same_as_sync() {
+        let g = graph(3000);
+        let mut output = vec![];
+        block_on(serialize_nt(g.triples(), &mut output, &NtConfig::default())).unwrap();
+        let expected = NtSerializer::new_stringifier()
+            .serialize_graph(&g)
+            .unwrap()
+            .to_string();
+        assert_eq!(std::str::from_utf8(&output).unwrap(), expected);
+    }
+
+    #[test]
+    fn nt_sorted_same_as_sync() {
+        let g = graph(3000);
+        let mut config = NtConfig::default();
+        config.set_sorted(true);
+        let mut output = vec![];
+        block_on(serialize_nt(g.triples(), &mut output, &config)).unwrap();
+        let expected = NtSerializer::new_stringifier_with_config(config)
+            .serialize_graph(&g)
+            .unwrap()
+            .to_string();
+        assert_eq!(std::str::from_utf8(&output).unwrap(), expected);
+    }
+
+    #[test]
+    fn nq_sorted_same_as_sync() {
+        let mut d = FastDataset::new();
+        for (i, t) in graph(3000).triples().enumerate() {
+            let t = t.unwrap();
+            let g = BoxTerm::new_iri_unchecked(format!("http://ex.org/g{}", i % 3));
+            d.insert(t.s(), t.p(), t.o(), Some(&g)).unwrap();
+        }
+        let mut config = NqConfig::default();
+        config.set_sorted(true);
+        let mut output = vec![];
+        block_on(serialize_nq(d.quads(), &mut output, &config)).unwrap();
+        let expected = NqSerializer::new_stringifier_with_config(config)
+            .serialize_dataset(&d)
+            .unwrap()
+            .to_string();
+        assert_eq!(std::str::from_utf8(&output).unwrap(), expected);
+    }
+
+    #[test]
+    fn turtle_round_trip() {
+        let g = graph(3000);
+        let config = TurtleConfig::new().with_own_prefix_map(vec![(
+            PrefixBox::new_unchecked("ex".into()),
+            IriBox::new_unchecked("http://ex.org/".into()),
+        )]);
+        for pretty in [false, true] {
+            let config = config.clone().with_pretty(pretty);
+            let mut output = vec![];
+            block_on(serialize_turtle(g.triples(), &mut output, &config)).unwrap();
+            let output = String::from_utf8(output).unwrap();
+            let g2: FastGraph = turtle::parse_str(&output).collect_triples().unwrap();
+            assert!(isomorphic_graphs(&g, &g2).unwrap());
+        }
+    }
+
+    #[test]
+    fn turtle_streaming_same_as_sync() {
+        let g = graph(3000);
+        let config = TurtleConfig::new().with_own_prefix_map(vec![(
+            PrefixBox::new_unchecked("ex".into()),
+            IriBox::new_unchecked("http://ex.org/".into()),
+        )]);
+        let mut output = vec![];
+        block_on(serialize_turtle(g.triples(), &mut output, &config)).unwrap();
+        let output = String::from_utf8(output).unwrap();
+        let expected = TurtleSerializer::new_stringifier_with_config(config)
+            .serialize_graph(&g)
+            .unwrap()
+            .to_string();
+        assert_eq!(output, expected);
+        assert_eq!(output.matches("PREFIX").count(), 1);
+    }
+
+    #[test]
+    fn trig_streaming_same_as_sync() {
+        let mut d = FastDataset::new();
+        for (i, t) in graph(3000).triples().enumerate() {
+            let t = t.unwrap();
+            let g = BoxTerm::new_iri_unchecked(format!("http://ex.org/g{}", i / 500));
+            d.insert(t.s(), t.p(), t.o(), Some(&g)).unwrap();
+        }
+        let config = TrigConfig::new().with_own_prefix_map(vec![(
+            PrefixBox::new_unchecked("ex".into()),
+            IriBox::new_unchecked("http://ex.org/".into()),
+        )]);
+        let mut output = vec![];
+        block_on(serialize_trig(d.quads(), &mut output, &config)).unwrap();
+        let output = String::from_utf8(output).unwrap();
+        let expected = TrigSerializer::new_stringifier_with_config(config)
+            .serialize_dataset(&d)
+            .unwrap()
+            .to_string();
+        assert_eq!(output, expected);
+        assert_eq!(output.matches("PREFIX").count(), 1);
+    }
+
+    #[test]
+    fn trig_round_trip() {
+        let d: FastDataset = trig::parse_str(
+            "<x:a> <x:b> <x:c>. <x:g> { <x:d> <x:e> [ <x:f> <x:h> ] } { <x:i> <x:j> (1 2) }",
+        )
+        .collect_quads()
+        .unwrap();
+        for pretty in [false, true] {
+            let mut output = vec![];
+            let config = TrigConfig::new().with_pretty(pretty);
+            block_on(serialize_trig(d.quads(), &mut output, &config)).unwrap();
+            let output = String::from_utf8(output).unwrap();
+            let d2: FastDataset = trig::parse_str(&output).collect_quads().unwrap();
+            assert!(isomorphic_datasets(&d, &d2).unwrap());
+        }
+    }
+}
@@ -0,0 +1,202 @@
+//! Serializer for the [HexTuples] concrete syntax of RDF datasets.
+//!
+//! Triples (when serializing a graph) are written in the default graph.
+//!
+//! **Important**:
+//! the methods in this module accepting a [`Write`]
+//! make no effort to minimize the number of write operations.
+//! Hence, in most cased, they should be passed a [`BufWriter`].
+//!
+//! [HexTuples]: https://github.com/ontola/hextuples
+//! [`Write`]: https://doc.rust-lang.org/std/io/trait.Write.html
+//! [`BufWriter`]: https://doc.rust-lang.org/std/io/struct.BufWriter.html
+
+use json::JsonValue;
+use sophia_api::quad::{stream::*, Quad};
+use sophia_api::serializer::*;
+use sophia_api::term::{TTerm, TermKind};
+use sophia_api::triple::stream::TripleSource;
+use sophia_api::triple::Triple;
+use std::io;
+
+/// HexTuples serializer.
+pub struct HextSerializer<W> {
+    write: W,
+}
+
+impl<W> HextSerializer<W>
+where
+    W: io::Write,
+{
+    /// Build a new HexTuples serializer writing to `write`.
+    #[inline]
+    pub fn new(write: W) -> HextSerializer<W> {
+        HextSerializer { write }
+    }
+}
+
+impl<W> TripleSerializer for HextSerializer<W>
+where
+    W: io::Write,
+{
+    type Error = io::Error;
+
+    fn serialize_triples<TS>(
+        &mut self,
+        mut source: TS,
+    ) -> StreamResult<&mut Self, TS::Error, Self::Error>
+    where
+        TS: TripleSource,
+    {
+        source
+            .try_for_each_triple(|t| write_hextuple(&mut self.write, t.s(), t.p(), t.o(), None))
+            .map(|_| self)
+    }
+}
+
+impl<W> QuadSerializer for HextSerializer<W>
+where
+    W: io::Write,
+{
+    type Error = io::Error;
+
+    fn serialize_quads<QS>(
+        &mut self,
+        mut source: QS,
+    ) -> StreamResult<&mut Self, QS::Error, Self::Error>
+    where
+        QS: QuadSource,
+    {
+        source
+            .try_for_each_quad(|q| write_hextuple(&mut self.write, q.s(), q.p(), q.o(), q.g()))
+            .map(|_| self)
+    }
+}
+
+impl HextSerializer<Vec<u8>> {
+    /// Create a new serializer which targets a `String`.
+    #[inline]
+    pub fn new_stringifier() -> Self {
+        HextSerializer::new(Vec::new())
+    }
+}
+
+impl Stringifier for HextSerializer<Vec<u8>> {
+    fn as_utf8(&self) -> &[u8] {
+        &self.write[..]
+    }
+}
+
+fn write_hextuple<W, T>(w: &mut W, s: &T, p: &T, o: &T, g: Option<&T>) -> io::Result<()>
+where
+    W: io::Write,
+    T: TTerm + ?Sized,
+{
+    let (value, datatype, lang) = match o.kind() {
+        TermKind::Iri => (o.value().to_string(), "globalId".into(), String::new()),
+        TermKind::BlankNode => (format!("_:{}", o.value()), "localId".into(), String::new()),
+        TermKind::Literal => (
+            o.value().to_string(),
+            o.datatype().unwrap().value().to_string(),
+            o.language().map(str::to_string).unwrap_or_default(),
+        ),
+        TermKind::Variable => return Err(unsupported(o, "object")),
+    };
+    let fields = [
+        node(s, "subject")?,
+        node(p, "predicate")?,
+        value,
+        datatype,
+        lang,
+        match g {
+            Some(g) => node(g, "graph name")?,
+            None => String::new(),
+        },
+    ];
+    let line = JsonValue::Array(fields.into_iter().map(JsonValue::from).collect());
+    w.write_all(line.dump().as_bytes())?;
+    w.write_all(b"\n")
+}
+
+/// The HexTuples representation of an IRI or a blank node.
+///
+/// `role` is only used in the error message, if `term` is neither.
+fn node<T: TTerm + ?Sized>(term: &T, role: &str) -> io::Result<String> {
+    match term.kind() {
+        TermKind::Iri => Ok(term.value().to_string()),
+        TermKind::BlankNode if role != "predicate" => Ok(format!("_:{}", term.value())),
+        _ => Err(unsupported(term, role)),
+    }
+}
+
+fn unsupported<T: TTerm + ?Sized>(term: &T, role: &str) -> io::Error {
+    io::Error::new(
+        io::ErrorKind::InvalidData,
+        format!(
+            "HexTuples does not support {:?} as {}: {}",
+            term.kind(),
+            role,
+            term.value()
+        ),
+    )
+}
+
+// ---------------------------------------------------------------------------------
+//                                      tests
+// ---------------------------------------------------------------------------------
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use sophia_api::dataset::isomorphic_datasets;
+    use sophia_inmem::dataset::FastDataset;
+
+    #[test]
+    fn serialize() -> Result<(), Box<dyn std::error::Error>> {
+        let nq = r#"
+            <tag:s> <tag:p> <tag:o>.
+            <tag:s> <tag:p> "a \"b\"\n"@en <tag:g>.
+            _:b <tag:p> "1"^^<http://www.w3.org/2001/XMLSchema#integer> _:g.
+            _:b <tag:p> _:c _:g.
+        "#;
+        let hext = HextSerializer::new_stringifier()
+            .serialize_quads(crate::parser::nq::parse_str(nq))?
+            .to_string();
+        assert_eq!(
+            hext,
+            r#"["tag:s","tag:p","tag:o","globalId","",""]
+["tag:s","tag:p","a \"b\"\n","http://www.w3.org/1999/02/22-rdf-syntax-ns#langString","en","tag:g"]
+["_:b","tag:p","1","http://www.w3.org/2001/XMLSchema#integer","","_:g"]
+["_:b","tag:p","_:c","localId","","_:g"]
+"#
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn roundtrip() -> Result<(), Box<dyn std::error::Error>> {
+        let trig = r#"
+            PREFIX : <http://example.org/ns/>
+            :alice a :Person; :name "Alice", "Alicia"@es; :age 42; :note "tab\tand é".
+            GRAPH :g1 { :bob :knows _:x. _:x :name "X"^^:custom. }
+            GRAPH _:g2 { _:x :p :alice. }
+        "#;
+        let d1: FastDataset = crate::parser::trig::parse_str(trig).collect_quads()?;
+        let hext = HextSerializer::new_stringifier()
+            .serialize_dataset(&d1)?
+            .to_string();
+        assert_eq!(hext.lines().count(), 8);
+        let d2: FastDataset = crate::parser::hext::parse_str(&hext).collect_quads()?;
+        assert!(isomorphic_datasets(&d1, &d2)?);
+        Ok(())
+    }
+
+    #[test]
+    fn unsupported_terms() {
+        let nq = r#""s" <tag:p> <tag:o>."#;
+        let mut serializer = HextSerializer::new_stringifier();
+        assert!(serializer
+            .serialize_quads(crate::parser::gtrig::parse_str(nq))
+            .is_err());
+    }
+}
@@ -9,7 +9,7 @@
 //! [`Write`]: https://doc.rust-lang.org/std/io/trait.Write.html
 //! [`BufWriter`]: https://doc.rust-lang.org/std/io/struct.BufWriter.html
 
-use super::nt::write_term;
+use super::nt::{write_sorted_lines, write_term_with, Style};
 use sophia_api::quad::{stream::*, Quad};
 use sophia_api::serializer::*;
 use std::io;
@@ -18,14 +18,51 @@ use std::io;
 #[derive(Clone, Debug, Default)]
 pub struct NqConfig {
     ascii: bool,
+    canonical: bool,
+    sorted: bool,
 }
 
 impl NqConfig {
+    /// Whether the output is restricted to ASCII characters (defaults to `false`).
+    ///
+    /// See [`write_ascii_term`](super::nt::write_ascii_term)
+    /// for how non-ASCII characters are handled.
+    pub fn ascii(&self) -> bool {
+        self.ascii
+    }
+
     /// Set the ascii configuration.
     pub fn set_ascii(&mut self, ascii: bool) -> &mut Self {
         self.ascii = ascii;
         self
     }
+
+    /// Whether the output is in canonical N-Quads (defaults to `false`).
+    ///
+    /// See [`NtConfig::canonical`](super::nt::NtConfig::canonical).
+    pub fn canonical(&self) -> bool {
+        self.canonical
+    }
+
+    /// Set the canonical configuration.
+    pub fn set_canonical(&mut self, canonical: bool) -> &mut Self {
+        self.canonical = canonical;
+        self
+    }
+
+    /// Whether the quads are sorted (in the byte order of their serialization)
+    /// and deduplicated (defaults to `false`).
+    ///
+    /// NB: this requires storing the whole output in memory.
+    pub fn sorted(&self) -> bool {
+        self.sorted
+    }
+
+    /// Set the sorted configuration.
+    pub fn set_sorted(&mut self, sorted: bool) -> &mut Self {
+        self.sorted = sorted;
+        self
+    }
 }
 
 /// N-Quads serializer.
@@ -68,30 +105,44 @@ where
     where
         QS: QuadSource,
     {
-        if self.config.ascii {
-            todo!("Pure-ASCII N-Quads is not implemented yet")
+        let style = Style::new(self.config.ascii, self.config.canonical);
+        if self.config.sorted {
+            let mut lines = vec![];
+            source.try_for_each_quad(|q| {
+                let mut line = vec![];
+                write_quad(&mut line, &q, style)?;
+                lines.push(line);
+                Ok::<_, io::Error>(())
+            })?;
+            write_sorted_lines(&mut self.write, lines).map_err(SinkError)?;
+            return Ok(self);
         }
         source
             .try_for_each_quad(|q| {
-                {
-                    let w = &mut self.write;
-                    write_term(w, q.s())?;
-                    w.write_all(b" ")?;
-                    write_term(w, q.p())?;
-                    w.write_all(b" ")?;
-                    write_term(w, q.o())?;
-                    if let Some(n) = q.g() {
-                        w.write_all(b" ")?;
-                        write_term(w, n)?;
-                    }
-                    w.write_all(b".\n")
-                }
-                .map_err(|e| io::Error::new(io::ErrorKind::Other, e))
+                write_quad(&mut self.write, &q, style)
+                    .map_err(|e| io::Error::new(io::ErrorKind::Other, e))
             })
             .map(|_| self)
     }
 }
 
+fn write_quad<W, Q>(w: &mut W, q: &Q, style: Style) -> io::Result<()>
+where
+    W: io::Write,
+    Q: Quad,
+{
+    write_term_with(w, q.s(), style)?;
+    w.write_all(b" ")?;
+    write_term_with(w, q.p(), style)?;
+    w.write_all(b" ")?;
+    write_term_with(w, q.o(), style)?;
+    if let Some(n) = q.g() {
+        w.write_all(b" ")?;
+        write_term_with(w, n, style)?;
+    }
+    w.write_all(style.terminator())
+}
+
 impl NqSerializer<Vec<u8>> {
     /// Create a new serializer which targets a `String`.
     #[inline]
@@ -122,6 +173,57 @@ pub(crate) mod test {
     use sophia_term::literal::convert::AsLiteral;
     use sophia_term::*;
 
+    #[test]
+    fn ascii() {
+        let d = vec![(
+            [
+                StaticTerm::new_bnode("x").unwrap(),
+                StaticTerm::new_iri("http://example.org/p").unwrap(),
+                StaticTerm::new_literal_lang("\u{e9}t\u{e9}", "fr").unwrap(),
+            ],
+            Some(StaticTerm::new_iri("http://example.org/\u{e9}t\u{e9}").unwrap()),
+        )];
+        let mut config = NqConfig::default();
+        config.set_ascii(true);
+        let s = NqSerializer::new_stringifier_with_config(config)
+            .serialize_dataset(&d)
+            .unwrap()
+            .to_string();
+        assert_eq!(
+            &s,
+            r#"_:x <http://example.org/p> "\u00E9t\u00E9"@fr <http://example.org/\u00E9t\u00E9>.
+"#
+        );
+    }
+
+    #[test]
+    fn canonical_sorted() {
+        let s = StaticTerm::new_iri("http://example.org/s").unwrap();
+        let p = StaticTerm::new_iri("http://example.org/p").unwrap();
+        let g = StaticTerm::new_iri("http://example.org/g").unwrap();
+        let d = vec![
+            (
+                [s, p, StaticTerm::new_literal_lang("Hi", "EN").unwrap()],
+                Some(g),
+            ),
+            ([s, p, s], None),
+            ([s, p, s], Some(g)),
+        ];
+        let mut config = NqConfig::default();
+        config.set_canonical(true).set_sorted(true);
+        let out = NqSerializer::new_stringifier_with_config(config)
+            .serialize_dataset(&d)
+            .unwrap()
+            .to_string();
+        assert_eq!(
+            out,
+            r#"<http://example.org/s> <http://example.org/p> "Hi"@en <http://example.org/g> .
+<http://example.org/s> <http://example.org/p> <http://example.org/s> .
+<http://example.org/s> <http://example.org/p> <http://example.org/s> <http://example.org/g> .
+"#
+        );
+    }
+
     #[test]
     fn dataset() {
         let me = StaticTerm::new_iri("http://champin.net/#pa").unwrap();
@@ -12,7 +12,7 @@
 use sophia_api::ns::xsd;
 use sophia_api::serializer::*;
 use sophia_api::term::{TTerm, TermKind};
-use sophia_api::triple::stream::{StreamResult, TripleSource};
+use sophia_api::triple::stream::{SinkError, StreamResult, TripleSource};
 use sophia_api::triple::Triple;
 use std::io;
 
@@ -20,14 +20,90 @@ use std::io;
 #[derive(Clone, Debug, Default)]
 pub struct NtConfig {
     ascii: bool,
+    canonical: bool,
+    sorted: bool,
 }
 
 impl NtConfig {
+    /// Whether the output is restricted to ASCII characters (defaults to `false`).
+    ///
+    /// See [`write_ascii_term`] for how non-ASCII characters are handled.
+    pub fn ascii(&self) -> bool {
+        self.ascii
+    }
+
     /// Set the ascii configuration.
     pub fn set_ascii(&mut self, ascii: bool) -> &mut Self {
         self.ascii = ascii;
         self
     }
+
+    /// Whether the output is in [canonical N-Triples] (defaults to `false`).
+    ///
+    /// In canonical mode, each triple is terminated by `" .\n"`,
+    /// literals only escape `"`, `\`, line feeds and carriage returns,
+    /// the `xsd:string` datatype is omitted,
+    /// and language tags are written in lowercase
+    /// (as they are case-insensitive, this ensures that equal terms are written identically).
+    /// This overrides the [`ascii`](NtConfig::ascii) configuration,
+    /// as canonical N-Triples forbids escape sequences for non-ASCII characters.
+    ///
+    /// Combined with [`sorted`](NtConfig::sorted),
+    /// this produces the same output for graphs containing the same triples
+    /// (blank node labels included).
+    ///
+    /// [canonical N-Triples]: https://www.w3.org/TR/n-triples/#canonical-ntriples
+    pub fn canonical(&self) -> bool {
+        self.canonical
+    }
+
+    /// Set the canonical configuration.
+    pub fn set_canonical(&mut self, canonical: bool) -> &mut Self {
+        self.canonical = canonical;
+        self
+    }
+
+    /// Whether the triples are sorted (in the byte order of their serialization)
+    /// and deduplicated (defaults to `false`).
+    ///
+    /// NB: this requires storing the whole output in memory.
+    pub fn sorted(&self) -> bool {
+        self.sorted
+    }
+
+    /// Set the sorted configuration.
+    pub fn set_sorted(&mut self, sorted: bool) -> &mut Self {
+        self.sorted = sorted;
+        self
+    }
+}
+
+/// How terms and statements are written, according to the configuration.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub(crate) enum Style {
+    Default,
+    Ascii,
+    Canonical,
+}
+
+impl Style {
+    pub(crate) fn new(ascii: bool, canonical: bool) -> Self {
+        if canonical {
+            Style::Canonical
+        } else if ascii {
+            Style::Ascii
+        } else {
+            Style::Default
+        }
+    }
+
+    /// The bytes terminating each statement.
+    pub(crate) fn terminator(self) -> &'static [u8] {
+        match self {
+            Style::Canonical => b" .\n",
+            _ => b".\n",
+        }
+    }
 }
 
 /// N-Triples serializer.
@@ -70,21 +146,22 @@ where
     where
         TS: TripleSource,
     {
-        if self.config.ascii {
-            todo!("Pure-ASCII N-Triples is not implemented yet")
+        let style = Style::new(self.config.ascii, self.config.canonical);
+        if self.config.sorted {
+            let mut lines = vec![];
+            source.try_for_each_triple(|t| {
+                let mut line = vec![];
+                write_triple(&mut line, &t, style)?;
+                lines.push(line);
+                Ok::<_, io::Error>(())
+            })?;
+            write_sorted_lines(&mut self.write, lines).map_err(SinkError)?;
+            return Ok(self);
         }
         source
             .try_for_each_triple(|t| {
-                {
-                    let w = &mut self.write;
-                    write_term(w, t.s())?;
-                    w.write_all(b" ")?;
-                    write_term(w, t.p())?;
-                    w.write_all(b" ")?;
-                    write_term(w, t.o())?;
-                    w.write_all(b".\n")
-                }
-                .map_err(|e| io::Error::new(io::ErrorKind::Other, e))
+                write_triple(&mut self.write, &t, style)
+                    .map_err(|e| io::Error::new(io::ErrorKind::Other, e))
             })
             .map(|_| self)
     }
@@ -109,36 +186,102 @@ impl Stringifier for NtSerializer<Vec<u8>> {
     }
 }
 
+fn write_triple<W, T>(w: &mut W, t: &T, style: Style) -> io::Result<()>
+where
+    W: io::Write,
+    T: Triple,
+{
+    write_term_with(w, t.s(), style)?;
+    w.write_all(b" ")?;
+    write_term_with(w, t.p(), style)?;
+    w.write_all(b" ")?;
+    write_term_with(w, t.o(), style)?;
+    w.write_all(style.terminator())
+}
+
+/// Sort and deduplicate `lines`, and write them to `w`.
+pub(crate) fn write_sorted_lines<W: io::Write>(
+    w: &mut W,
+    mut lines: Vec<Vec<u8>>,
+) -> io::Result<()> {
+    lines.sort_unstable();
+    lines.dedup();
+    for line in lines {
+        w.write_all(&line)?;
+    }
+    Ok(())
+}
+
 /// Write the given term into the given write in the N-Triples format.
 pub fn write_term<W, T>(w: &mut W, t: &T) -> io::Result<()>
+where
+    W: io::Write,
+    T: TTerm + ?Sized,
+{
+    write_term_with(w, t, Style::Default)
+}
+
+/// Write the given term into the given write in the N-Triples format,
+/// using only ASCII characters.
+///
+/// Non-ASCII characters in IRIs and literals are written as `\u` or `\U` escape sequences.
+/// As N-Triples allows no escape sequence in blank node labels,
+/// each non-ASCII character of a blank node label is replaced by `u` followed by its code point
+/// (4 or 8 hexadecimal digits, as in `\u` and `\U` escape sequences).
+/// So that distinct labels are never written the same way,
+/// the characters `u` and `U` of a label are replaced the same way
+/// (e.g. `_:café` is written `_:cafu00E9`, and `_:cafu00E9` is written `_:cafu007500E9`).
+pub fn write_ascii_term<W, T>(w: &mut W, t: &T) -> io::Result<()>
+where
+    W: io::Write,
+    T: TTerm + ?Sized,
+{
+    write_term_with(w, t, Style::Ascii)
+}
+
+pub(crate) fn write_term_with<W, T>(w: &mut W, t: &T, style: Style) -> io::Result<()>
 where
     W: io::Write,
     T: TTerm + ?Sized,
 {
     use TermKind::*;
+    let ascii = style == Style::Ascii;
     match t.kind() {
         Iri => {
             w.write_all(b"<")?;
             let v = t.value_raw();
-            w.write_all(v.0.as_bytes())?;
-            if let Some(suffix) = v.1 {
-                w.write_all(suffix.as_bytes())?;
+            if ascii {
+                escape_non_ascii(w, v.0)?;
+                escape_non_ascii(w, v.1.unwrap_or(""))?;
+            } else {
+                w.write_all(v.0.as_bytes())?;
+                if let Some(suffix) = v.1 {
+                    w.write_all(suffix.as_bytes())?;
+                }
             }
             w.write_all(b">")
         }
         Literal => {
             w.write_all(b"\"")?;
-            quoted_string(w, t.value_raw().0.as_bytes())?;
+            if ascii {
+                ascii_quoted_string(w, t.value_raw().0)?;
+            } else {
+                quoted_string(w, t.value_raw().0.as_bytes())?;
+            }
             match t.language() {
                 Some(tag) => {
                     w.write_all(b"\"@")?;
-                    w.write_all(tag.as_bytes())
+                    if style == Style::Canonical {
+                        w.write_all(tag.to_ascii_lowercase().as_bytes())
+                    } else {
+                        w.write_all(tag.as_bytes())
+                    }
                 }
                 None => {
                     let dt = t.datatype().unwrap();
                     if xsd::string != dt {
                         w.write_all(b"\"^^")?;
-                        write_term(w, &dt)
+                        write_term_with(w, &dt, style)
                     } else {
                         w.write_all(b"\"")
                     }
@@ -147,13 +290,73 @@ where
         }
         BlankNode => {
             w.write_all(b"_:")?;
-            w.write_all(t.value_raw().0.as_bytes())
+            write_label(w, t.value_raw().0, ascii)
         }
         Variable => {
             w.write_all(b"?")?;
-            w.write_all(t.value_raw().0.as_bytes())
+            write_label(w, t.value_raw().0, ascii)
+        }
+    }
+}
+
+fn write_label<W: io::Write>(w: &mut W, label: &str, ascii: bool) -> io::Result<()> {
+    if !ascii {
+        return w.write_all(label.as_bytes());
+    }
+    // 'u' and 'U' are replaced as well, for the mapping to be injective
+    let mut start = 0;
+    for (pos, chr) in label.char_indices() {
+        if !chr.is_ascii() || chr == 'u' || chr == 'U' {
+            w.write_all(&label.as_bytes()[start..pos])?;
+            write_uchar(w, chr, "")?;
+            start = pos + chr.len_utf8();
+        }
+    }
+    w.write_all(&label.as_bytes()[start..])
+}
+
+/// Write `txt`, replacing non-ASCII characters by `\u` and 4 hex digits, or `\U` and 8 hex digits.
+fn escape_non_ascii<W: io::Write>(w: &mut W, txt: &str) -> io::Result<()> {
+    let mut start = 0;
+    for (pos, chr) in txt.char_indices() {
+        if !chr.is_ascii() {
+            w.write_all(&txt.as_bytes()[start..pos])?;
+            write_uchar(w, chr, "\\")?;
+            start = pos + chr.len_utf8();
         }
     }
+    w.write_all(&txt.as_bytes()[start..])
+}
+
+fn write_uchar<W: io::Write>(w: &mut W, chr: char, escape: &str) -> io::Result<()> {
+    let code = chr as u32;
+    if code <= 0xFFFF {
+        write!(w, "{}u{:04X}", escape, code)
+    } else {
+        write!(w, "{}U{:08X}", escape, code)
+    }
+}
+
+fn ascii_quoted_string<W: io::Write>(w: &mut W, txt: &str) -> io::Result<()> {
+    let mut start = 0;
+    for (pos, chr) in txt.char_indices() {
+        let escaped = match chr {
+            '\n' => "\\n",
+            '\r' => "\\r",
+            '"' => "\\\"",
+            '\\' => "\\\\",
+            chr if chr.is_ascii() => continue,
+            _ => "",
+        };
+        w.write_all(&txt.as_bytes()[start..pos])?;
+        if escaped.is_empty() {
+            write_uchar(w, chr, "\\")?;
+        } else {
+            w.write_all(escaped.as_bytes())?;
+        }
+        start = pos + chr.len_utf8();
+    }
+    w.write_all(&txt.as_bytes()[start..])
 }
 
 pub(crate) fn quoted_string<W: io::Write>(w: &mut W, txt: &[u8]) -> io::Result<()> {
@@ -200,9 +403,85 @@ pub(crate) fn quoted_string<W: io::Write>(w: &mut W, txt: &[u8]) -> io::Result<(
 pub(crate) mod test {
     use super::*;
     use sophia_api::ns::*;
+    use sophia_api::triple::stream::TripleSource;
     use sophia_term::literal::convert::AsLiteral;
     use sophia_term::*;
 
+    #[test]
+    fn ascii() {
+        let g = vec![
+            [
+                StaticTerm::new_iri("http://example.org/caf\u{e9}").unwrap(),
+                StaticTerm::new_iri("http://example.org/p").unwrap(),
+                StaticTerm::new_literal_lang("\u{e9}t\u{e9} \u{1F31E}\n\"\\", "fr").unwrap(),
+            ],
+            [
+                StaticTerm::new_bnode("caf\u{e9}").unwrap(),
+                StaticTerm::new_iri("http://example.org/p").unwrap(),
+                StaticTerm::new_literal_dt(
+                    "\u{3c0}",
+                    StaticTerm::new_iri("http://example.org/\u{3c0}").unwrap(),
+                )
+                .unwrap(),
+            ],
+        ];
+        let mut config = NtConfig::default();
+        config.set_ascii(true);
+        let s = NtSerializer::new_stringifier_with_config(config)
+            .serialize_graph(&g)
+            .unwrap()
+            .to_string();
+        assert_eq!(
+            &s,
+            r#"<http://example.org/caf\u00E9> <http://example.org/p> "\u00E9t\u00E9 \U0001F31E\n\"\\"@fr.
+_:cafu00E9 <http://example.org/p> "\u03C0"^^<http://example.org/\u03C0>.
+"#
+        );
+        assert!(s.is_ascii());
+        let g2: Vec<[BoxTerm; 3]> = crate::parser::nt::parse_str(&s).collect_triples().unwrap();
+        assert_eq!(g2[0], g[0]);
+        assert_eq!(g2[1][2], g[1][2]);
+
+        // distinct labels are written differently
+        let written = |label: &str| {
+            let mut w = vec![];
+            write_ascii_term(&mut w, &BoxTerm::new_bnode(label).unwrap()).unwrap();
+            String::from_utf8(w).unwrap()
+        };
+        assert_eq!(written("café"), "_:cafu00E9");
+        assert_eq!(written("cafu00E9"), "_:cafu007500E9");
+        assert_eq!(written("U"), "_:u0055");
+    }
+
+    #[test]
+    fn canonical_sorted() {
+        let s = StaticTerm::new_iri("http://example.org/s").unwrap();
+        let p = StaticTerm::new_iri("http://example.org/p").unwrap();
+        let g = vec![
+            [
+                s,
+                p,
+                StaticTerm::new_literal_lang("Hello", "en-US").unwrap(),
+            ],
+            [s, p, StaticTerm::new_bnode("b").unwrap()],
+            [s, p, "caf\u{e9} \"x\"\n".as_literal().into()],
+            [s, p, StaticTerm::new_bnode("b").unwrap()],
+        ];
+        let mut config = NtConfig::default();
+        config.set_canonical(true).set_sorted(true).set_ascii(true);
+        let expected = r#"<http://example.org/s> <http://example.org/p> "Hello"@en-us .
+<http://example.org/s> <http://example.org/p> "café \"x\"\n" .
+<http://example.org/s> <http://example.org/p> _:b .
+"#;
+        for g in [g.clone(), g.into_iter().rev().collect()] {
+            let out = NtSerializer::new_stringifier_with_config(config.clone())
+                .serialize_graph(&g)
+                .unwrap()
+                .to_string();
+            assert_eq!(out, expected);
+        }
+    }
+
     #[test]
     fn graph() {
         let me = StaticTerm::new_iri("http://champin.net/#pa").unwrap();
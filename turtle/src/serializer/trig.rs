@@ -9,16 +9,14 @@
 //! [`Write`]: https://doc.rust-lang.org/std/io/trait.Write.html
 //! [`BufWriter`]: https://doc.rust-lang.org/std/io/struct.BufWriter.html
 
-use super::turtle::{prettify, write_prefixes, write_term};
-use rio_turtle::TriGFormatter;
+use super::turtle::{prettify, write_base, write_prefixes, write_term, Streamer, TurtleConfig};
 use sophia_api::dataset::{Dataset, MutableDataset};
-use sophia_api::quad::stream::{QuadSource, SinkError, SourceError, StreamResult};
+use sophia_api::quad::stream::{QuadSource, SinkError, StreamResult};
 use sophia_api::quad::Quad;
 use sophia_api::serializer::*;
-use sophia_api::term::{TTerm, TermKind::BlankNode};
+use sophia_api::term::{TTerm, TermKind::BlankNode, TermKind::Iri};
 use sophia_indexed::dataset::IndexedDataset;
 use sophia_inmem::dataset::FastDataset;
-use sophia_rio::serializer::rio_format_quads;
 use sophia_term::RcTerm;
 use std::collections::{HashMap, HashSet};
 use std::io;
@@ -47,42 +45,44 @@ where
             let mut graph_names = HashSet::new();
             let mut bnode_graph = HashMap::<RcTerm, Option<RcTerm>>::new();
             let mut anon_blacklist = HashSet::new();
-            source
-                .for_each_quad(|q| {
-                    dataset.insert(q.s(), q.p(), q.o(), q.g()).unwrap();
-                    // build graph_names and anon_blacklist
-                    let gn = q.g().map(|t| get_rcterm(t, &dataset));
-                    for t in [q.s(), q.p(), q.o()] {
-                        if t.kind() != BlankNode {
-                            continue;
-                        }
-                        let t = get_rcterm(t, &dataset);
-                        if anon_blacklist.contains(&t) {
-                            continue;
-                        }
-                        match bnode_graph.get(&t) {
-                            None => {
-                                if graph_names.contains(&t) {
-                                    anon_blacklist.insert(t);
-                                } else {
-                                    bnode_graph.insert(t, gn.clone());
-                                }
-                            }
-                            Some(gn2) if &gn != gn2 => {
+            source.try_for_each_quad(|q| {
+                dataset
+                    .insert(q.s(), q.p(), q.o(), q.g())
+                    .map_err(io::Error::other)?;
+                // build graph_names and anon_blacklist
+                let gn = q.g().map(|t| get_rcterm(t, &dataset));
+                for t in [q.s(), q.p(), q.o()] {
+                    if t.kind() != BlankNode {
+                        continue;
+                    }
+                    let t = get_rcterm(t, &dataset);
+                    if anon_blacklist.contains(&t) {
+                        continue;
+                    }
+                    match bnode_graph.get(&t) {
+                        None => {
+                            if graph_names.contains(&t) {
                                 anon_blacklist.insert(t);
+                            } else {
+                                bnode_graph.insert(t, gn.clone());
                             }
-                            _ => (),
                         }
-                    }
-                    if let Some(term) = gn {
-                        if term.kind() == BlankNode && bnode_graph.get(&term).is_some() {
-                            anon_blacklist.insert(term.clone());
+                        Some(gn2) if &gn != gn2 => {
+                            anon_blacklist.insert(t);
                         }
-                        graph_names.insert(term);
+                        _ => (),
                     }
-                })
-                .map_err(SourceError)?;
+                }
+                if let Some(term) = gn {
+                    if term.kind() == BlankNode && bnode_graph.get(&term).is_some() {
+                        anon_blacklist.insert(term.clone());
+                    }
+                    graph_names.insert(term);
+                }
+                Ok(())
+            })?;
 
+            write_base(&mut self.write, self.config.base()).map_err(SinkError)?;
             write_prefixes(&mut self.write, &self.config.prefix_map[..]).map_err(SinkError)?;
             prettify(
                 dataset.graph(None),
@@ -92,6 +92,8 @@ where
                 "",
             )
             .map_err(SinkError)?;
+            let mut graph_names: Vec<_> = graph_names.into_iter().collect();
+            self.config.subject_order().sort(&mut graph_names);
             for gn in &graph_names {
                 let allow_anon = !anon_blacklist.contains(gn);
                 self.write.write_all(b"GRAPH ").map_err(SinkError)?;
@@ -109,14 +111,82 @@ where
             }
             self.write.flush().map_err(SinkError)?;
         } else {
-            let mut tf = TriGFormatter::new(&mut self.write);
-            rio_format_quads(&mut tf, source)?;
-            tf.finish().map_err(SinkError)?;
+            write_base(&mut self.write, self.config.base()).map_err(SinkError)?;
+            write_prefixes(&mut self.write, &self.config.prefix_map[..]).map_err(SinkError)?;
+            let mut streamer = QuadStreamer::new(&self.config);
+            let write = &mut self.write;
+            source.try_for_each_quad(|q| streamer.write_quad(&mut *write, &q))?;
+            streamer.finish(&mut self.write).map_err(SinkError)?;
+            self.write.flush().map_err(SinkError)?;
         }
         Ok(self)
     }
 }
 
+/// Write quads as they come, in constant memory,
+/// grouping consecutive quads of the same graph in a `GRAPH` block.
+///
+/// Base IRI and prefixes are not written, this is the responsibility of the caller.
+pub(super) struct QuadStreamer<'a> {
+    config: &'a TurtleConfig,
+    streamer: Streamer<'a>,
+    /// the serialization of the current graph name (None for the default graph)
+    graph_name: Option<Vec<u8>>,
+    buffer: Vec<u8>,
+}
+
+impl<'a> QuadStreamer<'a> {
+    pub fn new(config: &'a TurtleConfig) -> Self {
+        QuadStreamer {
+            config,
+            streamer: Streamer::new(config, ""),
+            graph_name: None,
+            buffer: vec![],
+        }
+    }
+
+    /// Write quad `q` on `write`.
+    ///
+    /// NB: non-standard (generalized) RDF quads are silently ignored.
+    pub fn write_quad<W: io::Write, Q: Quad>(&mut self, mut write: W, q: &Q) -> io::Result<()> {
+        let config = self.config;
+        let gn = match q.g() {
+            Some(g) if matches!(g.kind(), Iri | BlankNode) => {
+                self.buffer.clear();
+                write_term(&mut self.buffer, g, config, false)?;
+                Some(&self.buffer[..])
+            }
+            Some(_) => return Ok(()), // non standard graph name, skip this quad
+            None => None,
+        };
+        if self.graph_name.as_deref() != gn {
+            self.streamer.finish(&mut write)?;
+            if self.graph_name.is_some() {
+                write.write_all(b"}\n")?;
+            }
+            if let Some(gn) = gn {
+                write.write_all(b"GRAPH ")?;
+                write.write_all(gn)?;
+                write.write_all(b" {\n")?;
+                self.streamer = Streamer::new(config, config.indentation());
+            } else {
+                self.streamer = Streamer::new(config, "");
+            }
+            self.graph_name = gn.map(<[u8]>::to_vec);
+        }
+        self.streamer.write_triple(&mut write, q.s(), q.p(), q.o())
+    }
+
+    /// Terminate the last quad written, and the last `GRAPH` block, if any.
+    pub fn finish<W: io::Write>(&mut self, mut write: W) -> io::Result<()> {
+        self.streamer.finish(&mut write)?;
+        if self.graph_name.take().is_some() {
+            write.write_all(b"}\n")?;
+        }
+        Ok(())
+    }
+}
+
 /// For a term that we know belongs to dataset,
 /// return a clone of the corresponding RcTerm used in dataset.
 fn get_rcterm<T: TTerm + ?Sized>(term: &T, dataset: &FastDataset) -> RcTerm {
@@ -204,4 +274,35 @@ pub(crate) mod test {
         }
         Ok(())
     }
+
+    #[test]
+    fn streaming() -> Result<(), Box<dyn Error>> {
+        let nq = r#"
+            <tag:a> <tag:p> "default".
+            <tag:a> <tag:p> "x" <tag:g1>.
+            <tag:a> <tag:q> "y" <tag:g1>.
+            <tag:b> <tag:p> "z" <tag:g1>.
+            _:c <tag:p> "w" _:g2.
+            <tag:a> <tag:p> "default again".
+        "#;
+        let config = TrigConfig::new().with_own_prefix_map(vec![]);
+        let out = TrigSerializer::new_stringifier_with_config(config)
+            .serialize_quads(crate::parser::nq::parse_str(nq))?
+            .to_string();
+        assert_eq!(
+            out,
+            r#"<tag:a> <tag:p> "default".
+GRAPH <tag:g1> {
+  <tag:a> <tag:p> "x";
+    <tag:q> "y".
+  <tag:b> <tag:p> "z".
+}
+GRAPH _:g2 {
+  _:c <tag:p> "w".
+}
+<tag:a> <tag:p> "default again".
+"#
+        );
+        Ok(())
+    }
 }
@@ -10,29 +10,36 @@
 //! [`BufWriter`]: https://doc.rust-lang.org/std/io/struct.BufWriter.html
 
 use regex::Regex;
-use rio_turtle::TurtleFormatter;
 use sophia_api::dataset::{Dataset, MutableDataset};
 use sophia_api::ns::{rdf, xsd};
 use sophia_api::prefix::{PrefixBox, PrefixMap};
 use sophia_api::serializer::*;
-use sophia_api::term::{TTerm, TermKind::*};
-use sophia_api::triple::stream::{SinkError, SourceError, StreamResult, TripleSource};
+use sophia_api::term::{term_cmp, SimpleIri, TTerm, TermKind::*};
+use sophia_api::triple::stream::{SinkError, StreamResult, TripleSource};
 use sophia_api::triple::Triple;
 use sophia_inmem::dataset::FastDataset;
+use sophia_iri::resolve::{IriParsed, Relativize};
 use sophia_iri::IriBox;
-use sophia_rio::serializer::rio_format_triples;
 use sophia_term::RcTerm;
+use std::cmp::Ordering;
 use std::io;
 
 mod _pretty;
 pub(super) use _pretty::prettify;
+mod _streaming;
+pub(super) use _streaming::Streamer;
 
 /// Turtle serializer configuration.
 #[derive(Clone, Debug)]
 pub struct TurtleConfig {
     pub(super) pretty: bool,
     pub(super) prefix_map: Vec<(PrefixBox, IriBox)>,
+    pub(super) base: Option<IriBox>,
     pub(super) indentation: String,
+    pub(super) line_width: Option<usize>,
+    pub(super) subject_order: SubjectOrder,
+    pub(super) predicate_order: Vec<IriBox>,
+    pub(super) sort_objects: bool,
 }
 
 impl TurtleConfig {
@@ -45,26 +52,76 @@ impl TurtleConfig {
     /// If true, extra effort will be made to group related triples together,
     /// and to use the collection syntax whenever possible.
     /// This requires storing the whole graph in memory.
+    ///
+    /// Otherwise, triples are written as they come, in constant memory;
+    /// only consecutive triples sharing the same subject (and predicate) are grouped together.
     pub fn pretty(&self) -> bool {
         self.pretty
     }
 
     /// [`PrefixMap`] to use in serialization.
     /// (defaults to a map containing rdf:, rdfs: and xsd:)
-    ///
-    /// NB: currently, only used if [`pretty`][`TurtleConfig::pretty`] is `true`.
     pub fn prefix_map(&self) -> &[(PrefixBox, IriBox)] {
         &self.prefix_map
     }
 
+    /// Base IRI to use in serialization (defaults to `None`).
+    ///
+    /// If set, it is declared with `BASE`,
+    /// and IRIs are written relative to it whenever possible
+    /// (unless they can be written as prefixed names).
+    pub fn base(&self) -> Option<&IriBox> {
+        self.base.as_ref()
+    }
+
     /// Indentation to use in serialization.
     /// (defaults to `"  "`, can only contain ASCII whitespaces)
-    ///
-    /// NB: currently, only used if [`pretty`][`TurtleConfig::pretty`] is `true`.
     pub fn indentation(&self) -> &str {
         &self.indentation
     }
 
+    /// Maximum width of lines (in characters) when writing object lists.
+    /// (defaults to `None`)
+    ///
+    /// If `None`, each object is written on its own line.
+    /// Otherwise, consecutive objects are written on the same line,
+    /// as long as it does not exceed this width.
+    ///
+    /// NB: currently, only used if [`pretty`][`TurtleConfig::pretty`] is `true`.
+    pub fn line_width(&self) -> Option<usize> {
+        self.line_width
+    }
+
+    /// The order in which subjects (and graph names, in TriG) are written.
+    /// (defaults to [`SubjectOrder::Unordered`])
+    ///
+    /// NB: currently, only used if [`pretty`][`TurtleConfig::pretty`] is `true`.
+    pub fn subject_order(&self) -> SubjectOrder {
+        self.subject_order
+    }
+
+    /// Predicates to write first, in that order, for each subject.
+    /// (defaults to `rdf:type`)
+    ///
+    /// The other predicates are written after them, sorted by IRI.
+    ///
+    /// NB: currently, only used if [`pretty`][`TurtleConfig::pretty`] is `true`.
+    pub fn predicate_order(&self) -> &[IriBox] {
+        &self.predicate_order
+    }
+
+    /// Whether the objects of each predicate are sorted (defaults to `false`).
+    ///
+    /// Objects are then sorted by kind (IRIs, then literals, then blank nodes),
+    /// then by value.
+    /// Anonymous blank nodes (written as `[...]` or as lists) come last,
+    /// sorted by their serialization rather than by their (arbitrary) label.
+    ///
+    /// NB: currently, only used if [`pretty`][`TurtleConfig::pretty`] is `true`.
+    pub fn sort_objects(&self) -> bool {
+        self.sort_objects
+    }
+
     /// Build a new default [`TurtleConfig`].
     pub fn new() -> Self {
         let pretty = false;
@@ -86,7 +143,12 @@ impl TurtleConfig {
         TurtleConfig {
             pretty,
             prefix_map,
+            base: None,
             indentation,
+            line_width: None,
+            subject_order: SubjectOrder::default(),
+            predicate_order: vec![IriBox::new_unchecked(rdf::type_.value().into())],
+            sort_objects: false,
         }
     }
 
@@ -108,6 +170,12 @@ impl TurtleConfig {
         self
     }
 
+    /// Transform a [`TurtleConfig`] by setting the [`base`][`TurtleConfig::base`] IRI.
+    pub fn with_base(mut self, base: Option<IriBox>) -> Self {
+        self.base = base;
+        self
+    }
+
     /// Transform a [`TurtleConfig`] by setting the [`indentation`][`TurtleConfig::indentation`] flag.
     ///
     /// # Precondition
@@ -118,6 +186,37 @@ impl TurtleConfig {
         self.indentation = indentation;
         self
     }
+
+    /// Transform a [`TurtleConfig`] by setting the [`line_width`][`TurtleConfig::line_width`] flag.
+    pub fn with_line_width(mut self, width: Option<usize>) -> Self {
+        self.line_width = width;
+        self
+    }
+
+    /// Transform a [`TurtleConfig`] by setting the [`subject_order`][`TurtleConfig::subject_order`] flag.
+    pub fn with_subject_order(mut self, order: SubjectOrder) -> Self {
+        self.subject_order = order;
+        self
+    }
+
+    /// Transform a [`TurtleConfig`] by setting the [`predicate_order`][`TurtleConfig::predicate_order`] flag.
+    pub fn with_predicate_order(mut self, predicates: Vec<IriBox>) -> Self {
+        self.predicate_order = predicates;
+        self
+    }
+
+    /// Transform a [`TurtleConfig`] by setting the [`sort_objects`][`TurtleConfig::sort_objects`] flag.
+    pub fn with_sort_objects(mut self, b: bool) -> Self {
+        self.sort_objects = b;
+        self
+    }
+
+    /// Transform a [`TurtleConfig`] so that its output is deterministic:
+    /// subjects are sorted (with blank nodes last), and so are objects.
+    pub fn with_sorted_output(self) -> Self {
+        self.with_subject_order(SubjectOrder::BlankNodesLast)
+            .with_sort_objects(true)
+    }
 }
 
 impl Default for TurtleConfig {
@@ -126,6 +225,47 @@ impl Default for TurtleConfig {
     }
 }
 
+/// The order in which subjects (and graph names) are written
+/// by the pretty Turtle and TriG serializers.
+///
+/// In sorted orders, anonymous blank nodes (written as `[]`) come last,
+/// sorted by their serialization rather than by their (arbitrary) label.
+#[derive(Clone, Copy, Debug, Default, Eq, Hash, PartialEq)]
+pub enum SubjectOrder {
+    /// No particular order; it may change from one run to another.
+    #[default]
+    Unordered,
+    /// Sorted by IRI or blank node label, regardless of the kind of term.
+    Sorted,
+    /// IRIs first, sorted, then blank nodes, sorted by label.
+    BlankNodesLast,
+}
+
+impl SubjectOrder {
+    /// Sort `terms` according to this order.
+    pub(super) fn sort<T: TTerm>(self, terms: &mut [T]) {
+        if let Some(cmp) = self.cmp_fn() {
+            terms.sort_by(cmp);
+        }
+    }
+
+    /// The comparison function of this order (`None` if unordered).
+    pub(super) fn cmp_fn<T: TTerm>(self) -> Option<fn(&T, &T) -> Ordering> {
+        match self {
+            SubjectOrder::Unordered => None,
+            SubjectOrder::Sorted => Some(value_cmp::<T>),
+            SubjectOrder::BlankNodesLast => Some(term_cmp::<T, T>),
+        }
+    }
+}
+
+fn value_cmp<T: TTerm>(t1: &T, t2: &T) -> Ordering {
+    t1.value_raw()
+        .bytes()
+        .cmp(t2.value_raw().bytes())
+        .then_with(|| t1.kind().cmp(&t2.kind()))
+}
+
 /// Turtle serializer.
 pub struct TurtleSerializer<W> {
     pub(super) config: TurtleConfig,
@@ -168,23 +308,28 @@ where
     {
         if self.config.pretty {
             let mut dataset = FastDataset::new();
-            source
-                .for_each_triple(|t| {
-                    dataset
-                        .insert(t.s(), t.p(), t.o(), None as Option<&RcTerm>)
-                        .unwrap();
-                })
-                .map_err(SourceError)?;
+            source.try_for_each_triple(|t| {
+                dataset
+                    .insert(t.s(), t.p(), t.o(), None as Option<&RcTerm>)
+                    .map(|_| ())
+                    .map_err(io::Error::other)
+            })?;
             let graph = dataset.graph(None); // get the default graph
             let blacklist = Default::default(); // no blacklist required for Turtle
 
+            write_base(&mut self.write, self.config.base()).map_err(SinkError)?;
             write_prefixes(&mut self.write, &self.config.prefix_map[..]).map_err(SinkError)?;
             prettify(graph, &mut self.write, &self.config, &blacklist, "").map_err(SinkError)?;
             self.write.flush().map_err(SinkError)?;
         } else {
-            let mut tf = TurtleFormatter::new(&mut self.write);
-            rio_format_triples(&mut tf, source)?;
-            tf.finish().map_err(SinkError)?;
+            write_base(&mut self.write, self.config.base()).map_err(SinkError)?;
+            write_prefixes(&mut self.write, &self.config.prefix_map[..]).map_err(SinkError)?;
+            let mut streamer = Streamer::new(&self.config, "");
+            let write = &mut self.write;
+            source
+                .try_for_each_triple(|t| streamer.write_triple(&mut *write, t.s(), t.p(), t.o()))?;
+            streamer.finish(&mut self.write).map_err(SinkError)?;
+            self.write.flush().map_err(SinkError)?;
         }
         Ok(self)
     }
@@ -238,13 +383,21 @@ lazy_static::lazy_static! {
         )?
     $").unwrap();
     pub(crate) static ref INTEGER: Regex = Regex::new(r"^[+-]?[0-9]+$").unwrap();
-    pub(crate) static ref DECIMAL: Regex = Regex::new(r"^[+-]?[0-9]*.[0-9]+$").unwrap();
+    pub(crate) static ref DECIMAL: Regex = Regex::new(r"^[+-]?[0-9]*\.[0-9]+$").unwrap();
     pub(crate) static ref DOUBLE: Regex = Regex::new(r"(?x)^
-      [+-]? ( [0-9]+ ( . [0-9]* )? | . [0-9]+ ) [eE] [+-]? [0-9]+
+      [+-]? ( [0-9]+ ( \. [0-9]* )? | \. [0-9]+ ) [eE] [+-]? [0-9]+
     $").unwrap();
     pub(crate) static ref BOOLEAN: Regex = Regex::new(r"^(true|false)$").unwrap();
 }
 
+/// write the declaration of the given base IRI (if any), using SPARQL style.
+pub fn write_base<W: io::Write>(mut write: W, base: Option<&IriBox>) -> io::Result<()> {
+    match base {
+        Some(base) => writeln!(&mut write, "BASE <{}>", base.as_ref()),
+        None => Ok(()),
+    }
+}
+
 /// write the prefix declaration of the given prefix_map, using SPARQL style.
 pub fn write_prefixes<W, P>(mut write: W, prefix_map: &P) -> io::Result<()>
 where
@@ -257,18 +410,22 @@ where
     Ok(())
 }
 
-/// write the given term in Turtle syntax, using the prefix map from `config` if appropriate.
+/// write the given term in Turtle syntax,
+/// using the prefix map and base IRI from `config` if appropriate.
 ///
 /// If `allow_anon` is true and `term` is a blank node, it will be written as `[]`
 /// instead of using it's label.
-pub fn write_term<W: io::Write, T: TTerm>(
+pub fn write_term<W: io::Write, T: TTerm + ?Sized>(
     mut write: W,
     term: &T,
     config: &TurtleConfig,
     allow_anon: bool,
 ) -> io::Result<()> {
     match term.kind() {
-        Iri => write_iri(write, term, config),
+        Iri => {
+            let raw = term.value_raw();
+            write_iri(write, &SimpleIri::new_unchecked(raw.0, raw.1), config)
+        }
         BlankNode => {
             if allow_anon {
                 write.write_all(b"[]")
@@ -300,13 +457,23 @@ fn write_iri<W: io::Write, T: TTerm>(
             write!(write, "{}:{}", pre.as_ref(), suf)
         }
         None => {
-            let raw = iri.value_raw();
-            write!(write, "<{}{}>", raw.0, raw.1.unwrap_or(""))
+            let relative = config.base.as_ref().and_then(|base| {
+                IriParsed::new(base.as_ref())
+                    .ok()?
+                    .relativize(&iri.value()[..])
+            });
+            match relative {
+                Some(relative) => write!(write, "<{}>", relative),
+                None => {
+                    let raw = iri.value_raw();
+                    write!(write, "<{}{}>", raw.0, raw.1.unwrap_or(""))
+                }
+            }
         }
     }
 }
 
-fn write_literal<W: io::Write, T: TTerm>(
+fn write_literal<W: io::Write, T: TTerm + ?Sized>(
     mut write: W,
     lit: &T,
     config: &TurtleConfig,
@@ -321,9 +488,15 @@ fn write_literal<W: io::Write, T: TTerm>(
     {
         write.write_all(value.as_bytes())
     } else {
-        write.write_all(b"\"")?;
-        super::nt::quoted_string(&mut write, value.as_bytes())?;
-        write.write_all(b"\"")?;
+        if value.contains('\n') {
+            write.write_all(b"\"\"\"")?;
+            long_quoted_string(&mut write, value)?;
+            write.write_all(b"\"\"\"")?;
+        } else {
+            write.write_all(b"\"")?;
+            super::nt::quoted_string(&mut write, value.as_bytes())?;
+            write.write_all(b"\"")?;
+        }
         if let Some(tag) = lit.language() {
             write!(write, "@{}", tag)
         } else {
@@ -336,6 +509,22 @@ fn write_literal<W: io::Write, T: TTerm>(
     }
 }
 
+/// Write `txt` as the content of a long string (delimited by `"""`),
+/// keeping line feeds as is.
+fn long_quoted_string<W: io::Write>(mut write: W, txt: &str) -> io::Result<()> {
+    let mut chars = txt.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => write.write_all(b"\\\\")?,
+            '\r' => write.write_all(b"\\r")?,
+            // escape quotes that could be mistaken for the closing delimiter
+            '"' if matches!(chars.peek(), None | Some('"')) => write.write_all(b"\\\"")?,
+            c => write.write_all(c.encode_utf8(&mut [0; 4]).as_bytes())?,
+        }
+    }
+    Ok(())
+}
+
 // ---------------------------------------------------------------------------------
 //                                      tests
 // ---------------------------------------------------------------------------------
@@ -343,7 +532,8 @@ fn write_literal<W: io::Write, T: TTerm>(
 #[cfg(test)]
 pub(crate) mod test {
     use super::*;
-    use sophia_api::graph::{isomorphic_graphs, Graph};
+    use sophia_api::graph::{isomorphic_graphs, Graph, MutableGraph};
+    use sophia_api::parser::TripleParser;
     use sophia_inmem::graph::FastGraph;
     use sophia_term::*;
     use std::error::Error;
@@ -406,8 +596,26 @@ pub(crate) mod test {
         _:a :n "a"; :p [ :q [ :r _:a ]].
         _:b :n "b"; :s [ :s _:b ].
         "#,
+        r#"# literals
+        PREFIX : <http://example.org/ns/>
+        PREFIX xsd: <http://www.w3.org/2001/XMLSchema#>
+        :x :p 42, -1.5, 1e3, false, "12"^^xsd:decimal, "1x5"^^xsd:decimal, "1.0"^^xsd:float;
+           :q "a\nb", "c\r\nd", """ends with a quote\"""", """ "" and \"\"\" and \\
+           """@en.
+        "#,
     ];
 
+    #[test]
+    fn decimal() {
+        for positive in ["3.14", "+3.14", "-3.14", ".1"] {
+            assert!(DECIMAL.is_match(positive), "{}", positive);
+        }
+        for negative in ["3", "3.", "3x14", "3.14e0"] {
+            assert!(!DECIMAL.is_match(negative), "{}", negative);
+        }
+        assert!(!DOUBLE.is_match("3x14e0"));
+    }
+
     #[test]
     fn roundtrip_not_pretty() -> Result<(), Box<dyn std::error::Error>> {
         for ttl in TESTS {
@@ -448,4 +656,239 @@ pub(crate) mod test {
         }
         Ok(())
     }
+
+    #[test]
+    fn sorted_output() -> Result<(), Box<dyn Error>> {
+        let config = TurtleConfig::new().with_pretty(true).with_sorted_output();
+        for ttl in TESTS {
+            println!("==========\n{}\n----------", ttl);
+            let g1: FastGraph = crate::parser::turtle::parse_str(ttl).collect_triples()?;
+            let mut triples: Vec<_> = g1.triples().collect::<Result<_, _>>()?;
+            triples.reverse();
+            let mut g2 = FastGraph::new();
+            for t in triples {
+                g2.insert(t.s(), t.p(), t.o())?;
+            }
+            let out1 = TurtleSerializer::new_stringifier_with_config(config.clone())
+                .serialize_triples(g1.triples())?
+                .to_string();
+            let out2 = TurtleSerializer::new_stringifier_with_config(config.clone())
+                .serialize_triples(g2.triples())?
+                .to_string();
+            println!("{}", &out1);
+            assert_eq!(out1, out2);
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn sorted_output_stable_across_parses() -> Result<(), Box<dyn Error>> {
+        let ttl = r#"
+            PREFIX : <http://example.org/ns/>
+            [ :p 1 ] .
+            [ :p 2; :q [ :r 3 ] ] .
+            [ :p (4 5) ] .
+            :s :p [ :q 6 ], [ :q 7 ], [ :q 8; :r [ :q 9 ] ], (10 11), (12), :o.
+        "#;
+        let config = TurtleConfig::new().with_pretty(true).with_sorted_output();
+        let mut ttl = ttl.to_string();
+        let mut outputs = vec![];
+        for _ in 0..3 {
+            let g: FastGraph = crate::parser::turtle::parse_str(&ttl).collect_triples()?;
+            ttl = TurtleSerializer::new_stringifier_with_config(config.clone())
+                .serialize_triples(g.triples())?
+                .to_string();
+            outputs.push(ttl.clone());
+        }
+        assert_eq!(outputs[0], outputs[1]);
+        assert_eq!(outputs[1], outputs[2]);
+        assert!(!outputs[0].contains("_:"));
+        Ok(())
+    }
+
+    #[test]
+    fn predicate_order() -> Result<(), Box<dyn Error>> {
+        let ttl = r#"
+            PREFIX : <http://example.org/ns/>
+            PREFIX rdfs: <http://www.w3.org/2000/01/rdf-schema#>
+            :b :z 3, 1; :y 2; rdfs:label "b"; a :C.
+            _:x :p :a.
+            :a :p _:x.
+        "#;
+        let g: FastGraph = crate::parser::turtle::parse_str(ttl).collect_triples()?;
+        let mut prefix_map = TurtleConfig::new().prefix_map().to_vec();
+        prefix_map.push((
+            PrefixBox::new_unchecked("".into()),
+            IriBox::new_unchecked("http://example.org/ns/".into()),
+        ));
+        let config = TurtleConfig::new()
+            .with_pretty(true)
+            .with_own_prefix_map(prefix_map)
+            .with_subject_order(SubjectOrder::Sorted)
+            .with_predicate_order(vec![
+                IriBox::new_unchecked(rdf::type_.value().into()),
+                IriBox::new_unchecked("http://www.w3.org/2000/01/rdf-schema#label".into()),
+            ])
+            .with_sort_objects(true);
+        let out = TurtleSerializer::new_stringifier_with_config(config)
+            .serialize_triples(g.triples())?
+            .to_string();
+        let body = &out[out.find("\n\n").unwrap()..];
+        assert_eq!(
+            body,
+            r#"
+
+:a :p [ :p :a ].
+
+:b a :C;
+  rdfs:label "b";
+  :y 2;
+  :z 1,
+    3.
+
+"#
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn base() -> Result<(), Box<dyn Error>> {
+        let ttl = r#"
+            <http://example.org/doc/a> <http://example.org/ns#p>
+                <http://example.org/doc/b>,
+                <http://example.org/other>,
+                <http://example.com/x>.
+        "#;
+        let g1: FastGraph = crate::parser::turtle::parse_str(ttl).collect_triples()?;
+        let base = "http://example.org/doc/";
+        let config = TurtleConfig::new()
+            .with_pretty(true)
+            .with_own_prefix_map(vec![])
+            .with_base(Some(IriBox::new_unchecked(base.into())))
+            .with_sorted_output();
+        let out = TurtleSerializer::new_stringifier_with_config(config)
+            .serialize_triples(g1.triples())?
+            .to_string();
+        assert_eq!(
+            out,
+            r#"BASE <http://example.org/doc/>
+
+<a> <../ns#p> <http://example.com/x>,
+    <b>,
+    <../other>.
+
+"#
+        );
+        let parser = crate::parser::turtle::TurtleParser {
+            base: Some(base.into()),
+            ..Default::default()
+        };
+        let g2: FastGraph = parser.parse_str(&out).collect_triples()?;
+        assert!(isomorphic_graphs(&g1, &g2)?);
+        Ok(())
+    }
+
+    #[test]
+    fn literals() -> Result<(), Box<dyn Error>> {
+        let ttl = r#"
+            PREFIX xsd: <http://www.w3.org/2001/XMLSchema#>
+            <tag:s> <tag:p> 42, "12"^^xsd:decimal, "1.5"^^xsd:decimal, "1.5E0"^^xsd:double,
+                true, "2"^^xsd:double, "first line\nsecond \"line\"".
+        "#;
+        let g: FastGraph = crate::parser::turtle::parse_str(ttl).collect_triples()?;
+        let config = TurtleConfig::new()
+            .with_pretty(true)
+            .with_own_prefix_map(vec![])
+            .with_sorted_output();
+        let out = TurtleSerializer::new_stringifier_with_config(config)
+            .serialize_triples(g.triples())?
+            .to_string();
+        assert_eq!(
+            out,
+            r#"
+<tag:s> <tag:p> true,
+    1.5,
+    "12"^^<http://www.w3.org/2001/XMLSchema#decimal>,
+    1.5E0,
+    "2"^^<http://www.w3.org/2001/XMLSchema#double>,
+    42,
+    """first line
+second "line\"""".
+
+"#
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn line_width() -> Result<(), Box<dyn Error>> {
+        let ttl = r#"
+            <tag:s> <tag:p> 1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14, 15;
+                <tag:q> "a", "b\nc", "d", [ <tag:r> "e" ], "f".
+        "#;
+        let g: FastGraph = crate::parser::turtle::parse_str(ttl).collect_triples()?;
+        let config = TurtleConfig::new()
+            .with_pretty(true)
+            .with_own_prefix_map(vec![])
+            .with_line_width(Some(30))
+            .with_sorted_output();
+        let out = TurtleSerializer::new_stringifier_with_config(config)
+            .serialize_triples(g.triples())?
+            .to_string();
+        assert_eq!(
+            out,
+            r#"
+<tag:s> <tag:p> 1, 10, 11, 12,
+    13, 14, 15, 2, 3, 4, 5, 6,
+    7, 8, 9;
+  <tag:q> "a",
+    """b
+c""", "d", "f",
+    [ <tag:r> "e" ].
+
+"#
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn streaming() -> Result<(), Box<dyn Error>> {
+        let nt = r#"
+            <http://example.org/ns/a> <http://www.w3.org/1999/02/22-rdf-syntax-ns#type> <http://example.org/ns/C>.
+            <http://example.org/ns/a> <http://example.org/ns/p> "x".
+            <http://example.org/ns/a> <http://example.org/ns/p> "y"@en.
+            <http://example.org/ns/a> <http://example.org/ns/p> "y"@en.
+            <http://example.org/ns/a> <http://example.org/ns/q> _:b.
+            _:b <http://example.org/ns/p> "1"^^<http://www.w3.org/2001/XMLSchema#integer>.
+            <http://example.org/ns/a> <http://example.org/ns/p> <http://example.org/other>.
+        "#;
+        let mut prefix_map = TurtleConfig::new().prefix_map().to_vec();
+        prefix_map.push((
+            PrefixBox::new_unchecked("".into()),
+            IriBox::new_unchecked("http://example.org/ns/".into()),
+        ));
+        let config = TurtleConfig::new()
+            .with_own_prefix_map(prefix_map)
+            .with_base(Some(IriBox::new_unchecked("http://example.org/".into())));
+        let out = TurtleSerializer::new_stringifier_with_config(config)
+            .serialize_triples(crate::parser::nt::parse_str(nt))?
+            .to_string();
+        assert_eq!(
+            out,
+            r#"BASE <http://example.org/>
+PREFIX rdf: <http://www.w3.org/1999/02/22-rdf-syntax-ns#>
+PREFIX rdfs: <http://www.w3.org/2000/01/rdf-schema#>
+PREFIX xsd: <http://www.w3.org/2001/XMLSchema#>
+PREFIX : <http://example.org/ns/>
+:a a :C;
+  :p "x",
+    "y"@en,
+    "y"@en;
+  :q _:b.
+_:b :p 1.
+:a :p <other>.
+"#
+        );
+        Ok(())
+    }
 }
@@ -4,10 +4,11 @@ use super::TurtleConfig;
 use sophia_api::dataset::adapter::DatasetGraph;
 use sophia_api::graph::Graph;
 use sophia_api::ns::rdf;
-use sophia_api::term::{CopiableTerm, TTerm, TermKind};
+use sophia_api::term::{term_cmp, TTerm, TermKind};
 use sophia_api::triple::Triple;
 use sophia_inmem::dataset::FastDataset;
 use sophia_term::RcTerm;
+use std::cmp::Ordering;
 use std::collections::{HashMap, HashSet};
 use std::io;
 
@@ -46,15 +47,21 @@ where
     let mut p = Prettifier {
         write,
         indent: base_indent.to_string(),
+        column: 0,
         config,
         anons,
+        dry_run: false,
     };
 
-    let gd = GraphData {
+    let mut gd = GraphData {
         graph,
-        roots,
+        roots: vec![],
         lists,
     };
+    if let Some(cmp) = config.subject_order().cmp_fn() {
+        p.sort_terms(&gd, &mut roots, cmp)?;
+    }
+    gd.roots = roots;
     p.write_all(&gd)
 }
 
@@ -175,8 +182,12 @@ struct GraphData<'a> {
 struct Prettifier<'a, W> {
     write: W,
     indent: String,
+    /// The width (in characters) of the current line
+    column: usize,
     config: &'a TurtleConfig,
     anons: HashSet<RcTerm>,
+    /// If true, anonymous blank nodes are not removed from `anons` when written
+    dry_run: bool,
 }
 
 impl<'a, W: io::Write> Prettifier<'a, W> {
@@ -184,10 +195,15 @@ impl<'a, W: io::Write> Prettifier<'a, W> {
         for root in &gd.roots {
             self.write_root(gd, root)?;
         }
-        // break blank node cycles that are still in anons
-        while let Some(bnode) = self.anons.iter().next().cloned() {
-            self.anons.remove(&bnode);
-            self.write_root(gd, &bnode)?;
+        // break blank node cycles that are still in anons,
+        // starting with the first one according to the subject order
+        // (writing a blank node removes from anons those nested in it)
+        let mut remaining: Vec<_> = self.anons.iter().cloned().collect();
+        self.config.subject_order().sort(&mut remaining);
+        for bnode in remaining {
+            if self.anons.remove(&bnode) {
+                self.write_root(gd, &bnode)?;
+            }
         }
         self.write_bytes(b"\n")?;
         Ok(())
@@ -206,7 +222,9 @@ impl<'a, W: io::Write> Prettifier<'a, W> {
         if node.kind() == BlankNode {
             self.write_bnode(gd, node, root)
         } else {
-            super::write_term(&mut self.write, node, self.config, false)
+            let mut buffer = vec![];
+            super::write_term(&mut buffer, node, self.config, false)?;
+            self.write_bytes(&buffer)
         }
     }
 
@@ -217,43 +235,40 @@ impl<'a, W: io::Write> Prettifier<'a, W> {
         skip_list_predicates: bool,
     ) -> io::Result<()> {
         self.indent(); // to predicate-level
-        let mut predicate = None;
-        let types: Vec<_> = gd
-            .graph
-            .triples_with_sp(node, &rdf::type_)
-            .map(Result::unwrap)
-            .map(|t| t.o().clone())
-            .collect();
-        if !types.is_empty() {
-            predicate = Some(rdf::type_.copied());
-            self.write_bytes(b"a ")?;
-            self.indent(); // to object-level
-            self.write_objects(gd, &types)?;
-        }
+        let mut properties: Vec<(RcTerm, Vec<RcTerm>)> = vec![];
         // NB: we know that PrettifiableGraph<'_> iterates triples grouped by predicate
         // (it is based on FastDataset, which uses GSPO indexes)
         for t in gd.graph.triples_with_s(node).map(Result::unwrap) {
             let p = t.p();
-            if p == &rdf::type_ || skip_list_predicates && (p == &rdf::first || p == &rdf::rest) {
+            if skip_list_predicates && (p == &rdf::first || p == &rdf::rest) {
                 continue;
             }
-            if Some(t.p()) != predicate.as_ref() {
-                if predicate.is_some() {
-                    self.write_bytes(b";")?;
-                    self.unindent(); // back to predicate-level
-                }
-                predicate = Some(t.p().clone());
+            match properties.last_mut() {
+                Some((last, objects)) if last == p => objects.push(t.o().clone()),
+                _ => properties.push((p.clone(), vec![t.o().clone()])),
+            }
+        }
+        let order = self.config.predicate_order();
+        properties.sort_by_cached_key(|(p, _)| {
+            let rank = order.iter().position(|i| p == i).unwrap_or(order.len());
+            (rank, p.value().to_string())
+        });
+        for (i, (predicate, objects)) in properties.iter_mut().enumerate() {
+            if i > 0 {
+                self.write_bytes(b";")?;
                 self.write_newline()?;
-                self.write_term(gd, predicate.as_ref().unwrap(), false)?;
-                self.write_bytes(b" ")?;
-                self.indent(); // to object-level
+            }
+            if predicate == &rdf::type_ {
+                self.write_bytes(b"a ")?;
             } else {
-                self.write_bytes(b",")?;
-                self.write_newline()?;
+                self.write_term(gd, predicate, false)?;
+                self.write_bytes(b" ")?;
             }
-            self.write_term(gd, t.o(), false)?;
-        }
-        if predicate.is_some() {
+            if self.config.sort_objects() {
+                self.sort_terms(gd, objects, term_cmp)?;
+            }
+            self.indent(); // to object-level
+            self.write_objects(gd, objects)?;
             self.unindent(); // back to predicate-level
         }
         self.unindent(); // back to original level
@@ -264,12 +279,42 @@ impl<'a, W: io::Write> Prettifier<'a, W> {
         self.write_term(gd, &objects[0], false)?;
         for obj in &objects[1..] {
             self.write_bytes(b",")?;
-            self.write_newline()?;
-            self.write_term(gd, obj, false)?;
+            match self.inline_object(gd, obj)? {
+                Some(buffer) => {
+                    self.write_bytes(b" ")?;
+                    self.write_bytes(&buffer)?;
+                }
+                None => {
+                    self.write_newline()?;
+                    self.write_term(gd, obj, false)?;
+                }
+            }
         }
         Ok(())
     }
 
+    /// If `obj` can be written on the current line without exceeding the line width,
+    /// return its serialization.
+    fn inline_object(&self, gd: &GraphData, obj: &RcTerm) -> io::Result<Option<Vec<u8>>> {
+        let width = match self.config.line_width() {
+            Some(width) => width,
+            None => return Ok(None),
+        };
+        if obj.kind() == TermKind::BlankNode
+            && (gd.lists.contains_key(obj) || self.anons.contains(obj))
+        {
+            return Ok(None);
+        }
+        let mut buffer = vec![];
+        super::write_term(&mut buffer, obj, self.config, false)?;
+        if buffer.contains(&b'\n') {
+            return Ok(None);
+        }
+        let len = buffer.iter().filter(|b| *b & 0xC0 != 0x80).count();
+        // +2 for the space before obj, and the punctuation after it
+        Ok((self.column + len + 2 <= width).then_some(buffer))
+    }
+
     fn write_bnode(&mut self, gd: &GraphData, bn: &RcTerm, root: bool) -> io::Result<()> {
         if let Some(items) = gd.lists.get(bn) {
             self.write_bytes(b"(")?;
@@ -281,7 +326,7 @@ impl<'a, W: io::Write> Prettifier<'a, W> {
             self.unindent();
             self.write_newline()?;
             self.write_bytes(b")")
-        } else if self.anons.remove(bn) {
+        } else if self.is_unwritten_anon(bn) {
             if root {
                 self.write_bytes(b"[]")
             } else {
@@ -290,16 +335,84 @@ impl<'a, W: io::Write> Prettifier<'a, W> {
                 self.write_bytes(b" ]")
             }
         } else {
-            write!(self.write, "_:{}", bn.value_raw().0)
+            self.write_bytes(b"_:")?;
+            self.write_bytes(bn.value_raw().0.as_bytes())
+        }
+    }
+
+    /// Whether `bn` is an anonymous blank node that was not written yet;
+    /// if so, mark it as written (except in dry run).
+    fn is_unwritten_anon(&mut self, bn: &RcTerm) -> bool {
+        if self.dry_run {
+            self.anons.contains(bn)
+        } else {
+            self.anons.remove(bn)
         }
     }
 
+    /// Sort `terms` with `cmp`, except for anonymous blank nodes (written as `[...]` or lists),
+    /// which come last, sorted by their serialization.
+    /// Thus, their order does not depend on their label,
+    /// which is arbitrary (and may change each time a document is parsed).
+    fn sort_terms(
+        &mut self,
+        gd: &GraphData,
+        terms: &mut Vec<RcTerm>,
+        cmp: fn(&RcTerm, &RcTerm) -> Ordering,
+    ) -> io::Result<()> {
+        let mut keyed = Vec::with_capacity(terms.len());
+        for t in terms.drain(..) {
+            let key = if t.kind() == TermKind::BlankNode
+                && (gd.lists.contains_key(&t) || self.anons.contains(&t))
+            {
+                Some(self.anon_key(gd, &t)?)
+            } else {
+                None
+            };
+            keyed.push((key, t));
+        }
+        keyed.sort_by(|(k1, t1), (k2, t2)| match (k1, k2) {
+            (None, None) => cmp(t1, t2),
+            (None, Some(_)) => Ordering::Less,
+            (Some(_), None) => Ordering::Greater,
+            (Some(k1), Some(k2)) => k1.cmp(k2),
+        });
+        terms.extend(keyed.into_iter().map(|(_, t)| t));
+        Ok(())
+    }
+
+    /// The serialization of the anonymous blank node `bn`, without marking it as written.
+    fn anon_key(&mut self, gd: &GraphData, bn: &RcTerm) -> io::Result<Vec<u8>> {
+        let mut shadow = Prettifier {
+            write: vec![],
+            indent: String::new(),
+            column: 0,
+            config: self.config,
+            anons: std::mem::take(&mut self.anons),
+            dry_run: true,
+        };
+        let res = shadow.write_bnode(gd, bn, false);
+        self.anons = shadow.anons;
+        res.map(|_| shadow.write)
+    }
+
     fn write_newline(&mut self) -> io::Result<()> {
-        self.write_bytes(b"\n")?;
-        self.write.write_all(self.indent.as_bytes())
+        self.write.write_all(b"\n")?;
+        self.write.write_all(self.indent.as_bytes())?;
+        self.column = self.indent.chars().count();
+        Ok(())
     }
 
     fn write_bytes(&mut self, bytes: &[u8]) -> io::Result<()> {
+        let line = match bytes.iter().rposition(|b| *b == b'\n') {
+            Some(pos) => {
+                self.column = 0;
+                &bytes[pos + 1..]
+            }
+            None => bytes,
+        };
+        // count characters, i.e. all bytes except UTF-8 continuation bytes
+        self.column += line.iter().filter(|b| *b & 0xC0 != 0x80).count();
         self.write.write_all(bytes)
     }
 
@@ -0,0 +1,110 @@
+//! Utility code for streaming Turtle (and TriG) serialization.
+
+use super::{write_term, TurtleConfig};
+use sophia_api::ns::rdf;
+use sophia_api::term::{TTerm, TermKind};
+use std::io;
+
+/// Write triples as they come, in constant memory,
+/// using the prefix map and base IRI of the configuration.
+///
+/// Consecutive triples sharing the same subject (and predicate) are factorized.
+pub struct Streamer<'a> {
+    config: &'a TurtleConfig,
+    indent: String,
+    /// the serialization of the current subject (empty if none)
+    subject: Vec<u8>,
+    /// the serialization of the current predicate
+    predicate: Vec<u8>,
+    buffer: Vec<u8>,
+}
+
+impl<'a> Streamer<'a> {
+    /// `base_indent` is the base indentation to be used for all triples.
+    pub fn new(config: &'a TurtleConfig, base_indent: &str) -> Self {
+        assert!(base_indent.chars().all(char::is_whitespace));
+        Streamer {
+            config,
+            indent: base_indent.to_string(),
+            subject: vec![],
+            predicate: vec![],
+            buffer: vec![],
+        }
+    }
+
+    /// Write the triple (`s`, `p`, `o`) on `write`.
+    ///
+    /// NB: non-standard (generalized) RDF triples are silently ignored.
+    pub fn write_triple<W, TS, TP, TO>(
+        &mut self,
+        mut write: W,
+        s: &TS,
+        p: &TP,
+        o: &TO,
+    ) -> io::Result<()>
+    where
+        W: io::Write,
+        TS: TTerm + ?Sized,
+        TP: TTerm + ?Sized,
+        TO: TTerm + ?Sized,
+    {
+        use TermKind::*;
+        if !matches!(s.kind(), Iri | BlankNode) || p.kind() != Iri || o.kind() == Variable {
+            return Ok(());
+        }
+        let indentation = self.config.indentation();
+        self.buffer.clear();
+        write_term(&mut self.buffer, s, self.config, false)?;
+        if self.subject == self.buffer {
+            self.buffer.clear();
+            write_predicate(&mut self.buffer, p, self.config)?;
+            if self.predicate == self.buffer {
+                write.write_all(b",\n")?;
+                write.write_all(self.indent.as_bytes())?;
+                write.write_all(indentation.as_bytes())?;
+                write.write_all(indentation.as_bytes())?;
+            } else {
+                write.write_all(b";\n")?;
+                write.write_all(self.indent.as_bytes())?;
+                write.write_all(indentation.as_bytes())?;
+                write.write_all(&self.buffer)?;
+                write.write_all(b" ")?;
+                std::mem::swap(&mut self.predicate, &mut self.buffer);
+            }
+        } else {
+            if !self.subject.is_empty() {
+                write.write_all(b".\n")?;
+            }
+            write.write_all(self.indent.as_bytes())?;
+            write.write_all(&self.buffer)?;
+            write.write_all(b" ")?;
+            std::mem::swap(&mut self.subject, &mut self.buffer);
+            self.predicate.clear();
+            write_predicate(&mut self.predicate, p, self.config)?;
+            write.write_all(&self.predicate)?;
+            write.write_all(b" ")?;
+        }
+        write_term(&mut write, o, self.config, false)
+    }
+
+    /// Terminate the last triple written, if any.
+    pub fn finish<W: io::Write>(&mut self, mut write: W) -> io::Result<()> {
+        if !self.subject.is_empty() {
+            write.write_all(b".\n")?;
+            self.subject.clear();
+        }
+        Ok(())
+    }
+}
+
+fn write_predicate<W, T>(mut write: W, p: &T, config: &TurtleConfig) -> io::Result<()>
+where
+    W: io::Write,
+    T: TTerm + ?Sized,
+{
+    if &rdf::type_ == p {
+        write.write_all(b"a")
+    } else {
+        write_term(write, p, config, false)
+    }
+}
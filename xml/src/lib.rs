@@ -2,12 +2,14 @@
 //! an [RDF] and [Linked Data] toolkit in Rust.
 //!
 //! Parser and serializer for the [RDF/XML] concrete syntax,
-//! based on [`rio_xml`].
+//! based on [`rio_xml`],
+//! and for the [TriX] concrete syntax of RDF datasets.
 //!
 //! [Sophia]: https://docs.rs/sophia/latest/sophia/
 //! [RDF]: https://www.w3.org/TR/rdf-primer/
 //! [Linked Data]: http://linkeddata.org/
 //! [RDF/XML]: https://www.w3.org/TR/rdf-syntax-grammar/
+//! [TriX]: https://www.hpl.hp.com/techreports/2004/HPL-2004-56.html
 
 pub mod parser;
 
@@ -3,29 +3,367 @@
 //!
 //! [RDF/XML]: https://www.w3.org/TR/rdf-syntax-grammar/
 
-use std::io::BufRead;
+use std::error::Error;
+use std::fmt;
+use std::io::{self, BufRead, Read};
 
 use rio_xml::{RdfXmlError, RdfXmlParser as RioRdfXmlParser};
 
-use sophia_api::parser::TripleParser;
+use sophia_api::parser::{Limit, LimitExceeded, ParserLimits, TripleParser};
+use sophia_api::triple::stream::{SinkError, SourceError, StreamResult, TripleSource};
+use sophia_api::triple::streaming_mode::StreamedTriple;
+use sophia_api::triple::Triple;
 use sophia_rio::parser::*;
 
+pub mod trix;
+
 /// N-Triples parser based on RIO.
 #[derive(Clone, Debug, Default)]
 pub struct RdfXmlParser {
     /// The base IRI used by this parser to resolve relative IRI-references.
     pub base: Option<String>,
+    /// Limits on the resources consumed by this parser (none by default).
+    ///
+    /// The input size is measured in bytes read from the underlying reader,
+    /// and the nesting depth is that of XML elements.
+    /// The literal length limit is enforced on text content as it is read;
+    /// other term lengths are checked as each triple is produced.
+    pub limits: ParserLimits,
 }
 
 impl<B: BufRead> TripleParser<B> for RdfXmlParser {
-    type Source = StrictRioSource<RioRdfXmlParser<B>, RdfXmlError>;
+    type Source = RdfXmlSource<B>;
     fn parse(&self, data: B) -> Self::Source {
         // TODO issue RdfXmlError if base can not be parsed
         let base = self.base.clone().and_then(|b| oxiri::Iri::parse(b).ok());
-        StrictRioSource::Parser(RioRdfXmlParser::new(data, base))
+        let data = LimitedRead::new(data, self.limits);
+        RdfXmlSource {
+            inner: StrictRioSource::Parser(RioRdfXmlParser::new(data, base)),
+            limits: self.limits,
+            count: 0,
+        }
+    }
+}
+
+/// The triple source returned by [`RdfXmlParser`].
+///
+/// When one of the [limits](RdfXmlParser::limits) is exceeded,
+/// it fails with an [`RdfXmlError`] wrapping an I/O error,
+/// whose inner error is the corresponding [`LimitExceeded`].
+pub struct RdfXmlSource<B: BufRead> {
+    inner: StrictRioSource<RioRdfXmlParser<LimitedRead<B>>, RdfXmlError>,
+    limits: ParserLimits,
+    count: usize,
+}
+
+impl<B: BufRead> TripleSource for RdfXmlSource<B> {
+    type Error = RdfXmlError;
+    type Triple = ScopedRioSourceTriple;
+
+    fn try_for_some_triple<F, EF>(&mut self, f: &mut F) -> StreamResult<bool, RdfXmlError, EF>
+    where
+        F: FnMut(StreamedTriple<Self::Triple>) -> Result<(), EF>,
+        EF: Error,
+    {
+        let limits = self.limits;
+        let count = &mut self.count;
+        self.inner
+            .try_for_some_triple(&mut |t| {
+                *count += 1;
+                limits
+                    .check(Limit::Triples, *count)
+                    .and_then(|_| limits.check_term(t.s()))
+                    .and_then(|_| limits.check_term(t.p()))
+                    .and_then(|_| limits.check_term(t.o()))
+                    .map_err(Interrupt::Limit)?;
+                f(t).map_err(Interrupt::Sink)
+            })
+            .map_err(|err| match err {
+                SourceError(err) => SourceError(err),
+                SinkError(Interrupt::Limit(err)) => SourceError(limit_error(err).into()),
+                SinkError(Interrupt::Sink(err)) => SinkError(err),
+            })
+    }
+}
+
+fn limit_error(err: LimitExceeded) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, err)
+}
+
+/// Used to interrupt the underlying source when a limit is exceeded.
+#[derive(Debug)]
+enum Interrupt<E> {
+    Limit(LimitExceeded),
+    Sink(E),
+}
+
+impl<E: fmt::Display> fmt::Display for Interrupt<E> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Interrupt::Limit(err) => err.fmt(f),
+            Interrupt::Sink(err) => err.fmt(f),
+        }
+    }
+}
+
+impl<E: Error> Error for Interrupt<E> {}
+
+/// A [`BufRead`] wrapper enforcing the input size and nesting depth limits,
+/// as well as the literal length limit on text content.
+///
+/// The nesting depth and the length of text content are computed
+/// by a lightweight scan of the XML syntax, ahead of the actual parser,
+/// so that huge text nodes are rejected before being buffered.
+struct LimitedRead<B> {
+    inner: B,
+    /// The number of bytes of the inner buffer that have already been scanned.
+    scanned: usize,
+    scanner: Scanner,
+}
+
+impl<B> LimitedRead<B> {
+    fn new(inner: B, limits: ParserLimits) -> Self {
+        LimitedRead {
+            inner,
+            scanned: 0,
+            scanner: Scanner {
+                limits,
+                read_bytes: 0,
+                depth: 0,
+                text_len: 0,
+                significant: false,
+                in_ref: false,
+                state: ScanState::Text,
+            },
+        }
+    }
+}
+
+impl<B: BufRead> BufRead for LimitedRead<B> {
+    fn fill_buf(&mut self) -> io::Result<&[u8]> {
+        let buf = self.inner.fill_buf()?;
+        if buf.len() > self.scanned {
+            self.scanner
+                .scan(&buf[self.scanned..])
+                .map_err(limit_error)?;
+            self.scanned = buf.len();
+        }
+        Ok(buf)
+    }
+
+    fn consume(&mut self, amt: usize) {
+        self.inner.consume(amt);
+        self.scanned = self.scanned.saturating_sub(amt);
+    }
+}
+
+impl<B: BufRead> Read for LimitedRead<B> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let available = self.fill_buf()?;
+        let amt = available.len().min(buf.len());
+        buf[..amt].copy_from_slice(&available[..amt]);
+        self.consume(amt);
+        Ok(amt)
     }
 }
 
+struct Scanner {
+    limits: ParserLimits,
+    read_bytes: usize,
+    depth: usize,
+    /// A lower bound of the length of the current text content,
+    /// ignoring entity and character references, and carriage returns.
+    text_len: usize,
+    /// Whether the current text content contains anything but whitespace
+    /// (whitespace between elements is not part of any literal).
+    significant: bool,
+    /// Whether the scanner is in an entity or character reference.
+    in_ref: bool,
+    state: ScanState,
+}
+
+#[derive(Clone, Copy)]
+enum ScanState {
+    Text,
+    /// After `<`
+    Open,
+    /// In a start tag; `slash` is true if the last byte was `/`.
+    StartTag {
+        quote: Option<u8>,
+        slash: bool,
+    },
+    EndTag,
+    /// After `<!`
+    Bang,
+    /// After `<!-`
+    BangDash,
+    Comment {
+        dashes: u8,
+    },
+    /// `head` is the number of bytes of the `[CDATA[` opening still to be read.
+    CData {
+        head: u8,
+        brackets: u8,
+    },
+    /// Processing instruction; `question` is true if the last byte was `?`.
+    Pi {
+        question: bool,
+    },
+    /// Other markup declaration (typically DOCTYPE).
+    Decl {
+        quote: Option<u8>,
+        brackets: usize,
+    },
+}
+
+impl Scanner {
+    fn scan(&mut self, bytes: &[u8]) -> Result<(), LimitExceeded> {
+        use ScanState::*;
+        self.read_bytes += bytes.len();
+        self.limits.check(Limit::InputBytes, self.read_bytes)?;
+        if self.limits.max_nesting_depth.is_none() && self.limits.max_literal_len.is_none() {
+            return Ok(());
+        }
+        for &b in bytes {
+            self.count_text(b)?;
+            self.state = match (self.state, b) {
+                (Text, b'<') => Open,
+                (Text, _) => Text,
+                (Open, b'/') => EndTag,
+                (Open, b'!') => Bang,
+                (Open, b'?') => Pi { question: false },
+                (Open, _) => StartTag {
+                    quote: None,
+                    slash: false,
+                },
+                (StartTag { quote: Some(q), .. }, _) if b == q => StartTag {
+                    quote: None,
+                    slash: false,
+                },
+                (StartTag { quote: Some(_), .. }, _) => self.state,
+                (StartTag { .. }, b'"' | b'\'') => StartTag {
+                    quote: Some(b),
+                    slash: false,
+                },
+                (StartTag { slash, .. }, b'>') => {
+                    if !slash {
+                        self.depth += 1;
+                        self.limits.check(Limit::NestingDepth, self.depth)?;
+                    }
+                    Text
+                }
+                (StartTag { .. }, _) => StartTag {
+                    quote: None,
+                    slash: b == b'/',
+                },
+                (EndTag, b'>') => {
+                    self.depth = self.depth.saturating_sub(1);
+                    Text
+                }
+                (EndTag, _) => EndTag,
+                (Bang, b'-') => BangDash,
+                (Bang, b'[') => CData {
+                    head: 6,
+                    brackets: 0,
+                },
+                (BangDash, b'-') => Comment { dashes: 0 },
+                (Bang, _) | (BangDash, _) => Decl {
+                    quote: None,
+                    brackets: 0,
+                },
+                (Comment { dashes }, b'>') if dashes >= 2 => Text,
+                (Comment { dashes }, b'-') => Comment {
+                    dashes: dashes.saturating_add(1),
+                },
+                (Comment { .. }, _) => Comment { dashes: 0 },
+                (CData { head, .. }, _) if head > 0 => CData {
+                    head: head - 1,
+                    brackets: 0,
+                },
+                (CData { brackets, .. }, b'>') if brackets >= 2 => Text,
+                (CData { brackets, .. }, b']') => CData {
+                    head: 0,
+                    brackets: brackets.saturating_add(1),
+                },
+                (CData { .. }, _) => CData {
+                    head: 0,
+                    brackets: 0,
+                },
+                (Pi { question: true }, b'>') => Text,
+                (Pi { .. }, _) => Pi {
+                    question: b == b'?',
+                },
+                (
+                    Decl {
+                        quote: Some(q),
+                        brackets,
+                    },
+                    _,
+                ) if b == q => Decl {
+                    quote: None,
+                    brackets,
+                },
+                (Decl { quote: Some(_), .. }, _) => self.state,
+                (Decl { brackets: 0, .. }, b'>') => Text,
+                (Decl { brackets, .. }, b'"' | b'\'') => Decl {
+                    quote: Some(b),
+                    brackets,
+                },
+                (Decl { brackets, .. }, b'[') => Decl {
+                    quote: None,
+                    brackets: brackets + 1,
+                },
+                (Decl { brackets, .. }, b']') => Decl {
+                    quote: None,
+                    brackets: brackets.saturating_sub(1),
+                },
+                (Decl { .. }, _) => self.state,
+            };
+        }
+        Ok(())
+    }
+
+    /// Account for byte `b` in the length of the current text content,
+    /// before `self.state` is updated.
+    fn count_text(&mut self, b: u8) -> Result<(), LimitExceeded> {
+        use ScanState::*;
+        let counted = match (self.state, b) {
+            (Text, b'<') => {
+                self.in_ref = false;
+                false
+            }
+            (Text, b'&') => {
+                self.in_ref = true;
+                self.significant = true;
+                false
+            }
+            (Text, b';') if self.in_ref => {
+                self.in_ref = false;
+                false
+            }
+            (Text, b'\r') => false,
+            (Text, _) => !self.in_ref,
+            (CData { head: 0, .. }, b']' | b'\r') => false,
+            (CData { head: 0, .. }, _) => true,
+            // comments do not interrupt text content, but tags do
+            (Open, b'!') | (Bang, b'-' | b'[') | (BangDash, b'-') => false,
+            (Open | Bang | BangDash, _) => {
+                self.text_len = 0;
+                self.significant = false;
+                false
+            }
+            _ => false,
+        };
+        if counted {
+            self.text_len += 1;
+            self.significant |= !b.is_ascii_whitespace();
+            if self.significant {
+                self.limits.check(Limit::LiteralLength, self.text_len)?;
+            }
+        }
+        Ok(())
+    }
+}
 sophia_api::def_mod_functions_for_bufread_parser!(RdfXmlParser, TripleParser);
 
 // ---------------------------------------------------------------------------------
@@ -60,6 +398,7 @@ mod test {
         let mut g = FastGraph::new();
         let p = RdfXmlParser {
             base: Some("http://localhost/ex".into()),
+            ..RdfXmlParser::default()
         };
         let c = p.parse_str(xml).add_to_graph(&mut g)?;
         assert_eq!(c, 3);
@@ -89,4 +428,142 @@ mod test {
             .is_some());
         Ok(())
     }
+
+    #[test]
+    fn test_limits() {
+        let xml = r#"<?xml version="1.0" encoding="utf-8"?>
+        <!-- a <comment/> -->
+        <rdf:RDF xmlns:rdf="http://www.w3.org/1999/02/22-rdf-syntax-ns#"
+                 xmlns="http://example.org/ns/">
+          <rdf:Description rdf:about="http://localhost/ex#me">
+            <name>Alice Liddell</name>
+            <knows rdf:resource="http://localhost/ex#you"/>
+            <knows>
+              <Person>
+                <name>Bob</name>
+              </Person>
+            </knows>
+          </rdf:Description>
+        </rdf:RDF>
+        "#;
+        let exceeded = |limits: ParserLimits| {
+            let parser = RdfXmlParser {
+                limits,
+                ..RdfXmlParser::default()
+            };
+            let err = match parser.parse_str(xml).for_each_triple(|_| ()) {
+                Ok(()) => return None,
+                Err(err) => err,
+            };
+            let mut source: Option<&(dyn Error + 'static)> = Some(&err);
+            while let Some(err) = source {
+                if let Some(err) = err.downcast_ref::<io::Error>() {
+                    let err = err.get_ref().unwrap().downcast_ref::<LimitExceeded>();
+                    return Some(err.unwrap().limit);
+                }
+                source = err.source();
+            }
+            panic!("unexpected error {:?}", err);
+        };
+        let none = ParserLimits::default();
+        assert_eq!(exceeded(none), None);
+        for (limits, limit) in [
+            (
+                ParserLimits {
+                    max_input_bytes: Some(100),
+                    ..none
+                },
+                Limit::InputBytes,
+            ),
+            (
+                ParserLimits {
+                    max_triples: Some(4),
+                    ..none
+                },
+                Limit::Triples,
+            ),
+            (
+                ParserLimits {
+                    max_literal_len: Some(10),
+                    ..none
+                },
+                Limit::LiteralLength,
+            ),
+            (
+                ParserLimits {
+                    max_iri_len: Some(40),
+                    ..none
+                },
+                Limit::IriLength,
+            ),
+            (
+                ParserLimits {
+                    max_nesting_depth: Some(4),
+                    ..none
+                },
+                Limit::NestingDepth,
+            ),
+        ] {
+            assert_eq!(exceeded(limits), Some(limit));
+        }
+        let limits = ParserLimits {
+            max_input_bytes: Some(xml.len()),
+            max_triples: Some(5),
+            max_literal_len: Some(13),
+            max_iri_len: Some(47),
+            max_nesting_depth: Some(5),
+        };
+        assert_eq!(exceeded(limits), None);
+    }
+
+    #[test]
+    fn test_long_text_rejected_while_reading() {
+        use std::cell::Cell;
+        use std::rc::Rc;
+
+        struct CountingRead<'a>(&'a [u8], Rc<Cell<usize>>);
+        impl Read for CountingRead<'_> {
+            fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+                let amt = self.0.read(buf)?;
+                self.1.set(self.1.get() + amt);
+                Ok(amt)
+            }
+        }
+
+        let xml = format!(
+            r#"<rdf:RDF xmlns:rdf="http://www.w3.org/1999/02/22-rdf-syntax-ns#">
+              <rdf:Description rdf:about="http://localhost/ex#me">
+                <name xmlns="http://example.org/ns/">a &amp; b<!-- c --><![CDATA[d]]>{}</name>
+              </rdf:Description>
+            </rdf:RDF>"#,
+            "x".repeat(1_000_000)
+        );
+        let read = Rc::new(Cell::new(0));
+        let data = io::BufReader::with_capacity(1024, CountingRead(xml.as_bytes(), read.clone()));
+        let parser = RdfXmlParser {
+            limits: ParserLimits {
+                max_literal_len: Some(100),
+                ..ParserLimits::default()
+            },
+            ..RdfXmlParser::default()
+        };
+        let err = parser.parse(data).for_each_triple(|_| ()).unwrap_err();
+        assert!(err.to_string().contains("literal length"), "{}", err);
+        assert!(read.get() < 10_000, "read {} bytes", read.get());
+
+        // text content is not over-estimated
+        let xml = r#"<rdf:RDF xmlns:rdf="http://www.w3.org/1999/02/22-rdf-syntax-ns#">
+              <rdf:Description rdf:about="http://localhost/ex#me">
+                <name xmlns="http://example.org/ns/">a &amp; b<!-- c --><![CDATA[d]]></name>
+              </rdf:Description>
+            </rdf:RDF>"#;
+        let parser = RdfXmlParser {
+            limits: ParserLimits {
+                max_literal_len: Some(6),
+                ..ParserLimits::default()
+            },
+            ..RdfXmlParser::default()
+        };
+        assert!(parser.parse_str(xml).for_each_triple(|_| ()).is_ok());
+    }
 }
@@ -0,0 +1,407 @@
+//! Parser for the [TriX] concrete syntax of RDF datasets.
+//!
+//! Named graphs are identified by an IRI (`<uri>`) or a blank node (`<id>`);
+//! `<graph>` elements without a name contain triples of the default graph.
+//! XML literals (i.e. typed literals containing markup) are not supported.
+//!
+//! [TriX]: https://www.hpl.hp.com/techreports/2004/HPL-2004-56.html
+
+use quick_xml::events::{BytesStart, Event};
+use quick_xml::Reader;
+use sophia_api::ns::xsd;
+use sophia_api::parser::QuadParser;
+use sophia_term::{BoxTerm, TermError};
+use std::io::BufRead;
+
+/// The namespace of TriX elements.
+pub const TRIX_NS: &str = "http://www.w3.org/2004/03/trix/trix-1/";
+
+/// TriX parser.
+#[derive(Clone, Debug, Default)]
+pub struct TrixParser {}
+
+impl<B: BufRead> QuadParser<B> for TrixParser {
+    type Source = TrixSource<B>;
+    fn parse(&self, data: B) -> Self::Source {
+        let mut reader = Reader::from_reader(data);
+        reader.expand_empty_elements(true);
+        TrixSource {
+            reader,
+            buffer: Vec::new(),
+            ns_buffer: Vec::new(),
+            state: State::Document,
+            graph_name: None,
+        }
+    }
+}
+
+sophia_api::def_mod_functions_for_bufread_parser!(TrixParser, QuadParser);
+
+/// The type of quads produced by [`TrixParser`].
+pub type TrixQuad = ([BoxTerm; 3], Option<BoxTerm>);
+
+/// The error raised when parsing a TriX document.
+#[derive(Debug, thiserror::Error)]
+pub enum TrixError {
+    /// The document is not well-formed XML.
+    #[error("Invalid XML: {source} at byte {position}")]
+    Xml {
+        /// The error raised by the XML parser.
+        source: quick_xml::Error,
+        /// The position of the error in the document.
+        position: usize,
+    },
+    /// The document does not comply with the structure of TriX.
+    #[error("Invalid TriX: {message} at byte {position}")]
+    Trix {
+        /// A description of the error.
+        message: String,
+        /// The position of the error in the document.
+        position: usize,
+    },
+    /// The document contains an invalid term.
+    #[error("Invalid term: {source} at byte {position}")]
+    Term {
+        /// The error raised when building the term.
+        source: TermError,
+        /// The position of the error in the document.
+        position: usize,
+    },
+}
+
+/// The quad source returned by [`TrixParser`].
+///
+/// Quads are produced as their `<triple>` element is read,
+/// so the document is never fully loaded in memory.
+pub struct TrixSource<B: BufRead> {
+    reader: Reader<B>,
+    buffer: Vec<u8>,
+    ns_buffer: Vec<u8>,
+    state: State,
+    graph_name: Option<BoxTerm>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum State {
+    /// Before the `TriX` element
+    Document,
+    /// Inside the `TriX` element
+    Trix,
+    /// Inside a `graph` element; the flag indicates whether the graph name is still allowed
+    Graph(bool),
+    /// After the end of the document, or after an error
+    Done,
+}
+
+/// A simplified XML event.
+enum Tag {
+    Start {
+        name: String,
+        lang: Option<String>,
+        datatype: Option<String>,
+    },
+    End,
+    Text(String),
+    Eof,
+}
+
+impl<B: BufRead> Iterator for TrixSource<B> {
+    type Item = Result<TrixQuad, TrixError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.state == State::Done {
+            return None;
+        }
+        match self.read_quad() {
+            Ok(Some(quad)) => Some(Ok(quad)),
+            Ok(None) => {
+                self.state = State::Done;
+                None
+            }
+            Err(err) => {
+                self.state = State::Done;
+                Some(Err(err))
+            }
+        }
+    }
+}
+
+impl<B: BufRead> TrixSource<B> {
+    fn read_quad(&mut self) -> Result<Option<TrixQuad>, TrixError> {
+        loop {
+            let tag = self.read_element()?;
+            match (self.state, tag) {
+                (State::Document, Tag::Start { name, .. }) if name == "TriX" => {
+                    self.state = State::Trix;
+                }
+                (State::Trix, Tag::Start { name, .. }) if name == "graph" => {
+                    self.graph_name = None;
+                    self.state = State::Graph(true);
+                }
+                (State::Trix, Tag::End) => {
+                    return match self.read_element()? {
+                        Tag::Eof => Ok(None),
+                        _ => Err(self.error("content after the TriX element")),
+                    };
+                }
+                (State::Graph(true), Tag::Start { name, .. }) if name == "uri" || name == "id" => {
+                    self.graph_name = Some(self.read_term(&name, None, None)?);
+                    self.state = State::Graph(false);
+                }
+                (State::Graph(_), Tag::Start { name, .. }) if name == "triple" => {
+                    self.state = State::Graph(false);
+                    let s = self.read_triple_term()?;
+                    let p = self.read_triple_term()?;
+                    let o = self.read_triple_term()?;
+                    return match self.read_element()? {
+                        Tag::End => Ok(Some(([s, p, o], self.graph_name.clone()))),
+                        _ => Err(self.error("a triple must contain exactly 3 terms")),
+                    };
+                }
+                (State::Graph(_), Tag::End) => {
+                    self.state = State::Trix;
+                }
+                (_, Tag::Start { name, .. }) => {
+                    return Err(self.error(format!("unexpected element {}", name)));
+                }
+                (_, Tag::Eof) => return Err(self.error("unexpected end of document")),
+                _ => return Err(self.error("unexpected end of element")),
+            }
+        }
+    }
+
+    fn read_triple_term(&mut self) -> Result<BoxTerm, TrixError> {
+        match self.read_element()? {
+            Tag::Start {
+                name,
+                lang,
+                datatype,
+            } => self.read_term(&name, lang, datatype),
+            _ => Err(self.error("a triple must contain exactly 3 terms")),
+        }
+    }
+
+    /// Read the content of the element `name` (which has just started) as a term.
+    fn read_term(
+        &mut self,
+        name: &str,
+        lang: Option<String>,
+        datatype: Option<String>,
+    ) -> Result<BoxTerm, TrixError> {
+        let mut txt = String::new();
+        loop {
+            match self.read_event()? {
+                Tag::Text(t) => txt.push_str(&t),
+                Tag::End => break,
+                Tag::Start { .. } => return Err(self.error("XML literals are not supported")),
+                Tag::Eof => return Err(self.error("unexpected end of document")),
+            }
+        }
+        let term = match (name, lang, datatype) {
+            ("uri", None, None) => BoxTerm::new_iri(txt),
+            ("id", None, None) => BoxTerm::new_bnode(txt),
+            ("plainLiteral", Some(lang), None) => BoxTerm::new_literal_lang(txt, lang),
+            ("plainLiteral", None, None) => BoxTerm::new_literal_dt(txt, xsd::string),
+            ("typedLiteral", None, Some(datatype)) => BoxTerm::new_iri(datatype)
+                .and_then(|datatype| BoxTerm::new_literal_dt(txt, datatype)),
+            ("typedLiteral", _, None) => {
+                return Err(self.error("typedLiteral without a datatype"));
+            }
+            _ => return Err(self.error(format!("unexpected element {}", name))),
+        };
+        term.map_err(|source| TrixError::Term {
+            source,
+            position: self.reader.buffer_position(),
+        })
+    }
+
+    /// Read the next event, skipping whitespace text between elements.
+    fn read_element(&mut self) -> Result<Tag, TrixError> {
+        loop {
+            match self.read_event()? {
+                Tag::Text(txt) if txt.trim().is_empty() => continue,
+                Tag::Text(_) => return Err(self.error("unexpected text")),
+                tag => return Ok(tag),
+            }
+        }
+    }
+
+    /// Read the next event, skipping comments, processing instructions and declarations.
+    fn read_event(&mut self) -> Result<Tag, TrixError> {
+        loop {
+            self.buffer.clear();
+            let (ns, event) = self
+                .reader
+                .read_namespaced_event(&mut self.buffer, &mut self.ns_buffer)
+                .map_err(|source| TrixError::Xml {
+                    source,
+                    position: self.reader.buffer_position(),
+                })?;
+            let tag = match event {
+                Event::Start(start) => {
+                    if ns != Some(TRIX_NS.as_bytes()) {
+                        let name = String::from_utf8_lossy(start.name()).to_string();
+                        return Err(TrixError::Trix {
+                            message: format!("element {} not in the TriX namespace", name),
+                            position: self.reader.buffer_position(),
+                        });
+                    }
+                    start_tag(&self.reader, &start)?
+                }
+                Event::End(_) => Tag::End,
+                // NB: quick-xml escapes the content of CDATA sections
+                Event::Text(txt) | Event::CData(txt) => {
+                    Tag::Text(txt.unescape_and_decode(&self.reader).map_err(|source| {
+                        TrixError::Xml {
+                            source,
+                            position: self.reader.buffer_position(),
+                        }
+                    })?)
+                }
+                Event::Eof => Tag::Eof,
+                _ => continue,
+            };
+            return Ok(tag);
+        }
+    }
+
+    fn error<T: ToString>(&self, message: T) -> TrixError {
+        TrixError::Trix {
+            message: message.to_string(),
+            position: self.reader.buffer_position(),
+        }
+    }
+}
+
+fn start_tag<B: BufRead>(reader: &Reader<B>, start: &BytesStart) -> Result<Tag, TrixError> {
+    let xml_error = |source| TrixError::Xml {
+        source,
+        position: reader.buffer_position(),
+    };
+    let mut lang = None;
+    let mut datatype = None;
+    for attr in start.attributes() {
+        let attr = attr.map_err(xml_error)?;
+        match attr.key {
+            b"xml:lang" => lang = Some(attr.unescape_and_decode_value(reader).map_err(xml_error)?),
+            b"datatype" => {
+                datatype = Some(attr.unescape_and_decode_value(reader).map_err(xml_error)?)
+            }
+            _ => (),
+        }
+    }
+    Ok(Tag::Start {
+        name: String::from_utf8_lossy(start.local_name()).to_string(),
+        lang,
+        datatype,
+    })
+}
+
+// ---------------------------------------------------------------------------------
+//                                      tests
+// ---------------------------------------------------------------------------------
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use sophia_api::dataset::isomorphic_datasets;
+    use sophia_api::quad::stream::QuadSource;
+
+    #[test]
+    fn parse() -> Result<(), Box<dyn std::error::Error>> {
+        let trix = r#"<?xml version="1.0"?>
+            <!-- a comment -->
+            <TriX xmlns="http://www.w3.org/2004/03/trix/trix-1/">
+              <graph>
+                <triple>
+                  <uri>http://example.org/a</uri>
+                  <uri>http://example.org/p</uri>
+                  <plainLiteral> spaces &amp; <![CDATA[<markup>]]> </plainLiteral>
+                </triple>
+              </graph>
+              <graph>
+                <uri>http://example.org/g</uri>
+                <triple>
+                  <id>b</id>
+                  <uri>http://example.org/p</uri>
+                  <plainLiteral xml:lang="en">hello</plainLiteral>
+                </triple>
+                <triple>
+                  <id>b</id>
+                  <uri>http://example.org/p</uri>
+                  <typedLiteral datatype="http://www.w3.org/2001/XMLSchema#integer">42</typedLiteral>
+                </triple>
+              </graph>
+              <graph>
+                <id>g2</id>
+                <triple>
+                  <uri>http://example.org/a</uri>
+                  <uri>http://example.org/p</uri>
+                  <id>b</id>
+                </triple>
+              </graph>
+            </TriX>
+        "#;
+        let got: Vec<TrixQuad> = parse_str(trix).collect_quads()?;
+        let expected: Vec<TrixQuad> = sophia_turtle::parser::trig::parse_str(
+            r#"
+            <http://example.org/a> <http://example.org/p> " spaces & <markup> ".
+            GRAPH <http://example.org/g> {
+                _:b <http://example.org/p> "hello"@en, 42.
+            }
+            GRAPH _:g2 {
+                <http://example.org/a> <http://example.org/p> _:b.
+            }
+        "#,
+        )
+        .collect_quads()?;
+        assert!(isomorphic_datasets(&got, &expected)?);
+        Ok(())
+    }
+
+    #[test]
+    fn errors() {
+        let triple = "<triple><uri>tag:s</uri><uri>tag:p</uri><uri>tag:o</uri></triple>";
+        for (txt, message) in [
+            (
+                format!("<TriX><graph>{}</graph></TriX>", triple),
+                "element TriX not in the TriX namespace",
+            ),
+            (
+                format!("<TriX xmlns='{}'><triple/></TriX>", TRIX_NS),
+                "unexpected element triple",
+            ),
+            (
+                format!(
+                    "<TriX xmlns='{}'><graph>{}<uri>tag:g</uri></graph></TriX>",
+                    TRIX_NS, triple
+                ),
+                "unexpected element uri",
+            ),
+            (
+                format!(
+                    "<TriX xmlns='{}'><graph><triple><uri>tag:s</uri><uri>tag:p</uri></triple></graph></TriX>",
+                    TRIX_NS
+                ),
+                "a triple must contain exactly 3 terms",
+            ),
+            (
+                format!(
+                    "<TriX xmlns='{}'><graph><triple><uri>tag:s</uri><uri>tag:p</uri><typedLiteral>x</typedLiteral></triple></graph></TriX>",
+                    TRIX_NS
+                ),
+                "typedLiteral without a datatype",
+            ),
+            (
+                format!("<TriX xmlns='{}'><graph>{}", TRIX_NS, triple),
+                "unexpected end of document",
+            ),
+        ] {
+            let res: Result<Vec<TrixQuad>, _> = parse_str(&txt).collect();
+            match res {
+                Err(TrixError::Trix { message: got, .. }) => assert_eq!(got, message, "{}", txt),
+                other => panic!("{} → {:?}", txt, other),
+            }
+        }
+    }
+}
@@ -11,17 +11,136 @@
 //! [`BufWriter`]: https://doc.rust-lang.org/std/io/struct.BufWriter.html
 
 use rio_xml::RdfXmlFormatter;
+use sophia_api::graph::MutableGraph;
+use sophia_api::prefix::{PrefixBox, PrefixMap};
 use sophia_api::serializer::*;
 use sophia_api::triple::stream::{SinkError, StreamResult, TripleSource};
+use sophia_api::triple::Triple;
+use sophia_inmem::graph::FastGraph;
+use sophia_iri::IriBox;
 use sophia_rio::serializer::rio_format_triples;
 use std::io;
 
+mod _pretty;
+pub mod trix;
+
 /// RDF/XML serializer configuration.
-#[derive(Clone, Debug, Default)]
-pub struct RdfXmlConfig {}
+#[derive(Clone, Debug)]
+pub struct RdfXmlConfig {
+    pretty: bool,
+    prefix_map: Vec<(PrefixBox, IriBox)>,
+    base: Option<IriBox>,
+    indentation: String,
+}
 
 impl RdfXmlConfig {
-    // TODO add ways to customize namespaces
+    /// Should the serializer make extra effort to produce pretty RDF/XML.
+    ///
+    /// If false (default), the triples will be serialized in streaming mode,
+    /// with one `rdf:Description` per triple and generated namespace prefixes.
+    ///
+    /// If true, the triples of each subject will be grouped together,
+    /// typed node elements will be used (e.g. `<foaf:Person>` instead of an `rdf:type` property),
+    /// blank nodes referenced only once will be nested in the referencing property,
+    /// and `rdf:parseType="Collection"` will be used for lists whenever possible.
+    /// This requires storing the whole graph in memory.
+    pub fn pretty(&self) -> bool {
+        self.pretty
+    }
+
+    /// [`PrefixMap`] to use in serialization.
+    /// (defaults to a map containing rdf:, rdfs: and xsd:)
+    ///
+    /// Namespaces are generated for IRIs that can not be abbreviated with this map,
+    /// as RDF/XML requires all predicates to be written as qualified names.
+    ///
+    /// NB: currently, only used if [`pretty`][`RdfXmlConfig::pretty`] is `true`.
+    pub fn prefix_map(&self) -> &[(PrefixBox, IriBox)] {
+        &self.prefix_map
+    }
+
+    /// Base IRI to use in serialization (defaults to `None`).
+    ///
+    /// If set, it is declared with `xml:base`,
+    /// and IRIs of nodes are written relative to it whenever possible.
+    ///
+    /// NB: currently, only used if [`pretty`][`RdfXmlConfig::pretty`] is `true`.
+    pub fn base(&self) -> Option<&IriBox> {
+        self.base.as_ref()
+    }
+
+    /// Indentation to use in serialization.
+    /// (defaults to `"  "`, can only contain ASCII whitespaces)
+    ///
+    /// NB: currently, only used if [`pretty`][`RdfXmlConfig::pretty`] is `true`.
+    pub fn indentation(&self) -> &str {
+        &self.indentation
+    }
+
+    /// Build a new default [`RdfXmlConfig`].
+    pub fn new() -> Self {
+        let prefix_map = vec![
+            (
+                PrefixBox::new_unchecked("rdf".into()),
+                IriBox::new_unchecked("http://www.w3.org/1999/02/22-rdf-syntax-ns#".into()),
+            ),
+            (
+                PrefixBox::new_unchecked("rdfs".into()),
+                IriBox::new_unchecked("http://www.w3.org/2000/01/rdf-schema#".into()),
+            ),
+            (
+                PrefixBox::new_unchecked("xsd".into()),
+                IriBox::new_unchecked("http://www.w3.org/2001/XMLSchema#".into()),
+            ),
+        ];
+        RdfXmlConfig {
+            pretty: false,
+            prefix_map,
+            base: None,
+            indentation: "  ".to_string(),
+        }
+    }
+
+    /// Transform an [`RdfXmlConfig`] by setting the [`pretty`][`RdfXmlConfig::pretty`] flag.
+    pub fn with_pretty(mut self, b: bool) -> Self {
+        self.pretty = b;
+        self
+    }
+
+    /// Transform an [`RdfXmlConfig`] by setting the [`prefix_map`][`RdfXmlConfig::prefix_map`] flag
+    /// (copying `pm` using [`PrefixMap::to_vec`]).
+    pub fn with_prefix_map<P: PrefixMap + ?Sized>(self, pm: &P) -> Self {
+        self.with_own_prefix_map(pm.to_vec())
+    }
+
+    /// Transform an [`RdfXmlConfig`] by setting the [`prefix_map`][`RdfXmlConfig::prefix_map`] flag.
+    pub fn with_own_prefix_map(mut self, pm: Vec<(PrefixBox, IriBox)>) -> Self {
+        self.prefix_map = pm;
+        self
+    }
+
+    /// Transform an [`RdfXmlConfig`] by setting the [`base`][`RdfXmlConfig::base`] IRI.
+    pub fn with_base(mut self, base: Option<IriBox>) -> Self {
+        self.base = base;
+        self
+    }
+
+    /// Transform an [`RdfXmlConfig`] by setting the [`indentation`][`RdfXmlConfig::indentation`] flag.
+    ///
+    /// # Precondition
+    /// `indentation` must only contain ASCII whitespaces, otherwise this method will panic.
+    pub fn with_indentation<T: ToString>(mut self, indentation: T) -> Self {
+        let indentation = indentation.to_string();
+        assert!(indentation.chars().all(char::is_whitespace));
+        self.indentation = indentation;
+        self
+    }
+}
+
+impl Default for RdfXmlConfig {
+    fn default() -> Self {
+        RdfXmlConfig::new()
+    }
 }
 
 /// RDF/XML serializer.
@@ -59,15 +178,26 @@ where
 
     fn serialize_triples<TS>(
         &mut self,
-        source: TS,
+        mut source: TS,
     ) -> StreamResult<&mut Self, TS::Error, Self::Error>
     where
         TS: TripleSource,
     {
-        // temporarily move out self.write
-        let mut tf = RdfXmlFormatter::new(&mut self.write).map_err(SinkError)?;
-        rio_format_triples(&mut tf, source)?;
-        tf.finish().map_err(SinkError)?;
+        if self.config.pretty {
+            let mut graph = FastGraph::new();
+            source.try_for_each_triple(|t| {
+                graph
+                    .insert(t.s(), t.p(), t.o())
+                    .map(|_| ())
+                    .map_err(io::Error::other)
+            })?;
+            _pretty::prettify(&graph, &mut self.write, &self.config).map_err(SinkError)?;
+        } else {
+            // temporarily move out self.write
+            let mut tf = RdfXmlFormatter::new(&mut self.write).map_err(SinkError)?;
+            rio_format_triples(&mut tf, source)?;
+            tf.finish().map_err(SinkError)?;
+        }
         Ok(self)
     }
 }
@@ -100,6 +230,7 @@ pub(crate) mod test {
     use super::*;
     use sophia_api::graph::isomorphic_graphs;
     use sophia_api::ns::*;
+    use sophia_api::parser::TripleParser;
     use sophia_term::literal::convert::AsLiteral;
     use sophia_term::*;
 
@@ -125,4 +256,87 @@ pub(crate) mod test {
         assert!(isomorphic_graphs(&g, &g2)?);
         Ok(())
     }
+
+    #[test]
+    fn pretty() -> Result<(), Box<dyn std::error::Error>> {
+        let ttl = r#"
+            @prefix : <http://example.org/ns/>.
+            @prefix foaf: <http://xmlns.com/foaf/0.1/>.
+            <http://example.org/doc#me> a foaf:Person;
+                foaf:name "Alice", "Alicia"@es;
+                :age 42;
+                :note "a < b & \"c\"\nd";
+                foaf:knows [ a foaf:Person; foaf:name "Bob" ], _:cycle1;
+                :list (<http://example.org/doc#a> [ :p :o ] <http://example.org/other>);
+                :empty [];
+                <http://example.org/other#p> :o.
+            _:cycle1 :next _:cycle2. _:cycle2 :next _:cycle1.
+            _:shared :p :o. :x :p _:shared. :y :p _:shared.
+        "#;
+        let g: Vec<[BoxTerm; 3]> =
+            sophia_turtle::parser::turtle::parse_str(ttl).collect_triples()?;
+        let config = RdfXmlConfig::new()
+            .with_pretty(true)
+            .with_own_prefix_map(vec![
+                (
+                    sophia_api::prefix::PrefixBox::new_unchecked("foaf".into()),
+                    IriBox::new_unchecked("http://xmlns.com/foaf/0.1/".into()),
+                ),
+                (
+                    sophia_api::prefix::PrefixBox::new_unchecked("".into()),
+                    IriBox::new_unchecked("http://example.org/ns/".into()),
+                ),
+            ])
+            .with_base(Some(IriBox::new_unchecked("http://example.org/doc".into())));
+        let xml = RdfXmlSerializer::new_stringifier_with_config(config.clone())
+            .serialize_graph(&g)?
+            .to_string();
+        for expected in [
+            r#"xmlns:rdf="http://www.w3.org/1999/02/22-rdf-syntax-ns#""#,
+            r#"xmlns="http://example.org/ns/""#,
+            r##"xmlns:ns="http://example.org/other#""##,
+            r#"xml:base="http://example.org/doc">"#,
+            r##"<foaf:Person rdf:about="#me">"##,
+            r#"<age rdf:datatype="http://www.w3.org/2001/XMLSchema#integer">42</age>"#,
+            r#"<foaf:name xml:lang="es">Alicia</foaf:name>"#,
+            "<note>a &lt; b &amp; \"c\"\nd</note>",
+            "<foaf:knows>\n      <foaf:Person>\n        <foaf:name>Bob</foaf:name>",
+            r#"<list rdf:parseType="Collection">"#,
+            r##"<rdf:Description rdf:about="#a"/>"##,
+            r#"<rdf:Description rdf:about="other"/>"#,
+            r#"<empty rdf:parseType="Resource"/>"#,
+            r#"<ns:p rdf:resource="ns/o"/>"#,
+            r#"<rdf:Description rdf:nodeID="b"#,
+        ] {
+            assert!(xml.contains(expected), "{} not found in\n{}", expected, xml);
+        }
+        assert!(!xml.contains("rdf:type"), "{}", xml);
+        let g2: Vec<[BoxTerm; 3]> = crate::parser::RdfXmlParser {
+            base: Some("http://example.org/doc".into()),
+            ..Default::default()
+        }
+        .parse_str(&xml)
+        .collect_triples()?;
+        assert!(isomorphic_graphs(&g, &g2)?);
+
+        // the output is deterministic
+        let xml2 = RdfXmlSerializer::new_stringifier_with_config(config)
+            .serialize_graph(&g2)?
+            .to_string();
+        assert_eq!(xml, xml2);
+        Ok(())
+    }
+
+    #[test]
+    fn pretty_unsupported_predicate() {
+        let g = vec![[
+            StaticTerm::new_iri("http://example.org/s").unwrap(),
+            StaticTerm::new_iri("http://example.org/123").unwrap(),
+            StaticTerm::new_iri("http://example.org/o").unwrap(),
+        ]];
+        let config = RdfXmlConfig::new().with_pretty(true);
+        assert!(RdfXmlSerializer::new_stringifier_with_config(config)
+            .serialize_graph(&g)
+            .is_err());
+    }
 }
@@ -0,0 +1,503 @@
+//! Utility code for pretty-printing RDF/XML.
+
+use super::RdfXmlConfig;
+use sophia_api::graph::Graph;
+use sophia_api::ns::rdf;
+use sophia_api::prefix::{PrefixBox, PrefixMap};
+use sophia_api::term::{term_cmp, TTerm, TermKind};
+use sophia_api::triple::Triple;
+use sophia_inmem::graph::FastGraph;
+use sophia_iri::resolve::{IriParsed, Relativize};
+use sophia_iri::IriBox;
+use sophia_term::RcTerm;
+use std::collections::{HashMap, HashSet};
+use std::io::{self, Write};
+
+const RDF_NS: &str = "http://www.w3.org/1999/02/22-rdf-syntax-ns#";
+const XSD_STRING: &str = "http://www.w3.org/2001/XMLSchema#string";
+
+/// Names of the RDF vocabulary that can not be used as node or property elements.
+const RESERVED: &[&str] = &[
+    "RDF",
+    "ID",
+    "about",
+    "bagID",
+    "parseType",
+    "resource",
+    "nodeID",
+    "datatype",
+    "Description",
+    "aboutEach",
+    "aboutEachPrefix",
+    "li",
+];
+
+/// Serialize `graph` in pretty RDF/XML on `write`, using the given `config`.
+pub fn prettify<W>(graph: &FastGraph, mut write: W, config: &RdfXmlConfig) -> io::Result<()>
+where
+    W: io::Write,
+{
+    let gd = GraphData::new(graph)?;
+    let mut namespaces = Namespaces::new(config);
+    let mut p = Prettifier {
+        write: Vec::new(),
+        config,
+        namespaces: &mut namespaces,
+        node_ids: HashMap::new(),
+    };
+    for root in &gd.roots {
+        p.write_node(&gd, root, 1)?;
+    }
+    let body = p.write;
+
+    write.write_all(b"<?xml version=\"1.0\" encoding=\"utf-8\"?>\n")?;
+    write!(&mut write, "<{}:RDF", namespaces.rdf)?;
+    let indent = config.indentation.repeat(2);
+    for (prefix, ns) in &namespaces.map {
+        write.write_all(b"\n")?;
+        write.write_all(indent.as_bytes())?;
+        if prefix.is_empty() {
+            write!(&mut write, "xmlns=\"{}\"", escape_attr(ns))?;
+        } else {
+            write!(&mut write, "xmlns:{}=\"{}\"", &prefix[..], escape_attr(ns))?;
+        }
+    }
+    if let Some(base) = &config.base {
+        write!(&mut write, "\n{}xml:base=\"{}\"", indent, escape_attr(base))?;
+    }
+    write.write_all(b">\n")?;
+    write.write_all(&body)?;
+    writeln!(&mut write, "</{}:RDF>", namespaces.rdf)?;
+    write.flush()
+}
+
+/// The structure of the graph, as it will be serialized.
+struct GraphData<'a> {
+    graph: &'a FastGraph,
+    /// The nodes described at the top level, in order.
+    roots: Vec<RcTerm>,
+    /// Blank nodes described inside the only property element referencing them.
+    nested: HashSet<RcTerm>,
+    /// Blank nodes referenced elsewhere than in a property element, requiring a `rdf:nodeID`.
+    referenced: HashSet<RcTerm>,
+    /// Lists serialized with `rdf:parseType="Collection"`, indexed by their head.
+    lists: HashMap<RcTerm, Vec<RcTerm>>,
+}
+
+impl<'a> GraphData<'a> {
+    fn new(graph: &'a FastGraph) -> io::Result<Self> {
+        let mut subjects: Vec<RcTerm> = graph.subjects().unwrap().into_iter().collect();
+        subjects.sort_by(term_cmp);
+
+        // count incoming arcs of blank nodes
+        let mut incoming = HashMap::<RcTerm, Vec<RcTerm>>::new();
+        for t in graph.triples() {
+            let t = t.unwrap();
+            match (t.s().kind(), t.p().kind(), t.o().kind()) {
+                (TermKind::Iri | TermKind::BlankNode, TermKind::Iri, TermKind::Variable) => {
+                    return Err(unsupported("variable", t.o()))
+                }
+                (TermKind::Iri | TermKind::BlankNode, TermKind::Iri, _) => (),
+                (TermKind::Iri | TermKind::BlankNode, _, _) => {
+                    return Err(unsupported("predicate", t.p()))
+                }
+                _ => return Err(unsupported("subject", t.s())),
+            }
+            if t.o().kind() == TermKind::BlankNode {
+                incoming
+                    .entry(t.o().clone())
+                    .or_default()
+                    .push(t.s().clone());
+            }
+        }
+        let mut nested: HashSet<RcTerm> = incoming
+            .iter()
+            .filter(|(o, subjects)| subjects.len() == 1 && &subjects[0] != *o)
+            .map(|(o, _)| o.clone())
+            .collect();
+
+        // nested blank nodes forming a cycle are not reachable from any root,
+        // so some of them must become roots
+        let mut roots: Vec<RcTerm> = subjects
+            .iter()
+            .filter(|s| !nested.contains(*s))
+            .cloned()
+            .collect();
+        let mut reached = HashSet::new();
+        for root in &roots {
+            reach(graph, &nested, root, &mut reached);
+        }
+        for s in &subjects {
+            if nested.contains(s) && !reached.contains(s) {
+                nested.remove(s);
+                roots.push(s.clone());
+                reach(graph, &nested, s, &mut reached);
+            }
+        }
+
+        let referenced = incoming
+            .into_keys()
+            .filter(|o| !nested.contains(o))
+            .collect();
+        let lists = build_lists(graph, &nested);
+        Ok(GraphData {
+            graph,
+            roots,
+            nested,
+            referenced,
+            lists,
+        })
+    }
+
+    /// The triples whose subject is `node`, in order.
+    fn triples_of(&self, node: &RcTerm) -> Vec<[RcTerm; 3]> {
+        let mut triples: Vec<_> = self
+            .graph
+            .triples_with_s(node)
+            .map(|t| {
+                let t = t.unwrap();
+                [t.s().clone(), t.p().clone(), t.o().clone()]
+            })
+            .collect();
+        triples.sort_by(|t1, t2| term_cmp(&t1[1], &t2[1]).then_with(|| term_cmp(&t1[2], &t2[2])));
+        triples
+    }
+}
+
+/// Add to `reached` all the nested blank nodes (transitively) described inside `node`.
+fn reach(
+    graph: &FastGraph,
+    nested: &HashSet<RcTerm>,
+    node: &RcTerm,
+    reached: &mut HashSet<RcTerm>,
+) {
+    let mut stack = vec![node.clone()];
+    while let Some(node) = stack.pop() {
+        for t in graph.triples_with_s(&node) {
+            let o = t.unwrap().o().clone();
+            if nested.contains(&o) && reached.insert(o.clone()) {
+                stack.push(o);
+            }
+        }
+    }
+}
+
+/// Find the well-formed lists whose items are not literals,
+/// and whose nodes are all nested blank nodes.
+fn build_lists(graph: &FastGraph, nested: &HashSet<RcTerm>) -> HashMap<RcTerm, Vec<RcTerm>> {
+    // nested blank nodes with exactly one rdf:first (non-literal) and one rdf:rest
+    let mut links = HashMap::new();
+    for node in nested {
+        let mut first = None;
+        let mut rest = None;
+        let mut other = false;
+        for t in graph.triples_with_s(node) {
+            let t = t.unwrap();
+            if t.p() == &rdf::first && first.is_none() && t.o().kind() != TermKind::Literal {
+                first = Some(t.o().clone());
+            } else if t.p() == &rdf::rest && rest.is_none() {
+                rest = Some(t.o().clone());
+            } else {
+                other = true;
+            }
+        }
+        if let (Some(first), Some(rest), false) = (first, rest, other) {
+            links.insert(node.clone(), (first, rest));
+        }
+    }
+    let mut rests = HashSet::new();
+    for (_, rest) in links.values() {
+        rests.insert(rest.clone());
+    }
+    let mut lists = HashMap::new();
+    'heads: for head in links.keys() {
+        if rests.contains(head) {
+            continue;
+        }
+        let mut items = vec![];
+        let mut node = head;
+        while node != &rdf::nil {
+            match links.get(node) {
+                Some((first, rest)) if items.len() < links.len() => {
+                    items.push(first.clone());
+                    node = rest;
+                }
+                _ => continue 'heads,
+            }
+        }
+        lists.insert(head.clone(), items);
+    }
+    lists
+}
+
+/// The namespaces declared in the output.
+struct Namespaces {
+    map: Vec<(PrefixBox, IriBox)>,
+    /// The prefix used for the RDF vocabulary.
+    rdf: String,
+}
+
+impl Namespaces {
+    fn new(config: &RdfXmlConfig) -> Self {
+        let mut namespaces = Namespaces {
+            map: config.prefix_map.clone(),
+            rdf: String::new(),
+        };
+        let rdf = namespaces
+            .map
+            .iter()
+            .find(|(prefix, ns)| !prefix.is_empty() && &ns[..] == RDF_NS)
+            .map(|(prefix, _)| prefix.to_string());
+        namespaces.rdf = match rdf {
+            Some(prefix) => prefix,
+            None => namespaces.declare("rdf", RDF_NS),
+        };
+        namespaces
+    }
+
+    /// Declare a new prefix for `ns`, based on `hint`.
+    fn declare(&mut self, hint: &str, ns: &str) -> String {
+        let mut prefix = hint.to_string();
+        let mut i = 0;
+        while self.map.iter().any(|(p, _)| p[..] == prefix) {
+            i += 1;
+            prefix = format!("{}{}", hint, i);
+        }
+        self.map.push((
+            PrefixBox::new_unchecked(prefix.clone().into()),
+            IriBox::new_unchecked(ns.into()),
+        ));
+        prefix
+    }
+
+    /// The qualified name of element representing `iri`, if any,
+    /// declaring a new namespace if required.
+    fn qname(&mut self, iri: &RcTerm) -> Option<String> {
+        if let Some((prefix, local)) = self.map.get_checked_prefixed_pair(iri, is_ncname) {
+            return Some(if prefix.is_empty() {
+                local.to_string()
+            } else {
+                format!("{}:{}", &prefix[..], local)
+            });
+        }
+        let iri = iri.value();
+        let start = iri
+            .char_indices()
+            .rev()
+            .take_while(|(_, c)| is_name_char(*c))
+            .map(|(i, _)| i)
+            .last()?;
+        let start = start + iri[start..].find(is_name_start_char)?;
+        let prefix = self.declare("ns", &iri[..start]);
+        Some(format!("{}:{}", prefix, &iri[start..]))
+    }
+
+    /// Whether `qname` is a name of the RDF vocabulary
+    /// that can not be used as a node or property element.
+    fn is_reserved(&self, qname: &str) -> bool {
+        let (prefix, local) = qname.split_once(':').unwrap_or(("", qname));
+        RESERVED.contains(&local)
+            && self
+                .map
+                .get_namespace(prefix)
+                .is_some_and(|ns| ns.as_ref() == RDF_NS)
+    }
+}
+
+struct Prettifier<'a> {
+    write: Vec<u8>,
+    config: &'a RdfXmlConfig,
+    namespaces: &'a mut Namespaces,
+    node_ids: HashMap<RcTerm, String>,
+}
+
+impl<'a> Prettifier<'a> {
+    /// Write the node element describing `node`.
+    fn write_node(&mut self, gd: &GraphData, node: &RcTerm, depth: usize) -> io::Result<()> {
+        let mut triples = gd.triples_of(node);
+        let mut name = None;
+        if let Some(i) = triples.iter().position(|t| {
+            t[1] == rdf::type_ && t[2].kind() == TermKind::Iri && {
+                let qname = self.namespaces.qname(&t[2]);
+                match qname {
+                    Some(qname) if !self.namespaces.is_reserved(&qname) => {
+                        name = Some(qname);
+                        true
+                    }
+                    _ => false,
+                }
+            }
+        }) {
+            triples.remove(i);
+        }
+        let name = name.unwrap_or_else(|| format!("{}:Description", self.namespaces.rdf));
+        self.write_indent(depth)?;
+        write!(&mut self.write, "<{}", name)?;
+        self.write_node_attr(gd, node, "about")?;
+        if triples.is_empty() {
+            return self.write.write_all(b"/>\n");
+        }
+        self.write.write_all(b">\n")?;
+        for [_, p, o] in &triples {
+            self.write_property(gd, p, o, depth + 1)?;
+        }
+        self.write_indent(depth)?;
+        writeln!(&mut self.write, "</{}>", name)
+    }
+
+    /// Write the attribute identifying `node`, if any
+    /// (`rdf:about` or `rdf:resource` for IRIs, `rdf:nodeID` for blank nodes).
+    fn write_node_attr(&mut self, gd: &GraphData, node: &RcTerm, attr: &str) -> io::Result<()> {
+        let rdf = &self.namespaces.rdf;
+        if node.kind() == TermKind::Iri {
+            let iri = node.value();
+            let iri = match &self.config.base {
+                Some(base) => IriParsed::new(base)
+                    .ok()
+                    .and_then(|base| base.relativize(&iri[..]))
+                    .map_or(iri, Into::into),
+                None => iri,
+            };
+            write!(
+                &mut self.write,
+                " {}:{}=\"{}\"",
+                rdf,
+                attr,
+                escape_attr(&iri)
+            )
+        } else if gd.referenced.contains(node) {
+            let n = self.node_ids.len();
+            let id = self
+                .node_ids
+                .entry(node.clone())
+                .or_insert_with(|| format!("b{}", n));
+            write!(&mut self.write, " {}:nodeID=\"{}\"", rdf, id)
+        } else {
+            Ok(())
+        }
+    }
+
+    /// Write the property element for predicate `p` and object `o`.
+    fn write_property(
+        &mut self,
+        gd: &GraphData,
+        p: &RcTerm,
+        o: &RcTerm,
+        depth: usize,
+    ) -> io::Result<()> {
+        let name = match self.namespaces.qname(p) {
+            Some(name) if !self.namespaces.is_reserved(&name) => name,
+            _ => return Err(unsupported("predicate", p)),
+        };
+        let rdf = self.namespaces.rdf.clone();
+        self.write_indent(depth)?;
+        write!(&mut self.write, "<{}", name)?;
+        match o.kind() {
+            TermKind::Literal => {
+                if let Some(tag) = o.language() {
+                    write!(&mut self.write, " xml:lang=\"{}\"", escape_attr(tag))?;
+                } else {
+                    let dt = o.datatype().unwrap();
+                    if dt.value() != XSD_STRING {
+                        write!(
+                            &mut self.write,
+                            " {}:datatype=\"{}\"",
+                            rdf,
+                            escape_attr(&dt.value())
+                        )?;
+                    }
+                }
+                writeln!(&mut self.write, ">{}</{}>", escape_text(&o.value()), name)
+            }
+            TermKind::BlankNode if gd.lists.contains_key(o) => {
+                writeln!(&mut self.write, " {}:parseType=\"Collection\">", rdf)?;
+                for item in &gd.lists[o] {
+                    if gd.nested.contains(item) {
+                        self.write_node(gd, item, depth + 1)?;
+                    } else {
+                        self.write_indent(depth + 1)?;
+                        write!(&mut self.write, "<{}:Description", rdf)?;
+                        self.write_node_attr(gd, item, "about")?;
+                        self.write.write_all(b"/>\n")?;
+                    }
+                }
+                self.write_indent(depth)?;
+                writeln!(&mut self.write, "</{}>", name)
+            }
+            TermKind::BlankNode if gd.nested.contains(o) => {
+                if gd.graph.triples_with_s(o).next().is_none() {
+                    return writeln!(&mut self.write, " {}:parseType=\"Resource\"/>", rdf);
+                }
+                self.write.write_all(b">\n")?;
+                self.write_node(gd, o, depth + 1)?;
+                self.write_indent(depth)?;
+                writeln!(&mut self.write, "</{}>", name)
+            }
+            _ => {
+                self.write_node_attr(gd, o, "resource")?;
+                self.write.write_all(b"/>\n")
+            }
+        }
+    }
+
+    fn write_indent(&mut self, depth: usize) -> io::Result<()> {
+        for _ in 0..depth {
+            self.write.write_all(self.config.indentation.as_bytes())?;
+        }
+        Ok(())
+    }
+}
+
+fn unsupported<T: TTerm + ?Sized>(role: &str, term: &T) -> io::Error {
+    io::Error::new(
+        io::ErrorKind::InvalidData,
+        format!(
+            "RDF/XML can not represent {} {}",
+            role,
+            sophia_api::term::term_to_string(term)
+        ),
+    )
+}
+
+fn is_name_start_char(c: char) -> bool {
+    c == '_' || c.is_alphabetic()
+}
+
+fn is_name_char(c: char) -> bool {
+    is_name_start_char(c) || c.is_numeric() || c == '-' || c == '.' || c == '\u{B7}'
+}
+
+fn is_ncname(txt: &str) -> bool {
+    let mut chars = txt.chars();
+    chars.next().is_some_and(is_name_start_char) && chars.all(is_name_char)
+}
+
+pub(super) fn escape_text(txt: &str) -> String {
+    let mut escaped = String::with_capacity(txt.len());
+    for c in txt.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '\r' => escaped.push_str("&#13;"),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+pub(super) fn escape_attr(txt: &str) -> String {
+    let mut escaped = String::with_capacity(txt.len());
+    for c in txt.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\t' => escaped.push_str("&#9;"),
+            '\n' => escaped.push_str("&#10;"),
+            '\r' => escaped.push_str("&#13;"),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
@@ -0,0 +1,288 @@
+//! Serializer for the [TriX] concrete syntax of RDF datasets.
+//!
+//! Consecutive quads sharing the same graph name are grouped in the same `<graph>` element,
+//! so that quads sorted by graph name produce one element per graph.
+//!
+//! **Important**:
+//! the methods in this module accepting a [`Write`]
+//! make no effort to minimize the number of write operations.
+//! Hence, in most cased, they should be passed a [`BufWriter`].
+//!
+//! [TriX]: https://www.hpl.hp.com/techreports/2004/HPL-2004-56.html
+//! [`Write`]: https://doc.rust-lang.org/std/io/trait.Write.html
+//! [`BufWriter`]: https://doc.rust-lang.org/std/io/struct.BufWriter.html
+
+use super::_pretty::{escape_attr, escape_text};
+use crate::parser::trix::TRIX_NS;
+use sophia_api::ns::xsd;
+use sophia_api::quad::stream::{QuadSource, SinkError, StreamResult};
+use sophia_api::quad::Quad;
+use sophia_api::serializer::*;
+use sophia_api::term::{TTerm, TermKind};
+use std::io;
+
+/// TriX serializer configuration.
+#[derive(Clone, Debug)]
+pub struct TrixConfig {
+    indentation: String,
+}
+
+impl TrixConfig {
+    /// Indentation to use in serialization.
+    /// (defaults to `"  "`, can only contain ASCII whitespaces)
+    pub fn indentation(&self) -> &str {
+        &self.indentation
+    }
+
+    /// Build a new default [`TrixConfig`].
+    pub fn new() -> Self {
+        TrixConfig {
+            indentation: "  ".to_string(),
+        }
+    }
+
+    /// Transform a [`TrixConfig`] by setting the [`indentation`][`TrixConfig::indentation`] flag.
+    ///
+    /// # Precondition
+    /// `indentation` must only contain ASCII whitespaces, otherwise this method will panic.
+    pub fn with_indentation<T: ToString>(mut self, indentation: T) -> Self {
+        let indentation = indentation.to_string();
+        assert!(indentation.chars().all(char::is_whitespace));
+        self.indentation = indentation;
+        self
+    }
+}
+
+impl Default for TrixConfig {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// TriX serializer.
+pub struct TrixSerializer<W> {
+    config: TrixConfig,
+    write: W,
+}
+
+impl<W> TrixSerializer<W>
+where
+    W: io::Write,
+{
+    /// Build a new TriX serializer writing to `write`, with the default config.
+    #[inline]
+    pub fn new(write: W) -> TrixSerializer<W> {
+        Self::new_with_config(write, TrixConfig::default())
+    }
+
+    /// Build a new TriX serializer writing to `write`, with the given config.
+    pub fn new_with_config(write: W, config: TrixConfig) -> TrixSerializer<W> {
+        TrixSerializer { config, write }
+    }
+
+    /// Borrow this serializer's configuration.
+    pub fn config(&self) -> &TrixConfig {
+        &self.config
+    }
+}
+
+impl<W> QuadSerializer for TrixSerializer<W>
+where
+    W: io::Write,
+{
+    type Error = io::Error;
+
+    fn serialize_quads<QS>(
+        &mut self,
+        mut source: QS,
+    ) -> StreamResult<&mut Self, QS::Error, Self::Error>
+    where
+        QS: QuadSource,
+    {
+        let indentation = &self.config.indentation;
+        let write = &mut self.write;
+        write
+            .write_all(b"<?xml version=\"1.0\" encoding=\"utf-8\"?>\n")
+            .map_err(SinkError)?;
+        writeln!(write, "<TriX xmlns=\"{}\">", TRIX_NS).map_err(SinkError)?;
+        // the serialization of the current graph name (None if no graph is open)
+        let mut current: Option<String> = None;
+        source.try_for_each_quad(|q| -> io::Result<()> {
+            let graph_name = match q.g() {
+                Some(g) => term_element(g, "graph name")?,
+                None => String::new(),
+            };
+            if current.as_ref() != Some(&graph_name) {
+                if current.is_some() {
+                    writeln!(write, "{}</graph>", indentation)?;
+                }
+                writeln!(write, "{}<graph>", indentation)?;
+                if !graph_name.is_empty() {
+                    writeln!(write, "{0}{0}{1}", indentation, graph_name)?;
+                }
+                current = Some(graph_name);
+            }
+            writeln!(write, "{0}{0}<triple>", indentation)?;
+            for (term, role) in [(q.s(), "subject"), (q.p(), "predicate"), (q.o(), "object")] {
+                let element = term_element(term, role)?;
+                writeln!(write, "{0}{0}{0}{1}", indentation, element)?;
+            }
+            writeln!(write, "{0}{0}</triple>", indentation)
+        })?;
+        if current.is_some() {
+            writeln!(write, "{}</graph>", indentation).map_err(SinkError)?;
+        }
+        write.write_all(b"</TriX>\n").map_err(SinkError)?;
+        write.flush().map_err(SinkError)?;
+        Ok(self)
+    }
+}
+
+impl TrixSerializer<Vec<u8>> {
+    /// Create a new serializer which targets a `String`.
+    #[inline]
+    pub fn new_stringifier() -> Self {
+        TrixSerializer::new(Vec::new())
+    }
+    /// Create a new serializer which targets a `String` with a custom config.
+    #[inline]
+    pub fn new_stringifier_with_config(config: TrixConfig) -> Self {
+        TrixSerializer::new_with_config(Vec::new(), config)
+    }
+}
+
+impl Stringifier for TrixSerializer<Vec<u8>> {
+    fn as_utf8(&self) -> &[u8] {
+        &self.write[..]
+    }
+}
+
+/// The XML element representing `term`.
+///
+/// `role` is only used in the error message, if `term` is a variable.
+fn term_element<T: TTerm + ?Sized>(term: &T, role: &str) -> io::Result<String> {
+    let value = escape_text(&term.value());
+    Ok(match term.kind() {
+        TermKind::Iri => format!("<uri>{}</uri>", value),
+        TermKind::BlankNode => format!("<id>{}</id>", value),
+        TermKind::Literal => match term.language() {
+            Some(tag) => format!(
+                "<plainLiteral xml:lang=\"{}\">{}</plainLiteral>",
+                escape_attr(tag),
+                value
+            ),
+            None => {
+                let datatype = term.datatype().unwrap();
+                if datatype == xsd::string {
+                    format!("<plainLiteral>{}</plainLiteral>", value)
+                } else {
+                    format!(
+                        "<typedLiteral datatype=\"{}\">{}</typedLiteral>",
+                        escape_attr(&datatype.value()),
+                        value
+                    )
+                }
+            }
+        },
+        TermKind::Variable => {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("TriX does not support variables as {}: {}", role, value),
+            ))
+        }
+    })
+}
+
+// ---------------------------------------------------------------------------------
+//                                      tests
+// ---------------------------------------------------------------------------------
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use sophia_api::dataset::isomorphic_datasets;
+    use sophia_api::parser::QuadParser;
+    use sophia_inmem::dataset::FastDataset;
+    use sophia_turtle::serializer::trig::TrigSerializer;
+
+    const TRIG: &str = r#"
+        PREFIX : <http://example.org/ns/>
+        :alice a :Person; :name "Alice", "Alicia"@es; :age 42; :note "a < b & c\r\n".
+        GRAPH :g1 {
+            :bob :knows _:x.
+            _:x :name "X"^^:custom.
+        }
+        GRAPH _:g2 {
+            _:x :p :alice.
+        }
+    "#;
+
+    #[test]
+    fn roundtrip_from_trig() -> Result<(), Box<dyn std::error::Error>> {
+        let d1: FastDataset = sophia_turtle::parser::trig::parse_str(TRIG).collect_quads()?;
+        let trix = TrixSerializer::new_stringifier()
+            .serialize_dataset(&d1)?
+            .to_string();
+        let d2: FastDataset = crate::parser::trix::parse_str(&trix).collect_quads()?;
+        assert!(isomorphic_datasets(&d1, &d2)?);
+        Ok(())
+    }
+
+    #[test]
+    fn roundtrip_to_trig() -> Result<(), Box<dyn std::error::Error>> {
+        let d1: FastDataset = sophia_turtle::parser::trig::parse_str(TRIG).collect_quads()?;
+        let trix = TrixSerializer::new_stringifier()
+            .serialize_dataset(&d1)?
+            .to_string();
+        let d2: FastDataset = crate::parser::trix::parse_str(&trix).collect_quads()?;
+        let trig = TrigSerializer::new_stringifier()
+            .serialize_dataset(&d2)?
+            .to_string();
+        let d3: FastDataset = sophia_turtle::parser::trig::TriGParser::default()
+            .parse_str(&trig)
+            .collect_quads()?;
+        assert!(isomorphic_datasets(&d1, &d3)?);
+        Ok(())
+    }
+
+    #[test]
+    fn serialize() -> Result<(), Box<dyn std::error::Error>> {
+        let nq = r#"
+            <tag:s> <tag:p> "a & b"@en <tag:g>.
+            <tag:s> <tag:p> "1"^^<http://www.w3.org/2001/XMLSchema#integer> <tag:g>.
+            _:b <tag:p> <tag:o>.
+        "#;
+        let trix =
+            TrixSerializer::new_stringifier_with_config(TrixConfig::new().with_indentation(" "))
+                .serialize_quads(sophia_turtle::parser::nq::parse_str(nq))?
+                .to_string();
+        assert_eq!(
+            trix,
+            r#"<?xml version="1.0" encoding="utf-8"?>
+<TriX xmlns="http://www.w3.org/2004/03/trix/trix-1/">
+ <graph>
+  <uri>tag:g</uri>
+  <triple>
+   <uri>tag:s</uri>
+   <uri>tag:p</uri>
+   <plainLiteral xml:lang="en">a &amp; b</plainLiteral>
+  </triple>
+  <triple>
+   <uri>tag:s</uri>
+   <uri>tag:p</uri>
+   <typedLiteral datatype="http://www.w3.org/2001/XMLSchema#integer">1</typedLiteral>
+  </triple>
+ </graph>
+ <graph>
+  <triple>
+   <id>b</id>
+   <uri>tag:p</uri>
+   <uri>tag:o</uri>
+  </triple>
+ </graph>
+</TriX>
+"#
+        );
+        Ok(())
+    }
+}